@@ -0,0 +1,243 @@
+//! Animated GIF, APNG and WebP encoding of rendered frames, without ffmpeg.
+//!
+//! Frames are full-canvas RGBA images shown for `1 / fps` seconds each, and `plays` is how
+//! many times the animation plays (0 = forever). Frame timings are rounded to each format's
+//! units as a running total, so long animations don't drift.
+
+use image::RgbaImage;
+use std::collections::HashMap;
+
+// alpha below this is transparent in a GIF, which has no partial transparency
+const GIF_ALPHA_CUTOFF: u8 = 128;
+
+// browsers slow down GIF frames shorter than this (in centiseconds), so faster ones are merged
+const GIF_MIN_DELAY: u32 = 2;
+
+// ANMF flags: replace the frame's area instead of blending it onto the previous one
+const WEBP_NO_BLEND: u8 = 0b10;
+
+/// Encode frames as an animated GIF, with a palette of up to 255 colors per frame.
+///
+/// Frames with more colors are quantized with NeuQuant.
+pub fn encode_gif(frames: &[RgbaImage], fps: i32, plays: u16) -> Result<Vec<u8>, String> {
+    let (width, height) = canvas_size(frames)?;
+    let (width, height) = match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(w), Ok(h)) => (w, h),
+        _ => return Err(format!("{}x{} is too big for a GIF", width, height)),
+    };
+
+    let mut buf = vec![];
+    let mut encoder = gif::Encoder::new(&mut buf, width, height, &[]).map_err(|e| e.to_string())?;
+    // the first play isn't a repeat
+    let repeat = match plays {
+        0 => Some(gif::Repeat::Infinite),
+        1 => None,
+        _ => Some(gif::Repeat::Finite(plays - 1)),
+    };
+    if let Some(repeat) = repeat {
+        encoder.set_repeat(repeat).map_err(|e| e.to_string())?;
+    }
+
+    // drop frames that would be shown for less than the minimum delay
+    let times = frame_times(frames.len() + 1, fps, 100);
+    let mut shown: Vec<(usize, u32)> = vec![];
+    for f in 0..frames.len() {
+        match shown.last_mut() {
+            Some((_, delay)) if *delay < GIF_MIN_DELAY => {}
+            _ => shown.push((f, 0)),
+        }
+        shown.last_mut().unwrap().1 += times[f + 1] - times[f];
+    }
+
+    for (f, delay) in shown {
+        let (palette, indices, transparent) = quantize(&frames[f]);
+        let frame = gif::Frame {
+            width,
+            height,
+            delay: delay.min(u16::MAX as u32) as u16,
+            dispose: gif::DisposalMethod::Background,
+            transparent: Some(transparent),
+            palette: Some(palette),
+            buffer: indices.into(),
+            ..Default::default()
+        };
+        encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+    }
+
+    drop(encoder);
+    Ok(buf)
+}
+
+/// Encode frames as an animated PNG.
+pub fn encode_apng(frames: &[RgbaImage], fps: i32, plays: u16) -> Result<Vec<u8>, String> {
+    let (width, height) = canvas_size(frames)?;
+    let err = |e: png::EncodingError| e.to_string();
+
+    let mut buf = vec![];
+    let mut encoder = png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, plays as u32)
+        .map_err(err)?;
+    encoder
+        .set_frame_delay(1, fps.clamp(1, u16::MAX as i32) as u16)
+        .map_err(err)?;
+    encoder
+        .set_dispose_op(png::DisposeOp::Background)
+        .map_err(err)?;
+    encoder.set_blend_op(png::BlendOp::Source).map_err(err)?;
+
+    let mut writer = encoder.write_header().map_err(err)?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw()).map_err(err)?;
+    }
+    writer.finish().map_err(err)?;
+    Ok(buf)
+}
+
+/// Encode frames as a lossless animated WebP.
+pub fn encode_webp(frames: &[RgbaImage], fps: i32, plays: u16) -> Result<Vec<u8>, String> {
+    let (width, height) = canvas_size(frames)?;
+    let times = frame_times(frames.len() + 1, fps, 1000);
+
+    // VP8X: alpha and animation flags, then the canvas size
+    let mut vp8x = vec![0x10 | 0x02, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+
+    // ANIM: transparent background, then the loop count
+    let mut anim = vec![0; 4];
+    anim.extend_from_slice(&plays.to_le_bytes());
+
+    let mut body = b"WEBP".to_vec();
+    push_chunk(&mut body, b"VP8X", &vp8x);
+    push_chunk(&mut body, b"ANIM", &anim);
+
+    for (f, frame) in frames.iter().enumerate() {
+        let mut still = vec![];
+        let encoder = image::codecs::webp::WebPEncoder::new_lossless(&mut still);
+        let rgba8 = image::ExtendedColorType::Rgba8;
+        encoder
+            .encode(frame.as_raw(), width, height, rgba8)
+            .map_err(|e| e.to_string())?;
+        let vp8l = find_chunk(&still, b"VP8L").ok_or("WebP encoder wrote no VP8L chunk")?;
+
+        // ANMF: offset, size, duration and flags, then the frame's own chunk
+        let mut anmf = vec![0; 6];
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24((times[f + 1] - times[f]).min(0xffffff)));
+        anmf.push(WEBP_NO_BLEND);
+        push_chunk(&mut anmf, b"VP8L", vp8l);
+        push_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut buf = b"RIFF".to_vec();
+    buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&body);
+    Ok(buf)
+}
+
+/// Palette (RGB), per-pixel indices and transparent index of a frame.
+///
+/// The palette is exact if the frame's opaque pixels have 255 colors or less.
+pub fn quantize(frame: &RgbaImage) -> (Vec<u8>, Vec<u8>, u8) {
+    let opaque = |p: &image::Rgba<u8>| p.0[3] >= GIF_ALPHA_CUTOFF;
+
+    let mut colors: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut exact = true;
+    for pixel in frame.pixels().filter(|p| opaque(p)) {
+        let rgb = [pixel.0[0], pixel.0[1], pixel.0[2]];
+        if colors.len() == 255 && !colors.contains_key(&rgb) {
+            exact = false;
+            break;
+        }
+        let next = colors.len() as u8;
+        colors.entry(rgb).or_insert(next);
+    }
+
+    let mut palette: Vec<u8>;
+    if exact {
+        palette = vec![0; colors.len() * 3];
+        for (rgb, idx) in &colors {
+            let start = *idx as usize * 3;
+            palette[start..start + 3].copy_from_slice(rgb);
+        }
+    } else {
+        let mut samples = vec![];
+        for pixel in frame.pixels().filter(|p| opaque(p)) {
+            samples.extend_from_slice(&[pixel.0[0], pixel.0[1], pixel.0[2], 255]);
+        }
+        let quant = color_quant::NeuQuant::new(10, 255, &samples);
+        palette = quant.color_map_rgb();
+        colors.clear();
+        for pixel in frame.pixels().filter(|p| opaque(p)) {
+            let rgb = [pixel.0[0], pixel.0[1], pixel.0[2]];
+            colors
+                .entry(rgb)
+                .or_insert_with(|| quant.index_of(&[rgb[0], rgb[1], rgb[2], 255]) as u8);
+        }
+    }
+
+    // transparent pixels get an entry of their own, after the colors
+    let transparent = (palette.len() / 3) as u8;
+    palette.extend_from_slice(&[0, 0, 0]);
+
+    let indices = frame
+        .pixels()
+        .map(|p| {
+            if opaque(p) {
+                colors[&[p.0[0], p.0[1], p.0[2]]]
+            } else {
+                transparent
+            }
+        })
+        .collect();
+    (palette, indices, transparent)
+}
+
+/// Start of each frame in `units_per_sec`, rounded from the exact time.
+pub fn frame_times(count: usize, fps: i32, units_per_sec: u32) -> Vec<u32> {
+    let fps = fps.max(1) as f64;
+    (0..count)
+        .map(|f| (f as f64 / fps * units_per_sec as f64).round() as u32)
+        .collect()
+}
+
+fn canvas_size(frames: &[RgbaImage]) -> Result<(u32, u32), String> {
+    let first = frames.first().ok_or("no frames to encode")?;
+    if frames.iter().any(|f| f.dimensions() != first.dimensions()) {
+        return Err("frames are of different sizes".to_string());
+    }
+    Ok(first.dimensions())
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+// RIFF chunks are padded to an even size
+fn push_chunk(buf: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    buf.extend_from_slice(id);
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
+
+/// Data of the first chunk with this id in a RIFF WebP file.
+pub fn find_chunk<'a>(webp: &'a [u8], id: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut pos = 12;
+    while pos + 8 <= webp.len() {
+        let size = u32::from_le_bytes(webp[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let data = webp.get(pos + 8..pos + 8 + size)?;
+        if &webp[pos..pos + 4] == id {
+            return Some(data);
+        }
+        pos += 8 + size + size % 2;
+    }
+    None
+}
@@ -0,0 +1,529 @@
+//! Aseprite (`.aseprite`/`.ase`) reading, to rig sprites from their layer groups like a PSD.
+//!
+//! Only what a rig needs is read: layers and their hierarchy, cels (raw, linked and
+//! compressed), palettes of indexed sprites, and tags. Layers are composited with normal
+//! blending. See https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+
+use crate::file_reader::{LayerGroup, LayerMarker, LayerRig};
+use crate::*;
+use image::{Rgba, RgbaImage};
+use std::io::Read;
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+// indexed pixels are single bytes, so no more palette entries can be used
+const MAX_PALETTE: usize = 256;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_BACKGROUND: u16 = 8;
+const LAYER_IMAGE: u16 = 0;
+const LAYER_GROUP: u16 = 1;
+const LAYER_TILEMAP: u16 = 2;
+
+const CEL_RAW: u16 = 0;
+const CEL_LINKED: u16 = 1;
+const CEL_COMPRESSED: u16 = 2;
+
+// header flag for layer opacity being used
+const FLAG_LAYER_OPACITY: u32 = 1;
+
+pub struct Aseprite {
+    pub width: u32,
+    pub height: u32,
+    /// bits per pixel: 32 (RGBA), 16 (grayscale) or 8 (indexed)
+    pub depth: u16,
+    /// palette index that is see-through (indexed sprites only)
+    pub transparent_idx: u8,
+    pub palette: Vec<[u8; 4]>,
+    /// bottom to top, with groups before their children
+    pub layers: Vec<AseLayer>,
+    /// cels of every frame
+    pub frames: Vec<Vec<Cel>>,
+    pub tags: Vec<Tag>,
+}
+
+pub struct AseLayer {
+    pub name: String,
+    pub flags: u16,
+    pub kind: u16,
+    /// how deep in groups this layer is
+    pub level: usize,
+    /// index of the group this layer is in
+    pub parent: Option<usize>,
+    pub opacity: u8,
+}
+
+#[derive(Clone)]
+pub struct Cel {
+    pub layer: usize,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: u8,
+    pub width: u32,
+    pub height: u32,
+    /// uncompressed pixels, in the sprite's color depth
+    pub pixels: Vec<u8>,
+}
+
+pub struct Tag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Little-endian reader over a slice, failing instead of panicking past its end.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SkfError> {
+        if self.pos + len > self.bytes.len() {
+            return Err(corrupt("Unexpected end of file."));
+        }
+        self.pos += len;
+        Ok(&self.bytes[self.pos - len..self.pos])
+    }
+
+    fn byte(&mut self) -> Result<u8, SkfError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16, SkfError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn short(&mut self) -> Result<i16, SkfError> {
+        Ok(self.word()? as i16)
+    }
+
+    fn dword(&mut self) -> Result<u32, SkfError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, SkfError> {
+        let len = self.word()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).to_string())
+    }
+}
+
+fn corrupt(message: &str) -> SkfError {
+    SkfError::Corrupt {
+        file: "Aseprite file".to_string(),
+        message: message.to_string(),
+    }
+}
+
+impl Aseprite {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Aseprite, SkfError> {
+        let mut r = Reader { bytes, pos: 0 };
+        r.dword()?;
+        if r.word()? != HEADER_MAGIC {
+            return Err(corrupt("Not an Aseprite file."));
+        }
+        let frame_count = r.word()? as usize;
+        let width = r.word()? as u32;
+        let height = r.word()? as u32;
+        let depth = r.word()?;
+        let flags = r.dword()?;
+        r.take(2 + 4 + 4)?;
+        let transparent_idx = r.byte()?;
+        if ![8, 16, 32].contains(&depth) {
+            return Err(corrupt(&format!("Unsupported color depth: {}", depth)));
+        }
+        r.pos = HEADER_SIZE;
+
+        let mut ase = Aseprite {
+            width,
+            height,
+            depth,
+            transparent_idx,
+            palette: vec![],
+            layers: vec![],
+            frames: vec![],
+            tags: vec![],
+        };
+        let mut old_palette: Vec<[u8; 4]> = vec![];
+
+        for _ in 0..frame_count {
+            let frame_start = r.pos;
+            let frame_len = r.dword()? as usize;
+            if r.word()? != FRAME_MAGIC || frame_len < FRAME_HEADER_SIZE {
+                return Err(corrupt("Frame is corrupted."));
+            }
+            r.pos = frame_start + FRAME_HEADER_SIZE;
+            let frame_end = frame_start + frame_len;
+            let mut cels = vec![];
+
+            while r.pos + 6 <= frame_end {
+                let chunk_start = r.pos;
+                let chunk_len = r.dword()? as usize;
+                let kind = r.word()?;
+                if chunk_len < 6 || chunk_start + chunk_len > bytes.len() {
+                    return Err(corrupt("Chunk is corrupted."));
+                }
+                let mut c = Reader {
+                    bytes: &bytes[..chunk_start + chunk_len],
+                    pos: r.pos,
+                };
+                match kind {
+                    CHUNK_LAYER => ase.read_layer(&mut c, flags)?,
+                    CHUNK_CEL => {
+                        if let Some(cel) = ase.read_cel(&mut c)? {
+                            cels.push(cel);
+                        }
+                    }
+                    CHUNK_PALETTE => ase.read_palette(&mut c)?,
+                    CHUNK_OLD_PALETTE => read_old_palette(&mut c, &mut old_palette)?,
+                    CHUNK_TAGS => ase.read_tags(&mut c)?,
+                    _ => {}
+                }
+                r.pos = chunk_start + chunk_len;
+            }
+
+            r.pos = frame_end;
+            ase.frames.push(cels);
+        }
+
+        if ase.palette.is_empty() {
+            ase.palette = old_palette;
+        }
+
+        Ok(ase)
+    }
+
+    fn read_layer(&mut self, r: &mut Reader, header_flags: u32) -> Result<(), SkfError> {
+        let flags = r.word()?;
+        let kind = r.word()?;
+        let level = r.word()? as usize;
+        r.take(2 + 2 + 2)?;
+        let mut opacity = r.byte()?;
+        r.take(3)?;
+        let name = r.string()?;
+        if header_flags & FLAG_LAYER_OPACITY == 0 {
+            opacity = 255;
+        }
+
+        // the parent is the closest group one level up
+        let mut parent = None;
+        if level > 0 {
+            parent = (0..self.layers.len())
+                .rev()
+                .find(|l| self.layers[*l].level == level - 1);
+        }
+
+        self.layers.push(AseLayer {
+            name,
+            flags,
+            kind,
+            level,
+            parent,
+            opacity,
+        });
+        Ok(())
+    }
+
+    fn read_cel(&self, r: &mut Reader) -> Result<Option<Cel>, SkfError> {
+        let layer = r.word()? as usize;
+        let x = r.short()? as i32;
+        let y = r.short()? as i32;
+        let opacity = r.byte()?;
+        let kind = r.word()?;
+        r.take(2 + 5)?;
+        if layer >= self.layers.len() {
+            return Ok(None);
+        }
+
+        let mut cel = Cel {
+            layer,
+            x,
+            y,
+            opacity,
+            width: 0,
+            height: 0,
+            pixels: vec![],
+        };
+        match kind {
+            CEL_RAW | CEL_COMPRESSED => {
+                cel.width = r.word()? as u32;
+                cel.height = r.word()? as u32;
+                let data = r.take(r.bytes.len() - r.pos)?;
+                if kind == CEL_RAW {
+                    cel.pixels = data.to_vec();
+                } else {
+                    let mut decoder = flate2::read::ZlibDecoder::new(data);
+                    if let Err(e) = decoder.read_to_end(&mut cel.pixels) {
+                        return Err(corrupt(&e.to_string()));
+                    }
+                }
+                let len = (cel.width * cel.height) as usize * (self.depth / 8) as usize;
+                if cel.pixels.len() < len {
+                    return Err(corrupt("Cel is missing pixels."));
+                }
+            }
+            CEL_LINKED => {
+                // same cel as in an earlier frame
+                let frame = r.word()? as usize;
+                return Ok(self.cel(frame, layer).cloned());
+            }
+            // tilemaps aren't supported
+            _ => return Ok(None),
+        }
+
+        Ok(Some(cel))
+    }
+
+    fn read_palette(&mut self, r: &mut Reader) -> Result<(), SkfError> {
+        let size = r.dword()? as usize;
+        let first = r.dword()? as usize;
+        let last = r.dword()? as usize;
+        r.take(8)?;
+        if first > last || last >= size {
+            return Err(corrupt("Palette entries are out of range."));
+        }
+        self.palette.resize(size.min(MAX_PALETTE), [0, 0, 0, 255]);
+        for i in first..=last {
+            let flags = r.word()?;
+            let rgba = r.take(4)?;
+            if let Some(entry) = self.palette.get_mut(i) {
+                *entry = [rgba[0], rgba[1], rgba[2], rgba[3]];
+            }
+            if flags & 1 != 0 {
+                r.string()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_tags(&mut self, r: &mut Reader) -> Result<(), SkfError> {
+        let count = r.word()?;
+        r.take(8)?;
+        for _ in 0..count {
+            let from = r.word()? as usize;
+            let to = r.word()? as usize;
+            r.take(1 + 2 + 6 + 3 + 1)?;
+            let name = r.string()?;
+            self.tags.push(Tag { name, from, to });
+        }
+        Ok(())
+    }
+
+    /// Whether this layer and all groups it's in are visible.
+    pub fn visible(&self, layer: usize) -> bool {
+        let l = &self.layers[layer];
+        l.flags & LAYER_VISIBLE != 0 && l.parent.is_none_or(|p| self.visible(p))
+    }
+
+    pub fn cel(&self, frame: usize, layer: usize) -> Option<&Cel> {
+        self.frames.get(frame)?.iter().find(|c| c.layer == layer)
+    }
+
+    /// Color of a cel's pixel, as RGBA.
+    pub fn pixel(&self, cel: &Cel, idx: usize) -> [u8; 4] {
+        let px = &cel.pixels;
+        match self.depth {
+            32 => [
+                px[idx * 4],
+                px[idx * 4 + 1],
+                px[idx * 4 + 2],
+                px[idx * 4 + 3],
+            ],
+            16 => [px[idx * 2], px[idx * 2], px[idx * 2], px[idx * 2 + 1]],
+            _ => {
+                let background = self.layers[cel.layer].flags & LAYER_BACKGROUND != 0;
+                if px[idx] == self.transparent_idx && !background {
+                    return [0, 0, 0, 0];
+                }
+                *self.palette.get(px[idx] as usize).unwrap_or(&[0, 0, 0, 0])
+            }
+        }
+    }
+
+    /// Composite these layers' cels of a frame (bottom to top) onto an image at `pos`.
+    pub fn flatten(&self, frame: usize, layers: &[usize], pos: Vec2I, size: Vec2I) -> RgbaImage {
+        let mut img = RgbaImage::new(size.x as u32, size.y as u32);
+        for layer in layers {
+            let cel = match self.cel(frame, *layer) {
+                Some(cel) => cel,
+                None => continue,
+            };
+            let opacity = cel.opacity as f32 / 255. * self.layers[*layer].opacity as f32 / 255.;
+            for cy in 0..cel.height {
+                for cx in 0..cel.width {
+                    let x = cel.x + cx as i32 - pos.x;
+                    let y = cel.y + cy as i32 - pos.y;
+                    if x < 0 || y < 0 || x >= size.x || y >= size.y {
+                        continue;
+                    }
+                    let src = self.pixel(cel, (cy * cel.width + cx) as usize);
+                    let dst = img.get_pixel_mut(x as u32, y as u32);
+                    *dst = blend(dst, src, opacity);
+                }
+            }
+        }
+        img
+    }
+
+    /// Top-left and size of the area covered by these layers' cels, across frames.
+    pub fn bounds(&self, frames: &[usize], layers: &[usize]) -> (Vec2I, Vec2I) {
+        let mut min = Vec2I::new(i32::MAX, i32::MAX);
+        let mut max = Vec2I::new(i32::MIN, i32::MIN);
+        for frame in frames {
+            for cel in layers.iter().filter_map(|l| self.cel(*frame, *l)) {
+                min.x = min.x.min(cel.x);
+                min.y = min.y.min(cel.y);
+                max.x = max.x.max(cel.x + cel.width as i32);
+                max.y = max.y.max(cel.y + cel.height as i32);
+            }
+        }
+        if min.x > max.x {
+            return (Vec2I::new(0, 0), Vec2I::new(0, 0));
+        }
+        (
+            Vec2I::new(min.x, min.y),
+            Vec2I::new(max.x - min.x, max.y - min.y),
+        )
+    }
+
+    /// Groups and `$` layers to rig from, with the same naming as PSDs.
+    ///
+    /// Groups are flattened from their first frame. Every frame of a tag is also flattened
+    /// as a `$"style"` group: the tag's name, numbered if it spans several frames.
+    pub fn layer_rig(&self) -> LayerRig {
+        let mut tag_frames: Vec<(String, usize)> = vec![];
+        for tag in &self.tags {
+            let name = tag.name.replace('"', "'");
+            for frame in tag.from..=tag.to.min(self.frames.len().saturating_sub(1)) {
+                let style = if tag.from == tag.to {
+                    name.clone()
+                } else {
+                    format!("{} {}", name, frame - tag.from + 1)
+                };
+                tag_frames.push((style, frame));
+            }
+        }
+        let mut frames = vec![0];
+        frames.extend(tag_frames.iter().map(|(_, f)| *f));
+
+        let mut groups = vec![];
+        let mut variant_id = self.layers.len() as u32;
+        for g in 0..self.layers.len() {
+            let children: Vec<usize> = (0..self.layers.len())
+                .filter(|l| self.layers[*l].parent == Some(g) && self.visible(*l))
+                .filter(|l| self.layers[*l].kind != LAYER_GROUP)
+                .collect();
+            if self.layers[g].kind != LAYER_GROUP || children.is_empty() {
+                continue;
+            }
+
+            let content: Vec<usize> = children
+                .into_iter()
+                .filter(|l| self.layers[*l].kind == LAYER_IMAGE)
+                .filter(|l| !self.layers[*l].name.contains("$"))
+                .collect();
+            let name = &self.layers[g].name;
+            let is_style = name.contains("$\"");
+
+            // variants share the group's area, so they line up when swapped
+            let rig_frames = if is_style { &frames[..1] } else { &frames[..] };
+            let (pos, size) = self.bounds(rig_frames, &content);
+            let offset = Vec2::new(pos.x as f32, pos.y as f32);
+
+            groups.push(LayerGroup {
+                id: g as u32,
+                name: name.clone(),
+                parent_id: self.layers[g].parent.map(|p| p as u32),
+                image: self.flatten(0, &content, pos.clone(), size.clone()),
+                offset,
+            });
+            if is_style {
+                continue;
+            }
+            for (style, frame) in &tag_frames {
+                groups.push(LayerGroup {
+                    id: variant_id,
+                    name: format!("$\"{}\"", style),
+                    parent_id: Some(g as u32),
+                    image: self.flatten(*frame, &content, pos.clone(), size.clone()),
+                    offset,
+                });
+                variant_id += 1;
+            }
+        }
+
+        let mut markers = vec![];
+        for l in 0..self.layers.len() {
+            let layer = &self.layers[l];
+            if layer.parent.is_none() || !layer.name.contains("$") || layer.kind == LAYER_TILEMAP {
+                continue;
+            }
+            if let Some(cel) = self.cel(0, l) {
+                markers.push(LayerMarker {
+                    group_id: layer.parent.unwrap() as u32,
+                    name: layer.name.clone(),
+                    pos: Vec2::new(cel.x as f32, cel.y as f32),
+                    size: Vec2::new(cel.width as f32, cel.height as f32),
+                });
+            }
+        }
+
+        LayerRig {
+            dimensions: Vec2::new(self.width as f32, self.height as f32),
+            groups,
+            markers,
+        }
+    }
+}
+
+fn read_old_palette(r: &mut Reader, palette: &mut Vec<[u8; 4]>) -> Result<(), SkfError> {
+    let packets = r.word()?;
+    let mut idx = 0;
+    for _ in 0..packets {
+        idx += r.byte()? as usize;
+        let count = match r.byte()? {
+            0 => 256,
+            count => count as usize,
+        };
+        for _ in 0..count {
+            let rgb = r.take(3)?;
+            if idx >= MAX_PALETTE {
+                return Err(corrupt("Palette entries are out of range."));
+            }
+            if palette.len() <= idx {
+                palette.resize(idx + 1, [0, 0, 0, 255]);
+            }
+            palette[idx] = [rgb[0], rgb[1], rgb[2], 255];
+            idx += 1;
+        }
+    }
+    Ok(())
+}
+
+// source-over blending, of straight (not premultiplied) colors
+fn blend(dst: &Rgba<u8>, src: [u8; 4], opacity: f32) -> Rgba<u8> {
+    let sa = src[3] as f32 / 255. * opacity;
+    let da = dst[3] as f32 / 255.;
+    let a = sa + da * (1. - sa);
+    if a == 0. {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let mut out = [0; 4];
+    for c in 0..3 {
+        let color = (src[c] as f32 * sa + dst[c] as f32 * da * (1. - sa)) / a;
+        out[c] = color.round() as u8;
+    }
+    out[3] = (a * 255.).round() as u8;
+    Rgba(out)
+}
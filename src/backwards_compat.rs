@@ -1,27 +1,52 @@
 use serde_json::Value;
 
-use crate::{InverseKinematics, Physics, Root, Visuals};
+use crate::{InverseKinematics, Physics, Root, SkfError, Visuals};
 
 macro_rules! get_array {
-    ($src:expr, $name:expr) => {
-        $src.get_mut($name).unwrap().as_array_mut().unwrap()
+    ($src:expr, $name:expr, $path:expr) => {
+        match $src.get_mut($name).and_then(|value| value.as_array_mut()) {
+            Some(array) => array,
+            None => return Err(SkfError::MissingEntry($path)),
+        }
     };
 }
 
-pub fn proceed(mut raw: serde_json::Value) -> (Root, String) {
-    let version_raw = raw.get("version").unwrap().clone();
-    let version = version_raw.as_str().unwrap();
-    let mut err = "".to_string();
+/// Deserialize `T` from json, with the path of any error prefixed by `prefix`.
+fn deserialize<T: serde::de::DeserializeOwned>(raw: &Value, prefix: &str) -> Result<T, SkfError> {
+    let result: Result<T, _> = serde_path_to_error::deserialize(raw);
+    result.map_err(|err| {
+        let path = match err.path().to_string().as_str() {
+            "." => prefix.to_string(),
+            path => join_path(prefix, path),
+        };
+        let message = err.into_inner().to_string();
+        SkfError::BadValue { path, message }
+    })
+}
+
+fn join_path(prefix: &str, path: &str) -> String {
+    if prefix.is_empty() {
+        return path.to_string();
+    }
+    if path.starts_with('[') {
+        return prefix.to_string() + path;
+    }
+    format!("{}.{}", prefix, path)
+}
+
+pub fn proceed(mut raw: serde_json::Value) -> Result<Root, SkfError> {
+    let version = match raw.get("version").and_then(|version| version.as_str()) {
+        Some(version) => version.to_string(),
+        None => return Err(SkfError::MissingEntry("version".to_string())),
+    };
 
     macro_rules! from_ver {
         ($ver:expr, $func:ident) => {
             if version.contains($ver) {
-                $func(&mut raw);
-                let json_string = serde_json::to_string(&raw).unwrap();
-                let mut deserializer = serde_json::Deserializer::from_str(&json_string);
-                let result: Result<Root, _> = serde_path_to_error::deserialize(&mut deserializer);
-                if let Err(invalid) = result {
-                    err = format!("from v{}: \n\n{}", $ver, &invalid.to_string());
+                $func(&mut raw)?;
+                if let Err(SkfError::BadValue { path, message }) = deserialize::<Root>(&raw, "") {
+                    let message = format!("from v{}: \n\n{}", $ver, message);
+                    return Err(SkfError::BadValue { path, message });
                 }
             }
         };
@@ -32,19 +57,16 @@ pub fn proceed(mut raw: serde_json::Value) -> (Root, String) {
     from_ver!("0.4", v0d4_to_v0d4d1);
     from_ver!("0.5", v0d5_to_v0d6);
 
-    if err == "" {
-        return (serde_json::from_value(raw).unwrap(), "".to_string());
-    } else {
-        return (Root::default(), err);
-    }
+    deserialize(&raw, "")
 }
 
-pub fn v0d2_to_v0d3(raw: &mut serde_json::Value) {
+pub fn v0d2_to_v0d3(raw: &mut serde_json::Value) -> Result<(), SkfError> {
     raw["version"] = "v0.4.0".into();
+    Ok(())
 }
 
-pub fn v0d3_to_v0d4(raw: &mut serde_json::Value) {
-    for bone in get_array!(raw, "bones") {
+pub fn v0d3_to_v0d4(raw: &mut serde_json::Value) -> Result<(), SkfError> {
+    for bone in get_array!(raw, "bones", "bones".to_string()) {
         if let Some(str) = bone.get("ik_constraint_str") {
             bone["ik_constraint"] = str.clone();
         }
@@ -52,21 +74,26 @@ pub fn v0d3_to_v0d4(raw: &mut serde_json::Value) {
             bone["ik_mode"] = str.clone();
         }
     }
-    for anim in get_array!(raw, "animations") {
-        for keyframe in get_array!(anim, "keyframes") {
+    let anims = get_array!(raw, "animations", "animations".to_string());
+    for (a, anim) in anims.iter_mut().enumerate() {
+        let path = format!("animations[{}].keyframes", a);
+        for keyframe in get_array!(anim, "keyframes", path) {
             keyframe["element"] = keyframe["element_str"].clone();
         }
     }
     raw["version"] = "v0.4.0".into();
+    Ok(())
 }
 
-pub fn v0d4_to_v0d4d1(raw: &mut serde_json::Value) {
+pub fn v0d4_to_v0d4d1(raw: &mut serde_json::Value) -> Result<(), SkfError> {
     if raw.get_mut("animations") != None {
-        for anim in get_array!(raw, "animations") {
+        let anims = get_array!(raw, "animations", "animations".to_string());
+        for (a, anim) in anims.iter_mut().enumerate() {
             if anim.get_mut("keyframes") == None {
                 continue;
             }
-            for keyframe in get_array!(anim, "keyframes") {
+            let path = format!("animations[{}].keyframes", a);
+            for keyframe in get_array!(anim, "keyframes", path) {
                 if keyframe["frame"] == -1 {
                     keyframe["frame"] = serde_json::to_value(0).unwrap();
                 }
@@ -75,34 +102,28 @@ pub fn v0d4_to_v0d4d1(raw: &mut serde_json::Value) {
     }
 
     raw["version"] = "v0.4.1".into();
+    Ok(())
 }
 
-pub fn v0d5_to_v0d6(raw: &mut serde_json::Value) {
+pub fn v0d5_to_v0d6(raw: &mut serde_json::Value) -> Result<(), SkfError> {
     // initiate Root
-    let json_string = serde_json::to_string(&raw).unwrap();
-    let mut deserializer = serde_json::Deserializer::from_str(&json_string);
-    let mut root: Result<Root, _> = serde_path_to_error::deserialize(&mut deserializer);
-
-    for b in 0..raw.get("bones").unwrap().as_array().unwrap().len() {
-        let bone = &raw.get("bones").unwrap().as_array().unwrap()[b];
+    let mut root: Root = deserialize(raw, "")?;
+    let bones = get_array!(raw, "bones", "bones".to_string()).clone();
 
-        let json_string = serde_json::to_string(&bone).unwrap();
+    for (b, bone) in bones.iter().enumerate() {
+        let path = format!("bones[{}]", b);
 
         // extract visuals fields from this bone
-        let mut deserializer = serde_json::Deserializer::from_str(&json_string);
-        let visuals: Result<Visuals, _> = serde_path_to_error::deserialize(&mut deserializer);
-        root.as_mut().unwrap().visuals.push(visuals.unwrap());
-        let len = root.as_ref().unwrap().visuals.len();
-        root.as_mut().unwrap().bones[b].visuals_id = len as i32 - 1;
+        let visuals: Visuals = deserialize(bone, &path)?;
+        root.visuals.push(visuals);
+        root.bones[b].visuals_id = root.visuals.len() as i32 - 1;
 
         // extract IK fields from this bone
-        let mut deserializer = serde_json::Deserializer::from_str(&json_string);
-        let mut ik_json: Result<Value, _> = serde_path_to_error::deserialize(&mut deserializer);
-        if ik_json.as_ref().unwrap().get("ik_bone_ids") != None {
+        if bone.get("ik_bone_ids").is_some() {
+            let mut ik_json = bone.clone();
             macro_rules! rename {
                 ($field:expr) => {
-                    ik_json.as_mut().unwrap()[$field] =
-                        ik_json.as_ref().unwrap()[format!("ik_{}", $field)].clone();
+                    ik_json[$field] = ik_json[format!("ik_{}", $field)].clone();
                 };
             }
 
@@ -113,26 +134,33 @@ pub fn v0d5_to_v0d6(raw: &mut serde_json::Value) {
             rename!("bone_ids");
 
             // with the renamed fields, reparsing IK json will work
-            let ik_str = ik_json.unwrap().to_string();
-            let mut des = serde_json::Deserializer::from_str(&ik_str);
-            let ik: Result<InverseKinematics, _> = serde_path_to_error::deserialize(&mut des);
-
-            let len = root.as_mut().unwrap().inverse_kinematics.len() as i32;
-            for id in &ik.as_ref().unwrap().bone_ids {
-                root.as_mut().unwrap().bones[*id as usize].ik_family_id = len;
+            let ik: InverseKinematics = deserialize(&ik_json, &path)?;
+
+            let len = root.inverse_kinematics.len() as i32;
+            for (i, id) in ik.bone_ids.iter().enumerate() {
+                let bones_len = root.bones.len();
+                match root.bones.get_mut(*id as usize) {
+                    Some(bone) => bone.ik_family_id = len,
+                    None => {
+                        return Err(SkfError::OutOfRange {
+                            path: format!("{}.ik_bone_ids[{}]", path, i),
+                            idx: *id,
+                            len: bones_len,
+                        })
+                    }
+                }
             }
 
-            root.as_mut().unwrap().inverse_kinematics.push(ik.unwrap());
+            root.inverse_kinematics.push(ik);
         }
 
         // extract physics fields from this bone
-        let mut deserializer = serde_json::Deserializer::from_str(&json_string);
-        let mut phys_json: Result<Value, _> = serde_path_to_error::deserialize(&mut deserializer);
+        let mut phys_json = bone.clone();
 
         macro_rules! rename {
             ($field:expr) => {
-                if let Some(field) = phys_json.as_ref().unwrap().get(format!("phys_{}", $field)) {
-                    phys_json.as_mut().unwrap()[$field] = field.clone();
+                if let Some(field) = bone.get(format!("phys_{}", $field)) {
+                    phys_json[$field] = field.clone();
                 }
             };
         }
@@ -143,14 +171,11 @@ pub fn v0d5_to_v0d6(raw: &mut serde_json::Value) {
         rename!("sway");
         rename!("rot_bounce");
 
-        let phys_str = phys_json.unwrap().to_string();
-        let mut des = serde_json::Deserializer::from_str(&phys_str);
-        let physics: Result<Physics, _> = serde_path_to_error::deserialize(&mut des);
-
-        root.as_mut().unwrap().physics.push(physics.unwrap());
-        let len = root.as_ref().unwrap().physics.len();
-        root.as_mut().unwrap().bones[b].physics_id = len as i32 - 1;
+        let physics: Physics = deserialize(&phys_json, &path)?;
+        root.physics.push(physics);
+        root.bones[b].physics_id = root.physics.len() as i32 - 1;
     }
 
-    *raw = serde_json::to_value(&root.unwrap()).unwrap();
+    *raw = serde_json::to_value(&root).unwrap();
+    Ok(())
 }
@@ -0,0 +1,342 @@
+//! Compact binary encoding of armature.json (saved as armature.bin), for runtimes
+//! that can't afford to parse large JSON files.
+//!
+//! Layout (little-endian):
+//! - header: `SKFB` magic, then the format version (u16)
+//! - string table: count, then every unique string (names, texture names, keys)
+//!   as length + UTF-8 bytes
+//! - the root value, with all strings as indices into the string table
+//!
+//! Arrays of objects sharing the same keys (keyframes, vertices, etc) are packed
+//! as tables, so that keys are only written once.
+//!
+//! Counts, lengths, string indices and integers are LEB128 varints (integers zigzagged).
+
+use crate::*;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+pub const MAGIC: &[u8; 4] = b"SKFB";
+pub const VERSION: u16 = 1;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_F32: u8 = 4;
+const TAG_F64: u8 = 5;
+const TAG_STR: u8 = 6;
+const TAG_ARRAY: u8 = 7;
+const TAG_OBJECT: u8 = 8;
+const TAG_TABLE: u8 = 9;
+
+/// Deepest nesting of arrays and objects that will be decoded.
+/// armature.json itself never goes past a handful of levels.
+const MAX_DEPTH: usize = 64;
+
+/// Encode a `Root` into the binary format.
+pub fn encode_root(root: &Root) -> Vec<u8> {
+    encode(&serde_json::to_value(root).unwrap())
+}
+
+/// Encode any armature.json value into the binary format.
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut strings = StringTable::default();
+    collect_strings(value, &mut strings);
+
+    let mut buf = MAGIC.to_vec();
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    write_varint(&mut buf, strings.list.len() as u64);
+    for str in &strings.list {
+        write_varint(&mut buf, str.len() as u64);
+        buf.extend_from_slice(str.as_bytes());
+    }
+    write_value(&mut buf, value, &strings);
+
+    buf
+}
+
+/// Decode the binary format back into the same value that armature.json would have.
+pub fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+
+    if reader.take(4)? != MAGIC {
+        return Err("not a SkelForm binary armature".to_string());
+    }
+    let version = u16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+    if version > VERSION {
+        return Err(format!("unsupported binary version {}", version));
+    }
+
+    let count = reader.count(1)?;
+    let mut strings = vec![];
+    for _ in 0..count {
+        let len = reader.varint()? as usize;
+        let str = std::str::from_utf8(reader.take(len)?);
+        if str.is_err() {
+            return Err(format!("invalid string at byte {}", reader.pos - len));
+        }
+        strings.push(str.unwrap().to_string());
+    }
+
+    let value = reader.value(&strings)?;
+    if reader.pos != bytes.len() {
+        return Err(format!("unexpected data at byte {}", reader.pos));
+    }
+    Ok(value)
+}
+
+#[derive(Default)]
+struct StringTable {
+    list: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl StringTable {
+    fn add(&mut self, str: &str) {
+        if !self.indices.contains_key(str) {
+            self.indices.insert(str.to_string(), self.list.len());
+            self.list.push(str.to_string());
+        }
+    }
+
+    fn idx(&self, str: &str) -> u64 {
+        self.indices[str] as u64
+    }
+}
+
+fn collect_strings(value: &Value, strings: &mut StringTable) {
+    match value {
+        Value::String(str) => strings.add(str),
+        Value::Array(array) => {
+            for item in array {
+                collect_strings(item, strings);
+            }
+        }
+        Value::Object(object) => {
+            for (key, item) in object {
+                strings.add(key);
+                collect_strings(item, strings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys shared by every object in this array, if it can be packed as a table.
+fn table_keys(array: &[Value]) -> Option<Vec<&String>> {
+    if array.len() < 2 {
+        return None;
+    }
+    let keys: Vec<&String> = array[0].as_object()?.keys().collect();
+    for item in &array[1..] {
+        let object = item.as_object()?;
+        if object.len() != keys.len() || !object.keys().eq(keys.iter().copied()) {
+            return None;
+        }
+    }
+    Some(keys)
+}
+
+fn write_value(buf: &mut Vec<u8>, value: &Value, strings: &StringTable) {
+    match value {
+        Value::Null => buf.push(TAG_NULL),
+        Value::Bool(false) => buf.push(TAG_FALSE),
+        Value::Bool(true) => buf.push(TAG_TRUE),
+        Value::Number(num) => write_number(buf, num),
+        Value::String(str) => {
+            buf.push(TAG_STR);
+            write_varint(buf, strings.idx(str));
+        }
+        Value::Array(array) => {
+            if let Some(keys) = table_keys(array) {
+                buf.push(TAG_TABLE);
+                write_varint(buf, array.len() as u64);
+                write_varint(buf, keys.len() as u64);
+                for key in &keys {
+                    write_varint(buf, strings.idx(key));
+                }
+                for item in array {
+                    for item in item.as_object().unwrap().values() {
+                        write_value(buf, item, strings);
+                    }
+                }
+                return;
+            }
+
+            buf.push(TAG_ARRAY);
+            write_varint(buf, array.len() as u64);
+            for item in array {
+                write_value(buf, item, strings);
+            }
+        }
+        Value::Object(object) => {
+            buf.push(TAG_OBJECT);
+            write_varint(buf, object.len() as u64);
+            for (key, item) in object {
+                write_varint(buf, strings.idx(key));
+                write_value(buf, item, strings);
+            }
+        }
+    }
+}
+
+fn write_number(buf: &mut Vec<u8>, num: &Number) {
+    if let Some(int) = num.as_i64() {
+        buf.push(TAG_INT);
+        write_varint(buf, ((int << 1) ^ (int >> 63)) as u64);
+        return;
+    }
+
+    // armature floats are all f32, so they can almost always be halved
+    let float = num.as_f64().unwrap();
+    if float as f32 as f64 == float {
+        buf.push(TAG_F32);
+        buf.extend_from_slice(&(float as f32).to_le_bytes());
+    } else {
+        buf.push(TAG_F64);
+        buf.extend_from_slice(&float.to_le_bytes());
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() - self.pos {
+            return Err(format!("unexpected end of file at byte {}", self.pos));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.take(1)?[0];
+            if shift >= 64 {
+                return Err(format!("invalid varint at byte {}", self.pos - 1));
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// A count of items that take at least `min_size` bytes each, so that counts
+    /// larger than what's left of the file are rejected before allocating anything.
+    fn count(&mut self, min_size: usize) -> Result<usize, String> {
+        let count = self.varint()?;
+        let left = (self.bytes.len() - self.pos) as u64;
+        if count.saturating_mul(min_size as u64) > left {
+            return Err(format!("invalid count {} at byte {}", count, self.pos));
+        }
+        Ok(count as usize)
+    }
+
+    fn string(&mut self, strings: &[String]) -> Result<String, String> {
+        let idx = self.varint()? as usize;
+        match strings.get(idx) {
+            Some(str) => Ok(str.clone()),
+            None => Err(format!("invalid string index {} at byte {}", idx, self.pos)),
+        }
+    }
+
+    fn value(&mut self, strings: &[String]) -> Result<Value, String> {
+        let tag = self.take(1)?[0];
+        if matches!(tag, TAG_ARRAY | TAG_OBJECT | TAG_TABLE) {
+            if self.depth == MAX_DEPTH {
+                return Err(format!("nested too deeply at byte {}", self.pos - 1));
+            }
+            self.depth += 1;
+        }
+        let value = match tag {
+            TAG_NULL => Value::Null,
+            TAG_FALSE => Value::Bool(false),
+            TAG_TRUE => Value::Bool(true),
+            TAG_INT => {
+                let zigzag = self.varint()?;
+                Value::from((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64))
+            }
+            TAG_F32 => {
+                let float = f32::from_le_bytes(self.take(4)?.try_into().unwrap());
+                Value::from(float as f64)
+            }
+            TAG_F64 => Value::from(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            TAG_STR => Value::String(self.string(strings)?),
+            TAG_ARRAY => {
+                // every value has at least a tag
+                let len = self.count(1)?;
+                let mut array = vec![];
+                for _ in 0..len {
+                    array.push(self.value(strings)?);
+                }
+                Value::Array(array)
+            }
+            TAG_OBJECT => {
+                // key and tag
+                let len = self.count(2)?;
+                let mut object = Map::new();
+                for _ in 0..len {
+                    let key = self.string(strings)?;
+                    object.insert(key, self.value(strings)?);
+                }
+                Value::Object(object)
+            }
+            TAG_TABLE => {
+                let rows = self.varint()?;
+                let cols = self.count(1)?;
+                // rows of no columns would take no bytes at all
+                if rows > 0 && cols == 0 {
+                    return Err(format!("table without columns at byte {}", self.pos));
+                }
+                let left = (self.bytes.len() - self.pos) as u64;
+                if rows.saturating_mul(cols as u64) > left {
+                    return Err(format!("invalid count {} at byte {}", rows, self.pos));
+                }
+                let mut keys = vec![];
+                for _ in 0..cols {
+                    keys.push(self.string(strings)?);
+                }
+                let mut array = vec![];
+                for _ in 0..rows {
+                    let mut object = Map::new();
+                    for key in &keys {
+                        object.insert(key.clone(), self.value(strings)?);
+                    }
+                    array.push(Value::Object(object));
+                }
+                Value::Array(array)
+            }
+            _ => return Err(format!("invalid tag {} at byte {}", tag, self.pos - 1)),
+        };
+        if matches!(tag, TAG_ARRAY | TAG_OBJECT | TAG_TABLE) {
+            self.depth -= 1;
+        }
+        Ok(value)
+    }
+}
@@ -240,6 +240,18 @@ pub fn draw(
     .response
     .on_disabled_hover_text(str_cant_edit);
 
+    ui.horizontal(|ui| {
+        ui.label(shared_ui.loc("bone_panel.root_motion"))
+            .on_hover_text(shared_ui.loc("bone_panel.root_motion_desc"));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let mut checked = bone.root_motion;
+            ui.checkbox(&mut checked, "".into_atoms());
+            if checked != bone.root_motion {
+                events.toggle_root_motion(if checked { 1 } else { 0 });
+            }
+        });
+    });
+
     // show 'IK root bone' button if this is a target bone
     let bones = &mut armature.bones.iter();
     let is_target_of = bones.position(|b| b.ik_family_id != -1 && b.ik_target_id == bone.id);
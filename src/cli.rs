@@ -0,0 +1,427 @@
+//! Command-line subcommands, for batch operations without opening the window.
+
+use crate::*;
+use std::fs::File;
+use std::io::Read;
+
+pub const USAGE: &str = "usage:
+  skelform <file.skf>                        open file in the editor
+  skelform validate <file.skf>...            report errors, repairs and warnings
+  skelform convert <old.skf> <new.skf>       upgrade to the current format
+  skelform info <file.skf>                   print bones, animations and styles as JSON
+  skelform export <file.skf> <out.skf> [armature options]
+  skelform export <file.skf> <out.zip> --spritesheets|--sequences [image options]
+  skelform export <file.skf> <out.zip> --gif|--apng|--webp [image options]
+
+armature options:
+  --bake-ik                  bake inverse kinematics into animations
+  --exclude-ik               remove IK data after baking (with --bake-ik)
+  --binary                   save armature.bin instead of armature.json
+  --spine                    save a Spine skeleton (skeleton.json + atlas) zip instead
+  --dragonbones              save a DragonBones skeleton (_ske.json + _tex.json) zip instead
+  --godot                    save a Godot 4 scene (.tscn with a Skeleton2D) zip instead
+  --img-format <fmt>         atlas image format: png, jpg, webp or webp-reduced-colors
+  --premultiplied            premultiply atlas colors by alpha (SkelForm and Spine only)
+  --clear-color <r,g,b>      color to clear JPG atlases with (0-255)
+  --padding <x,y>            padding between textures in atlases
+  --atlas-size <w,h>         largest atlas size (default: 2048,2048)
+  --power-of-two             round atlas sizes up to powers of two
+  --non-square               allow atlases of different width and height
+  --extrude <n>              repeat texture edges by n pixels in atlases
+  --trim                     crop transparent edges off textures in atlases
+  --rotate                   allow textures to be turned 90° in atlases
+
+image options (rendered on the CPU):
+  --sprite-size <w,h>        size of each frame (default: 128,128)
+  --sprites-per-row <n>      frames per spritesheet row (default: 4)
+  --packed                   trim, dedupe and pack spritesheet frames, with a JSON sidecar
+  --fps <n>                  frame rate to resample to (default: each animation's)
+  --cycles <n>               times to play each animation (default: 1)
+  --global-bounds            use the same boundaries for every animation
+  --anims <name,name>        animations to export (default: all)
+  --plays <n>                times animated images play, 0 = forever (default: 1 if the
+                             animation plays once, otherwise 0)";
+
+/// Run the subcommand in `args`, if there is one.
+///
+/// Returns the exit code, or `None` if the editor should open as usual.
+pub fn run(args: &[String]) -> Option<i32> {
+    let result = match args.get(1).map(|arg| arg.as_str()) {
+        Some("validate") => validate(&args[2..]),
+        Some("convert") => export(&args[2..], false),
+        Some("info") => info(&args[2..]),
+        Some("export") => export(&args[2..], true),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => return None,
+    };
+
+    match result {
+        Ok(_) => Some(0),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            Some(1)
+        }
+    }
+}
+
+/// Import an .skf file the same way the editor does (upgrading and repairing it), but headlessly.
+fn load(path: &str) -> Result<Shared, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut shared = Shared::default();
+    let bytes = include_bytes!("../assets/i18n/en.json").as_slice();
+    shared
+        .ui
+        .init_default_lang(serde_json::from_slice(bytes).unwrap());
+    shared.ui.use_fallback = true;
+
+    utils::import(file, &mut shared, None, None, None, None);
+    if !shared.ui.custom_error.is_empty() {
+        return Err(format!("{}: {}", path, shared.ui.custom_error));
+    }
+
+    Ok(shared)
+}
+
+fn validate(args: &[String]) -> Result<(), String> {
+    if args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut needs_repair = false;
+    let mut failed = false;
+    for path in args {
+        // every file is reported, even after one fails to load
+        let shared = match load(path) {
+            Ok(shared) => shared,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                failed = true;
+                continue;
+            }
+        };
+        let armature = &shared.armature;
+        let warnings = warnings::check_warnings(armature);
+
+        for repair in &shared.ui.repairs {
+            let str = warnings::warning_text(repair, &shared.ui, armature);
+            println!("{}: repaired: {}", path, str);
+        }
+        for warning in &warnings {
+            let str = warnings::warning_text(warning, &shared.ui, armature);
+            println!("{}: warning: {}", path, str);
+        }
+        if shared.ui.repairs.is_empty() && warnings.is_empty() {
+            println!("{}: ok", path);
+        }
+
+        needs_repair |= !shared.ui.repairs.is_empty();
+    }
+
+    if failed {
+        return Err("some files could not be loaded".to_string());
+    }
+    if needs_repair {
+        let str = "some files needed repairs; run 'skelform convert' to save them";
+        return Err(str.to_string());
+    }
+    Ok(())
+}
+
+fn info(args: &[String]) -> Result<(), String> {
+    if args.len() != 1 {
+        return Err(USAGE.to_string());
+    }
+    let shared = load(&args[0])?;
+    let armature = &shared.armature;
+
+    let bones: Vec<serde_json::Value> = armature
+        .bones
+        .iter()
+        .map(|bone| {
+            serde_json::json!({
+                "id": bone.id,
+                "name": bone.name,
+                "parent_id": bone.parent_id,
+                "tex": bone.tex,
+            })
+        })
+        .collect();
+    let animations: Vec<serde_json::Value> = armature
+        .animations
+        .iter()
+        .map(|anim| {
+            serde_json::json!({
+                "name": anim.name,
+                "fps": anim.fps,
+                "frames": anim.len_frames(),
+                "loop_mode": anim.loop_mode.to_string(),
+                "keyframes": anim.keyframes.len(),
+                "events": anim.events.len(),
+            })
+        })
+        .collect();
+    let styles: Vec<serde_json::Value> = armature
+        .styles
+        .iter()
+        .map(|style| {
+            let textures: Vec<&String> = style.textures.iter().map(|t| &t.name).collect();
+            serde_json::json!({ "name": style.name, "textures": textures })
+        })
+        .collect();
+
+    let info = serde_json::json!({
+        "bones": bones,
+        "animations": animations,
+        "styles": styles,
+    });
+    println!("{}", serde_json::to_string_pretty(&info).unwrap());
+    Ok(())
+}
+
+/// Save a file with the current format, or export its animations as images.
+/// `convert` is `export` without options.
+fn export(args: &[String], allow_options: bool) -> Result<(), String> {
+    if args.len() < 2 || (!allow_options && args.len() != 2) {
+        return Err(USAGE.to_string());
+    }
+    let (src, dest) = (&args[0], &args[1]);
+
+    let mut shared = load(src)?;
+    let mut edit_mode = EditMode::default();
+    let mut export_images = false;
+
+    // same defaults as the export modal, with atlases packed like the file's
+    edit_mode.export_atlas = shared.edit_mode.export_atlas.clone();
+    shared.ui.sprite_size = Vec2::new(128., 128.);
+    shared.ui.sprites_per_row = 4;
+    shared.ui.anim_cycles = 1;
+    shared.ui.exporting_anims = vec![true; shared.armature.animations.len()];
+    let anims = shared.armature.animations.iter();
+    shared.ui.exporting_loops = anims
+        .map(|a| (a.loop_mode == LoopMode::Once) as u16)
+        .collect();
+
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        let mut value = || match options.next() {
+            Some(value) => Ok(value.clone()),
+            None => Err(format!("missing value for {}", option)),
+        };
+        let invalid = |value: &str| format!("invalid value for {}: '{}'", option, value);
+        let numbers = |value: &str| -> Vec<f32> {
+            let nums = value.split(',').map(|n| n.trim().parse::<f32>());
+            nums.filter_map(|n| n.ok()).collect()
+        };
+
+        match option.as_str() {
+            "--bake-ik" => edit_mode.export_bake_ik = true,
+            "--exclude-ik" => edit_mode.export_exclude_ik = true,
+            "--binary" => edit_mode.export_binary = true,
+            "--spine" => edit_mode.export_format = ExportArmatureFormat::Spine,
+            "--dragonbones" => edit_mode.export_format = ExportArmatureFormat::DragonBones,
+            "--godot" => edit_mode.export_format = ExportArmatureFormat::Godot,
+            "--img-format" => {
+                edit_mode.export_img_format = match value()?.to_lowercase().as_str() {
+                    "png" => ExportImgFormat::PNG,
+                    "jpg" | "jpeg" => ExportImgFormat::JPG,
+                    "webp" => ExportImgFormat::Webp,
+                    "webp-reduced-colors" => ExportImgFormat::WebpReducedColors,
+                    other => return Err(invalid(other)),
+                }
+            }
+            "--premultiplied" => edit_mode.export_premultiplied = true,
+            "--clear-color" => {
+                let value = value()?;
+                let rgb = numbers(&value);
+                if rgb.len() != 3 {
+                    return Err(invalid(&value));
+                }
+                let [r, g, b] = [rgb[0] as u8, rgb[1] as u8, rgb[2] as u8];
+                edit_mode.export_clear_color = Color::new(r, g, b, 255);
+            }
+            "--padding" => {
+                let value = value()?;
+                let xy = numbers(&value);
+                if xy.len() != 2 {
+                    return Err(invalid(&value));
+                }
+                edit_mode.export_tex_padding = Vec2::new(xy[0], xy[1]);
+            }
+            "--atlas-size" => {
+                let value = value()?;
+                let wh = numbers(&value);
+                if wh.len() != 2 || wh.iter().any(|n| *n < 1.) {
+                    return Err(invalid(&value));
+                }
+                edit_mode.export_atlas.max_size = Vec2I::new(wh[0] as i32, wh[1] as i32);
+            }
+            "--power-of-two" => edit_mode.export_atlas.power_of_two = true,
+            "--non-square" => edit_mode.export_atlas.square = false,
+            "--extrude" => {
+                let value = value()?;
+                match value.parse::<i32>() {
+                    Ok(extrude) if extrude >= 0 => edit_mode.export_atlas.extrude = extrude,
+                    _ => return Err(invalid(&value)),
+                }
+            }
+            "--trim" => edit_mode.export_atlas.trim = true,
+            "--rotate" => edit_mode.export_atlas.rotate = true,
+            "--spritesheets" => export_images = true,
+            "--packed" => {
+                export_images = true;
+                shared.ui.pack_spritesheets = true;
+            }
+            "--sequences" => {
+                export_images = true;
+                shared.ui.image_sequences = true;
+            }
+            "--gif" | "--apng" | "--webp" => {
+                export_images = true;
+                shared.ui.exporting_video_type = match option.as_str() {
+                    "--gif" => ExportVideoType::Gif,
+                    "--apng" => ExportVideoType::Apng,
+                    _ => ExportVideoType::Webp,
+                };
+            }
+            "--sprite-size" => {
+                let value = value()?;
+                let xy = numbers(&value);
+                if xy.len() != 2 || xy[0] < 1. || xy[1] < 1. {
+                    return Err(invalid(&value));
+                }
+                shared.ui.sprite_size = Vec2::new(xy[0].floor(), xy[1].floor());
+            }
+            "--sprites-per-row" => {
+                let value = value()?;
+                match value.parse::<i32>() {
+                    Ok(count) if count > 0 => shared.ui.sprites_per_row = count,
+                    _ => return Err(invalid(&value)),
+                }
+            }
+            "--fps" => {
+                let value = value()?;
+                match value.parse::<i32>() {
+                    Ok(fps) if fps >= 0 => shared.ui.export_fps = fps,
+                    _ => return Err(invalid(&value)),
+                }
+            }
+            "--cycles" => {
+                let value = value()?;
+                match value.parse::<i32>() {
+                    Ok(cycles) if cycles > 0 => shared.ui.anim_cycles = cycles,
+                    _ => return Err(invalid(&value)),
+                }
+            }
+            "--global-bounds" => shared.ui.export_global_bounds = true,
+            "--plays" => {
+                let value = value()?;
+                match value.parse::<u16>() {
+                    Ok(plays) => shared.ui.exporting_loops.fill(plays),
+                    _ => return Err(invalid(&value)),
+                }
+            }
+            "--anims" => {
+                let value = value()?;
+                let names: Vec<&str> = value.split(',').map(|n| n.trim()).collect();
+                for (a, anim) in shared.armature.animations.iter().enumerate() {
+                    shared.ui.exporting_anims[a] = names.contains(&anim.name.trim());
+                }
+                if !shared.ui.exporting_anims.contains(&true) {
+                    return Err(invalid(&value));
+                }
+            }
+            _ => return Err(format!("unknown option '{}'\n\n{}", option, USAGE)),
+        }
+    }
+
+    // written beside `dest` first, so that converting a file onto itself doesn't truncate it
+    // before it's read, and failed exports don't leave half a file behind
+    let part = format!("{}.part", dest);
+    let file = File::create(&part).map_err(|e| format!("{}: {}", part, e))?;
+    let written = write_export(file, src, dest, &mut shared, &edit_mode, export_images)
+        .and_then(|_| std::fs::rename(&part, dest).map_err(|e| format!("{}: {}", dest, e)));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&part);
+    }
+    written?;
+
+    for repair in &shared.ui.repairs {
+        let str = warnings::warning_text(repair, &shared.ui, &shared.armature);
+        println!("{}: repaired: {}", src, str);
+    }
+    Ok(())
+}
+
+// write the export or converted file into `file` (`dest` is only for errors)
+fn write_export(
+    file: File,
+    src: &str,
+    dest: &str,
+    shared: &mut Shared,
+    edit_mode: &EditMode,
+    export_images: bool,
+) -> Result<(), String> {
+    let write_err = |e: zip::result::ZipError| format!("{}: {}", dest, e);
+
+    if export_images {
+        // there's no GPU here, so frames are drawn by the CPU rasterizer
+        #[rustfmt::skip]
+        utils::render_spritesheets(&shared.armature, &mut shared.ui, &shared.camera, &shared.config, None, &shared.renderer);
+
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FullFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        if shared.ui.exporting_video_type.is_animated_image() {
+            for (name, data) in utils::encode_animated(&shared.armature, &shared.ui, None)? {
+                zip.start_file(name, options.clone()).map_err(write_err)?;
+                zip.write_all(&data)
+                    .map_err(|e| format!("{}: {}", dest, e))?;
+            }
+        } else {
+            utils::pack_sprites(&shared.armature, &mut shared.ui, None, &mut zip, &options)
+                .map_err(write_err)?;
+        }
+        zip.finish().map_err(write_err)?;
+    } else if edit_mode.export_format != ExportArmatureFormat::Skf {
+        let armature = &shared.armature;
+        let unsupported = match edit_mode.export_format {
+            ExportArmatureFormat::Spine => {
+                spine::write_spine(file, armature, edit_mode).map_err(write_err)?;
+                spine::UNSUPPORTED
+            }
+            ExportArmatureFormat::DragonBones => {
+                dragonbones::write_dragonbones(file, armature, edit_mode).map_err(write_err)?;
+                dragonbones::UNSUPPORTED
+            }
+            _ => {
+                godot::write_godot(file, armature, edit_mode).map_err(write_err)?;
+                godot::UNSUPPORTED
+            }
+        };
+        for key in export_common::lost_features(armature, unsupported) {
+            println!("{}: not exported: {}", src, shared.ui.loc(key));
+        }
+    } else {
+        // keep the original thumbnail, rather than re-rendering it
+        let mut thumbnail = vec![];
+        let src_file = File::open(src).map_err(|e| format!("{}: {}", src, e))?;
+        if let Ok(mut zip) = zip::ZipArchive::new(src_file) {
+            if let Ok(mut file) = zip.by_name("thumbnail.png") {
+                let _ = file.read_to_end(&mut thumbnail);
+            }
+        }
+        let thumbnail = if !thumbnail.is_empty() {
+            Some(thumbnail.as_slice())
+        } else {
+            None
+        };
+
+        #[rustfmt::skip]
+        utils::write_skf(file, &shared.armature, &shared.camera, edit_mode, thumbnail).map_err(write_err)?;
+    }
+    Ok(())
+}
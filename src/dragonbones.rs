@@ -0,0 +1,1352 @@
+//! DragonBones conversion (`_ske.json` skeletons with `_tex.json` atlases), in both directions.
+//!
+//! Armatures, slots, styles (as skins), meshes, weights, IK and animations with their tweens
+//! are converted. Anything DragonBones has no equivalent for is listed in `UNSUPPORTED`.
+
+use crate::export_common::{
+    active_tex, array, bone_length, draw_order, draw_order_offsets, float, floats, from_bone_space,
+    from_setup_relative, ik_families, ik_name, keyframe, num, propagated_hidden, region_name,
+    set_mesh, skin_tex_name, skins_to_styles, slot_bones, slot_tex_names, str_of, tex_at,
+    tex_frames, to_bone_space, to_setup_relative, track, unique_names, unweighted_pos, value_at,
+    vertex_weights, world_bones, Feature, SkinEntries,
+};
+use crate::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{Seek, Write};
+use std::str::FromStr;
+
+/// Reads one channel's value out of a DragonBones frame.
+type Channel<'a> = &'a dyn Fn(&Value) -> f32;
+/// A slot's name and display list, within one skin.
+type SlotDisplays<'a> = (&'a str, &'a [Value]);
+type TintChannel = fn(&TintColor) -> f32;
+
+/// DragonBones data version that exported skeletons are formatted for.
+pub const DB_VERSION: &str = "5.5";
+
+/// Features that DragonBones skeletons can't represent.
+///
+/// Of the animated IK settings, only constraints are keyed. Inactive styles are kept as extra
+/// skins, so they aren't lost.
+pub const UNSUPPORTED: &[Feature] = &[
+    Feature::Sway,
+    Feature::Physics,
+    Feature::PathBinds,
+    Feature::LongIk,
+    Feature::IkMimic,
+    Feature::IkKeys,
+    Feature::Additive,
+    Feature::PingPong,
+    Feature::Reverse,
+];
+
+/// Write a DragonBones skeleton (skeleton_ske.json, skeleton_tex.json and its image) into a zip.
+///
+/// Extra atlases are numbered (skeleton_tex_1.json, etc).
+pub fn write_dragonbones<W: Write + Seek>(
+    writer: W,
+    armature: &Armature,
+    edit_mode: &EditMode,
+) -> zip::result::ZipResult<W> {
+    let mut carmature = armature.clone();
+    let mut bufs = vec![];
+    let mut sizes = vec![];
+    if carmature.styles.iter().any(|s| !s.textures.is_empty()) {
+        (bufs, sizes) = utils::create_tex_sheet(&mut carmature, edit_mode);
+    }
+
+    let ext = format!(".{}", edit_mode.export_img_format.extension());
+
+    let mut zip = zip::ZipWriter::new(writer);
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    let skeleton = serde_json::to_string_pretty(&skeleton_json(&carmature)).unwrap();
+    zip.start_file("skeleton_ske.json", options)?;
+    zip.write_all(skeleton.as_bytes())?;
+    for (p, buf) in bufs.iter().enumerate() {
+        let stem = match p {
+            0 => "skeleton_tex".to_string(),
+            _ => format!("skeleton_tex_{}", p),
+        };
+        let image_path = format!("{}{}", stem, ext);
+        let atlas = tex_json(&carmature, p, &image_path, &sizes[p]);
+        zip.start_file(format!("{}.json", stem), options)?;
+        zip.write_all(serde_json::to_string_pretty(&atlas).unwrap().as_bytes())?;
+        zip.start_file(image_path, options)?;
+        zip.write_all(buf)?;
+    }
+
+    zip.finish()
+}
+
+/// DragonBones texture atlas of one page placed by `utils::create_tex_sheet`.
+///
+/// Sub-textures are named `style/texture`, like the regions of a Spine export.
+pub fn tex_json(armature: &Armature, page: usize, image_path: &str, size: &Vec2I) -> Value {
+    let mut sub_textures = vec![];
+    for style in &armature.styles {
+        for tex in &style.textures {
+            if tex.atlas_idx != page as i32 {
+                continue;
+            }
+            sub_textures.push(json!({
+                "name": region_name(style, tex),
+                "x": num(tex.offset.x),
+                "y": num(tex.offset.y),
+                "width": num(tex.size.x),
+                "height": num(tex.size.y),
+            }));
+        }
+    }
+    json!({
+        "name": "skeleton",
+        "imagePath": image_path,
+        "width": size.x,
+        "height": size.y,
+        "SubTexture": sub_textures,
+    })
+}
+
+/// DragonBones skeleton JSON of the armature, as its only armature.
+///
+/// Textures should already be placed in atlases, for display paths to line up with `tex_json`.
+pub fn skeleton_json(armature: &Armature) -> Value {
+    let names = unique_names(armature);
+
+    // DragonBones plays every animation at the armature's frame rate
+    let frame_rate = armature.animations.first().map_or(30, |a| a.fps.max(1));
+
+    // bones, under a generated root like in DragonBones' own armatures
+    let mut bones = vec![json!({ "name": "root" })];
+    for bone in &armature.bones {
+        let mut json = json!({
+            "name": names[&bone.id],
+            "parent": "root",
+            "length": num(bone_length(armature, bone)),
+            "transform": transform(bone.pos, bone.rot, bone.scale),
+        });
+        if let Some(parent) = names.get(&bone.parent_id) {
+            json["parent"] = json!(parent);
+        }
+        bones.push(json);
+    }
+
+    // slots, in draw order. The setup texture is always a slot's first display
+    let hiddens = propagated_hidden(armature);
+    let slot_bones = slot_bones(armature);
+    let mut slots = vec![];
+    for bone in &slot_bones {
+        let shown = !hiddens[&bone.id] && slot_displays(armature, bone).contains(&bone.tex);
+        let mut json = json!({
+            "name": names[&bone.id],
+            "parent": names[&bone.id],
+            "displayIndex": if shown { 0 } else { -1 },
+        });
+        if bone.tint != TintColor::new(1., 1., 1., 1.) {
+            json["color"] = color(&bone.tint);
+        }
+        slots.push(json);
+    }
+
+    // inverse kinematics (DragonBones can only bend 1 or 2 bones towards a target)
+    let mut iks = vec![];
+    for joints in ik_families(armature) {
+        let target = names.get(&joints[0].ik_target_id);
+        let skipped = joints[0].ik_mode == InverseKinematicsMode::Skip;
+        if target.is_none() || joints.len() < 2 || skipped {
+            continue;
+        }
+        let bent = &joints[..joints.len().min(3) - 1];
+        let mut json = json!({
+            "name": ik_name(&joints),
+            "bone": names[&bent.last().unwrap().id],
+            "target": target.unwrap(),
+            "chain": bent.len() - 1,
+        });
+        set_ik(&mut json, &joints[0].ik_constraint);
+        iks.push(json);
+    }
+
+    // styles as skins, with the active textures in the default one
+    let mut skins = vec![skin(armature, "default", None, &slot_bones, &names)];
+    for style in &armature.styles {
+        skins.push(skin(
+            armature,
+            &style.name,
+            Some(style),
+            &slot_bones,
+            &names,
+        ));
+    }
+
+    let mut animations = vec![];
+    for a in 0..armature.animations.len() {
+        animations.push(animation(armature, a, frame_rate, &names, &slot_bones));
+    }
+    let default_actions: Vec<Value> = armature
+        .animations
+        .first()
+        .map(|anim| json!({ "gotoAndPlay": anim.name }))
+        .into_iter()
+        .collect();
+
+    json!({
+        "frameRate": frame_rate,
+        "name": "skeleton",
+        "version": DB_VERSION,
+        "compatibleVersion": DB_VERSION,
+        "armature": [{
+            "type": "Armature",
+            "frameRate": frame_rate,
+            "name": "Armature",
+            "bone": bones,
+            "slot": slots,
+            "ik": iks,
+            "skin": skins,
+            "animation": animations,
+            "defaultActions": default_actions,
+        }],
+    })
+}
+
+// displays are picked by index, so every skin lists the same textures for a slot (taking the
+// active one where a style doesn't have it). Style skins only have the slots they change
+fn skin(
+    armature: &Armature,
+    name: &str,
+    style: Option<&Style>,
+    slot_bones: &[&Bone],
+    names: &HashMap<i32, String>,
+) -> Value {
+    let mut slots = vec![];
+    for bone in slot_bones {
+        let tex_names = slot_displays(armature, bone);
+        let own = |tex_name: &String| {
+            style.and_then(|style| {
+                let tex = style.textures.iter().find(|t| t.name == *tex_name);
+                tex.map(|tex| (style, tex))
+            })
+        };
+        if style.is_some() && !tex_names.iter().any(|name| own(name).is_some()) {
+            continue;
+        }
+        let mut displays = vec![];
+        for tex_name in &tex_names {
+            let own = own(tex_name);
+            let (style, tex) = own.or_else(|| active_tex(armature, tex_name)).unwrap();
+            displays.push(display(armature, bone, tex_name, style, tex));
+        }
+        if !displays.is_empty() {
+            slots.push(json!({ "name": names[&bone.id], "display": displays }));
+        }
+    }
+    json!({ "name": name, "slot": slots })
+}
+
+fn display(armature: &Armature, bone: &Bone, name: &str, style: &Style, tex: &Texture) -> Value {
+    let size = tex.size;
+    let pivot = size * bone.pivot_pos;
+
+    // untouched rects are plain images, placed by their center
+    if !bone.verts_edited && bone.binds.is_empty() {
+        return json!({
+            "type": "image",
+            "name": name,
+            "path": region_name(style, tex),
+            "transform": transform(pivot, bone.pivot_rot, bone.pivot_scale),
+        });
+    }
+
+    let local =
+        |vert: &Vertex| utils::rotate(&(vert.pos * bone.pivot_scale), bone.pivot_rot) + pivot;
+    let mut vertices = vec![];
+    let mut uvs = vec![];
+    for vert in &bone.vertices {
+        let pos = local(vert);
+        vertices.push(num(pos.x));
+        vertices.push(flip(pos.y));
+        uvs.push(num(vert.uv.x));
+        uvs.push(num(vert.uv.y));
+    }
+    let mut json = json!({
+        "type": "mesh",
+        "name": name,
+        "path": region_name(style, tex),
+        "vertices": vertices,
+        "uvs": uvs,
+        "triangles": bone.indices,
+        "width": num(size.x),
+        "height": num(size.y),
+    });
+
+    let bound = bone
+        .binds
+        .iter()
+        .any(|bind| !bind.is_path && armature.bones.iter().any(|b| b.id == bind.bone_id));
+    if !bound {
+        return json;
+    }
+
+    // vertices have the same local position under every bone they're bound to, so every
+    // pose is the identity
+    let identity = [1, 0, 0, 1, 0, 0];
+    let mut weights = vec![];
+    let mut posed: Vec<usize> = vec![];
+    for vert in &bone.vertices {
+        let vert_weights = vertex_weights(armature, bone, vert);
+        weights.push(json!(vert_weights.len()));
+        for (idx, weight) in vert_weights {
+            // DragonBones bones are offset by the generated root
+            weights.push(json!(idx + 1));
+            weights.push(num(weight));
+            if !posed.contains(&(idx + 1)) {
+                posed.push(idx + 1);
+            }
+        }
+    }
+    posed.sort();
+    let mut bone_pose = vec![];
+    for idx in posed {
+        bone_pose.push(json!(idx));
+        bone_pose.extend(identity.iter().map(|v| json!(v)));
+    }
+    json["weights"] = json!(weights);
+    json["slotPose"] = json!(identity);
+    json["bonePose"] = json!(bone_pose);
+    json
+}
+
+fn animation(
+    armature: &Armature,
+    anim_idx: usize,
+    frame_rate: i32,
+    names: &HashMap<i32, String>,
+    slot_bones: &[&Bone],
+) -> Value {
+    let anim = &armature.animations[anim_idx];
+    let rate = frame_rate as f32 / anim.fps.max(1) as f32;
+    let to_db = |frame: i32| (frame as f32 * rate).round() as i32;
+    let duration = to_db(anim.len_frames());
+    type AE = AnimElement;
+
+    // transforms are relative to the setup pose, like in Spine
+    let mut bones = vec![];
+    for bone in &armature.bones {
+        let mut json = json!({ "name": names[&bone.id] });
+        let value = |element: AE, frame: i32| {
+            let setup = match element {
+                AE::PositionX => bone.pos.x,
+                AE::PositionY => bone.pos.y,
+                AE::Rotation => bone.rot,
+                AE::ScaleX => bone.scale.x,
+                _ => bone.scale.y,
+            };
+            #[rustfmt::skip]
+            let value = armature.interpolate_keyframes(anim_idx, bone.id, element.clone(), setup, frame as f32);
+            to_setup_relative(bone, &element, value)
+        };
+
+        let frames = tween_frames(anim, bone.id, &[AE::PositionX, AE::PositionY]);
+        let keys = frames.iter().map(|(frame, tween)| {
+            let mut key = json!({
+                "x": num(value(AE::PositionX, *frame)),
+                "y": flip(value(AE::PositionY, *frame)),
+            });
+            set_tween(&mut key, tween);
+            (to_db(*frame), key)
+        });
+        insert_timeline(&mut json, "translateFrame", keys.collect(), duration);
+
+        // DragonBones turns the short way, unless a frame says which way to turn to the next
+        let frames = tween_frames(anim, bone.id, &[AE::Rotation]);
+        let angles: Vec<f32> = frames
+            .iter()
+            .map(|(frame, _)| 0. - value(AE::Rotation, *frame))
+            .collect();
+        let mut keys = vec![];
+        for (f, (frame, tween)) in frames.iter().enumerate() {
+            let mut key = json!({ "rotate": num(angles[f]) });
+            set_tween(&mut key, tween);
+            let turn = angles.get(f + 1).map_or(0., |next| next - angles[f]);
+            if turn.abs() > 180. {
+                key["clockwise"] = json!(turn.signum() as i32);
+            }
+            keys.push((to_db(*frame), key));
+        }
+        insert_timeline(&mut json, "rotateFrame", keys, duration);
+
+        let frames = tween_frames(anim, bone.id, &[AE::ScaleX, AE::ScaleY]);
+        let keys = frames.iter().map(|(frame, tween)| {
+            let mut key = json!({
+                "x": num(value(AE::ScaleX, *frame)),
+                "y": num(value(AE::ScaleY, *frame)),
+            });
+            set_tween(&mut key, tween);
+            (to_db(*frame), key)
+        });
+        insert_timeline(&mut json, "scaleFrame", keys.collect(), duration);
+
+        if json.as_object().unwrap().len() > 1 {
+            bones.push(json);
+        }
+    }
+
+    let mut slots = vec![];
+    for bone in slot_bones {
+        let mut json = json!({ "name": names[&bone.id] });
+
+        // textures and visibility both decide the display, and -1 shows none
+        let displays = slot_displays(armature, bone);
+        let mut frames = tex_frames(armature, anim, bone);
+        if !frames.is_empty() && frames[0] != 0 {
+            frames.insert(0, 0);
+        }
+        let keys = frames.iter().map(|frame| {
+            let tex = tex_at(armature, anim, bone, *frame);
+            let idx = displays.iter().position(|d| *d == tex);
+            (
+                to_db(*frame),
+                json!({ "value": idx.map_or(-1, |i| i as i32) }),
+            )
+        });
+        insert_timeline(&mut json, "displayFrame", keys.collect(), duration);
+
+        let tints = [AE::TintR, AE::TintG, AE::TintB, AE::TintA];
+        let keys = tween_frames(anim, bone.id, &tints)
+            .into_iter()
+            .map(|(frame, tween)| {
+                let channel = |element: AE, setup: f32| {
+                    armature.interpolate_keyframes(anim_idx, bone.id, element, setup, frame as f32)
+                };
+                let tint = TintColor::new(
+                    channel(AE::TintR, bone.tint.r),
+                    channel(AE::TintG, bone.tint.g),
+                    channel(AE::TintB, bone.tint.b),
+                    channel(AE::TintA, bone.tint.a),
+                );
+                let mut key = json!({ "value": color(&tint) });
+                set_tween(&mut key, &tween);
+                (to_db(frame), key)
+            });
+        insert_timeline(&mut json, "colorFrame", keys.collect(), duration);
+
+        if json.as_object().unwrap().len() > 1 {
+            slots.push(json);
+        }
+    }
+
+    // zindex changes become draw order frames, as (slot index, offset) pairs
+    let mut frames = key_frames(anim, |kf| kf.element == AE::Zindex);
+    if !frames.is_empty() && frames[0] != 0 {
+        frames.insert(0, 0);
+    }
+    let keys = frames.iter().map(|frame| {
+        let offsets = draw_order_offsets(anim, slot_bones, *frame);
+        let pairs: Vec<i32> = offsets.iter().flat_map(|(s, o)| [*s as i32, *o]).collect();
+        (to_db(*frame), json!({ "zOrder": pairs }))
+    });
+    let z_order = timeline(keys.collect(), duration);
+
+    // IK constraints can only be turned off or bent the other way
+    let mut iks = vec![];
+    for joints in ik_families(armature) {
+        let start = joints[0];
+        let mut frames = key_frames(anim, |kf| {
+            kf.bone_id == start.id && kf.element == AE::IkConstraint
+        });
+        if frames.is_empty() {
+            continue;
+        }
+        if frames[0] != 0 {
+            frames.insert(0, 0);
+        }
+        let keys = frames.iter().map(|frame| {
+            let constraint = value_at(anim, start.id, &AE::IkConstraint, *frame)
+                .map_or(start.ik_constraint, |kf| {
+                    JointConstraint::from_str(&kf.value_str).unwrap_or_default()
+                });
+            let mut key = json!({});
+            set_ik(&mut key, &constraint);
+            (to_db(*frame), key)
+        });
+        let name = ik_name(&joints);
+        iks.push(json!({ "name": name, "frame": timeline(keys.collect(), duration) }));
+    }
+
+    // events are grouped into action frames
+    let mut event_frames: Vec<(i32, Value)> = vec![];
+    for event in &anim.events {
+        let mut json = json!({ "name": event.name });
+        if event.value != 0. {
+            json["floats"] = json!([num(event.value)]);
+        }
+        if !event.value_str.is_empty() {
+            json["strings"] = json!([event.value_str]);
+        }
+        let frame = to_db(event.frame);
+        match event_frames.iter_mut().find(|(f, _)| *f == frame) {
+            Some((_, key)) => key["events"].as_array_mut().unwrap().push(json),
+            None => event_frames.push((frame, json!({ "events": [json] }))),
+        }
+    }
+    event_frames.sort_by_key(|(frame, _)| *frame);
+    if !event_frames.is_empty() && event_frames[0].0 != 0 {
+        event_frames.insert(0, (0, json!({})));
+    }
+
+    let mut json = json!({
+        "name": anim.name,
+        "duration": duration,
+        "playTimes": if anim.loop_mode == LoopMode::Loop { 0 } else { 1 },
+    });
+    macro_rules! insert {
+        ($key:expr, $value:expr) => {
+            if !$value.is_empty() {
+                json[$key] = json!($value);
+            }
+        };
+    }
+    let events = timeline(event_frames, duration);
+    insert!("frame", events);
+    insert!("bone", bones);
+    insert!("slot", slots);
+    insert!("ik", iks);
+    if !z_order.is_empty() {
+        json["zOrder"] = json!({ "frame": z_order });
+    }
+    json
+}
+
+/// Frames of a timeline that combines several elements of a bone, from the first frame, with
+/// the handles of the tween to the next one (`None` to hold).
+///
+/// DragonBones keys x and y (and color channels) together, so segments where the elements'
+/// keyframes don't line up with the same curve are baked frame by frame.
+fn tween_frames(
+    anim: &Animation,
+    bone_id: i32,
+    elements: &[AnimElement],
+) -> Vec<(i32, Option<(Vec2, Vec2)>)> {
+    let tracks: Vec<Vec<&Keyframe>> = elements
+        .iter()
+        .map(|element| track(anim, bone_id, element))
+        .collect();
+    let mut frames: Vec<i32> = tracks.iter().flatten().map(|kf| kf.frame).collect();
+    if frames.is_empty() {
+        return vec![];
+    }
+    frames.push(0);
+    frames.sort();
+    frames.dedup();
+
+    let linear = utils::interp_preset(HandlePreset::Linear);
+    let mut tweens = vec![];
+    for (f, frame) in frames.iter().enumerate() {
+        let next = match frames.get(f + 1) {
+            Some(next) => *next,
+            None => {
+                tweens.push((*frame, None));
+                break;
+            }
+        };
+
+        // handles of the elements that change over this span
+        let mut handles = vec![];
+        let mut exact = true;
+        for kfs in &tracks {
+            let start = kfs.iter().rev().find(|kf| kf.frame <= *frame);
+            let end = kfs.iter().find(|kf| kf.frame >= next);
+            if let (Some(start), Some(end)) = (start, end) {
+                if start.value == end.value {
+                    continue;
+                }
+                exact &= start.frame == *frame && end.frame == next;
+                handles.push((end.start_handle, end.end_handle));
+            }
+        }
+
+        if !exact || handles.windows(2).any(|h| h[0] != h[1]) {
+            tweens.extend((*frame..next).map(|i| (i, Some(linear))));
+        } else {
+            let stepped = handles.first().is_none_or(|h| h.0.y == 999.);
+            tweens.push((*frame, handles.first().copied().filter(|_| !stepped)));
+        }
+    }
+    tweens
+}
+
+// DragonBones eases with a preset or a cubic curve, from the frame that starts the tween
+fn set_tween(key: &mut Value, tween: &Option<(Vec2, Vec2)>) {
+    match tween {
+        None => {}
+        Some(handles) if *handles == utils::interp_preset(HandlePreset::Linear) => {
+            key["tweenEasing"] = json!(0);
+        }
+        Some((start, end)) => {
+            key["curve"] = json!([num(start.x), num(start.y), num(end.x), num(end.y)]);
+        }
+    }
+}
+
+// y is flipped, so DragonBones bends clockwise on screen when positive
+fn set_ik(json: &mut Value, constraint: &JointConstraint) {
+    json["bendPositive"] = json!(*constraint == JointConstraint::Clockwise);
+    json["weight"] = json!(if *constraint == JointConstraint::Skip {
+        0
+    } else {
+        1
+    });
+}
+
+// frames (by the armature's frame rate) with their duration set from when the next one starts.
+// Keys that land on the same frame after resampling are left to the last one
+fn timeline(keys: Vec<(i32, Value)>, duration: i32) -> Vec<Value> {
+    let mut frames = vec![];
+    for (k, (frame, key)) in keys.iter().enumerate() {
+        let next = keys.get(k + 1).map_or(duration.max(*frame), |n| n.0);
+        if next == *frame && k + 1 < keys.len() {
+            continue;
+        }
+        let mut key = key.clone();
+        key["duration"] = json!(next - frame);
+        frames.push(key);
+    }
+    frames
+}
+
+fn insert_timeline(json: &mut Value, name: &str, keys: Vec<(i32, Value)>, duration: i32) {
+    if !keys.is_empty() {
+        json[name] = json!(timeline(keys, duration));
+    }
+}
+
+// frames with keyframes that match a filter, in order
+fn key_frames(anim: &Animation, filter: impl Fn(&Keyframe) -> bool) -> Vec<i32> {
+    let mut frames: Vec<i32> = anim
+        .keyframes
+        .iter()
+        .filter(|kf| filter(kf))
+        .map(|kf| kf.frame)
+        .collect();
+    frames.sort();
+    frames.dedup();
+    frames
+}
+
+// textures a slot can show, by display index
+fn slot_displays(armature: &Armature, bone: &Bone) -> Vec<String> {
+    let names = slot_tex_names(armature, bone);
+    let resolved = |name: &String| active_tex(armature, name).is_some();
+    names.into_iter().filter(resolved).collect()
+}
+
+// DragonBones is y-down, so y and angles are flipped
+fn transform(pos: Vec2, rot: f32, scale: Vec2) -> Value {
+    let angle = flip(rot.to_degrees());
+    json!({
+        "x": num(pos.x),
+        "y": flip(pos.y),
+        "skX": angle,
+        "skY": angle,
+        "scX": num(scale.x),
+        "scY": num(scale.y),
+    })
+}
+
+// negated (from 0, so zeros don't turn into -0)
+fn flip(value: f32) -> Value {
+    num(0. - value)
+}
+
+// color multipliers, as percentages
+fn color(tint: &TintColor) -> Value {
+    let pct = |c: f32| (c.clamp(0., 1.) * 100.).round() as i32;
+    json!({ "aM": pct(tint.a), "rM": pct(tint.r), "gM": pct(tint.g), "bM": pct(tint.b) })
+}
+
+/// Armature from a DragonBones skeleton (`_ske.json`) and its texture atlases (`_tex.json`).
+///
+/// `load_page` returns an atlas' image by its `imagePath`. Only the first armature is read, and
+/// features without a SkelForm equivalent (skew, deforms, nested armatures, etc) are skipped.
+pub fn read_dragonbones(
+    skeleton: &str,
+    atlases: &[String],
+    load_page: &mut dyn FnMut(&str) -> Option<image::DynamicImage>,
+) -> Result<Armature, SkfError> {
+    let json = parse(skeleton, "skeleton JSON")?;
+    let mut atlas_jsons = vec![];
+    for atlas in atlases {
+        atlas_jsons.push(parse(atlas, "texture atlas JSON")?);
+    }
+    let db = &json["armature"][0];
+    if db.is_null() {
+        return Err(SkfError::MissingEntry("armature".to_string()));
+    }
+    let frame_rate = float(db, "frameRate", float(&json, "frameRate", 24.));
+    let mut armature = Armature::default();
+
+    // bones (with the root left out if it does nothing, since SkelForm doesn't need one)
+    let skip_root = is_empty_root(db);
+    let mut bone_ids: HashMap<String, i32> = HashMap::new();
+    let mut db_bones: Vec<i32> = vec![];
+    for (b, db_bone) in array(&db["bone"]).iter().enumerate() {
+        let name = str_of(db_bone, "name").to_string();
+        if b == 0 && skip_root {
+            db_bones.push(-1);
+            bone_ids.insert(name, -1);
+            continue;
+        }
+        let parent_id = match db_bone["parent"].as_str() {
+            Some(parent) => match bone_ids.get(parent) {
+                Some(id) => *id,
+                None => {
+                    let path = format!("armature[0].bone[{}].parent", b);
+                    let message = format!("There is no bone named '{}' before it.", parent);
+                    return Err(SkfError::BadValue { path, message });
+                }
+            },
+            None => -1,
+        };
+        armature.new_bone(-1);
+        let bone = armature.bones.last_mut().unwrap();
+        bone.name = name.clone();
+        bone.parent_id = parent_id;
+        (bone.pos, bone.rot, bone.scale) = from_transform(&db_bone["transform"]);
+        db_bones.push(bone.id);
+        bone_ids.insert(name, bone.id);
+    }
+
+    // IK constraints name the last bent bone, so its child ends the family
+    let mut ik_starts: HashMap<String, i32> = HashMap::new();
+    for ik in array(&db["ik"]) {
+        let last = bone_ids.get(str_of(ik, "bone")).copied().unwrap_or(-1);
+        let mut joints = vec![last];
+        let parent = armature.bones.iter().find(|b| b.id == last);
+        if float(ik, "chain", 0.) >= 1. && parent.is_some_and(|p| p.parent_id != -1) {
+            joints.insert(0, parent.unwrap().parent_id);
+        }
+        let target = bone_ids.get(str_of(ik, "target")).copied().unwrap_or(-1);
+        let end = armature
+            .bones
+            .iter()
+            .find(|b| last != -1 && b.parent_id == last);
+        let taken = |id: &i32| {
+            armature
+                .bones
+                .iter()
+                .any(|b| b.id == *id && b.ik_family_id != -1)
+        };
+        if end.is_none() || target == -1 || joints.iter().any(taken) {
+            continue;
+        }
+        joints.push(end.unwrap().id);
+        let family_id = ik_starts.len() as i32;
+        for id in &joints {
+            armature.find_bone_mut(*id).unwrap().ik_family_id = family_id;
+        }
+        let start = armature.find_bone_mut(joints[0]).unwrap();
+        start.ik_target_id = target;
+        start.ik_constraint = ik_constraint(ik);
+        ik_starts.insert(str_of(ik, "name").to_string(), joints[0]);
+    }
+
+    // slots become the bones that show textures, in draw order
+    let slots = array(&db["slot"]);
+    let mut slot_ids: HashMap<String, i32> = HashMap::new();
+    for (s, slot) in slots.iter().enumerate() {
+        let name = str_of(slot, "name");
+        let bone_name = str_of(slot, "parent");
+        let bone_id = bone_ids.get(bone_name).copied().unwrap_or(-1);
+        let shared_bone = slots
+            .iter()
+            .filter(|other| other["parent"] == slot["parent"])
+            .count()
+            > 1;
+        let id = if bone_id != -1 && name == bone_name && !shared_bone {
+            bone_id
+        } else {
+            // bones only show one texture, so other slots get a child bone each
+            armature.new_bone(-1);
+            let bone = armature.bones.last_mut().unwrap();
+            bone.name = name.to_string();
+            bone.parent_id = bone_id;
+            bone.id
+        };
+        let bone = armature.find_bone_mut(id).unwrap();
+        bone.zindex = s as i32;
+        if slot["color"].is_object() {
+            bone.tint = tint_of(&slot["color"]);
+        }
+        slot_ids.insert(name.to_string(), id);
+    }
+
+    // skins, as (name, slot name, displays)
+    let mut skins: Vec<(String, Vec<SlotDisplays>)> = vec![];
+    for skin in array(&db["skin"]) {
+        let name = match str_of(skin, "name") {
+            "" => "default",
+            name => name,
+        };
+        let slots = array(&skin["slot"])
+            .iter()
+            .map(|slot| (str_of(slot, "name"), array(&slot["display"])))
+            .collect();
+        skins.push((name.to_string(), slots));
+    }
+    let entries: Vec<SkinEntries> = skins
+        .iter()
+        .map(|(name, slots)| {
+            let mut entries = vec![];
+            for (slot, displays) in slots {
+                for display in displays.iter().filter(|d| is_textured(d)) {
+                    let key = str_of(display, "name");
+                    let path = display["path"].as_str().unwrap_or(key);
+                    entries.push((slot.to_string(), key.to_string(), path.to_string()));
+                }
+            }
+            (name.clone(), entries)
+        })
+        .collect();
+    let default = skins.iter().position(|(name, _)| name == "default");
+    let mut pages: Vec<Option<image::DynamicImage>> = vec![None; atlas_jsons.len()];
+    let mut image = |path: &str| sub_texture(&atlas_jsons, path, &mut pages, load_page);
+    let clashing = skins_to_styles(&mut armature, &entries, default, &mut image)?;
+    let tex_name = |slot: &str, key: &str| skin_tex_name(&clashing, slot, key);
+
+    // display lists of each slot, from the default skin (or the first that has the slot)
+    let mut displays: HashMap<&str, &[Value]> = HashMap::new();
+    let default_first = default.into_iter().chain(0..skins.len());
+    for (_, slots) in default_first.map(|s| &skins[s]) {
+        for (slot, slot_displays) in slots {
+            displays.entry(slot).or_insert(slot_displays);
+        }
+    }
+    let display_tex = |slot: &str, idx: i64| {
+        let display = displays.get(slot).and_then(|d| d.get(idx as usize));
+        match display {
+            Some(display) if idx >= 0 && is_textured(display) => {
+                tex_name(slot, str_of(display, "name"))
+            }
+            _ => String::new(),
+        }
+    };
+
+    // geometry comes from each slot's setup display, or its first textured one
+    let world = world_bones(&armature);
+    for slot in slots {
+        let name = str_of(slot, "name");
+        let setup = float(slot, "displayIndex", 0.) as i64;
+        let slot_displays = displays.get(name).copied().unwrap_or_default();
+        let d = match slot_displays.get(setup as usize) {
+            Some(display) if setup >= 0 && is_textured(display) => Some(setup as usize),
+            _ => slot_displays.iter().position(is_textured),
+        };
+        if d.is_none() {
+            continue;
+        }
+        let display = &slot_displays[d.unwrap()];
+        let tex = tex_name(name, str_of(display, "name"));
+        let size = armature
+            .styles
+            .iter()
+            .flat_map(|style| &style.textures)
+            .find(|t| t.name == tex)
+            .map(|t| t.size);
+        let bone = armature.find_bone_mut(slot_ids[name]).unwrap();
+        bone.tex = display_tex(name, setup);
+        if let Some(size) = size {
+            let path = format!("armature[0].skin.{}.display[{}]", name, d.unwrap());
+            set_geometry(bone, display, size, &db_bones, &world, &path)?;
+        }
+    }
+
+    let mut animations = vec![];
+    for db_anim in array(&db["animation"]) {
+        let mut anim = Animation {
+            name: str_of(db_anim, "name").to_string(),
+            id: animations.len() as i32,
+            fps: frame_rate as i32,
+            length: float(db_anim, "duration", 0.) as i32,
+            loop_mode: if float(db_anim, "playTimes", 1.) == 0. {
+                LoopMode::Loop
+            } else {
+                LoopMode::Once
+            },
+            ..Default::default()
+        };
+        type AE = AnimElement;
+
+        // transforms are relative to the setup pose, like in Spine. Older versions key them
+        // together in `frame`
+        for timelines in array(&db_anim["bone"]) {
+            let id = bone_ids.get(str_of(timelines, "name")).copied();
+            if id.is_none_or(|id| id == -1) {
+                continue;
+            }
+            let id = id.unwrap();
+            let setup = armature.bones.iter().find(|b| b.id == id).unwrap().clone();
+            let legacy =
+                |key: &Value, field: &str, default: f32| float(&key["transform"], field, default);
+            #[rustfmt::skip]
+            let channels: [(&str, AE, Channel); 8] = [
+                ("translateFrame", AE::PositionX, &|key| float(key, "x", 0.)),
+                ("translateFrame", AE::PositionY, &|key| 0. - float(key, "y", 0.)),
+                ("scaleFrame",     AE::ScaleX,    &|key| float(key, "x", 1.)),
+                ("scaleFrame",     AE::ScaleY,    &|key| float(key, "y", 1.)),
+                ("frame",          AE::PositionX, &|key| legacy(key, "x", 0.)),
+                ("frame",          AE::PositionY, &|key| 0. - legacy(key, "y", 0.)),
+                ("frame",          AE::ScaleX,    &|key| legacy(key, "scX", 1.)),
+                ("frame",          AE::ScaleY,    &|key| legacy(key, "scY", 1.)),
+            ];
+            for (timeline, element, raw) in channels {
+                let keys = array(&timelines[timeline]);
+                let values: Vec<f32> = keys
+                    .iter()
+                    .map(|key| from_setup_relative(&setup, &element, raw(key)))
+                    .collect();
+                anim.keyframes.extend(keyframes(keys, id, element, &values));
+            }
+
+            #[rustfmt::skip]
+            let rotations: [(&str, &str, Channel); 2] = [
+                ("rotateFrame", "clockwise",   &|key| float(key, "rotate", 0.)),
+                ("frame",       "tweenRotate", &|key| legacy(key, "skY", 0.)),
+            ];
+            for (timeline, turns, raw) in rotations {
+                let keys = array(&timelines[timeline]);
+                let values: Vec<f32> = unwrapped_angles(keys, raw, turns)
+                    .into_iter()
+                    .map(|angle| from_setup_relative(&setup, &AE::Rotation, 0. - angle))
+                    .collect();
+                anim.keyframes
+                    .extend(keyframes(keys, id, AE::Rotation, &values));
+            }
+        }
+
+        for timelines in array(&db_anim["slot"]) {
+            let slot_name = str_of(timelines, "name");
+            let id = match slot_ids.get(slot_name) {
+                Some(id) => *id,
+                None => continue,
+            };
+
+            // displays are stepped, and -1 is an empty texture
+            for timeline in ["displayFrame", "frame"] {
+                let mut frame = 0;
+                for key in array(&timelines[timeline]) {
+                    let idx = key["value"].as_i64().or(key["displayIndex"].as_i64());
+                    if timeline == "displayFrame" || idx.is_some() {
+                        let mut kf = keyframe(frame, id, AE::Texture);
+                        kf.value_str = display_tex(slot_name, idx.unwrap_or(0));
+                        anim.keyframes.push(kf);
+                    }
+                    frame += duration(key);
+                }
+            }
+
+            for timeline in ["colorFrame", "frame"] {
+                let keys = array(&timelines[timeline]);
+                if timeline == "frame" && keys.iter().all(|key| key["color"].is_null()) {
+                    continue;
+                }
+                let tints: Vec<TintColor> = keys
+                    .iter()
+                    .map(|key| tint_of(key.get("value").unwrap_or(&key["color"])))
+                    .collect();
+                #[rustfmt::skip]
+                let channels: [(AE, TintChannel); 4] = [
+                    (AE::TintR, |t| t.r), (AE::TintG, |t| t.g), (AE::TintB, |t| t.b), (AE::TintA, |t| t.a),
+                ];
+                for (element, channel) in channels {
+                    let values: Vec<f32> = tints.iter().map(channel).collect();
+                    anim.keyframes.extend(keyframes(keys, id, element, &values));
+                }
+            }
+        }
+
+        // draw order frames become zindex changes, for the slots that move
+        let mut orders: Vec<(i32, Vec<usize>)> = vec![];
+        let mut frame = 0;
+        for key in array(&db_anim["zOrder"]["frame"]) {
+            let pairs = floats(&key["zOrder"]);
+            let offsets: Vec<(usize, i64)> = pairs
+                .chunks(2)
+                .filter(|pair| pair.len() == 2)
+                .map(|pair| (pair[0] as usize, pair[1] as i64))
+                .collect();
+            orders.push((frame, draw_order(&offsets, slots.len())));
+            frame += duration(key);
+        }
+        for (s, slot) in slots.iter().enumerate() {
+            if orders.iter().all(|(_, order)| order[s] == s) {
+                continue;
+            }
+            for (frame, order) in &orders {
+                let mut kf = keyframe(*frame, slot_ids[str_of(slot, "name")], AE::Zindex);
+                kf.value = order[s] as f32;
+                anim.keyframes.push(kf);
+            }
+        }
+
+        for timeline in array(&db_anim["ik"]) {
+            if let Some(id) = ik_starts.get(str_of(timeline, "name")) {
+                let mut frame = 0;
+                for key in array(&timeline["frame"]) {
+                    let mut kf = keyframe(frame, *id, AE::IkConstraint);
+                    kf.value_str = ik_constraint(key).to_string();
+                    anim.keyframes.push(kf);
+                    frame += duration(key);
+                }
+            }
+        }
+
+        // events, or the single event of older versions
+        let mut frame = 0;
+        for key in array(&db_anim["frame"]) {
+            let mut events: Vec<&Value> = array(&key["events"]).iter().collect();
+            if key["event"].is_string() {
+                events.push(key);
+            }
+            for event in events {
+                let name = event["name"].as_str().or(event["event"].as_str());
+                let first = |field: &str| event[field][0].as_f64();
+                anim.events.push(AnimEvent {
+                    frame,
+                    name: name.unwrap_or_default().to_string(),
+                    value: first("floats").or(first("ints")).unwrap_or(0.) as f32,
+                    value_str: event["strings"][0].as_str().unwrap_or_default().to_string(),
+                });
+            }
+            frame += duration(key);
+        }
+
+        anim.sort_keyframes();
+        anim.sort_events();
+        animations.push(anim);
+    }
+    armature.animations = animations;
+
+    Ok(armature)
+}
+
+fn parse(text: &str, file: &str) -> Result<Value, SkfError> {
+    serde_json::from_str(text).map_err(|e| SkfError::Corrupt {
+        file: file.to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn duration(key: &Value) -> i32 {
+    float(key, "duration", 1.) as i32
+}
+
+// keyframes of one value of a DragonBones timeline, with each frame's tween kept in the next
+// keyframe (since SkelForm keeps a segment's handles in its last keyframe)
+fn keyframes(keys: &[Value], bone_id: i32, element: AnimElement, values: &[f32]) -> Vec<Keyframe> {
+    let mut kfs = vec![];
+    let mut frame = 0;
+    for (k, key) in keys.iter().enumerate() {
+        let mut kf = keyframe(frame, bone_id, element.clone());
+        kf.value = values[k];
+        if k > 0 {
+            (kf.start_handle, kf.end_handle, kf.handle_preset) = handles(&keys[k - 1]);
+        }
+        kfs.push(kf);
+        frame += duration(key);
+    }
+    kfs
+}
+
+// DragonBones' easing presets are quadratic, and only their direction is kept
+fn handles(key: &Value) -> (Vec2, Vec2, HandlePreset) {
+    let curve = floats(&key["curve"]);
+    if curve.len() >= 4 {
+        // multi-segment curves are simplified to their outer handles
+        let n = curve.len();
+        let (start, end) = (
+            Vec2::new(curve[0], curve[1]),
+            Vec2::new(curve[n - 2], curve[n - 1]),
+        );
+        return (start, end, HandlePreset::Custom);
+    }
+    let preset = match key["tweenEasing"].as_f64() {
+        None => HandlePreset::Snap,
+        Some(0.) => HandlePreset::Linear,
+        Some(easing) if easing < 0. => HandlePreset::SineIn,
+        Some(easing) if easing <= 1. => HandlePreset::SineOut,
+        Some(_) => HandlePreset::SineInOut,
+    };
+    let (start, end) = utils::interp_preset(preset.clone());
+    (start, end, preset)
+}
+
+// angles of a rotation timeline (in degrees) as DragonBones turns through them: the short way,
+// unless the previous frame sets how many times to turn clockwise (or counter-clockwise if
+// negative)
+fn unwrapped_angles(keys: &[Value], raw: &dyn Fn(&Value) -> f32, turns: &str) -> Vec<f32> {
+    let mut angles: Vec<f32> = vec![];
+    let mut prev_turns = 0.;
+    for key in keys {
+        let mut angle = raw(key);
+        if let Some(prev) = angles.last() {
+            if prev_turns == 0. {
+                let delta = (angle - prev + 180.) % 360.;
+                angle = prev
+                    + if delta > 0. {
+                        delta - 180.
+                    } else {
+                        delta + 180.
+                    };
+            } else {
+                let passed = match prev_turns > 0. {
+                    true => angle >= *prev,
+                    false => angle <= *prev,
+                };
+                if passed {
+                    prev_turns -= f32::signum(prev_turns);
+                }
+                angle += 360. * prev_turns;
+            }
+        }
+        prev_turns = float(key, turns, 0.);
+        angles.push(angle);
+    }
+    angles
+}
+
+fn ik_constraint(json: &Value) -> JointConstraint {
+    if float(json, "weight", 1.) == 0. {
+        JointConstraint::Skip
+    } else if json["bendPositive"].as_bool().unwrap_or(true) {
+        JointConstraint::Clockwise
+    } else {
+        JointConstraint::CounterClockwise
+    }
+}
+
+// position, rotation and scale of a y-down transform, as SkelForm's y-up ones
+fn from_transform(json: &Value) -> (Vec2, f32, Vec2) {
+    let pos = Vec2::new(float(json, "x", 0.), 0. - float(json, "y", 0.));
+    let angle = json["rotate"]
+        .as_f64()
+        .map_or(float(json, "skY", 0.), |r| r as f32);
+    let scale = Vec2::new(float(json, "scX", 1.), float(json, "scY", 1.));
+    (pos, -angle.to_radians(), scale)
+}
+
+fn tint_of(json: &Value) -> TintColor {
+    let channel = |key: &str| float(json, key, 100.) / 100.;
+    TintColor::new(channel("rM"), channel("gM"), channel("bM"), channel("aM"))
+}
+
+// `world` is the setup pose of every bone (see `world_bones`), and `path` where the display
+// is, for errors
+fn set_geometry(
+    bone: &mut Bone,
+    display: &Value,
+    size: Vec2,
+    db_bones: &[i32],
+    world: &[Bone],
+    path: &str,
+) -> Result<(), SkfError> {
+    if display["type"].as_str().unwrap_or("image") == "image" {
+        let (pos, rot, scale) = from_transform(&display["transform"]);
+
+        // images are placed by their pivot, and SkelForm places them by their center
+        let pivot = Vec2::new(
+            float(&display["pivot"], "x", 0.5),
+            float(&display["pivot"], "y", 0.5),
+        );
+        let center = Vec2::new((0.5 - pivot.x) * size.x, (pivot.y - 0.5) * size.y);
+        bone.pivot_pos = (pos + utils::rotate(&(center * scale), rot)) / size;
+        bone.pivot_rot = rot;
+        bone.pivot_scale = scale;
+        (bone.vertices, bone.indices) = renderer::create_tex_rect(&size);
+        return Ok(());
+    }
+
+    let bad_value = |field: &str, message: String| {
+        let path = format!("{}.{}", path, field);
+        SkfError::BadValue { path, message }
+    };
+    let raw = floats(&display["vertices"]);
+    let raw_weights = floats(&display["weights"]);
+    if !raw.len().is_multiple_of(2) {
+        let message = format!("{} coordinates do not make whole pairs.", raw.len());
+        return Err(bad_value("vertices", message));
+    }
+    let mut positions = vec![];
+    let mut weights: Vec<Vec<(i32, f32)>> = vec![];
+    if raw_weights.is_empty() {
+        positions = raw.chunks(2).map(|p| Vec2::new(p[0], 0. - p[1])).collect();
+    } else {
+        // weighted vertices are in the armature's space, and each bone's pose places them in
+        // its own space. They're rebuilt in setup pose and moved to where SkelForm's weighing
+        // places them
+        let slot_pose = matrix(&floats(&display["slotPose"]));
+        let mut bone_poses: HashMap<usize, [f32; 6]> = HashMap::new();
+        for pose in floats(&display["bonePose"]).chunks(7) {
+            if pose.len() == 7 {
+                bone_poses.insert(pose[0] as usize, matrix(&pose[1..]));
+            }
+        }
+        #[rustfmt::skip]
+        let identity = Bone { scale: Vec2::new(1., 1.), pivot_scale: Vec2::new(1., 1.), ..Default::default() };
+        let world_bone = |id: i32| world.iter().find(|b| b.id == id).unwrap_or(&identity);
+        let mesh = world_bone(bone.id);
+        let pivot = size * bone.pivot_pos;
+        let mut i = 0;
+        for pos in raw.chunks(2) {
+            let global = transform_point(&slot_pose, Vec2::new(pos[0], pos[1]));
+            let count = raw_weights.get(i).copied().unwrap_or(0.) as usize;
+            i += 1;
+            if raw_weights.len() < i + count * 2 {
+                let message = format!("Vertex {} is cut short.", positions.len());
+                return Err(bad_value("weights", message));
+            }
+            let mut setup_pos = Vec2::ZERO;
+            let mut binds = vec![];
+            let mut vert_weights = vec![];
+            for entry in raw_weights[i..i + count * 2].chunks(2) {
+                let idx = entry[0] as usize;
+                let id = match db_bones.get(idx) {
+                    Some(id) if entry[0] >= 0. => *id,
+                    _ => {
+                        let path = format!("{}.weights[{}]", path, i);
+                        let (idx, len) = (entry[0] as i32, db_bones.len());
+                        return Err(SkfError::OutOfRange { path, idx, len });
+                    }
+                };
+                let pose = bone_poses.get(&idx).copied().unwrap_or(IDENTITY);
+                let local = transform_point(&invert(&pose), global);
+                let bind = world_bone(id);
+                setup_pos += from_bone_space(bind, Vec2::new(local.x, 0. - local.y)) * entry[1];
+                binds.push((bind, entry[1]));
+                if id != -1 && id != bone.id {
+                    vert_weights.push((id, entry[1]));
+                }
+                i += 2;
+            }
+            let unbound = Vec2::new(global.x, 0. - global.y);
+            let pos = unweighted_pos(mesh, &binds, setup_pos, pivot);
+            positions.push(pos.unwrap_or_else(|| to_bone_space(mesh, unbound)));
+            weights.push(vert_weights);
+        }
+    }
+
+    let uvs = floats(&display["uvs"]);
+    let indices = floats(&display["triangles"]);
+    set_mesh(bone, &positions, &uvs, &indices, &weights, path)
+}
+
+// 2D affine matrices, as (a, b, c, d, tx, ty)
+const IDENTITY: [f32; 6] = [1., 0., 0., 1., 0., 0.];
+
+fn matrix(values: &[f32]) -> [f32; 6] {
+    values.try_into().unwrap_or(IDENTITY)
+}
+
+fn transform_point(m: &[f32; 6], point: Vec2) -> Vec2 {
+    Vec2::new(
+        m[0] * point.x + m[2] * point.y + m[4],
+        m[1] * point.x + m[3] * point.y + m[5],
+    )
+}
+
+fn invert(m: &[f32; 6]) -> [f32; 6] {
+    let det = m[0] * m[3] - m[1] * m[2];
+    if det == 0. {
+        return IDENTITY;
+    }
+    [
+        m[3] / det,
+        -m[1] / det,
+        -m[2] / det,
+        m[0] / det,
+        (m[2] * m[5] - m[3] * m[4]) / det,
+        (m[1] * m[4] - m[0] * m[5]) / det,
+    ]
+}
+
+// the first bone, if it's a root that has nothing bound, keyed or shown on it
+fn is_empty_root(db: &Value) -> bool {
+    let root = &db["bone"][0];
+    let name = str_of(root, "name");
+    let (pos, rot, scale) = from_transform(&root["transform"]);
+    let untransformed = pos == Vec2::ZERO && rot == 0. && scale == Vec2::new(1., 1.);
+    let weighted = array(&db["skin"])
+        .iter()
+        .flat_map(|skin| array(&skin["slot"]))
+        .flat_map(|slot| array(&slot["display"]))
+        .any(|display| {
+            // bone 0 only appears as a weight's bone index after a count
+            let weights = floats(&display["weights"]);
+            let mut i = 0;
+            while i < weights.len() {
+                let count = weights[i] as usize;
+                let entries = weights[i + 1..].chunks(2).take(count);
+                if entries.clone().any(|entry| entry[0] == 0.) {
+                    return true;
+                }
+                i += 1 + count * 2;
+            }
+            false
+        });
+    let used = array(&db["slot"]).iter().any(|slot| slot["parent"] == name)
+        || array(&db["ik"]).iter().any(|ik| ik["target"] == name)
+        || array(&db["animation"])
+            .iter()
+            .any(|anim| array(&anim["bone"]).iter().any(|b| b["name"] == name));
+    !name.is_empty() && root.get("parent").is_none() && untransformed && !used && !weighted
+}
+
+fn is_textured(display: &Value) -> bool {
+    let kind = display["type"].as_str().unwrap_or("image");
+    kind == "image" || kind == "mesh"
+}
+
+// image of a sub-texture, as it was before packing
+fn sub_texture(
+    atlases: &[Value],
+    name: &str,
+    pages: &mut [Option<image::DynamicImage>],
+    load_page: &mut dyn FnMut(&str) -> Option<image::DynamicImage>,
+) -> Result<image::DynamicImage, SkfError> {
+    let found = atlases.iter().enumerate().find_map(|(a, atlas)| {
+        let sub = array(&atlas["SubTexture"])
+            .iter()
+            .find(|s| s["name"] == name);
+        sub.map(|sub| (a, sub))
+    });
+    if found.is_none() {
+        return Err(SkfError::MissingEntry(format!("Sub-texture '{}'", name)));
+    }
+    let (a, sub) = found.unwrap();
+    if pages[a].is_none() {
+        let image_path = str_of(&atlases[a], "imagePath");
+        pages[a] = load_page(image_path);
+        if pages[a].is_none() {
+            return Err(SkfError::MissingFile(image_path.to_string()));
+        }
+    }
+    let page = pages[a].as_ref().unwrap();
+
+    // rotated sub-textures are packed 90 degrees clockwise
+    let [x, y, w, h] = ["x", "y", "width", "height"].map(|key| float(sub, key, 0.) as u32);
+    let mut image = match sub["rotated"].as_bool().unwrap_or(false) {
+        true => page.crop_imm(x, y, h, w).rotate270(),
+        false => page.crop_imm(x, y, w, h),
+    };
+
+    // add back the whitespace that was trimmed when packing
+    let frame = ["frameX", "frameY", "frameWidth", "frameHeight"].map(|key| float(sub, key, 0.));
+    let [frame_x, frame_y, frame_w, frame_h] = frame;
+    if frame_w > 0. && frame_h > 0. {
+        let mut canvas = image::DynamicImage::new_rgba8(frame_w as u32, frame_h as u32);
+        image::imageops::overlay(&mut canvas, &image, -frame_x as i64, -frame_y as i64);
+        image = canvas;
+    }
+    Ok(image)
+}
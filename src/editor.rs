@@ -1,6 +1,7 @@
 use crate::*;
 use armature_window::get_all_children;
 use image::DynamicImage;
+use renderer::sort_vertices;
 use spade::Triangulation;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -32,12 +33,14 @@ pub fn iterate_events(
         type E = Events;
         #[rustfmt::skip]
             match last_event {
-                E::NewBone | E::DragBone | E::DeleteBone | E::PasteBone | E::RaiseGlobalZindex => undo_states.new_undo_bones(&armature.bones),
+                E::NewBone | E::DragBone | E::DeleteBone | E::PasteBone | E::RaiseGlobalZindex | E::ToggleRootMotion => undo_states.new_undo_bones(&armature.bones),
                 E::NewAnimation | E::DeleteAnim => undo_states.new_undo_anims(&armature.animations),
                 E::DeleteSelectedTextures       => undo_states.new_undo_style(&armature.sel_style(&selections).unwrap()),
                 E::DeleteStyle | E::NewStyle    => undo_states.new_undo_styles(&armature.styles),
                 E::RenameStyle => if !ui.just_made_style { undo_states.new_undo_style(&armature.sel_style(&selections).unwrap()); ui.just_made_style = false }
-                E::DeleteSelectedKeyframes | E::DeleteKeyframeLine | E::SetKeyframeFrame | E::SetAllKeyframesFrame | E::PasteKeyframesOnFrame => {
+                E::DeleteSelectedKeyframes | E::DeleteKeyframeLine | E::SetKeyframeFrame | E::SetAllKeyframesFrame | E::PasteKeyframesOnFrame
+                | E::ToggleAnimAdditive | E::SetAnimLength | E::SetAnimLoopMode | E::ToggleBoneInAnimMask | E::NewAnimEvent | E::DeleteAnimEvent | E::DeleteAnimEventsByFrame
+                | E::RenameAnimEvent | E::SetAnimEventStr | E::SetAnimEventValue | E::SetAnimEventFrame | E::PasteAnimEventsOnFrame => {
                     undo_states.new_undo_anim(armature.sel_anim(&selections).unwrap())
                 }
                 E::ResetVertices | E::CenterBoneVerts | E::DeleteVertex | E::TraceBoneVerts | E::NewVertex | E::DeleteTriangle => {
@@ -68,6 +71,11 @@ pub fn iterate_events(
         edit_mode.export_tex_padding = Vec2::new(events.values[0], events.values[1]);
         events.events.remove(0);
         events.values.drain(0..=1);
+    } else if event == Events::SetExportAtlasMaxSize {
+        let size = Vec2I::new(events.values[0] as i32, events.values[1] as i32);
+        edit_mode.export_atlas.max_size = size;
+        events.events.remove(0);
+        events.values.drain(0..=1);
     } else if event == Events::EditVertexUV {
         let sel = &selections;
         let bone = armature.sel_bone_mut(sel).unwrap();
@@ -289,6 +297,47 @@ pub fn iterate_events(
         let anim = &mut armature.animations[events.values[0] as usize];
         let playing = events.values[1] == 1.;
         anim.elapsed = if playing { Some(Instant::now()) } else { None };
+        events.events.remove(0);
+        events.values.drain(0..=1);
+    } else if event == Events::ToggleBoneInAnimMask {
+        let bone_id = events.values[0] as i32;
+        let mut ids = vec![bone_id];
+        if events.values[1] == 1. {
+            let bone = armature
+                .bones
+                .iter()
+                .find(|b| b.id == bone_id)
+                .unwrap()
+                .clone();
+            let mut children = vec![];
+            get_all_children(&armature.bones, &mut children, &bone);
+            ids.append(&mut children.iter().map(|child| child.id).collect());
+        }
+
+        let mask = &mut armature.sel_anim_mut(selections).unwrap().mask;
+        if mask.contains(&bone_id) {
+            mask.retain(|id| !ids.contains(id));
+        } else {
+            for id in ids {
+                if !mask.contains(&id) {
+                    mask.push(id);
+                }
+            }
+        }
+
+        events.events.remove(0);
+        events.values.drain(0..=1);
+    } else if event == Events::SetAnimEventValue {
+        let anim = armature.sel_anim_mut(selections).unwrap();
+        anim.events[events.values[0] as usize].value = events.values[1];
+
+        events.events.remove(0);
+        events.values.drain(0..=1);
+    } else if event == Events::SetAnimEventFrame {
+        let anim = armature.sel_anim_mut(selections).unwrap();
+        anim.events[events.values[0] as usize].frame = (events.values[1] as i32).max(0);
+        anim.sort_events();
+
         events.events.remove(0);
         events.values.drain(0..=1);
     } else if event == Events::SetAllKeyframesFrame {
@@ -417,6 +466,21 @@ pub fn simple_event(
             }
         }
         Events::ToggleOnionLayers => edit_mode.onion_layers = value == 1.,
+        Events::ToggleAnimAdditive => {
+            armature.sel_anim_mut(selections).unwrap().additive = value == 1.
+        }
+        Events::ToggleRootMotion => {
+            // only one bone can be the source of root motion
+            let id = armature.sel_bone(selections).unwrap().id;
+            for bone in &mut armature.bones {
+                bone.root_motion = bone.id == id && value == 1.;
+            }
+        }
+        Events::SetAnimLength => armature.sel_anim_mut(selections).unwrap().length = value as i32,
+        Events::SetAnimLoopMode => {
+            let mode = LoopMode::from_repr(value as usize).unwrap();
+            armature.sel_anim_mut(selections).unwrap().loop_mode = mode;
+        }
         Events::DeleteIkTarget => armature.sel_bone_mut(selections).unwrap().ik_target_id = -1,
         Events::ToggleIkFolded => {
             armature.sel_bone_mut(&selections).unwrap().ik_folded = value == 1.
@@ -467,6 +531,7 @@ pub fn simple_event(
             camera.pos = Vec2::new(0., 0.);
             camera.zoom = 2000.;
             *armature = Armature::default();
+            ui.repairs = vec![];
         }
         Events::NewStyle => {
             let ids = armature.styles.iter().map(|set| set.id).collect();
@@ -856,6 +921,10 @@ pub fn simple_event(
             for kf in &mut anim_clone.keyframes {
                 kf.frame = ((kf.frame as f32) / div) as i32
             }
+            for event in &mut anim_clone.events {
+                event.frame = ((event.frame as f32) / div) as i32
+            }
+            anim_clone.length = ((anim_clone.length as f32) / div) as i32;
 
             let mut unique_keyframes: Vec<i32> =
                 anim_clone.keyframes.iter().map(|kf| kf.frame).collect();
@@ -864,6 +933,8 @@ pub fn simple_event(
             if unique_keyframes.len() == old_unique_keyframes.len() {
                 anim_mut.fps = value as i32;
                 anim_mut.keyframes = anim_clone.keyframes;
+                anim_mut.events = anim_clone.events;
+                anim_mut.length = anim_clone.length;
             } else {
                 open_modal(ui, value == 1., ui.loc("keyframe_editor.invalid_fps"));
             }
@@ -875,6 +946,42 @@ pub fn simple_event(
             let anim = armature.sel_anim_mut(&selections).unwrap();
             anim.keyframes.retain(|kf| kf.frame != value as i32);
         }
+        Events::NewAnimEvent => {
+            let anim = armature.sel_anim_mut(selections).unwrap();
+            anim.events.push(AnimEvent {
+                frame: value as i32,
+                name: ui.loc("keyframe_editor.new_event"),
+                ..Default::default()
+            });
+            anim.sort_events();
+            selections.anim_frame = value as i32;
+            ui.last_selected = "anim_event".to_string();
+        }
+        Events::SelectAnimEvents => {
+            selections.anim_frame = value as i32;
+            ui.last_selected = "anim_event".to_string();
+        }
+        Events::DeleteAnimEvent => {
+            armature
+                .sel_anim_mut(selections)
+                .unwrap()
+                .events
+                .remove(value as usize);
+        }
+        Events::DeleteAnimEventsByFrame => {
+            let anim = armature.sel_anim_mut(selections).unwrap();
+            anim.events.retain(|event| event.frame != value as i32);
+        }
+        Events::RenameAnimEvent => {
+            armature.sel_anim_mut(selections).unwrap().events[value as usize].name = str_value
+        }
+        Events::SetAnimEventStr => {
+            armature.sel_anim_mut(selections).unwrap().events[value as usize].value_str = str_value
+        }
+        Events::CopyAnimEvents => copy_anim_events(copy_buffer, armature, selections, value as i32),
+        Events::PasteAnimEventsOnFrame => {
+            paste_anim_events_on_frame(copy_buffer, armature, selections, value as i32)
+        }
         Events::ResetVertices => {
             let sel_bone = armature.sel_bone(&selections).unwrap().clone();
             let tex_size = armature.tex_of(sel_bone.id).unwrap().size.clone();
@@ -950,12 +1057,28 @@ pub fn simple_event(
         }
         Events::ToggleBakingIk => edit_mode.export_bake_ik = value == 1.,
         Events::ToggleExcludeIk => edit_mode.export_exclude_ik = value == 1.,
+        Events::ToggleExportBinary => edit_mode.export_binary = value == 1.,
         Events::SetExportImgFormat => {
             edit_mode.export_img_format = ExportImgFormat::from_repr(value as usize).unwrap()
         }
+        Events::SetExportFormat => {
+            edit_mode.export_format = ExportArmatureFormat::from_repr(value as usize).unwrap()
+        }
+        Events::TogglePremultiplied => edit_mode.export_premultiplied = value == 1.,
+        Events::SetExportAtlasExtrude => edit_mode.export_atlas.extrude = value as i32,
+        Events::ToggleAtlasPowerOfTwo => edit_mode.export_atlas.power_of_two = value == 1.,
+        Events::ToggleAtlasSquare => edit_mode.export_atlas.square = value == 1.,
+        Events::ToggleAtlasTrim => edit_mode.export_atlas.trim = value == 1.,
+        Events::ToggleAtlasRotate => edit_mode.export_atlas.rotate = value == 1.,
         Events::OpenExportModal => {
             ui.export_modal = true;
-            for _ in &armature.animations {
+            // one of each per animation, as they are now
+            ui.exporting_anims.clear();
+            ui.exporting_loops.clear();
+            for anim in &armature.animations {
+                // animated images play once if their animation does, and loop otherwise
+                ui.exporting_loops
+                    .push((anim.loop_mode == LoopMode::Once) as u16);
                 ui.exporting_anims.push({
                     #[cfg(target_arch = "wasm32")]
                     {
@@ -1141,6 +1264,9 @@ pub fn simple_event(
         }
         Events::GlobalCopy => match ui.last_selected.as_str() {
             "keyframe" => copy_selected_keyframes(copy_buffer, ui),
+            "anim_event" => {
+                copy_anim_events(copy_buffer, armature, selections, selections.anim_frame)
+            }
             "bone" => copy_bone(copy_buffer, selections, armature, selections.bone_idx),
             _ => {}
         },
@@ -1149,6 +1275,11 @@ pub fn simple_event(
                 undo_states.new_undo_anim(armature.sel_anim(&selections).unwrap());
                 paste_keyframes_on_frame(copy_buffer, armature, selections, value as i32);
             }
+            "anim_event" => {
+                undo_states.new_undo_anim(armature.sel_anim(selections).unwrap());
+                let frame = selections.anim_frame;
+                paste_anim_events_on_frame(copy_buffer, armature, selections, frame);
+            }
             "bone" => {
                 undo_states.new_undo_bones(&armature.bones);
                 paste_bone(copy_buffer, selections, armature, selections.bone_idx);
@@ -1804,23 +1935,6 @@ fn winding_sort(mut points: Vec<Vec2>) -> Vec<Vec2> {
     points
 }
 
-/// sort vertices in cw (or ccw?) order
-pub fn sort_vertices(mut verts: Vec<Vertex>) -> Vec<Vertex> {
-    let mut center = Vec2::default();
-    for v in 0..verts.len() {
-        center += verts[v].pos;
-    }
-    center /= verts.len() as f32;
-
-    verts.sort_by(|a, b| {
-        let angle_a = (a.pos.y - center.y).atan2(a.pos.x - center.x);
-        let angle_b = (b.pos.y - center.y).atan2(b.pos.x - center.x);
-        angle_a.partial_cmp(&angle_b).unwrap()
-    });
-
-    verts
-}
-
 fn vert(pos: Option<Vec2>, col: Option<Color>, uv: Option<Vec2>) -> Vertex {
     Vertex {
         pos: pos.unwrap_or_default(),
@@ -2122,3 +2236,50 @@ fn paste_keyframes_on_frame(
 
     armature.sel_anim_mut(&selections).unwrap().sort_keyframes();
 }
+
+fn copy_anim_events(
+    copy_buffer: &mut CopyBuffer,
+    armature: &Armature,
+    selections: &SelectionState,
+    frame: i32,
+) {
+    *copy_buffer = CopyBuffer::default();
+    let anim = armature.sel_anim(selections).unwrap();
+    copy_buffer.anim_events = anim
+        .events
+        .iter()
+        .filter(|e| e.frame == frame)
+        .cloned()
+        .collect();
+}
+
+fn paste_anim_events_on_frame(
+    copy_buffer: &CopyBuffer,
+    armature: &mut Armature,
+    selections: &SelectionState,
+    frame: i32,
+) {
+    if copy_buffer.anim_events.is_empty() {
+        return;
+    }
+
+    let base_frame = copy_buffer
+        .anim_events
+        .iter()
+        .map(|e| e.frame)
+        .min()
+        .unwrap();
+    let anim = armature.sel_anim_mut(selections).unwrap();
+    for event in &copy_buffer.anim_events {
+        let pasted = AnimEvent {
+            frame: frame + event.frame - base_frame,
+            ..event.clone()
+        };
+
+        // don't duplicate identical events
+        if !anim.events.contains(&pasted) {
+            anim.events.push(pasted);
+        }
+    }
+    anim.sort_events();
+}
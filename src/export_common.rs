@@ -0,0 +1,694 @@
+//! Helpers shared by the converters to and from other formats (Spine, DragonBones, Godot).
+
+use crate::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Armature features that some formats can't represent.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Feature {
+    Sway,
+    Physics,
+    PathBinds,
+    /// IK families of more than 3 bones
+    LongIk,
+    IkMimic,
+    /// Animated IK constraints, modes, families or mimicking
+    IkKeys,
+    Additive,
+    PingPong,
+    Reverse,
+    /// Styles other than the active ones, with textures
+    InactiveStyles,
+}
+
+impl Feature {
+    /// Localization key of the feature, for listing it as lost.
+    pub fn loc_key(&self) -> &'static str {
+        match self {
+            Feature::Sway => "export_modal.lost.sway",
+            Feature::Physics => "export_modal.lost.physics",
+            Feature::PathBinds => "export_modal.lost.path_binds",
+            Feature::LongIk => "export_modal.lost.long_ik",
+            Feature::IkMimic => "export_modal.lost.ik_mimic",
+            Feature::IkKeys => "export_modal.lost.ik_keys",
+            Feature::Additive => "export_modal.lost.additive",
+            Feature::PingPong => "export_modal.lost.ping_pong",
+            Feature::Reverse => "export_modal.lost.reverse",
+            Feature::InactiveStyles => "export_modal.lost.styles",
+        }
+    }
+
+    /// Whether the armature uses this feature.
+    pub fn used(&self, armature: &Armature) -> bool {
+        let bones = &armature.bones;
+        let anims = &armature.animations;
+        let ik_elements = [
+            AnimElement::IkConstraint,
+            AnimElement::IkMode,
+            AnimElement::IkFamilyId,
+            AnimElement::MimicTarget,
+        ];
+        #[rustfmt::skip]
+        let used = match self {
+            Feature::Sway => bones.iter().any(|b| b.phys_sway > 0.),
+            Feature::Physics => bones.iter().any(|b| b.phys_pos_damping > 0. || b.phys_rot_damping > 0. || b.phys_scale_damping > 0.),
+            Feature::PathBinds => bones.iter().any(|b| b.binds.iter().any(|bind| bind.is_path)),
+            Feature::LongIk => ik_families(armature).iter().any(|joints| joints.len() > 3),
+            Feature::IkMimic => ik_families(armature).iter().any(|joints| joints.last().unwrap().ik_mimic_target),
+            Feature::IkKeys => anims.iter().any(|a| a.keyframes.iter().any(|kf| ik_elements.contains(&kf.element))),
+            Feature::Additive => anims.iter().any(|a| a.additive || !a.mask.is_empty()),
+            Feature::PingPong => anims.iter().any(|a| a.loop_mode == LoopMode::PingPong),
+            Feature::Reverse => anims.iter().any(|a| a.loop_mode == LoopMode::Reverse),
+            Feature::InactiveStyles => armature.styles.iter().any(|s| !s.active && !s.textures.is_empty()),
+        };
+        used
+    }
+}
+
+/// Localization keys of the `unsupported` features that this armature uses, in the given order.
+pub fn lost_features(armature: &Armature, unsupported: &[Feature]) -> Vec<&'static str> {
+    let used = unsupported.iter().filter(|feature| feature.used(armature));
+    used.map(|feature| feature.loc_key()).collect()
+}
+
+/// Unique name of each bone, since Spine and DragonBones require them. 'root' is reserved for
+/// the generated root bone.
+pub fn unique_names(armature: &Armature) -> HashMap<i32, String> {
+    let mut names = HashMap::new();
+    let mut taken = vec!["root".to_string()];
+    for bone in &armature.bones {
+        let mut name = bone.name.clone();
+        if taken.contains(&name) {
+            name = format!("{} ({})", bone.name, bone.id);
+        }
+        taken.push(name.clone());
+        names.insert(bone.id, name);
+    }
+    names
+}
+
+/// Bones in each IK family, in order.
+pub fn ik_families(armature: &Armature) -> Vec<Vec<&Bone>> {
+    let mut families: Vec<(i32, Vec<&Bone>)> = vec![];
+    for bone in &armature.bones {
+        if bone.ik_family_id == -1 {
+            continue;
+        }
+        match families.iter_mut().find(|f| f.0 == bone.ik_family_id) {
+            Some(family) => family.1.push(bone),
+            None => families.push((bone.ik_family_id, vec![bone])),
+        }
+    }
+    families.into_iter().map(|f| f.1).collect()
+}
+
+/// Name of an IK family's constraint.
+pub fn ik_name(joints: &[&Bone]) -> String {
+    format!("ik{}", joints[0].ik_family_id)
+}
+
+/// Distance to the child a bone points at, for IK.
+pub fn bone_length(armature: &Armature, bone: &Bone) -> f32 {
+    bone_tip(armature, bone).map_or(0., |tip| tip.mag())
+}
+
+/// Position of the child a bone points at (its next IK joint, or else its first child).
+pub fn bone_tip(armature: &Armature, bone: &Bone) -> Option<Vec2> {
+    let family = ik_families(armature)
+        .into_iter()
+        .find(|joints| joints.iter().any(|j| j.id == bone.id));
+    let child = match family {
+        Some(joints) => {
+            let j = joints.iter().position(|j| j.id == bone.id).unwrap();
+            joints.get(j + 1).copied()
+        }
+        None => None,
+    };
+    let child = child.or_else(|| armature.bones.iter().find(|b| b.parent_id == bone.id));
+    child.map(|child| child.pos)
+}
+
+/// Whether each bone is hidden, by itself or by a parent.
+pub fn propagated_hidden(armature: &Armature) -> HashMap<i32, bool> {
+    let mut hiddens = HashMap::new();
+    for bone in &armature.bones {
+        let parent = hiddens.get(&bone.parent_id).copied().unwrap_or(false);
+        hiddens.insert(bone.id, bone.hidden || parent);
+    }
+    hiddens
+}
+
+/// Bones that show textures at some point, sorted by zindex.
+pub fn slot_bones(armature: &Armature) -> Vec<&Bone> {
+    let mut bones: Vec<&Bone> = armature
+        .bones
+        .iter()
+        .filter(|bone| !slot_tex_names(armature, bone).is_empty())
+        .collect();
+    bones.sort_by_key(|bone| bone.zindex);
+    bones
+}
+
+/// Every texture name a bone is set to, in setup and in animations.
+pub fn slot_tex_names(armature: &Armature, bone: &Bone) -> Vec<String> {
+    let mut names = vec![];
+    if !bone.tex.is_empty() {
+        names.push(bone.tex.clone());
+    }
+    for anim in &armature.animations {
+        for kf in &anim.keyframes {
+            let is_tex = kf.element == AnimElement::Texture && kf.bone_id == bone.id;
+            if is_tex && !kf.value_str.is_empty() && !names.contains(&kf.value_str) {
+                names.push(kf.value_str.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Texture that a name resolves to, like `Armature::tex_of`.
+pub fn active_tex<'a>(armature: &'a Armature, name: &str) -> Option<(&'a Style, &'a Texture)> {
+    let find = |active_only: bool| {
+        armature
+            .styles
+            .iter()
+            .filter(|style| style.active || !active_only)
+            .find_map(|style| {
+                let tex = style.textures.iter().find(|t| t.name == name);
+                tex.map(|tex| (style, tex))
+            })
+    };
+    find(true).or_else(|| find(false))
+}
+
+/// Atlas name of a texture.
+pub fn region_name(style: &Style, tex: &Texture) -> String {
+    format!("{}/{}", style.name, tex.name)
+}
+
+/// Keyframes of one element of a bone, in order.
+pub fn track<'a>(anim: &'a Animation, bone_id: i32, element: &AnimElement) -> Vec<&'a Keyframe> {
+    let mut kfs: Vec<&Keyframe> = anim
+        .keyframes
+        .iter()
+        .filter(|kf| kf.bone_id == bone_id && kf.element == *element)
+        .collect();
+    kfs.sort_by_key(|kf| kf.frame);
+    kfs
+}
+
+/// Last keyframe of an element at or before `frame`.
+pub fn value_at<'a>(
+    anim: &'a Animation,
+    bone_id: i32,
+    element: &AnimElement,
+    frame: i32,
+) -> Option<&'a Keyframe> {
+    let kfs = track(anim, bone_id, element);
+    kfs.into_iter().rev().find(|kf| kf.frame <= frame)
+}
+
+/// Frames of an animation where the texture a bone shows can change: its texture keyframes,
+/// and the visibility keyframes of it and its parents.
+pub fn tex_frames(armature: &Armature, anim: &Animation, bone: &Bone) -> Vec<i32> {
+    let ancestors = ancestors(armature, bone);
+    let mut frames: Vec<i32> = anim
+        .keyframes
+        .iter()
+        .filter(|kf| {
+            (kf.element == AnimElement::Texture && kf.bone_id == bone.id)
+                || (kf.element == AnimElement::Hidden && ancestors.contains(&kf.bone_id))
+        })
+        .map(|kf| kf.frame)
+        .collect();
+    frames.sort();
+    frames.dedup();
+    frames
+}
+
+/// Texture a bone shows at a frame of an animation (empty if it or a parent is hidden).
+pub fn tex_at(armature: &Armature, anim: &Animation, bone: &Bone, frame: i32) -> String {
+    let hidden = ancestors(armature, bone).iter().any(|id| {
+        let setup = armature.bones.iter().find(|b| b.id == *id).unwrap().hidden;
+        value_at(anim, *id, &AnimElement::Hidden, frame).map_or(setup, |kf| kf.value != 0.)
+    });
+    if hidden {
+        return String::new();
+    }
+    value_at(anim, bone.id, &AnimElement::Texture, frame)
+        .map_or(bone.tex.clone(), |kf| kf.value_str.clone())
+}
+
+// the bone and its parents
+fn ancestors(armature: &Armature, bone: &Bone) -> Vec<i32> {
+    let mut ancestors = vec![bone.id];
+    let mut parent = bone.parent_id;
+    while let Some(p) = armature.bones.iter().find(|b| b.id == parent) {
+        ancestors.push(p.id);
+        parent = p.parent_id;
+    }
+    ancestors
+}
+
+/// Offsets of the slots that are drawn out of order at a frame, by index in `slot_bones`
+/// (which are sorted by setup zindex).
+pub fn draw_order_offsets(anim: &Animation, slot_bones: &[&Bone], frame: i32) -> Vec<(usize, i32)> {
+    let mut order: Vec<(i32, usize)> = slot_bones
+        .iter()
+        .enumerate()
+        .map(|(s, bone)| {
+            let zindex = value_at(anim, bone.id, &AnimElement::Zindex, frame)
+                .map_or(bone.zindex, |kf| kf.value as i32);
+            (zindex, s)
+        })
+        .collect();
+    order.sort();
+    let mut offsets = vec![];
+    for s in 0..slot_bones.len() {
+        let new_idx = order.iter().position(|o| o.1 == s).unwrap();
+        if new_idx != s {
+            offsets.push((s, new_idx as i32 - s as i32));
+        }
+    }
+    offsets
+}
+
+/// Zindex of each of `n` slots, from the offsets of the slots that move (by slot index, in
+/// order). Offset slots are placed first and the rest fill the gaps, like in Spine's runtimes.
+pub fn draw_order(offsets: &[(usize, i64)], n: usize) -> Vec<usize> {
+    let setup: Vec<usize> = (0..n).collect();
+    if offsets.is_empty() {
+        return setup;
+    }
+
+    let mut order = vec![usize::MAX; n];
+    let mut unchanged = vec![];
+    let mut original = 0;
+    for (slot, offset) in offsets {
+        let new_idx = *slot as i64 + offset;
+        if *slot < original || !(0..n as i64).contains(&new_idx) {
+            continue;
+        }
+        while original < *slot {
+            unchanged.push(original);
+            original += 1;
+        }
+        order[new_idx as usize] = original;
+        original += 1;
+    }
+    unchanged.extend(original..n);
+    for i in (0..n).rev() {
+        if order[i] == usize::MAX {
+            order[i] = unchanged.pop().unwrap_or(i);
+        }
+    }
+
+    let mut zindices = setup;
+    for (i, s) in order.into_iter().enumerate() {
+        if s < n {
+            zindices[s] = i;
+        }
+    }
+    zindices
+}
+
+/// Spine and DragonBones key rotation and translation as offsets from the setup pose, and scale
+/// as a multiple of it.
+pub fn to_setup_relative(bone: &Bone, element: &AnimElement, value: f32) -> f32 {
+    let ratio = |setup: f32| if setup != 0. { value / setup } else { value };
+    match element {
+        AnimElement::Rotation => (value - bone.rot).to_degrees(),
+        AnimElement::PositionX => value - bone.pos.x,
+        AnimElement::PositionY => value - bone.pos.y,
+        AnimElement::ScaleX => ratio(bone.scale.x),
+        AnimElement::ScaleY => ratio(bone.scale.y),
+        _ => value,
+    }
+}
+
+/// Inverse of `to_setup_relative`.
+pub fn from_setup_relative(bone: &Bone, element: &AnimElement, value: f32) -> f32 {
+    match element {
+        AnimElement::Rotation => bone.rot + value.to_radians(),
+        AnimElement::PositionX => bone.pos.x + value,
+        AnimElement::PositionY => bone.pos.y + value,
+        AnimElement::ScaleX => bone.scale.x * value,
+        AnimElement::ScaleY => bone.scale.y * value,
+        _ => value,
+    }
+}
+
+/// Keyframe with linear handles.
+pub fn keyframe(frame: i32, bone_id: i32, element: AnimElement) -> Keyframe {
+    let (start_handle, end_handle) = utils::interp_preset(HandlePreset::Linear);
+    #[rustfmt::skip]
+    return Keyframe { frame, bone_id, element, start_handle, end_handle, ..Default::default() };
+}
+
+/// Weights of a vertex (by bone index) that add up to 1, like most runtimes expect.
+///
+/// Each bind moves the vertex part of the way towards its bone, so the weights that came
+/// before it are scaled down by its own.
+pub fn vertex_weights(armature: &Armature, bone: &Bone, vert: &Vertex) -> Vec<(usize, f32)> {
+    let idx = |id: i32| armature.bones.iter().position(|b| b.id == id);
+    let mut weights: Vec<(usize, f32)> = vec![(idx(bone.id).unwrap(), 1.)];
+    for bind in &bone.binds {
+        let bind_idx = idx(bind.bone_id);
+        let bind_vert = bind.verts.iter().find(|v| v.id == vert.id as i32);
+        if bind.is_path || bind_idx.is_none() || bind_vert.is_none() {
+            continue;
+        }
+        let weight = bind_vert.unwrap().weight;
+        for w in &mut weights {
+            w.1 *= 1. - weight;
+        }
+        match weights.iter_mut().find(|w| w.0 == bind_idx.unwrap()) {
+            Some(w) => w.1 += weight,
+            None => weights.push((bind_idx.unwrap(), weight)),
+        }
+    }
+    weights.retain(|w| w.1 > 0.);
+    weights
+}
+
+/// Setup pose of every bone in world space (without IK or physics).
+pub fn world_bones(armature: &Armature) -> Vec<Bone> {
+    let mut bones = armature.bones.clone();
+    renderer::inheritance(&mut bones, HashMap::new(), &vec![]);
+    bones
+}
+
+/// Where one of the bones a vertex is weighted to places it, in world space. SkelForm keeps a
+/// vertex at the same position in the space of each of its bones (see
+/// `renderer::construct_verts`), with the mesh's pivot added on top.
+///
+/// `mesh` and `bind` are world bones (see `world_bones`), and `pivot` is the mesh's pivot in
+/// pixels.
+pub fn weighted_pos(mesh: &Bone, bind: &Bone, pos: Vec2, pivot: Vec2) -> Vec2 {
+    let pivot = utils::rotate(&pivot, mesh.rot) * mesh.scale;
+    renderer::inherit_vert(pos, bind, mesh.pivot_rot, mesh.pivot_scale) + pivot
+}
+
+/// World position in the space of a world bone.
+pub fn to_bone_space(bone: &Bone, world: Vec2) -> Vec2 {
+    utils::rotate(&(world - bone.pos), -bone.rot) / bone.scale
+}
+
+/// Inverse of `to_bone_space`.
+pub fn from_bone_space(bone: &Bone, local: Vec2) -> Vec2 {
+    renderer::inherit_vert(local, bone, 0., Vec2::new(1., 1.))
+}
+
+/// Position of a vertex that its weighted bones place at `world` in setup pose, as the
+/// inverse of `weighted_pos` (summed over `weights`). `None` if the bones flatten it.
+pub fn unweighted_pos(
+    mesh: &Bone,
+    weights: &[(&Bone, f32)],
+    world: Vec2,
+    pivot: Vec2,
+) -> Option<Vec2> {
+    let total: f32 = weights.iter().map(|w| w.1).sum();
+    if total <= 0. {
+        return None;
+    }
+
+    // weighted positions are linear in the vertex, so this solves `origin + x * x_axis +
+    // y * y_axis = world`
+    let (mut origin, mut x_axis, mut y_axis) = (Vec2::ZERO, Vec2::ZERO, Vec2::ZERO);
+    for (bind, weight) in weights {
+        let weight = weight / total;
+        let at = |pos: Vec2| weighted_pos(mesh, bind, pos, pivot) * weight;
+        let bind_origin = at(Vec2::ZERO);
+        origin += bind_origin;
+        x_axis += at(Vec2::new(1., 0.)) - bind_origin;
+        y_axis += at(Vec2::new(0., 1.)) - bind_origin;
+    }
+    let det = x_axis.x * y_axis.y - y_axis.x * x_axis.y;
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let diff = world - origin;
+    Some(Vec2::new(
+        (diff.x * y_axis.y - y_axis.x * diff.y) / det,
+        (x_axis.x * diff.y - diff.x * x_axis.y) / det,
+    ))
+}
+
+/// Binds of a mesh from the weights of each vertex (by bone ID, without the mesh's own bone).
+/// Vertex IDs are expected to be their index.
+///
+/// The inverse of `vertex_weights`: weights are unrolled from the last bind back.
+pub fn unroll_weights(bone: &mut Bone, weights: &[Vec<(i32, f32)>]) {
+    let mut bind_ids: Vec<i32> = vec![];
+    for (id, _) in weights.iter().flatten() {
+        if !bind_ids.contains(id) {
+            bind_ids.push(*id);
+        }
+    }
+    bone.binds = bind_ids
+        .iter()
+        .map(|id| BoneBind {
+            bone_id: *id,
+            is_path: false,
+            verts: vec![],
+        })
+        .collect();
+    for (v, vert_weights) in weights.iter().enumerate() {
+        let mut remaining = 1.;
+        for bind in bone.binds.iter_mut().rev() {
+            let weight: f32 = vert_weights
+                .iter()
+                .filter(|w| w.0 == bind.bone_id)
+                .map(|w| w.1)
+                .sum();
+            if weight <= 0. {
+                continue;
+            }
+            let weight_left = if remaining > 0. {
+                (weight / remaining).min(1.)
+            } else {
+                1.
+            };
+            bind.verts.push(BoneBindVert {
+                id: v as i32,
+                weight: weight_left,
+            });
+            remaining -= weight;
+        }
+    }
+}
+
+/// Give a bone an edited mesh, from the flat UVs and triangle indices of a converted format.
+/// `weights` can be empty for unbound meshes (see `unroll_weights`).
+///
+/// `path` is where the mesh is in its file, for errors.
+pub fn set_mesh(
+    bone: &mut Bone,
+    positions: &[Vec2],
+    uvs: &[f32],
+    indices: &[f32],
+    weights: &[Vec<(i32, f32)>],
+    path: &str,
+) -> Result<(), SkfError> {
+    if uvs.len() != positions.len() * 2 {
+        let path = format!("{}.uvs", path);
+        let (len, count) = (uvs.len(), positions.len());
+        let message = format!("There are {} UV coordinates for {} vertices.", len, count);
+        return Err(SkfError::BadValue { path, message });
+    }
+    if !indices.len().is_multiple_of(3) {
+        let path = format!("{}.triangles", path);
+        let message = format!("{} indices do not make whole triangles.", indices.len());
+        return Err(SkfError::BadValue { path, message });
+    }
+    for (i, idx) in indices.iter().enumerate() {
+        if *idx < 0. || *idx as usize >= positions.len() {
+            let path = format!("{}.triangles[{}]", path, i);
+            let (idx, len) = (*idx as i32, positions.len());
+            return Err(SkfError::OutOfRange { path, idx, len });
+        }
+    }
+
+    bone.vertices = positions
+        .iter()
+        .enumerate()
+        .map(|(v, pos)| Vertex {
+            id: v as u32,
+            pos: *pos,
+            init_pos: *pos,
+            uv: Vec2::new(uvs[v * 2], uvs[v * 2 + 1]),
+            ..Default::default()
+        })
+        .collect();
+    bone.indices = indices.iter().map(|i| *i as u32).collect();
+    bone.verts_edited = true;
+
+    unroll_weights(bone, weights);
+    Ok(())
+}
+
+/// Skin of an imported skeleton: its name, and the slot, attachment name and atlas region
+/// of each textured attachment in it.
+pub type SkinEntries = (String, Vec<(String, String, String)>);
+
+/// Add the skins of an imported skeleton as styles, with textures from `region_image`.
+///
+/// The default skin is left out if it only repeats the others (like in SkelForm's own
+/// exports). Attachment names only need to be unique per slot, so the names that show
+/// different regions in different slots are returned, for `skin_tex_name` to prefix.
+pub fn skins_to_styles(
+    armature: &mut Armature,
+    skins: &[SkinEntries],
+    default: Option<usize>,
+    region_image: &mut dyn FnMut(&str) -> Result<image::DynamicImage, SkfError>,
+) -> Result<Vec<String>, SkfError> {
+    let mut key_regions: HashMap<(usize, &str), &str> = HashMap::new();
+    let mut clashing: Vec<String> = vec![];
+    for (s, (_, entries)) in skins.iter().enumerate() {
+        for (_, key, path) in entries {
+            let region = key_regions.entry((s, key)).or_insert(path);
+            if region != path && !clashing.contains(key) {
+                clashing.push(key.clone());
+            }
+        }
+    }
+
+    let entries = |s: usize| &skins[s].1;
+    let covered = default.is_some_and(|d| {
+        let others: Vec<_> = (0..skins.len()).filter(|o| *o != d).collect();
+        let repeated = |e| others.iter().any(|o| entries(*o).contains(e));
+        !others.is_empty() && entries(d).iter().all(repeated)
+    });
+    for (s, (skin_name, _)) in skins.iter().enumerate() {
+        if covered && Some(s) == default {
+            continue;
+        }
+        let active = match default {
+            Some(d) if covered => entries(s).iter().any(|e| entries(d).contains(e)),
+            Some(d) => s == d,
+            None => s == 0,
+        };
+        let id = armature.styles.len() as i32;
+        let mut style = Style {
+            id,
+            name: skin_name.clone(),
+            active,
+            textures: vec![],
+        };
+        for (slot, key, path) in entries(s) {
+            let name = skin_tex_name(&clashing, slot, key);
+            if style.textures.iter().any(|t| t.name == name) {
+                continue;
+            }
+            let image = region_image(path)?;
+            let size = Vec2::new(image.width() as f32, image.height() as f32);
+            let data_id = armature.tex_data.len() as i32;
+            #[rustfmt::skip]
+            armature.tex_data.push(TextureData { id: data_id, image, ..Default::default() });
+            style.textures.push(Texture {
+                name,
+                size,
+                data_id,
+                ..Default::default()
+            });
+        }
+        armature.styles.push(style);
+    }
+    Ok(clashing)
+}
+
+/// Texture name of a skin attachment (see `skins_to_styles`).
+pub fn skin_tex_name(clashing: &[String], slot: &str, key: &str) -> String {
+    match clashing.iter().any(|c| c == key) {
+        true => format!("{}/{}", slot, key),
+        false => key.to_string(),
+    }
+}
+
+/// Elements of a JSON array (none if it isn't one).
+pub fn array(json: &Value) -> &[Value] {
+    json.as_array().map_or(&[], |a| a.as_slice())
+}
+
+/// Entries of a JSON object (none if it isn't one).
+pub fn objects(json: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    json.as_object().into_iter().flatten()
+}
+
+/// Numbers of a JSON array.
+pub fn floats(json: &Value) -> Vec<f32> {
+    array(json)
+        .iter()
+        .map(|v| v.as_f64().unwrap_or(0.) as f32)
+        .collect()
+}
+
+/// Number field of a JSON object, or `default` if it's missing.
+pub fn float(json: &Value, key: &str, default: f32) -> f32 {
+    json[key].as_f64().map_or(default, |v| v as f32)
+}
+
+/// String field of a JSON object, or an empty string if it's missing.
+pub fn str_of<'a>(json: &'a Value, key: &str) -> &'a str {
+    json[key].as_str().unwrap_or_default()
+}
+
+/// Number with float noise rounded away, so the JSON stays readable.
+pub fn num(value: f32) -> Value {
+    json!((value as f64 * 10000.).round() / 10000.)
+}
+
+// atlases are named after their skeleton, and numbered if there's more than one
+pub fn is_dragonbones_atlas(stem: &str, name: &str) -> bool {
+    name.starts_with(&format!("{}_tex", stem)) && name.ends_with(".json")
+}
+
+/// Spine or DragonBones skeleton zipped together with its atlases and their images (like
+/// SkelForm's exports of either).
+pub fn read_skeleton_zip<R: std::io::Read + std::io::Seek>(data: R) -> Result<Armature, SkfError> {
+    let mut zip = zip::ZipArchive::new(data).map_err(|e| SkfError::Io(e.to_string()))?;
+    let mut names: Vec<String> = zip.file_names().map(|n| n.to_string()).collect();
+    names.sort();
+    let find = |ext: &[&str]| names.iter().find(|n| ext.iter().any(|e| n.ends_with(e)));
+
+    let mut read = |name: &str| -> Result<Vec<u8>, SkfError> {
+        let mut file = zip.by_name(name).map_err(|e| SkfError::Io(e.to_string()))?;
+        let mut bytes = vec![];
+        std::io::Read::read_to_end(&mut file, &mut bytes)
+            .map_err(|e| SkfError::Io(e.to_string()))?;
+        Ok(bytes)
+    };
+    let in_dir = |dir: &str, name: &str| match dir {
+        "" => name.to_string(),
+        _ => format!("{}/{}", dir, name),
+    };
+
+    // DragonBones skeletons are told apart by their suffix
+    if let Some(skeleton_name) = find(&["_ske.json"]) {
+        let skeleton = String::from_utf8_lossy(&read(skeleton_name)?).to_string();
+        let stem = skeleton_name.trim_end_matches("_ske.json");
+        let mut atlases = vec![];
+        for name in names.iter().filter(|n| is_dragonbones_atlas(stem, n)) {
+            atlases.push(String::from_utf8_lossy(&read(name)?).to_string());
+        }
+
+        // images are relative to the skeleton
+        let dir = skeleton_name.rsplit_once('/').map_or("", |(dir, _)| dir);
+        return dragonbones::read_dragonbones(&skeleton, &atlases, &mut |image| {
+            image::load_from_memory(&read(&in_dir(dir, image)).ok()?).ok()
+        });
+    }
+
+    let skeleton_name = find(&[".json"]).ok_or(SkfError::MissingFile("*.json".to_string()))?;
+    let atlas_name =
+        find(&[".atlas", ".atlas.txt"]).ok_or(SkfError::MissingFile("*.atlas".to_string()))?;
+    let skeleton = String::from_utf8_lossy(&read(skeleton_name)?).to_string();
+    let atlas = String::from_utf8_lossy(&read(atlas_name)?).to_string();
+
+    // pages are relative to the atlas
+    let dir = atlas_name.rsplit_once('/').map_or("", |(dir, _)| dir);
+    spine::read_spine(&skeleton, &atlas, &mut |page| {
+        image::load_from_memory(&read(&in_dir(dir, page)).ok()?).ok()
+    })
+}
@@ -4,9 +4,11 @@ use std::os::unix::fs::PermissionsExt;
 use egui::IntoAtoms;
 
 use crate::{
+    dragonbones, export_common, godot,
     settings_modal::{alt_hor, basic_checkbox, settings_button},
+    spine,
     ui::EguiUi,
-    Armature, Config, EditMode, EventState, ExportImgFormat, SettingsState,
+    Armature, Config, EditMode, EventState, ExportArmatureFormat, ExportImgFormat, SettingsState,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -99,7 +101,7 @@ pub fn draw(
                         let layout = egui::Layout::top_down(egui::Align::Min);
                         ui.with_layout(layout, |ui| match shared_ui.settings_state {
                             SettingsState::Ui => {
-                                armature_export(ui, shared_ui, edit_mode, events, config)
+                                armature_export(ui, shared_ui, edit_mode, events, config, armature)
                             }
                             SettingsState::Editing => image_export(ui, shared_ui, config, armature),
                             SettingsState::Keyboard => {
@@ -172,7 +174,8 @@ pub fn draw(
                 *sui.saving.lock().unwrap() = crate::Saving::Spritesheet;
             }
             #[cfg(not(target_arch = "wasm32"))]
-            utils::open_save_dialog(&sui.file_path, &sui.saving, crate::Saving::Exporting);
+            #[rustfmt::skip]
+            utils::open_export_dialog(&sui.file_path, &sui.saving, &edit_mode.export_format);
             sui.export_modal = false;
         }
         // Image Export
@@ -206,6 +209,7 @@ pub fn armature_export(
     edit_mode: &EditMode,
     events: &mut EventState,
     config: &Config,
+    armature: &Armature,
 ) {
     ui.heading(shared_ui.loc("export_modal.armature.header"));
 
@@ -245,6 +249,73 @@ pub fn armature_export(
 
     ui.add_space(20.);
 
+    ui.heading(shared_ui.loc("export_modal.armature.format"));
+
+    alt_hor(ui, config, true, |ui| {
+        ui.label(shared_ui.loc("export_modal.armature.armature_format"));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let str_skf = shared_ui.loc("export_modal.armature.skf");
+            let str_spine = shared_ui.loc("export_modal.armature.spine");
+            let str_dragonbones = shared_ui.loc("export_modal.armature.dragonbones");
+            let str_godot = shared_ui.loc("export_modal.armature.godot");
+            let selected_str = match edit_mode.export_format {
+                ExportArmatureFormat::Skf => &str_skf,
+                ExportArmatureFormat::Spine => &str_spine,
+                ExportArmatureFormat::DragonBones => &str_dragonbones,
+                ExportArmatureFormat::Godot => &str_godot,
+            };
+            let dropdown = egui::ComboBox::new("armature_format", "")
+                .selected_text(selected_str.to_string())
+                .width(80.);
+            dropdown.show_ui(ui, |ui| {
+                let mut selected = edit_mode.export_format.clone();
+                ui.selectable_value(&mut selected, ExportArmatureFormat::Skf, str_skf);
+                ui.selectable_value(&mut selected, ExportArmatureFormat::Spine, str_spine);
+                #[rustfmt::skip]
+                ui.selectable_value(&mut selected, ExportArmatureFormat::DragonBones, str_dragonbones);
+                ui.selectable_value(&mut selected, ExportArmatureFormat::Godot, str_godot);
+                if selected != edit_mode.export_format {
+                    events.set_export_format(selected as usize);
+                }
+            });
+        });
+    });
+
+    // list what the format can't represent
+    let (header, unsupported) = match edit_mode.export_format {
+        ExportArmatureFormat::Skf => ("", &[][..]),
+        ExportArmatureFormat::Spine => ("export_modal.spine.lost", spine::UNSUPPORTED),
+        ExportArmatureFormat::DragonBones => {
+            ("export_modal.dragonbones.lost", dragonbones::UNSUPPORTED)
+        }
+        ExportArmatureFormat::Godot => ("export_modal.godot.lost", godot::UNSUPPORTED),
+    };
+    let lost = export_common::lost_features(armature, unsupported);
+    if !lost.is_empty() {
+        ui.label(shared_ui.loc(header));
+        for key in lost {
+            let str = format!("- {}", shared_ui.loc(key));
+            ui.label(egui::RichText::new(str).color(config.colors.warning_text));
+        }
+    }
+
+    // Spine and DragonBones skeletons are always JSON, and Godot scenes always text
+    ui.add_enabled_ui(edit_mode.export_format == ExportArmatureFormat::Skf, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(shared_ui.loc("export_modal.armature.binary"))
+                .on_hover_text(shared_ui.loc("export_modal.armature.binary_desc"));
+            let mut binary = edit_mode.export_binary;
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.checkbox(&mut binary, "".into_atoms());
+            });
+            if binary != edit_mode.export_binary {
+                events.toggle_export_binary(if binary { 1 } else { 0 });
+            }
+        });
+    });
+
+    ui.add_space(20.);
+
     let text = shared_ui.loc("export_modal.armature.tex_atlas");
     ui.heading(text);
 
@@ -252,12 +323,16 @@ pub fn armature_export(
         ui.label(shared_ui.loc("export_modal.armature.img_format"));
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             let dropdown = egui::ComboBox::new("img_format", "")
-                .selected_text(&edit_mode.export_img_format.to_string())
-                .width(80.);
+                .selected_text(edit_mode.export_img_format.label())
+                .width(120.);
             dropdown.show_ui(ui, |ui| {
                 let mut selected = edit_mode.export_img_format.clone();
-                ui.selectable_value(&mut selected, ExportImgFormat::PNG, "PNG");
-                ui.selectable_value(&mut selected, ExportImgFormat::JPG, "JPG");
+                #[rustfmt::skip]
+                let formats = [ExportImgFormat::PNG, ExportImgFormat::JPG, ExportImgFormat::Webp, ExportImgFormat::WebpReducedColors];
+                for format in formats {
+                    let label = format.label();
+                    ui.selectable_value(&mut selected, format, label);
+                }
                 if selected != edit_mode.export_img_format {
                     events.set_export_img_format(selected as usize);
                 }
@@ -296,6 +371,100 @@ pub fn armature_export(
             }
         });
     });
+
+    let atlas = &edit_mode.export_atlas;
+    ui.horizontal(|ui| {
+        ui.label(shared_ui.loc("export_modal.armature.max_size"));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let max = &atlas.max_size;
+            let mut either = false;
+            let mut result = max.clone();
+            #[rustfmt::skip]
+            let (edited, value, _) = ui.float_input("atlas_max_x".into(), shared_ui, max.x as f32, 1., None);
+            either |= edited;
+            result.x = (value as i32).max(1);
+            #[rustfmt::skip]
+            let (edited, value, _) = ui.float_input("atlas_max_y".into(), shared_ui, max.y as f32, 1., None);
+            either |= edited;
+            result.y = (value as i32).max(1);
+            if either {
+                events.set_export_atlas_max_size(result);
+            }
+        });
+    });
+
+    let str_pot = shared_ui.loc("export_modal.armature.power_of_two");
+    let str_pot_desc = shared_ui.loc("export_modal.armature.power_of_two_desc");
+    let mut pot = atlas.power_of_two;
+    basic_checkbox(ui, &str_pot, &str_pot_desc, &mut pot, config, true);
+    if pot != atlas.power_of_two {
+        events.toggle_atlas_power_of_two(if pot { 1 } else { 0 });
+    }
+
+    let str_square = shared_ui.loc("export_modal.armature.square");
+    let str_square_desc = shared_ui.loc("export_modal.armature.square_desc");
+    let mut square = atlas.square;
+    basic_checkbox(
+        ui,
+        &str_square,
+        &str_square_desc,
+        &mut square,
+        config,
+        false,
+    );
+    if square != atlas.square {
+        events.toggle_atlas_square(if square { 1 } else { 0 });
+    }
+
+    alt_hor(ui, config, true, |ui| {
+        ui.label(shared_ui.loc("export_modal.armature.extrude"))
+            .on_hover_text(shared_ui.loc("export_modal.armature.extrude_desc"));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let extrude = atlas.extrude as f32;
+            #[rustfmt::skip]
+            let (edited, value, _) = ui.float_input("atlas_extrude".into(), shared_ui, extrude, 1., None);
+            if edited {
+                events.set_export_atlas_extrude((value as i32).max(0));
+            }
+        });
+    });
+
+    // other formats' atlases can't describe trimmed or turned textures
+    ui.add_enabled_ui(edit_mode.export_format == ExportArmatureFormat::Skf, |ui| {
+        let str_trim = shared_ui.loc("export_modal.armature.trim");
+        let str_trim_desc = shared_ui.loc("export_modal.armature.trim_desc");
+        let mut trim = atlas.trim;
+        basic_checkbox(ui, &str_trim, &str_trim_desc, &mut trim, config, false);
+        if trim != atlas.trim {
+            events.toggle_atlas_trim(if trim { 1 } else { 0 });
+        }
+
+        let str_rotate = shared_ui.loc("export_modal.armature.rotate");
+        let str_rotate_desc = shared_ui.loc("export_modal.armature.rotate_desc");
+        let mut rotate = atlas.rotate;
+        basic_checkbox(ui, &str_rotate, &str_rotate_desc, &mut rotate, config, true);
+        if rotate != atlas.rotate {
+            events.toggle_atlas_rotate(if rotate { 1 } else { 0 });
+        }
+    });
+
+    // DragonBones and Godot scenes can't mark atlases as premultiplied
+    ui.add_enabled_ui(edit_mode.can_premultiply(), |ui| {
+        let str_premult = shared_ui.loc("export_modal.armature.premultiplied");
+        let str_premult_desc = shared_ui.loc("export_modal.armature.premultiplied_desc");
+        let mut premult = edit_mode.export_premultiplied;
+        basic_checkbox(
+            ui,
+            &str_premult,
+            &str_premult_desc,
+            &mut premult,
+            config,
+            false,
+        );
+        if premult != edit_mode.export_premultiplied {
+            events.toggle_premultiplied(if premult { 1 } else { 0 });
+        }
+    });
 }
 
 pub fn image_export(
@@ -334,8 +503,9 @@ pub fn image_export(
         });
     });
 
-    // sprites per row if spritesheet is selected
-    ui.add_enabled_ui(!shared_ui.image_sequences, |ui: &mut egui::Ui| {
+    // sprites per row if spritesheet is selected (packed sheets are laid out by the packer)
+    let is_grid = !shared_ui.image_sequences && !shared_ui.pack_spritesheets;
+    ui.add_enabled_ui(is_grid, |ui: &mut egui::Ui| {
         ui.horizontal(|ui| {
             ui.label(shared_ui.loc("export_modal.image.sprites_per_row"));
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -349,8 +519,20 @@ pub fn image_export(
         });
     });
 
+    // trim & pack frames, with a JSON sidecar
+    ui.add_enabled_ui(!shared_ui.image_sequences, |ui: &mut egui::Ui| {
+        basic_checkbox(
+            ui,
+            &shared_ui.loc("export_modal.image.pack"),
+            &shared_ui.loc("export_modal.image.pack_desc"),
+            &mut shared_ui.pack_spritesheets,
+            config,
+            true,
+        );
+    });
+
     // size per sprite (width & height)
-    alt_hor(ui, config, true, |ui| {
+    alt_hor(ui, config, false, |ui| {
         ui.label(shared_ui.loc("export_modal.image.size_per_sprite"));
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             let x = shared_ui.sprite_size.x;
@@ -367,6 +549,8 @@ pub fn image_export(
         });
     });
 
+    fps_input(ui, shared_ui, config, true);
+
     basic_checkbox(
         ui,
         &shared_ui.loc("export_modal.video.global_bounds"),
@@ -402,12 +586,21 @@ pub fn video_export(
         ui.label(shared_ui.loc("export_modal.video.format"));
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             let dropdown = egui::ComboBox::new("export_video", "")
-                .selected_text(&shared_ui.exporting_video_type.to_string().to_uppercase())
-                .width(80.);
+                .selected_text(shared_ui.exporting_video_type.label())
+                .width(130.);
             dropdown.show_ui(ui, |ui| {
-                let export = &mut shared_ui.exporting_video_type;
-                ui.selectable_value(export, crate::ExportVideoType::Mp4, "MP4");
-                ui.selectable_value(export, crate::ExportVideoType::Gif, "GIF");
+                use crate::ExportVideoType as Type;
+                #[rustfmt::skip]
+                let types = [Type::Mp4, Type::Gif, Type::Apng, Type::Webp, Type::Webm, Type::ProRes, Type::PngMov];
+                for video_type in types {
+                    // ffmpeg on the web only encodes mp4
+                    #[cfg(target_arch = "wasm32")]
+                    if !video_type.is_animated_image() && video_type != Type::Mp4 {
+                        continue;
+                    }
+                    let label = video_type.label();
+                    ui.selectable_value(&mut shared_ui.exporting_video_type, video_type, label);
+                }
             });
         });
     });
@@ -430,9 +623,9 @@ pub fn video_export(
         });
     });
 
-    // background (clear) color
-    let is_mp4 = shared_ui.exporting_video_type == crate::ExportVideoType::Mp4;
-    ui.add_enabled_ui(is_mp4, |ui| {
+    // background (clear) color, for formats without alpha
+    let is_video = !shared_ui.exporting_video_type.is_animated_image();
+    ui.add_enabled_ui(!shared_ui.exporting_video_type.has_alpha(), |ui| {
         alt_hor(ui, config, true, |ui| {
             ui.label(shared_ui.loc("export_modal.video.background_color"));
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -444,11 +637,11 @@ pub fn video_export(
         });
     });
 
-    // loop cycles
-    if !is_mp4 {
+    // loop cycles (animated images loop by themselves)
+    if !is_video {
         shared_ui.anim_cycles = 1;
     }
-    ui.add_enabled_ui(is_mp4, |ui| {
+    ui.add_enabled_ui(is_video, |ui| {
         ui.horizontal(|ui| {
             ui.label(shared_ui.loc("export_modal.video.cycles"));
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -462,21 +655,24 @@ pub fn video_export(
         });
     });
 
+    fps_input(ui, shared_ui, config, true);
+
     basic_checkbox(
         ui,
         &shared_ui.loc("export_modal.video.global_bounds"),
         &shared_ui.loc("export_modal.video.global_bounds_desc"),
         &mut shared_ui.export_global_bounds,
         config,
-        true,
+        false,
     );
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        // disabled: encoder dropdown - default is always used for now
-        if false {
-            ui.horizontal(|ui| {
-                ui.label(shared_ui.loc("export_modal.video.encoder"));
+        // encoder, for mp4 (the other video formats only have one)
+        let is_mp4 = shared_ui.exporting_video_type == crate::ExportVideoType::Mp4;
+        ui.horizontal(|ui| {
+            ui.label(shared_ui.loc("export_modal.video.encoder"));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 ui.add_enabled_ui(is_mp4, |ui| {
                     let encoder_str = &shared_ui.exporting_video_encoder.to_string().to_lowercase();
                     let dropdown = egui::ComboBox::new("export_encoder", "")
@@ -489,31 +685,49 @@ pub fn video_export(
                     });
                 });
             });
-        }
-
-        // allow using system (global) ffmpeg, if user already has it and prefers
-        // not to download local
-
-        basic_checkbox(
-            ui,
-            &shared_ui.loc("export_modal.video.use_system_ffmpeg"),
-            &shared_ui.loc("export_modal.video.use_system_ffmpeg_desc"),
-            &mut shared_ui.use_system_ffmpeg,
-            config,
-            false,
-        );
+        });
 
-        // download ffmpeg button (not needed for macos)
-        #[cfg(not(target_os = "macos"))]
-        {
-            download_ffmpeg_button(ui);
-        }
+        // only videos need ffmpeg, animated images are encoded in-house
+        ui.add_enabled_ui(is_video, |ui| {
+            // allow using system (global) ffmpeg, if user already has it and prefers
+            // not to download local
+
+            basic_checkbox(
+                ui,
+                &shared_ui.loc("export_modal.video.use_system_ffmpeg"),
+                &shared_ui.loc("export_modal.video.use_system_ffmpeg_desc"),
+                &mut shared_ui.use_system_ffmpeg,
+                config,
+                false,
+            );
+
+            // download ffmpeg button (not needed for macos)
+            #[cfg(not(target_os = "macos"))]
+            {
+                download_ffmpeg_button(ui);
+            }
+        });
     }
 
     ui.add_space(20.);
     animations_list(ui, shared_ui, armature, _width, config);
 }
 
+// frame rate to resample animations to, with 0 keeping their own
+fn fps_input(ui: &mut egui::Ui, shared_ui: &mut crate::Ui, config: &Config, alt: bool) {
+    alt_hor(ui, config, alt, |ui| {
+        ui.label(shared_ui.loc("export_modal.video.fps"))
+            .on_hover_text(shared_ui.loc("export_modal.video.fps_desc"));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let fps = shared_ui.export_fps as f32;
+            let (edited, value, _) = ui.float_input("export_fps".into(), shared_ui, fps, 1., None);
+            if edited {
+                shared_ui.export_fps = (value as i32).max(0);
+            }
+        });
+    });
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn download_ffmpeg_button(ui: &mut egui::Ui) {
     #[allow(unreachable_code)]
@@ -669,15 +883,27 @@ fn animations_list(
 
                     ui.add_space(10.);
 
+                    // times to play, for animated images
+                    if shared_ui.exporting_video_type.is_animated_image() {
+                        let id = "export_loops_".to_string() + &a.to_string();
+                        let plays = shared_ui.exporting_loops[a] as f32;
+                        let (edited, value, _) = ui.float_input(id, shared_ui, plays, 1., None);
+                        if edited {
+                            shared_ui.exporting_loops[a] = value.clamp(0., u16::MAX as f32) as u16;
+                        }
+                        ui.label(shared_ui.loc("export_modal.video.plays"))
+                            .on_hover_text(shared_ui.loc("export_modal.video.plays_desc"));
+                        ui.add_space(10.);
+                    }
+
                     // show frame info, if this animation has any
                     // (frameless anims are allowed for export)
-                    let total_frames = anim.keyframes.last();
-                    if total_frames == None {
+                    if anim.keyframes.is_empty() && anim.length == 0 {
                         return;
                     }
                     let str = anim.fps.to_string()
                         + &" FPS  -  ".to_string()
-                        + &total_frames.unwrap().frame.to_string()
+                        + &anim.len_frames().to_string()
                         + &shared_ui.loc("export_modal.image.frames");
                     let mut meta_col = config.colors.text;
                     meta_col -= crate::Color::new(40, 40, 40, 0);
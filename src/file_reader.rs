@@ -203,6 +203,32 @@ pub fn add_pending_textures(
     shared.ui.pending_textures = vec![];
 }
 
+/// A layer group flattened into one image, ready to become a textured bone.
+pub struct LayerGroup {
+    pub id: u32,
+    pub name: String,
+    pub parent_id: Option<u32>,
+    pub image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    /// top-left of the image on the canvas
+    pub offset: Vec2,
+}
+
+/// A `$` layer (`$pivot`, `$ik_`) inside a group, with its bounds on the canvas.
+pub struct LayerMarker {
+    pub group_id: u32,
+    pub name: String,
+    pub pos: Vec2,
+    pub size: Vec2,
+}
+
+/// Everything a layered image (PSD, Aseprite) gives to build a rig from.
+pub struct LayerRig {
+    pub dimensions: Vec2,
+    /// parents come before their children
+    pub groups: Vec<LayerGroup>,
+    pub markers: Vec<LayerMarker>,
+}
+
 pub fn read_psd(
     bytes: Vec<u8>,
     shared: &mut Shared,
@@ -213,16 +239,6 @@ pub fn read_psd(
 ) {
     let psd = psd::Psd::from_bytes(&bytes).unwrap();
 
-    // reset armature (but not all of it) to make way for the psd rig
-    shared.psd_armature.bones = vec![];
-    shared.psd_armature.styles = shared.armature.styles.clone();
-    shared.psd_armature.tex_data = shared.armature.tex_data.clone();
-
-    // create root bone, where all except targets will go
-    shared.psd_armature.new_bone(-1);
-    shared.psd_armature.bones[0].name = "Root".to_string();
-    shared.psd_armature.bones[0].folded = true;
-
     // collect group ids, to be used later
     let mut group_ids: Vec<u32> = vec![];
     for l in 0..psd.layers().len() {
@@ -235,22 +251,6 @@ pub fn read_psd(
         }
     }
 
-    // add Default style, if it doesn't already exist
-    let styles = &shared.psd_armature.styles;
-    let str_default = shared.ui.loc("settings_modal.default");
-    if styles.iter().find(|s| s.name == str_default) == None {
-        let ids = shared.psd_armature.styles.iter().map(|s| s.id).collect();
-        shared.psd_armature.styles.push(Style {
-            id: generate_id(ids),
-            name: str_default.clone(),
-            textures: vec![],
-            active: true,
-        });
-    }
-
-    let mut bone_psd_id: std::collections::HashMap<u32, i32> = Default::default();
-    let mut start_eff_ids: Vec<i32> = vec![];
-    let mut ik_family_ids: Vec<i32> = vec![];
     let dimensions = Vec2::new(psd.width() as f32, psd.height() as f32);
 
     type ImageType = (ImageBuffer<Rgba<u8>, Vec<u8>>, Vec2);
@@ -275,6 +275,7 @@ pub fn read_psd(
     }
 
     group_ids.reverse();
+    let mut groups = vec![];
     for g in 0..group_ids.len() {
         let group = &psd.groups()[&group_ids[g]];
         let image: (image::ImageBuffer<Rgba<u8>, Vec<u8>>, Vec2);
@@ -292,6 +293,143 @@ pub fn read_psd(
             image = load_psd_tex(cpsd, cgroup.clone());
         }
 
+        groups.push(LayerGroup {
+            id: group_ids[g],
+            name: group.name().to_string(),
+            parent_id: group.parent_id(),
+            image: image.0,
+            offset: image.1,
+        });
+    }
+
+    let mut markers = vec![];
+    for layer in psd.layers() {
+        if layer.parent_id().is_none() || !layer.name().contains("$") {
+            continue;
+        }
+        markers.push(LayerMarker {
+            group_id: layer.parent_id().unwrap(),
+            name: layer.name().to_string(),
+            pos: Vec2::new(layer.layer_left() as f32, layer.layer_top() as f32),
+            size: Vec2::new(layer.width() as f32, layer.height() as f32),
+        });
+    }
+
+    let rig = LayerRig {
+        dimensions,
+        groups,
+        markers,
+    };
+    build_layer_rig(rig, shared, queue, device, bind_group_layout, ctx);
+
+    // show polar modal to replace real armature bones as well
+    let str = shared.ui.loc("psd_imported");
+    shared.events.open_polar_modal(PolarId::ImportedPsd, str);
+
+    shared.ui.startup_window = false;
+}
+
+/// Aseprite sprite, rigged from its layer groups like a PSD. Tagged frames become styles.
+pub fn read_aseprite(
+    bytes: Vec<u8>,
+    shared: &mut Shared,
+    queue: Option<&Queue>,
+    device: Option<&Device>,
+    bind_group_layout: Option<&BindGroupLayout>,
+    ctx: Option<&egui::Context>,
+) {
+    let ase = match aseprite::Aseprite::from_bytes(&bytes) {
+        Ok(ase) => ase,
+        Err(e) => {
+            shared.ui.custom_error = e.to_string();
+            let str = shared.ui.loc("error_skf");
+            editor::open_modal(&mut shared.ui, false, str);
+            return;
+        }
+    };
+
+    let bgl = bind_group_layout;
+    build_layer_rig(ase.layer_rig(), shared, queue, device, bgl, ctx);
+
+    let str = shared.ui.loc("aseprite_imported");
+    shared.events.open_polar_modal(PolarId::ImportedPsd, str);
+
+    shared.ui.startup_window = false;
+}
+
+/// OpenRaster or Krita document, rigged from its layer groups like a PSD.
+pub fn read_ora(
+    bytes: Vec<u8>,
+    shared: &mut Shared,
+    queue: Option<&Queue>,
+    device: Option<&Device>,
+    bind_group_layout: Option<&BindGroupLayout>,
+    ctx: Option<&egui::Context>,
+) {
+    let rig = match openraster::read_layered_zip(std::io::Cursor::new(bytes)) {
+        Ok(rig) => rig,
+        Err(e) => {
+            shared.ui.custom_error = e.to_string();
+            let str = shared.ui.loc("error_skf");
+            editor::open_modal(&mut shared.ui, false, str);
+            return;
+        }
+    };
+
+    build_layer_rig(rig, shared, queue, device, bind_group_layout, ctx);
+
+    let str = shared.ui.loc("ora_imported");
+    shared.events.open_polar_modal(PolarId::ImportedPsd, str);
+
+    shared.ui.startup_window = false;
+}
+
+/// Build `shared.psd_armature` out of layer groups, and import its textures.
+///
+/// Groups become textured bones, `$pivot` and `$ik_` markers set their pivots and IK, and
+/// groups named `$"style"` add their parent's texture to that style instead.
+pub fn build_layer_rig(
+    rig: LayerRig,
+    shared: &mut Shared,
+    queue: Option<&Queue>,
+    device: Option<&Device>,
+    bind_group_layout: Option<&BindGroupLayout>,
+    ctx: Option<&egui::Context>,
+) {
+    // reset armature (but not all of it) to make way for the rig
+    shared.psd_armature.bones = vec![];
+    shared.psd_armature.styles = shared.armature.styles.clone();
+    shared.psd_armature.tex_data = shared.armature.tex_data.clone();
+
+    // create root bone, where all except targets will go
+    shared.psd_armature.new_bone(-1);
+    shared.psd_armature.bones[0].name = "Root".to_string();
+    shared.psd_armature.bones[0].folded = true;
+
+    // add Default style, if it doesn't already exist
+    let styles = &shared.psd_armature.styles;
+    let str_default = shared.ui.loc("settings_modal.default");
+    if !styles.iter().any(|s| s.name == str_default) {
+        let ids = shared.psd_armature.styles.iter().map(|s| s.id).collect();
+        shared.psd_armature.styles.push(Style {
+            id: generate_id(ids),
+            name: str_default.clone(),
+            textures: vec![],
+            active: true,
+        });
+    }
+
+    let mut bone_psd_id: std::collections::HashMap<u32, i32> = Default::default();
+    let mut start_eff_ids: Vec<i32> = vec![];
+    let mut ik_family_ids: Vec<i32> = vec![];
+    let LayerRig {
+        dimensions,
+        groups,
+        markers,
+    } = rig;
+
+    for group in &groups {
+        let image = (&group.image, group.offset);
         let mut dims = Vec2::new(image.0.width() as f32, image.0.height() as f32);
 
         // add tex if not a duplicate
@@ -312,13 +450,13 @@ pub fn read_psd(
             let styles = &shared.psd_armature.styles;
             let str_default = str_default.clone();
             let mut style_id = styles.iter().find(|s| s.name == str_default).unwrap().id;
-            let mut tex_name = group.name();
+            let mut tex_name = group.name.as_str();
 
-            if group.name().contains("$\"") {
-                let split: Vec<&str> = group.name().split('"').collect();
+            if group.name.contains("$\"") {
+                let split: Vec<&str> = group.name.split('"').collect();
 
-                let parent_id = &group.parent_id().unwrap();
-                tex_name = psd.groups().get(parent_id).unwrap().name();
+                let parent = groups.iter().find(|g| Some(g.id) == group.parent_id);
+                tex_name = parent.map(|g| g.name.as_str()).unwrap_or(tex_name);
 
                 let low = split[1].to_lowercase().trim().to_string();
 
@@ -363,13 +501,13 @@ pub fn read_psd(
             tex_idx = default_style.textures.len() - 1;
         }
 
-        if group.name().contains("$\"") {
+        if group.name.contains("$\"") {
             continue;
         }
 
         // create texture bone
         let bone_id = shared.psd_armature.new_bone(-1).0.id;
-        bone_psd_id.insert(group_ids[g] as u32, bone_id);
+        bone_psd_id.insert(group.id, bone_id);
 
         let styles = &shared.psd_armature.styles;
         let str_default = str_default.clone();
@@ -377,23 +515,19 @@ pub fn read_psd(
         let tex = default_style.textures[tex_idx].clone();
         let bone = shared.psd_armature.find_bone_mut(bone_id).unwrap();
         bone.parent_id = 0;
-        bone.name = group.name().to_string();
+        bone.name = group.name.to_string();
         bone.tex = tex.name.clone();
         shared
             .psd_armature
             .set_bone_tex(bone_id, tex.name.clone(), usize::MAX, -1);
 
         // check if this group has a pivot, and create it if so
-        for l in 0..psd.layers().len() {
-            let layer = &psd.layers()[l];
-            if layer.parent_id() != Some(group_ids[g]) || !layer.name().contains("$pivot") {
+        for marker in &markers {
+            if marker.group_id != group.id || !marker.name.contains("$pivot") {
                 continue;
             }
 
-            let pivot_pos = Vec2::new(
-                layer.layer_left() as f32 + layer.width() as f32 / 2.,
-                layer.layer_top() as f32 + layer.height() as f32 / 2.,
-            );
+            let pivot_pos = marker.pos + marker.size / 2.;
             let bone = shared.psd_armature.find_bone_mut(bone_id).unwrap();
             let pivot = Vec2::new(
                 -(pivot_pos.x - image.1.x - image.0.width() as f32 / 2.) / image.0.width() as f32,
@@ -403,20 +537,19 @@ pub fn read_psd(
         }
 
         // process inverse kinematics layers ($ik_)
-        for l in 0..psd.layers().len() {
-            let layer = &psd.layers()[l];
-            if layer.parent_id() != Some(group_ids[g]) || !layer.name().contains("$ik_") {
+        for marker in &markers {
+            if marker.group_id != group.id || !marker.name.contains("$ik_") {
                 continue;
             }
 
             let bone = shared.psd_armature.find_bone_mut(bone_id).unwrap();
 
-            if layer.name().contains("counterclockwise") {
+            if marker.name.contains("counterclockwise") {
                 bone.ik_constraint = JointConstraint::CounterClockwise;
-            } else if layer.name().contains("clockwise") {
+            } else if marker.name.contains("clockwise") {
                 bone.ik_constraint = JointConstraint::Clockwise;
             } else {
-                let num = utils::without_unicode(utils::after_underscore(layer.name()));
+                let num = utils::without_unicode(utils::after_underscore(&marker.name));
                 match num.parse::<i32>() {
                     Ok(id) => {
                         bone.ik_family_id = id;
@@ -444,8 +577,8 @@ pub fn read_psd(
         new_bone.pos -= new_bone.pivot_pos * dims;
 
         // add this bone to parent, if appropriate
-        if group.parent_id() != None {
-            if let Some(parent_id) = bone_psd_id.get(&group.parent_id().unwrap()) {
+        if let Some(group_parent) = group.parent_id {
+            if let Some(parent_id) = bone_psd_id.get(&group_parent) {
                 let parent = shared.psd_armature.find_bone_mut(bone_id).unwrap();
                 parent.parent_id = *parent_id;
                 parent.folded = true;
@@ -549,13 +682,8 @@ pub fn read_psd(
     // immediately import textures to real armature
     shared.armature.styles = shared.psd_armature.styles.clone();
     shared.armature.tex_data = shared.psd_armature.tex_data.clone();
-
-    // show polar modal to replace real armature bones as well
-    let str = shared.ui.loc("psd_imported");
-    shared.events.open_polar_modal(PolarId::ImportedPsd, str);
-
-    shared.ui.startup_window = false;
 }
+
 pub fn create_texture(
     image: &image::DynamicImage,
     dimensions: Vec2,
@@ -631,6 +759,7 @@ pub fn add_texture(
         ser_size: Vec2I::new(0, 0),
         data_id: id,
         atlas_idx: 0,
+        ..Default::default()
     });
 }
 
@@ -703,6 +832,46 @@ pub fn read_import(
             #[cfg(target_arch = "wasm32")]
             read_psd(file, shared, queue, device, bgl, context)
         }
+        "aseprite" | "ase" => {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let file = std::fs::read(filepath).unwrap();
+                read_aseprite(file, shared, queue, device, bgl, context);
+            }
+            #[cfg(target_arch = "wasm32")]
+            read_aseprite(file, shared, queue, device, bgl, context)
+        }
+        "ora" | "kra" => {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let file = std::fs::read(filepath).unwrap();
+                read_ora(file, shared, queue, device, bgl, context);
+            }
+            #[cfg(target_arch = "wasm32")]
+            read_ora(file, shared, queue, device, bgl, context)
+        }
+        "json" => {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let path = std::path::Path::new(&filepath);
+                let armature = if filename.ends_with("_ske.json") {
+                    read_dragonbones_files(path)
+                } else {
+                    read_spine_files(path)
+                };
+                import_armature(armature, shared, queue, device, bgl, context);
+            }
+            // the atlas next to the skeleton can't be reached from the browser
+            #[cfg(target_arch = "wasm32")]
+            shared.events.open_modal("import_skeleton_zip", false);
+        }
+        "zip" => {
+            #[cfg(not(target_arch = "wasm32"))]
+            let armature = export_common::read_skeleton_zip(file.unwrap());
+            #[cfg(target_arch = "wasm32")]
+            let armature = export_common::read_skeleton_zip(std::io::Cursor::new(file));
+            import_armature(armature, shared, queue, device, bgl, context);
+        }
         _ => {
             shared.events.open_modal("import_unrecognized", false);
         }
@@ -713,6 +882,108 @@ pub fn read_import(
     removeFile();
 }
 
+/// Replace the armature with one converted from another format, or show why it couldn't be.
+pub fn import_armature(
+    armature: Result<Armature, SkfError>,
+    shared: &mut Shared,
+    queue: Option<&Queue>,
+    device: Option<&Device>,
+    bgl: Option<&BindGroupLayout>,
+    context: Option<&egui::Context>,
+) {
+    let mut armature = match armature {
+        Ok(armature) => armature,
+        Err(e) => {
+            shared.ui.custom_error = e.to_string();
+            let str = shared.ui.loc("error_skf");
+            editor::open_modal(&mut shared.ui, false, str);
+            return;
+        }
+    };
+
+    for data in &mut armature.tex_data {
+        let size = Vec2::new(data.image.width() as f32, data.image.height() as f32);
+        (data.bind_group, data.ui_img) =
+            create_texture(&data.image, size, queue, device, bgl, context);
+    }
+    shared.ui.repairs = utils::repair_armature(&mut armature);
+    shared.armature = armature;
+
+    shared.events.unselect_all();
+    shared.ui.startup_window = false;
+}
+
+/// Spine skeleton JSON, with its atlas (and the atlas' pages) next to it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_spine_files(path: &std::path::Path) -> Result<Armature, SkfError> {
+    let io_err = |e: std::io::Error| SkfError::Io(e.to_string());
+    let skeleton = std::fs::read_to_string(path).map_err(io_err)?;
+
+    // an atlas of the same name, otherwise any atlas in the same folder
+    let is_atlas = |p: &std::path::PathBuf| {
+        let name = p
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        name.ends_with(".atlas") || name.ends_with(".atlas.txt")
+    };
+    let dir = path.parent().unwrap_or(std::path::Path::new("."));
+    let mut atlas_path = path.with_extension("atlas");
+    if !atlas_path.exists() {
+        let entries = std::fs::read_dir(dir).map_err(io_err)?;
+        let atlas = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .find(is_atlas);
+        if atlas.is_none() {
+            let name = atlas_path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            return Err(SkfError::MissingFile(name));
+        }
+        atlas_path = atlas.unwrap();
+    }
+    let atlas = std::fs::read_to_string(&atlas_path).map_err(io_err)?;
+
+    let atlas_dir = atlas_path.parent().unwrap_or(dir);
+    spine::read_spine(&skeleton, &atlas, &mut |page| {
+        image::open(atlas_dir.join(page)).ok()
+    })
+}
+
+/// DragonBones skeleton (`{name}_ske.json`), with its atlases (`{name}_tex.json`, and
+/// `{name}_tex_1.json` etc for extra ones) and their images next to it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_dragonbones_files(path: &std::path::Path) -> Result<Armature, SkfError> {
+    let io_err = |e: std::io::Error| SkfError::Io(e.to_string());
+    let skeleton = std::fs::read_to_string(path).map_err(io_err)?;
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = file_name.trim_end_matches("_ske.json");
+    let dir = path.parent().unwrap_or(std::path::Path::new("."));
+    let mut atlas_names: Vec<String> = std::fs::read_dir(dir)
+        .map_err(io_err)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| export_common::is_dragonbones_atlas(stem, name))
+        .collect();
+    if atlas_names.is_empty() {
+        return Err(SkfError::MissingFile(format!("{}_tex.json", stem)));
+    }
+    atlas_names.sort();
+    let mut atlases = vec![];
+    for name in atlas_names {
+        atlases.push(std::fs::read_to_string(dir.join(name)).map_err(io_err)?);
+    }
+
+    dragonbones::read_dragonbones(&skeleton, &atlases, &mut |image| {
+        image::open(dir.join(image)).ok()
+    })
+}
+
 /// Load image by reading an `img` tag with the specified ID.
 #[cfg(target_arch = "wasm32")]
 pub fn load_image_wasm(id: String) -> Option<(Vec<u8>, Vec2)> {
@@ -0,0 +1,771 @@
+//! Godot 4 export: a `.tscn` scene with a `Skeleton2D` rig, its textures and an `AnimationPlayer`.
+//!
+//! Bones become `Bone2D`s, rects `Sprite2D`s and meshes `Polygon2D`s (skinned by the skeleton
+//! if they have binds). IK families become skeleton modifications, and animations become
+//! bezier tracks with the same handles. Godot is Y-down, so Y and rotations are flipped.
+
+use crate::export_common::{
+    active_tex, bone_tip, ik_families, num, propagated_hidden, slot_tex_names, tex_at, tex_frames,
+    track, vertex_weights, Feature,
+};
+use crate::*;
+use std::collections::HashMap;
+use std::io::{Seek, Write};
+
+/// Method that animation events call on the scene's root node, with the event's name, value
+/// and string value.
+pub const EVENT_METHOD: &str = "skelform_event";
+
+// bezier tracks can't step, so stepped segments jump just before their next key instead
+const STEP_GAP: f32 = 0.001;
+
+/// Features that Godot scenes can't represent.
+pub const UNSUPPORTED: &[Feature] = &[
+    Feature::Sway,
+    Feature::Physics,
+    Feature::PathBinds,
+    Feature::IkMimic,
+    Feature::IkKeys,
+    Feature::Additive,
+    Feature::Reverse,
+    Feature::InactiveStyles,
+];
+
+/// Write a Godot scene (skeleton.tscn and its atlas pages) into a zip.
+///
+/// Pages are referenced relative to the scene, so they're imported wherever it's unzipped.
+pub fn write_godot<W: Write + Seek>(
+    writer: W,
+    armature: &Armature,
+    edit_mode: &EditMode,
+) -> zip::result::ZipResult<W> {
+    let mut carmature = armature.clone();
+    let mut bufs = vec![];
+    if carmature.styles.iter().any(|s| !s.textures.is_empty()) {
+        (bufs, _) = utils::create_tex_sheet(&mut carmature, edit_mode);
+    }
+
+    let ext = format!(".{}", edit_mode.export_img_format.extension());
+    let pages: Vec<String> = (0..bufs.len())
+        .map(|i| match i {
+            0 => format!("skeleton{}", ext),
+            _ => format!("skeleton_{}{}", i, ext),
+        })
+        .collect();
+
+    let mut zip = zip::ZipWriter::new(writer);
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    zip.start_file("skeleton.tscn", options)?;
+    zip.write_all(scene(&carmature, &pages).as_bytes())?;
+    for (page, buf) in pages.iter().zip(&bufs) {
+        zip.start_file(page, options)?;
+        zip.write_all(buf)?;
+    }
+
+    zip.finish()
+}
+
+/// Godot scene of the armature, with textures from the given atlas pages.
+///
+/// Textures should already be placed in atlases by `utils::create_tex_sheet`. Only the active
+/// styles are used, since Godot has nothing like them.
+pub fn scene(armature: &Armature, pages: &[String]) -> String {
+    let bones = tree_order(armature);
+    let (names, drawable_names) = node_names(armature);
+    let hiddens = propagated_hidden(armature);
+
+    // bone paths from the skeleton, as modifications and polygons refer to them
+    let mut paths: HashMap<i32, String> = HashMap::new();
+    for bone in &bones {
+        let path = match paths.get(&bone.parent_id) {
+            Some(parent) => format!("{}/{}", parent, names[&bone.id]),
+            None => names[&bone.id].clone(),
+        };
+        paths.insert(bone.id, path);
+    }
+    let idx = |id: i32| bones.iter().position(|b| b.id == id).unwrap();
+
+    // skinned polygons sit next to the skeleton, and the rest of the drawables under their bones
+    let mut drawables: HashMap<i32, String> = HashMap::new();
+    for bone in &bones {
+        if setup_tex(armature, bone).is_none() {
+            continue;
+        }
+        let path = match is_skinned(armature, bone) {
+            true => drawable_names[&bone.id].clone(),
+            false => format!(
+                "Skeleton2D/{}/{}",
+                paths[&bone.id], drawable_names[&bone.id]
+            ),
+        };
+        drawables.insert(bone.id, path);
+    }
+
+    // SkelForm weighs vertices in each bone's own space rather than from a rest pose, so
+    // bones that skin polygons (and their parents) rest at identity
+    let mut identity_rests: Vec<i32> = vec![];
+    for bone in armature.bones.iter().filter(|b| is_skinned(armature, b)) {
+        for vert in &bone.vertices {
+            for (b, _) in vertex_weights(armature, bone, vert) {
+                let mut id = armature.bones[b].id;
+                while let Some(bone) = armature.bones.iter().find(|b| b.id == id) {
+                    if !identity_rests.contains(&id) {
+                        identity_rests.push(id);
+                    }
+                    id = bone.parent_id;
+                }
+            }
+        }
+    }
+
+    let mut ext_resources = String::new();
+    for (p, page) in pages.iter().enumerate() {
+        #[rustfmt::skip]
+        let line = format!("[ext_resource type=\"Texture2D\" path={} id=\"page_{}\"]\n", string(page), p);
+        ext_resources += &line;
+    }
+
+    // inverse kinematics, as modifications of the skeleton
+    let mut sub_resources: Vec<String> = vec![];
+    let mut modifications = vec![];
+    for joints in ik_families(armature) {
+        let target = paths.get(&joints[0].ik_target_id);
+        if target.is_none() || joints.len() < 2 {
+            continue;
+        }
+        let id = format!("ik_{}", joints[0].ik_family_id);
+        let last = joints.len() - 1;
+        let joint = |j: usize, prefix: &str, idx_name: &str| {
+            let bone = joints[j];
+            let mut props = format!("{}{} = {}\n", prefix, idx_name, idx(bone.id));
+            props += &format!("{}bone2d_node = {}\n", prefix, node_path(&paths[&bone.id]));
+            props
+        };
+
+        // the last joint is the tip, which the bones before it bend to reach the target
+        let (kind, props) = if joints.len() == 2 {
+            ("SkeletonModification2DLookAt", joint(0, "", "bone_index"))
+        } else if joints[0].ik_mode == InverseKinematicsMode::FABRIK {
+            let mut props = format!("fabrik_data_chain_length = {}\n", last);
+            for j in 0..last {
+                props += &joint(j, &format!("joint_data/{}/", j), "bone_index");
+            }
+            ("SkeletonModification2DFABRIK", props)
+        } else if joints.len() == 3 {
+            let flip = joints[0].ik_constraint != JointConstraint::Clockwise;
+            let mut props = format!("flip_bend_direction = {}\n", flip);
+            props += &joint(0, "joint_one_", "bone_idx");
+            props += &joint(1, "joint_two_", "bone_idx");
+            ("SkeletonModification2DTwoBoneIK", props)
+        } else {
+            let mut props = format!("tip_nodepath = {}\n", node_path(&paths[&joints[last].id]));
+            props += &format!("ccdik_data_chain_length = {}\n", last);
+            for j in 0..last {
+                props += &joint(j, &format!("joint_data/{}/", j), "bone_index");
+            }
+            ("SkeletonModification2DCCDIK", props)
+        };
+
+        let mut res = format!("[sub_resource type=\"{}\" id=\"{}\"]\n", kind, id);
+        let skipped = joints[0].ik_mode == InverseKinematicsMode::Skip
+            || joints[0].ik_constraint == JointConstraint::Skip;
+        if skipped {
+            res += "enabled = false\n";
+        }
+        res += &format!("target_nodepath = {}\n", node_path(target.unwrap()));
+        res += &props;
+        sub_resources.push(res);
+        modifications.push(id);
+    }
+    if !modifications.is_empty() {
+        let mut res =
+            "[sub_resource type=\"SkeletonModificationStack2D\" id=\"ik_stack\"]\n".to_string();
+        res += "enabled = true\n";
+        res += &format!("modification_count = {}\n", modifications.len());
+        for (m, id) in modifications.iter().enumerate() {
+            res += &format!("modifications/{} = SubResource(\"{}\")\n", m, id);
+        }
+        sub_resources.push(res);
+    }
+
+    // animations, in a library of their own
+    let mut anim_names: Vec<String> = vec![];
+    for (a, anim) in armature.animations.iter().enumerate() {
+        let mut name = anim.name.clone();
+        if anim_names.contains(&name) {
+            name = format!("{} ({})", anim.name, anim.id);
+        }
+        let tracks = tracks(armature, a, &paths, &drawables);
+        sub_resources.push(animation(a, &name, anim, &tracks));
+        anim_names.push(name);
+    }
+    if !anim_names.is_empty() {
+        let mut res = "[sub_resource type=\"AnimationLibrary\" id=\"library\"]\n".to_string();
+        let entries: Vec<String> = anim_names
+            .iter()
+            .enumerate()
+            .map(|(a, name)| format!("{}: SubResource(\"anim_{}\")", string(name), a))
+            .collect();
+        res += &format!("_data = {{\n{}\n}}\n", entries.join(",\n"));
+        sub_resources.push(res);
+    }
+
+    // nodes, parents first
+    let mut nodes = vec![];
+    // textures are drawn with nearest filtering in the editor
+    nodes.push("[node name=\"Armature\" type=\"Node2D\"]\ntexture_filter = 1\n".to_string());
+    let mut skeleton = "[node name=\"Skeleton2D\" type=\"Skeleton2D\" parent=\".\"]\n".to_string();
+    if !modifications.is_empty() {
+        skeleton += "modification_stack = SubResource(\"ik_stack\")\n";
+    }
+    nodes.push(skeleton);
+    let mut skinned = vec![];
+    for bone in &bones {
+        let parent = match paths.get(&bone.parent_id) {
+            Some(path) => format!("Skeleton2D/{}", path),
+            None => "Skeleton2D".to_string(),
+        };
+        let mut node = node(&names[&bone.id], "Bone2D", &parent);
+        node += &format!("position = {}\n", vec2(flip(bone.pos)));
+        node += &format!("rotation = {}\n", real(-bone.rot));
+        node += &format!("scale = {}\n", vec2(bone.scale));
+        node += &format!(
+            "rest = {}\n",
+            match identity_rests.contains(&bone.id) {
+                true => transform2d(Vec2::default(), 0., Vec2::new(1., 1.)),
+                false => transform2d(flip(bone.pos), -bone.rot, bone.scale),
+            }
+        );
+        let tip = bone_tip(armature, bone).map(flip);
+        node += "auto_calculate_length_and_angle = false\n";
+        // Bone2D's default length, for bones that don't point at anything
+        node += &format!("length = {}\n", real(tip.map_or(16., |tip| tip.mag())));
+        node += &format!(
+            "bone_angle = {}\n",
+            real(tip.map_or(0., |tip| tip.y.atan2(tip.x)))
+        );
+        nodes.push(node);
+
+        if !drawables.contains_key(&bone.id) {
+            continue;
+        }
+        let (name, hidden) = (&drawable_names[&bone.id], hiddens[&bone.id]);
+        if is_skinned(armature, bone) {
+            skinned.push(bone);
+        } else {
+            let parent = format!("{}/{}", parent, names[&bone.id]);
+            nodes.push(drawable(armature, bone, name, &parent, hidden, None));
+        }
+    }
+    for bone in skinned {
+        let (name, hidden) = (&drawable_names[&bone.id], hiddens[&bone.id]);
+        let skin = Some((&paths, &bones[..]));
+        nodes.push(drawable(armature, bone, name, ".", hidden, skin));
+    }
+    if !anim_names.is_empty() {
+        let mut player = node("AnimationPlayer", "AnimationPlayer", ".");
+        player += "libraries = {\n\"\": SubResource(\"library\")\n}\n";
+        nodes.push(player);
+    }
+
+    let load_steps = pages.len() + sub_resources.len() + 1;
+    let mut scene = format!("[gd_scene load_steps={} format=3]\n\n", load_steps);
+    if !ext_resources.is_empty() {
+        scene += &ext_resources;
+        scene += "\n";
+    }
+    for res in sub_resources {
+        scene += &res;
+        scene += "\n";
+    }
+    scene += &nodes.join("\n");
+    scene
+}
+
+fn node(name: &str, kind: &str, parent: &str) -> String {
+    format!(
+        "[node name={} type=\"{}\" parent={}]\n",
+        string(name),
+        kind,
+        string(parent)
+    )
+}
+
+/// Node that draws a bone's texture: a `Sprite2D` for rects, and a `Polygon2D` for meshes.
+///
+/// Skinned polygons are given the bone paths (from the skeleton) and bones in skeleton order.
+fn drawable(
+    armature: &Armature,
+    bone: &Bone,
+    name: &str,
+    parent: &str,
+    hidden: bool,
+    skin: Option<(&HashMap<i32, String>, &[&Bone])>,
+) -> String {
+    let (_, tex) = setup_tex(armature, bone).unwrap();
+    let kind = if is_mesh(bone) {
+        "Polygon2D"
+    } else {
+        "Sprite2D"
+    };
+    let mut node = node(name, kind, parent);
+    if hidden || bone.tex.is_empty() {
+        node += "visible = false\n";
+    }
+    if bone.tint != TintColor::new(1., 1., 1., 1.) {
+        node += &format!("self_modulate = {}\n", color(&bone.tint));
+    }
+    if bone.zindex != 0 {
+        node += &format!("z_index = {}\n", bone.zindex);
+    }
+
+    let pivot = tex.size * bone.pivot_pos;
+    if !is_mesh(bone) {
+        node += &format!("position = {}\n", vec2(flip(pivot)));
+        node += &format!("rotation = {}\n", real(-bone.pivot_rot));
+        node += &format!("scale = {}\n", vec2(bone.pivot_scale));
+        node += &format!("texture = ExtResource(\"page_{}\")\n", tex.atlas_idx);
+        node += "region_enabled = true\n";
+        node += &format!("region_rect = {}\n", rect2(tex));
+        return node;
+    }
+
+    let polygon: Vec<Vec2> = bone
+        .vertices
+        .iter()
+        .map(|vert| {
+            let pos = utils::rotate(&(vert.pos * bone.pivot_scale), bone.pivot_rot) + pivot;
+            flip(pos)
+        })
+        .collect();
+    let triangles: Vec<String> = bone
+        .indices
+        .chunks(3)
+        .map(|tri| {
+            format!(
+                "PackedInt32Array({})",
+                join(tri.iter().map(|i| i.to_string()))
+            )
+        })
+        .collect();
+    if skin.is_some() {
+        node += "skeleton = NodePath(\"../Skeleton2D\")\n";
+    }
+    node += &format!("texture = ExtResource(\"page_{}\")\n", tex.atlas_idx);
+    node += &format!("polygon = {}\n", packed_vec2s(&polygon));
+    node += &format!("uv = {}\n", uvs(bone, tex));
+    node += &format!("polygons = [{}]\n", triangles.join(", "));
+
+    // one weight per vertex for every bone that moves any of them
+    if let Some((paths, bones)) = skin {
+        let mut weights: Vec<(i32, Vec<f32>)> = vec![];
+        for (v, vert) in bone.vertices.iter().enumerate() {
+            for (b, weight) in vertex_weights(armature, bone, vert) {
+                let id = armature.bones[b].id;
+                if !weights.iter().any(|w| w.0 == id) {
+                    weights.push((id, vec![0.; bone.vertices.len()]));
+                }
+                weights.iter_mut().find(|w| w.0 == id).unwrap().1[v] = weight;
+            }
+        }
+        weights.sort_by_key(|w| bones.iter().position(|b| b.id == w.0));
+        let entries: Vec<String> = weights
+            .iter()
+            .map(|(id, weights)| {
+                let weights = join(weights.iter().map(|w| real(*w)));
+                format!("{}, PackedFloat32Array({})", node_path(&paths[id]), weights)
+            })
+            .collect();
+        node += &format!("bones = [{}]\n", entries.join(", "));
+        node += "internal_vertex_count = 0\n";
+    }
+    node
+}
+
+fn animation(anim_idx: usize, name: &str, anim: &Animation, tracks: &[Track]) -> String {
+    let fps = anim.fps.max(1) as f32;
+    let mut res = format!(
+        "[sub_resource type=\"Animation\" id=\"anim_{}\"]\n",
+        anim_idx
+    );
+    res += &format!("resource_name = {}\n", string(name));
+    res += &format!("length = {}\n", real(anim.len_frames().max(1) as f32 / fps));
+    let loop_mode = match anim.loop_mode {
+        LoopMode::Loop => 1,
+        LoopMode::PingPong => 2,
+        LoopMode::Once | LoopMode::Reverse => 0,
+    };
+    if loop_mode != 0 {
+        res += &format!("loop_mode = {}\n", loop_mode);
+    }
+    res += &format!("step = {}\n", real(1. / fps));
+    for (t, track) in tracks.iter().enumerate() {
+        let prefix = format!("tracks/{}/", t);
+        res += &format!("{}type = \"{}\"\n", prefix, track.kind);
+        res += &format!("{}imported = false\n", prefix);
+        res += &format!("{}enabled = true\n", prefix);
+        res += &format!("{}path = {}\n", prefix, node_path(&track.path));
+        res += &format!("{}interp = 1\n", prefix);
+        res += &format!("{}loop_wrap = true\n", prefix);
+        res += &format!("{}keys = {}\n", prefix, track.keys);
+    }
+    res
+}
+
+/// An animation track: its type, node path (from the scene's root) and keys.
+pub struct Track {
+    pub kind: &'static str,
+    pub path: String,
+    pub keys: String,
+}
+
+/// Tracks of an animation, for bones by their path from the skeleton and drawables by their
+/// path from the scene's root.
+pub fn tracks(
+    armature: &Armature,
+    anim_idx: usize,
+    paths: &HashMap<i32, String>,
+    drawables: &HashMap<i32, String>,
+) -> Vec<Track> {
+    let anim = &armature.animations[anim_idx];
+    let fps = anim.fps.max(1) as f32;
+    let time = |frame: i32| frame as f32 / fps;
+    type AE = AnimElement;
+    let mut tracks = vec![];
+
+    for bone in tree_order(armature) {
+        #[rustfmt::skip]
+        let elements = [
+            (AE::PositionX, "position:x",  1.),
+            (AE::PositionY, "position:y", -1.),
+            (AE::Rotation,  "rotation",   -1.),
+            (AE::ScaleX,    "scale:x",     1.),
+            (AE::ScaleY,    "scale:y",     1.),
+        ];
+        for (element, property, sign) in elements {
+            let kfs = track(anim, bone.id, &element);
+            if !kfs.is_empty() {
+                tracks.push(Track {
+                    kind: "bezier",
+                    path: format!("Skeleton2D/{}:{}", paths[&bone.id], property),
+                    keys: bezier_keys(&kfs, fps, sign),
+                });
+            }
+        }
+
+        let drawable = match drawables.get(&bone.id) {
+            Some(drawable) => drawable,
+            None => continue,
+        };
+
+        #[rustfmt::skip]
+        let tints = [(AE::TintR, "r"), (AE::TintG, "g"), (AE::TintB, "b"), (AE::TintA, "a")];
+        for (element, channel) in tints {
+            let kfs = track(anim, bone.id, &element);
+            if !kfs.is_empty() {
+                tracks.push(Track {
+                    kind: "bezier",
+                    path: format!("{}:self_modulate:{}", drawable, channel),
+                    keys: bezier_keys(&kfs, fps, 1.),
+                });
+            }
+        }
+
+        // textures and visibility, held from the start like the setup pose
+        let mut frames = tex_frames(armature, anim, bone);
+        if !frames.is_empty() {
+            if frames[0] != 0 {
+                frames.insert(0, 0);
+            }
+            let mut visible = vec![];
+            let mut textures = vec![];
+            let mut regions = vec![];
+            for frame in frames {
+                let tex = active_tex(armature, &tex_at(armature, anim, bone, frame));
+                visible.push((time(frame), tex.is_some().to_string()));
+                if let Some((_, tex)) = tex {
+                    let page = format!("ExtResource(\"page_{}\")", tex.atlas_idx);
+                    textures.push((time(frame), page));
+                    let region = match is_mesh(bone) {
+                        true => uvs(bone, tex),
+                        false => rect2(tex),
+                    };
+                    regions.push((time(frame), region));
+                }
+            }
+            // only what actually changes is keyed
+            let property = if is_mesh(bone) { "uv" } else { "region_rect" };
+            for (property, keys) in [
+                ("visible", visible),
+                (property, regions),
+                ("texture", textures),
+            ] {
+                if keys.iter().all(|key| key.1 == keys[0].1) {
+                    continue;
+                }
+                tracks.push(Track {
+                    kind: "value",
+                    path: format!("{}:{}", drawable, property),
+                    keys: value_keys(&keys),
+                });
+            }
+        }
+
+        let kfs = track(anim, bone.id, &AE::Zindex);
+        if !kfs.is_empty() {
+            let mut keys: Vec<(f32, String)> = vec![];
+            if kfs[0].frame != 0 {
+                keys.push((0., bone.zindex.to_string()));
+            }
+            for kf in kfs {
+                keys.push((time(kf.frame), (kf.value as i32).to_string()));
+            }
+            tracks.push(Track {
+                kind: "value",
+                path: format!("{}:z_index", drawable),
+                keys: value_keys(&keys),
+            });
+        }
+    }
+
+    // events call a method on the root, for scripts to react to
+    if !anim.events.is_empty() {
+        let times: Vec<f32> = anim.events.iter().map(|e| time(e.frame)).collect();
+        let values: Vec<String> = anim
+            .events
+            .iter()
+            .map(|e| {
+                let args = format!(
+                    "[{}, {}, {}]",
+                    string(&e.name),
+                    real(e.value),
+                    string(&e.value_str)
+                );
+                format!(
+                    "{{\n\"args\": {},\n\"method\": &\"{}\"\n}}",
+                    args, EVENT_METHOD
+                )
+            })
+            .collect();
+        let keys = format!(
+            "{{\n\"times\": {},\n\"transitions\": {},\n\"values\": [{}]\n}}",
+            packed_f32s(&times),
+            packed_f32s(&vec![1.; times.len()]),
+            values.join(", ")
+        );
+        tracks.push(Track {
+            kind: "method",
+            path: ".".to_string(),
+            keys,
+        });
+    }
+
+    tracks
+}
+
+/// Keys of a bezier track, with SkelForm's handles converted to Godot's. Values are multiplied
+/// by `sign`, to flip Y and rotations.
+pub fn bezier_keys(kfs: &[&Keyframe], fps: f32, sign: f32) -> String {
+    let mut kfs = kfs.to_vec();
+    kfs.dedup_by_key(|kf| kf.frame);
+    let is_stepped = |kf: &Keyframe| kf.start_handle.y == 999. && kf.end_handle.y == 999.;
+
+    let mut times = vec![];
+    let mut points = vec![];
+    for (k, kf) in kfs.iter().enumerate() {
+        let time = kf.frame as f32 / fps;
+        let value = kf.value * sign;
+
+        // the handles of a segment are stored in its last keyframe. Godot's are offsets
+        // from each end, in seconds and units
+        let mut in_handle = Vec2::default();
+        if k > 0 {
+            let prev_time = kfs[k - 1].frame as f32 / fps;
+            let prev_value = kfs[k - 1].value * sign;
+            let (dt, dv) = (time - prev_time, value - prev_value);
+            if is_stepped(kf) {
+                times.push(time - STEP_GAP.min(dt / 2.));
+                points.extend([prev_value, 0., 0., 0., 0.]);
+            } else {
+                in_handle = Vec2::new((kf.end_handle.x - 1.) * dt, (kf.end_handle.y - 1.) * dv);
+            }
+        }
+        let mut out_handle = Vec2::default();
+        if let Some(next) = kfs.get(k + 1) {
+            let dt = next.frame as f32 / fps - time;
+            let dv = next.value * sign - value;
+            if !is_stepped(next) {
+                out_handle = Vec2::new(next.start_handle.x * dt, next.start_handle.y * dv);
+            }
+        }
+
+        times.push(time);
+        points.extend([value, in_handle.x, in_handle.y, out_handle.x, out_handle.y]);
+    }
+
+    format!(
+        "{{\n\"handle_modes\": PackedInt32Array({}),\n\"points\": {},\n\"times\": {}\n}}",
+        join(times.iter().map(|_| "0".to_string())),
+        packed_f32s(&points),
+        packed_f32s(&times)
+    )
+}
+
+/// Keys of a value track that jumps between values (already formatted for Godot).
+pub fn value_keys(keys: &[(f32, String)]) -> String {
+    let times: Vec<f32> = keys.iter().map(|k| k.0).collect();
+    let values: Vec<&str> = keys.iter().map(|k| k.1.as_str()).collect();
+    format!(
+        "{{\n\"times\": {},\n\"transitions\": {},\n\"update\": 1,\n\"values\": [{}]\n}}",
+        packed_f32s(&times),
+        packed_f32s(&vec![1.; times.len()]),
+        values.join(", ")
+    )
+}
+
+/// Bones with their parents first, in the order that `Skeleton2D` indexes them.
+pub fn tree_order(armature: &Armature) -> Vec<&Bone> {
+    fn add_children<'a>(armature: &'a Armature, parent_id: i32, order: &mut Vec<&'a Bone>) {
+        for bone in armature.bones.iter().filter(|b| b.parent_id == parent_id) {
+            order.push(bone);
+            add_children(armature, bone.id, order);
+        }
+    }
+    let mut order = vec![];
+    for bone in &armature.bones {
+        if !armature.bones.iter().any(|b| b.id == bone.parent_id) {
+            order.push(bone);
+            add_children(armature, bone.id, &mut order);
+        }
+    }
+    order
+}
+
+/// Node names of each bone, and of the node that draws its texture.
+///
+/// Names are unique among their siblings, and free of characters Godot doesn't allow.
+pub fn node_names(armature: &Armature) -> (HashMap<i32, String>, HashMap<i32, String>) {
+    // taken names by parent: a bone, the skeleton (None), or the scene's root (skinned
+    // polygons)
+    let mut taken: HashMap<Option<i32>, Vec<String>> = HashMap::new();
+    let mut root = vec!["Skeleton2D".to_string(), "AnimationPlayer".to_string()];
+    let unique = |taken: &mut Vec<String>, name: &str, id: i32| {
+        let mut name: String = name
+            .chars()
+            .map(|c| if ".:@/\"%".contains(c) { '_' } else { c })
+            .collect();
+        if taken.contains(&name) {
+            name = format!("{} ({})", name, id);
+        }
+        taken.push(name.clone());
+        name
+    };
+
+    let mut names = HashMap::new();
+    for bone in &armature.bones {
+        let has_parent = armature.bones.iter().any(|b| b.id == bone.parent_id);
+        let siblings = taken
+            .entry(has_parent.then_some(bone.parent_id))
+            .or_default();
+        names.insert(bone.id, unique(siblings, &bone.name, bone.id));
+    }
+    let mut drawable_names = HashMap::new();
+    for bone in &armature.bones {
+        let siblings = match is_skinned(armature, bone) {
+            true => &mut root,
+            false => taken.entry(Some(bone.id)).or_default(),
+        };
+        let name = unique(siblings, &format!("{} Texture", bone.name), bone.id);
+        drawable_names.insert(bone.id, name);
+    }
+    (names, drawable_names)
+}
+
+/// Texture a bone is drawn with in the setup pose, or the first one it's animated to.
+fn setup_tex<'a>(armature: &'a Armature, bone: &Bone) -> Option<(&'a Style, &'a Texture)> {
+    let names = slot_tex_names(armature, bone);
+    names.first().and_then(|name| active_tex(armature, name))
+}
+
+// untouched rects are sprites
+fn is_mesh(bone: &Bone) -> bool {
+    bone.verts_edited || !bone.binds.is_empty()
+}
+
+fn is_skinned(armature: &Armature, bone: &Bone) -> bool {
+    let bound = |bind: &BoneBind| armature.bones.iter().any(|b| b.id == bind.bone_id);
+    setup_tex(armature, bone).is_some() && bone.binds.iter().any(|b| !b.is_path && bound(b))
+}
+
+fn uvs(bone: &Bone, tex: &Texture) -> String {
+    let uvs: Vec<Vec2> = bone
+        .vertices
+        .iter()
+        .map(|vert| tex.offset + vert.uv * tex.size)
+        .collect();
+    packed_vec2s(&uvs)
+}
+
+fn flip(v: Vec2) -> Vec2 {
+    Vec2::new(v.x, -v.y)
+}
+
+// rounded like JSON exports, without negative zeroes
+fn real(value: f32) -> String {
+    (num(value).as_f64().unwrap() + 0.).to_string()
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
+
+fn string(str: &str) -> String {
+    format!("\"{}\"", str.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn node_path(path: &str) -> String {
+    format!("NodePath({})", string(path))
+}
+
+fn vec2(v: Vec2) -> String {
+    format!("Vector2({}, {})", real(v.x), real(v.y))
+}
+
+fn rect2(tex: &Texture) -> String {
+    #[rustfmt::skip]
+    let rect = format!("Rect2({}, {}, {}, {})", real(tex.offset.x), real(tex.offset.y), real(tex.size.x), real(tex.size.y));
+    rect
+}
+
+fn color(tint: &TintColor) -> String {
+    #[rustfmt::skip]
+    let color = format!("Color({}, {}, {}, {})", real(tint.r), real(tint.g), real(tint.b), real(tint.a));
+    color
+}
+
+fn transform2d(pos: Vec2, rot: f32, scale: Vec2) -> String {
+    let (sin, cos) = rot.sin_cos();
+    let values = [
+        cos * scale.x,
+        sin * scale.x,
+        -sin * scale.y,
+        cos * scale.y,
+        pos.x,
+        pos.y,
+    ];
+    format!("Transform2D({})", join(values.iter().map(|v| real(*v))))
+}
+
+fn packed_f32s(values: &[f32]) -> String {
+    format!(
+        "PackedFloat32Array({})",
+        join(values.iter().map(|v| real(*v)))
+    )
+}
+
+fn packed_vec2s(values: &[Vec2]) -> String {
+    let coords = values.iter().flat_map(|v| [real(v.x), real(v.y)]);
+    format!("PackedVector2Array({})", join(coords))
+}
@@ -202,6 +202,12 @@ fn draw_animations_list(
                                 ui.add_space(5.);
                                 let col = config.colors.text;
                                 ui.label(egui::RichText::new(name.clone()).color(col));
+                                if armature.animations[i].additive {
+                                    let mut col = col;
+                                    col -= Color::new(60, 60, 60, 0);
+                                    ui.label(egui::RichText::new("+").color(col))
+                                        .on_hover_text(shared_ui.loc("keyframe_editor.additive"));
+                                }
                             });
                         });
                     });
@@ -266,7 +272,8 @@ fn timeline_editor(
 
         let sel = selections.clone();
 
-        if armature.sel_anim(&sel).unwrap().keyframes.len() > 0 {
+        let sel_anim = armature.sel_anim(&sel).unwrap();
+        if !sel_anim.keyframes.is_empty() || !sel_anim.events.is_empty() {
             let frame = egui::Frame::new().inner_margin(egui::Margin {
                 top: 27,
                 bottom: 27,
@@ -286,14 +293,8 @@ fn timeline_editor(
         let hitbox = gap / shared_ui.timeline_zoom / fps / 2.;
 
         // add 1 second worth of frames after the last keyframe
-        let frames: i32;
         let extra = armature.sel_anim(&sel).unwrap().fps * 5;
-        if armature.sel_anim(&sel).unwrap().keyframes.last() != None {
-            let sel_anim = armature.sel_anim(&sel).unwrap();
-            frames = sel_anim.keyframes.last().unwrap().frame + extra;
-        } else {
-            frames = extra
-        }
+        let frames = armature.sel_anim(&sel).unwrap().len_frames() + extra;
 
         let width: f32;
         let generated_width = hitbox * frames as f32 * 2. + LINE_OFFSET;
@@ -358,6 +359,13 @@ pub fn draw_bones_list(
         let mut last_bone_id = -1;
         let mut first = true;
 
+        // animation events get their own row, above all bones
+        if !armature.sel_anim(sel).unwrap().events.is_empty() {
+            let label = ui.label(shared_ui.loc("keyframe_editor.events"));
+            shared_ui.bone_tops.events_top = Some(label.rect.top());
+            first = false;
+        }
+
         // keep track of elements, to prevent showing multiple of the same
         let mut added_elements: Vec<AnimElement> = vec![];
 
@@ -653,11 +661,13 @@ pub fn draw_timeline_graph(
                 // keep cursor on the frame
                 cursor.y -= shared_ui.timeline_offset.y;
 
-                // render darkened background after last keyframe
+                // render darkened background after the end of the animation
                 let sel = selections.clone();
-                let lkf = armature.sel_anim(&sel).unwrap().keyframes.last();
-                if lkf != None && (lkf.unwrap().frame as usize) < shared_ui.lines_x.len() {
-                    let left_top = egui::vec2(shared_ui.lines_x[lkf.unwrap().frame as usize], -3.);
+                let anim = armature.sel_anim(&sel).unwrap();
+                let len = anim.len_frames() as usize;
+                if (!anim.keyframes.is_empty() || anim.length > 0) && len < shared_ui.lines_x.len()
+                {
+                    let left_top = egui::vec2(shared_ui.lines_x[len], -3.);
                     let right_bot =
                         egui::vec2(0., shared_ui.bone_tops.tops.last().unwrap().height + 999.);
 
@@ -761,6 +771,36 @@ pub fn draw_bottom_bar(
                 events.adjust_keyframes_by_fps(value as usize);
             }
 
+            // 0 means the length is inferred from the last keyframe
+            let anim = armature.sel_anim(&sel).unwrap();
+            ui.label(shared_ui.loc("keyframe_editor.length"))
+                .on_hover_text(shared_ui.loc("keyframe_editor.length_desc"));
+            let length = anim.len_frames() as f32;
+            let (edited, value, _) =
+                ui.float_input("anim_length".to_string(), shared_ui, length, 1., None);
+            if edited {
+                events.set_anim_length(value.max(0.) as i32);
+            }
+
+            let loop_mode = anim.loop_mode;
+            let str_mode = shared_ui.loc(&format!("keyframe_editor.loop_modes.{}", loop_mode));
+            egui::ComboBox::new("loop_mode", "")
+                .selected_text(str_mode)
+                .width(70.)
+                .show_ui(ui, |ui| {
+                    let mut selected = -1;
+                    for (i, mode) in ["Loop", "Once", "PingPong", "Reverse"].iter().enumerate() {
+                        let str_mode =
+                            shared_ui.loc(&format!("keyframe_editor.loop_modes.{}", mode));
+                        ui.selectable_value(&mut selected, i as i32, str_mode);
+                    }
+                    if selected != -1 {
+                        events.set_anim_loop_mode(selected as usize);
+                    }
+                })
+                .response
+                .on_hover_text(shared_ui.loc("keyframe_editor.loop_mode_desc"));
+
             let mut col = config.colors.text;
             if !edit_mode.onion_layers {
                 col -= Color::new(60, 60, 60, 0);
@@ -772,6 +812,37 @@ pub fn draw_bottom_bar(
             {
                 events.toggle_onion_layers(if edit_mode.onion_layers { 0 } else { 1 });
             }
+
+            let anim = armature.sel_anim(&sel).unwrap();
+            let mut col = config.colors.text;
+            if !anim.additive {
+                col -= Color::new(60, 60, 60, 0);
+            }
+            let str_additive = shared_ui.loc("keyframe_editor.additive");
+            if ui
+                .skf_button(egui::RichText::new(str_additive).color(col))
+                .on_hover_text(shared_ui.loc("keyframe_editor.additive_desc"))
+                .clicked()
+            {
+                events.toggle_anim_additive(if anim.additive { 0 } else { 1 });
+            }
+
+            // toggle selected bone in the mask, with its children if holding shift
+            let bone_id = armature.sel_bone(&sel).map(|bone| bone.id);
+            let mut col = config.colors.text;
+            if !bone_id.is_some_and(|id| anim.mask.contains(&id)) {
+                col -= Color::new(60, 60, 60, 0);
+            }
+            let str_mask = shared_ui
+                .loc("keyframe_editor.mask")
+                .replace("$count", &anim.mask.len().to_string());
+            let button = ui
+                .skf_button(egui::RichText::new(str_mask).color(col))
+                .on_hover_text(shared_ui.loc("keyframe_editor.mask_desc"));
+            if let Some(id) = bone_id.filter(|_| button.clicked()) {
+                let shift = ui.input(|i| i.modifiers.shift);
+                events.toggle_bone_in_anim_mask(id, shift);
+            }
         });
     });
 }
@@ -805,14 +876,13 @@ fn draw_frame_lines(
 
         let mut color: egui::Color32 = config.colors.frameline.into();
 
-        // use different color for frameline if it exceeds last keyframe
-        let last_keyframe = armature.animations[selections.anim].keyframes.last();
-        if last_keyframe != None && i > last_keyframe.unwrap().frame {
+        // use different color for frameline if it exceeds the end of the animation
+        let anim = &armature.animations[selections.anim];
+        if (!anim.keyframes.is_empty() || anim.length > 0) && i > anim.len_frames() {
             color = config.colors.dark_accent.into();
         }
 
         // brighten frameline if current animation is playing, and on this frame
-        let anim = &armature.animations[selections.anim];
         if i == anim.get_frame() && anim.elapsed != None {
             color = color + egui::Color32::from_rgb(60, 60, 60);
         }
@@ -886,6 +956,8 @@ fn draw_frame_lines(
 
     shared_ui.hovering_diamond = false;
 
+    draw_anim_events(ui, shared_ui, armature, config, selections, events);
+
     let is_multi = input.holding_mod || input.holding_shift;
 
     // draw per-change icons
@@ -1040,6 +1112,76 @@ fn draw_frame_lines(
     }
 }
 
+/// Draw markers of animation events, one per frame.
+fn draw_anim_events(
+    ui: &mut egui::Ui,
+    shared_ui: &mut crate::Ui,
+    armature: &Armature,
+    config: &Config,
+    selections: &SelectionState,
+    events: &mut EventState,
+) {
+    let top = match shared_ui.bone_tops.events_top {
+        Some(top) => top,
+        None => return,
+    };
+
+    let anim_events = &armature.animations[selections.anim].events;
+    let mut last_frame = -1;
+    for event in anim_events {
+        if event.frame == last_frame || event.frame as usize >= shared_ui.lines_x.len() {
+            continue;
+        }
+        last_frame = event.frame;
+
+        let x = shared_ui.lines_x[event.frame as usize] + ui.min_rect().left();
+        let pos = Vec2::new(x, top + 17. / 2.);
+        if let Some(panel) = shared_ui.keyframe_panel_rect {
+            if pos.y > panel.bottom() {
+                return;
+            }
+        }
+
+        let rect = egui::Rect::from_center_size(pos.into(), egui::Vec2::splat(10.));
+        let response: egui::Response = ui.allocate_rect(rect, egui::Sense::click());
+
+        let mut size = 5.;
+        let is_selected =
+            shared_ui.last_selected == "anim_event" && selections.anim_frame == event.frame;
+        if response.hovered() {
+            shared_ui.hovering_diamond = true;
+            shared_ui.cursor_icon = egui::CursorIcon::PointingHand;
+            size += 2.;
+        } else if is_selected {
+            size += 2.;
+        }
+        draw_diamond(ui.painter(), pos, config.colors.link.into(), size);
+
+        // list names of all events in this frame
+        let names: Vec<&str> = anim_events
+            .iter()
+            .filter(|e| e.frame == event.frame)
+            .map(|e| e.name.as_str())
+            .collect();
+        ui.painter().text(
+            (pos + Vec2::new(10., 0.)).into(),
+            egui::Align2::LEFT_CENTER,
+            names.join(", "),
+            egui::FontId::default(),
+            config.colors.text.into(),
+        );
+
+        if response.clicked() || response.secondary_clicked() {
+            events.select_anim_frame(event.frame as usize, false, false);
+            events.select_anim_events(event.frame);
+        }
+        if response.secondary_clicked() {
+            let context_id = format!("animevent_{}", event.frame);
+            shared_ui.context_menu.show(&context_id);
+        }
+    }
+}
+
 pub fn draw_diamond(painter: &egui::Painter, pos: Vec2, color: egui::Color32, size: f32) {
     let points = vec![
         egui::Pos2::new(pos.x, pos.y - size), // Top
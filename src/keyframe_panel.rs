@@ -1,4 +1,4 @@
-use ui::EguiUi;
+use ui::{EguiUi, TextInputOptions};
 
 use egui::{
     epaint::{self},
@@ -241,3 +241,69 @@ pub fn bezier_graph(ui: &mut egui::Ui, keyframe: &mut Keyframe) -> bool {
     painter.extend(control_point_shapes);
     dragged
 }
+
+/// Edit all animation events on the selected frame.
+pub fn draw_anim_events(
+    ui: &mut egui::Ui,
+    selections: &SelectionState,
+    armature: &Armature,
+    events: &mut EventState,
+    shared_ui: &mut crate::Ui,
+) {
+    let str_header = shared_ui.loc("keyframe_panel.events_header");
+    ui.heading(format!("{} ({})", str_header, selections.anim_frame));
+    ui.add_space(10.);
+
+    let anim = armature.sel_anim(selections).unwrap();
+    for (e, event) in anim.events.iter().enumerate() {
+        if event.frame != selections.anim_frame {
+            continue;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(shared_ui.loc("keyframe_panel.event_name"));
+            let options = Some(TextInputOptions {
+                default: event.name.clone(),
+                ..Default::default()
+            });
+            let id = format!("anim_event_name_{}", e);
+            let (edited, value, _) = ui.text_input(id, shared_ui, event.name.clone(), options);
+            if edited {
+                events.rename_anim_event(e, value);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(shared_ui.loc("keyframe_panel.event_frame"));
+            let id = format!("anim_event_frame_{}", e);
+            let frame = event.frame as f32;
+            let (edited, value, _) = ui.float_input(id, shared_ui, frame, 1., None);
+            if edited {
+                events.set_anim_event_frame(e, value as i32);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(shared_ui.loc("keyframe_panel.event_str"));
+            let id = format!("anim_event_str_{}", e);
+            let value_str = event.value_str.clone();
+            let (edited, value, _) = ui.text_input(id, shared_ui, value_str, None);
+            if edited {
+                events.set_anim_event_str(e, value);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(shared_ui.loc("keyframe_panel.event_value"));
+            let id = format!("anim_event_value_{}", e);
+            let (edited, value, _) = ui.float_input(id, shared_ui, event.value, 1., None);
+            if edited {
+                events.set_anim_event_value(e, value);
+            }
+        });
+        if ui
+            .skf_button(shared_ui.loc("keyframe_panel.delete_event"))
+            .clicked()
+        {
+            events.delete_anim_event(e);
+        }
+        ui.separator();
+    }
+}
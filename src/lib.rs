@@ -7,15 +7,19 @@
 //! `iterable` - snippets that aren't automated/iterated, but probably should be.
 //!
 //! `todo:` - not important as of being written, but good to keep in mind.
+//!
+//! Without the `editor` feature, only the window-free parts are built: loading and posing .skf
+//! files (`runtime`), armature.bin (`binary`) and Spine, DragonBones and Godot conversion. Layered
+//! image rigs (PSD, Aseprite, OpenRaster/Krita) and the CLI go through the editor's import path,
+//! so they need `editor`.
 
-use std::{
-    io::{Seek, Write},
-    path::PathBuf,
-    sync::Mutex,
-};
+#[cfg(feature = "editor")]
+use std::{io::Write, path::PathBuf, sync::Mutex};
 
+#[cfg(feature = "editor")]
 use egui_wgpu::wgpu::ExperimentalFeatures;
 use shared::*;
+#[cfg(feature = "editor")]
 use wgpu::{util::DeviceExt, BindGroupLayout, Buffer, InstanceDescriptor};
 
 // native-only imports
@@ -23,13 +27,14 @@ use wgpu::{util::DeviceExt, BindGroupLayout, Buffer, InstanceDescriptor};
 mod native {
     pub use image::*;
     pub use std::fs;
+    #[cfg(feature = "editor")]
     pub use std::io::Read;
+    #[cfg(feature = "editor")]
     pub use std::process::{Command, Stdio};
     pub use std::time::Instant;
 }
 #[cfg(not(target_arch = "wasm32"))]
 use native::*;
-use zip::write::FullFileOptions;
 
 #[cfg(target_arch = "wasm32")]
 mod web {
@@ -37,13 +42,17 @@ mod web {
     pub use web_sys::*;
     pub use web_time::Instant;
 }
+#[cfg(feature = "editor")]
 use std::sync::Arc;
 #[cfg(target_arch = "wasm32")]
 use web::*;
 
+#[cfg(feature = "editor")]
 use image::GenericImageView;
+#[cfg(feature = "editor")]
 use image::ImageEncoder;
 
+#[cfg(feature = "editor")]
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
@@ -51,23 +60,52 @@ use winit::{
     window::{Theme, Window},
 };
 
+#[cfg(feature = "editor")]
+pub mod animated;
+#[cfg(feature = "editor")]
 pub mod armature_window;
+#[cfg(feature = "editor")]
+pub mod aseprite;
+#[cfg(feature = "editor")]
 pub mod atlas_modal;
 pub mod backwards_compat;
+pub mod binary;
+#[cfg(feature = "editor")]
 pub mod bone_panel;
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
+pub mod cli;
+pub mod dragonbones;
+#[cfg(feature = "editor")]
 pub mod editor;
+pub mod export_common;
+#[cfg(feature = "editor")]
 pub mod export_modal;
+#[cfg(feature = "editor")]
 pub mod file_reader;
+pub mod godot;
+#[cfg(feature = "editor")]
 pub mod keyframe_editor;
+#[cfg(feature = "editor")]
 pub mod keyframe_panel;
+#[cfg(feature = "editor")]
 pub mod modal;
+#[cfg(feature = "editor")]
+pub mod openraster;
+pub mod raster;
 pub mod renderer;
+pub mod runtime;
+#[cfg(feature = "editor")]
 pub mod settings_modal;
 pub mod shared;
+pub mod spine;
+#[cfg(feature = "editor")]
 pub mod startup_window;
+#[cfg(feature = "editor")]
 pub mod styles_modal;
+#[cfg(feature = "editor")]
 pub mod ui;
 pub mod utils;
+#[cfg(feature = "editor")]
 pub mod warnings;
 
 #[cfg(target_arch = "wasm32")]
@@ -99,8 +137,7 @@ extern "C" {
     pub fn hasLoadedAllImages() -> bool;
     pub fn downloadZip(zip: Vec<u8>, saving: String);
     pub fn downloadMp4(data: Vec<u8>, resX: f32, resY: f32, name: &str, fps: i32);
-    pub fn downloadGif(resX: f32, resY: f32, name: &str, fps: i32);
-    pub fn addGifFrame(frame: Vec<u8>);
+    pub fn downloadFile(data: Vec<u8>, name: &str, mime: &str);
     pub fn changeLang(filename: String);
     pub fn getLang() -> Vec<u8>;
     pub fn clearLang();
@@ -108,6 +145,7 @@ extern "C" {
     pub fn ensureFFmpeg();
 }
 
+#[cfg(feature = "editor")]
 #[derive(Default)]
 pub struct App {
     window: Option<Arc<Window>>,
@@ -119,6 +157,7 @@ pub struct App {
     pub shared: shared::Shared,
 }
 
+#[cfg(feature = "editor")]
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         #[allow(unused_mut)]
@@ -277,8 +316,10 @@ impl ApplicationHandler for App {
                         .to_string()
                         .to_lowercase();
 
-                    if ext.contains("skf") || ext.contains("psd") {
-                        // load .skf or .skfe file
+                    let skeleton = ext == "json" || ext == "zip";
+                    let layered = ["aseprite", "ase", "ora", "kra"].contains(&ext.as_str());
+                    if ext.contains("skf") || ext.contains("psd") || skeleton || layered {
+                        // load .skf, .skfe, .psd, Aseprite, OpenRaster, Krita, Spine or DragonBones file
                         *self.shared.ui.file_path.lock().unwrap() = vec![_path_buf];
                         *self.shared.ui.file_type.lock().unwrap() = 2;
                     } else {
@@ -520,6 +561,7 @@ impl ApplicationHandler for App {
     }
 }
 
+#[cfg(feature = "editor")]
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct BlitUniforms {
@@ -527,6 +569,7 @@ struct BlitUniforms {
     _pad: [f32; 7], // std140 alignment (important!)
 }
 
+#[cfg(feature = "editor")]
 pub struct BackendRenderer {
     gpu: Gpu,
     egui_renderer: egui_wgpu::Renderer,
@@ -536,6 +579,7 @@ pub struct BackendRenderer {
     blit_buffer: Buffer,
 }
 
+#[cfg(feature = "editor")]
 impl BackendRenderer {
     pub async fn new(
         window: impl Into<wgpu::SurfaceTarget<'static>>,
@@ -743,7 +787,11 @@ impl BackendRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        pixel_pass.set_pipeline(&self.scene.pipeline);
+        if shared.edit_mode.premultiplied_atlases() {
+            pixel_pass.set_pipeline(&self.scene.premultiplied_pipeline);
+        } else {
+            pixel_pass.set_pipeline(&self.scene.pipeline);
+        }
         self.skf_render(shared, &mut pixel_pass.forget_lifetime());
 
         let sampler = self.gpu.device.create_sampler(&wgpu::SamplerDescriptor {
@@ -855,12 +903,24 @@ impl BackendRenderer {
             #[cfg(not(target_arch = "wasm32"))]
             self.save(shared);
         } else if recording {
-            // recording animations for images/videos
-            shared.events.open_modal("exporting", true);
-            #[rustfmt::skip]
-            utils::render_spritesheets(&shared.armature, &mut shared.ui, &shared.camera, &shared.config, self, &shared.renderer);
+            #[cfg(not(target_arch = "wasm32"))]
+            let streaming =
+                saving == Saving::Video && !shared.ui.exporting_video_type.is_animated_image();
+            #[cfg(target_arch = "wasm32")]
+            let streaming = false;
+
+            if streaming {
+                // videos are piped into ffmpeg as they're rendered, instead of kept until the end
+                #[cfg(not(target_arch = "wasm32"))]
+                self.skf_stream_videos(shared);
+            } else {
+                // recording animations for images/videos
+                shared.events.open_modal("exporting", true);
+                #[rustfmt::skip]
+                utils::render_spritesheets(&shared.armature, &mut shared.ui, &shared.camera, &shared.config, Some(self), &shared.renderer);
+                shared.ui.spritesheet_elapsed = Some(Instant::now());
+            }
             *shared.ui.saving.lock().unwrap() = Saving::None;
-            shared.ui.spritesheet_elapsed = Some(Instant::now());
             shared.ui.export_modal = false;
         }
 
@@ -915,7 +975,13 @@ impl BackendRenderer {
             &shared.config, &shared.edit_mode, &mut shared.selections, &mut shared.renderer, &mut shared.events,
         );
 
+        // drop repairs of bones that have since been deleted
+        let bones = &shared.armature.bones;
+        let exists = |id: &usize| bones.iter().any(|b| b.id == *id as i32);
+        shared.ui.repairs.retain(|r| r.ids.iter().all(exists));
+
         shared.ui.warnings = warnings::check_warnings(&shared.armature);
+        shared.ui.warnings.append(&mut shared.ui.repairs.clone());
     }
 
     // processes style to export, if appropriate
@@ -1005,6 +1071,7 @@ impl BackendRenderer {
         }
 
         if *elapsed != None && elapsed.unwrap().elapsed().as_millis() > duration_in_millis {
+            shared.ui.custom_error = "".to_string();
             if shared.ui.exporting_video_type != ExportVideoType::None {
                 self.skf_export_videos(&shared.armature, &mut shared.ui);
             } else {
@@ -1017,6 +1084,15 @@ impl BackendRenderer {
             shared.ui.spritesheet_elapsed = None;
             shared.ui.modal = false;
             *shared.ui.mapped_frames.lock().unwrap() = 0;
+
+            // encoding errors, now that the exporting modal is closed
+            if !shared.ui.custom_error.is_empty() {
+                let headline = match shared.ui.exporting_video_type {
+                    ExportVideoType::Mp4 => shared.ui.loc("error_vid_export"),
+                    _ => shared.ui.loc("error_skf"),
+                };
+                editor::open_modal(&mut shared.ui, false, headline);
+            }
         }
     }
 
@@ -1077,28 +1153,48 @@ impl BackendRenderer {
         #[cfg(not(target_arch = "wasm32"))]
         {
             path = shared_ui.file_path.lock().unwrap()[0].clone();
+            if !Self::recreate_dir(&path) {
+                return;
+            }
+        }
+        self.skf_pack_videos(armature, shared_ui, &path);
+    }
 
-            // attempt to remove the existing dir, if it exists
-            let can_proceed = if let Ok(exists) = std::fs::exists(&path) {
-                if exists {
-                    if let Err(e) = std::fs::remove_dir_all(&path) {
-                        eprintln!("{}", e);
-                        false
-                    } else {
-                        true
-                    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn skf_stream_videos(&self, shared: &mut Shared) {
+        let path = shared.ui.file_path.lock().unwrap()[0].clone();
+        if !Self::recreate_dir(&path) {
+            return;
+        }
+
+        #[rustfmt::skip]
+        let result = utils::stream_videos(&shared.armature, &shared.ui, &shared.camera, &shared.config, Some(self), &shared.renderer, &path);
+        if let Err(e) = result {
+            shared.ui.custom_error = e;
+            let headline = shared.ui.loc("error_vid_export");
+            editor::open_modal(&mut shared.ui, false, headline);
+        }
+    }
+
+    // export dirs are replaced entirely
+    #[cfg(not(target_arch = "wasm32"))]
+    fn recreate_dir(path: &PathBuf) -> bool {
+        // attempt to remove the existing dir, if it exists
+        let can_proceed = if let Ok(exists) = std::fs::exists(path) {
+            if exists {
+                if let Err(e) = std::fs::remove_dir_all(path) {
+                    eprintln!("{}", e);
+                    false
                 } else {
                     true
                 }
             } else {
                 true
-            };
-            if !can_proceed {
-                return;
             }
-            let _ = std::fs::create_dir(&path).unwrap();
-        }
-        self.skf_pack_videos(armature, shared_ui, &path);
+        } else {
+            true
+        };
+        can_proceed && std::fs::create_dir(path).is_ok()
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -1108,7 +1204,7 @@ impl BackendRenderer {
         let options = zip::write::FullFileOptions::default()
             .compression_method(zip::CompressionMethod::Stored);
 
-        self.skf_pack_sprites(armature, shared_ui, &mut zip, &options);
+        utils::pack_sprites(armature, shared_ui, Some(self), &mut zip, &options).unwrap();
 
         _ = zip.finish();
     }
@@ -1121,82 +1217,58 @@ impl BackendRenderer {
         let options = zip::write::FullFileOptions::default()
             .compression_method(zip::CompressionMethod::Stored);
 
-        self.skf_pack_sprites(armature, shared_ui, &mut zip, &options);
+        utils::pack_sprites(armature, shared_ui, Some(self), &mut zip, &options).unwrap();
 
         let bytes = zip.finish().unwrap().into_inner().to_vec();
         downloadZip(bytes, Saving::Spritesheet.to_string());
     }
 
-    // sprite-packing stuff that applies to both native and web
-    fn skf_pack_sprites<W: Write + Seek>(
-        &self,
-        armature: &Armature,
-        shared_ui: &mut Ui,
-        zip: &mut zip::ZipWriter<W>,
-        options: &FullFileOptions,
-    ) {
-        if shared_ui.image_sequences {
-            let bufs = utils::encode_sequence(armature, shared_ui, self);
+    fn skf_pack_videos(&self, armature: &Armature, shared_ui: &mut Ui, _path: &PathBuf) {
+        if shared_ui.exporting_video_type.is_animated_image() {
+            let files = match utils::encode_animated(armature, shared_ui, Some(self)) {
+                Ok(files) => files,
+                Err(e) => {
+                    shared_ui.custom_error = e;
+                    return;
+                }
+            };
+            for (name, data) in files {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Err(e) = std::fs::write(_path.join(&name), data) {
+                    shared_ui.custom_error = format!("{}: {}", name, e);
+                    return;
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    let mime = match shared_ui.exporting_video_type {
+                        ExportVideoType::Gif => "image/gif",
+                        ExportVideoType::Apng => "image/apng",
+                        _ => "image/webp",
+                    };
+                    downloadFile(data, &name, mime);
+                }
+            }
+            return;
+        }
 
+        // native videos are streamed into ffmpeg while rendering (see skf_stream_videos)
+        #[cfg(target_arch = "wasm32")]
+        {
+            let bufs = utils::encode_sequence(armature, shared_ui, Some(self));
             let mut buf_idx = 0;
-            for a in 0..shared_ui.exporting_anims.len() {
+            let size = shared_ui.sprite_size;
+            for a in 0..armature.animations.len() {
                 if !shared_ui.exporting_anims[a] {
                     continue;
                 }
-                zip.add_directory(armature.animations[a].name.clone(), options.clone())
-                    .unwrap();
-                for b in 0..bufs[buf_idx].len() {
-                    let png_name =
-                        armature.animations[a].name.to_string() + "/" + &b.to_string() + ".png";
-                    zip.start_file(png_name, options.clone()).unwrap();
-                    zip.write(&bufs[buf_idx][b]).unwrap();
+                let mut fps = armature.animations[a].fps;
+                if shared_ui.export_fps > 0 {
+                    fps = shared_ui.export_fps;
                 }
+                let name = &armature.animations[a].name;
+                Self::encode_video(bufs[buf_idx].clone(), fps, size, name);
                 buf_idx += 1;
             }
-        } else {
-            let bufs = utils::encode_spritesheets(armature, shared_ui, self);
-            for b in 0..bufs.len() {
-                let png_name = b.to_string() + ".png";
-                zip.start_file(png_name, options.clone()).unwrap();
-                zip.write(&bufs[b]).unwrap();
-            }
-        }
-    }
-
-    fn skf_pack_videos(&self, armature: &Armature, shared_ui: &mut Ui, path: &PathBuf) {
-        #[rustfmt::skip]
-        let ffmpeg_bin = if shared_ui.use_system_ffmpeg {
-            "ffmpeg".to_string()
-        } else {
-            #[cfg(target_os = "windows")] {
-            utils::bin_path().join("ffmpeg.exe").to_str().unwrap().to_string() }
-            #[cfg(not(target_os = "windows"))] {
-            utils::bin_path().join("ffmpeg").to_str().unwrap().to_string() }
-        };
-
-        let bufs = utils::encode_sequence(armature, shared_ui, self);
-        let mut buf_idx = 0;
-        let size = shared_ui.sprite_size;
-        for a in 0..armature.animations.len() {
-            let mut base = path.clone();
-            if !shared_ui.exporting_anims[a] {
-                continue;
-            }
-            base = base.join(armature.animations[a].name.clone());
-            let mut _path_str = &base.to_str().unwrap().to_string();
-            let fps = armature.animations[a].fps;
-            #[cfg(target_arch = "wasm32")]
-            {
-                _path_str = &armature.animations[a].name;
-            }
-            shared_ui.custom_error = if shared_ui.exporting_video_type == ExportVideoType::Mp4 {
-                Self::encode_video(bufs[buf_idx].clone(), fps, size, _path_str, &ffmpeg_bin)
-            } else {
-                Self::encode_gif(bufs[buf_idx].clone(), fps, size, _path_str, &ffmpeg_bin)
-            };
-
-            if shared_ui.custom_error != "" {}
-            buf_idx += 1;
         }
     }
 
@@ -1251,18 +1323,21 @@ impl BackendRenderer {
             shared.screenshot_res, &shared.armature, &shared.camera, &shared.config.colors.background,
             &mut frames, &mut shared.ui.mapped_frames, &shared.renderer
         );
-        let buffer = frames[0].buffer.clone();
+        let buffer = frames[0].buffer.clone().unwrap();
         let screenshot_res = shared.screenshot_res;
 
-        let mut armature = shared.armature.clone();
+        let armature = shared.armature.clone();
         let camera = shared.camera.clone();
         let edit_mode = shared.edit_mode.clone();
 
         // clear export options
         shared.edit_mode.export_bake_ik = false;
         shared.edit_mode.export_exclude_ik = false;
+        shared.edit_mode.export_binary = false;
         shared.edit_mode.export_clear_color = Color::new(0, 0, 0, 0);
         shared.edit_mode.export_img_format = ExportImgFormat::PNG;
+        shared.edit_mode.export_premultiplied = false;
+        shared.edit_mode.export_format = ExportArmatureFormat::Skf;
 
         let was_exporting = *shared.ui.saving.lock().unwrap() == Saving::Exporting;
         let autosaving = *shared.ui.saving.lock().unwrap() == Saving::Autosaving;
@@ -1273,47 +1348,21 @@ impl BackendRenderer {
         let device = self.gpu.device.clone();
         let surface_format = self.gpu.surface_format;
         std::thread::spawn(move || {
-            let mut png_bufs = vec![];
-            let mut sizes = vec![];
-
-            if armature.styles.len() > 0 && armature.styles[0].textures.len() > 0 {
-                (png_bufs, sizes) = utils::create_tex_sheet(&mut armature, &edit_mode);
-            }
-
-            let (armatures_json, editor_json) =
-                utils::prepare_files(&armature, camera, sizes.clone(), &edit_mode);
-
-            // create zip file
-            let mut zip = zip::ZipWriter::new(std::fs::File::create(save_path.clone()).unwrap());
-
-            let options = zip::write::FullFileOptions::default()
-                .compression_method(zip::CompressionMethod::Stored);
-
             let thumb_buf =
                 utils::process_screenshot(&buffer, &device, surface_format, screenshot_res);
 
-            // save relevant files into the zip
-            zip.start_file("armature.json", options.clone()).unwrap();
-            zip.write(armatures_json.as_bytes()).unwrap();
-            zip.start_file("editor.json", options.clone()).unwrap();
-            zip.write(editor_json.as_bytes()).unwrap();
-            zip.start_file("thumbnail.png", options.clone()).unwrap();
-            zip.write(&thumb_buf).unwrap();
-            zip.start_file("readme.md", options.clone()).unwrap();
-            zip.write(include_bytes!("../assets/skf_readme.md"))
-                .unwrap();
-            let atlas_ext = match edit_mode.export_img_format {
-                ExportImgFormat::PNG => ".png",
-                ExportImgFormat::JPG => ".jpg",
-            };
-            for i in 0..png_bufs.len() {
-                let atlas_name = format!("atlas{}{}", i.to_string(), atlas_ext);
-                zip.start_file(atlas_name, options.clone()).unwrap();
-                zip.write(&png_bufs[i]).unwrap();
+            let file = std::fs::File::create(save_path.clone()).unwrap();
+            if was_exporting && edit_mode.export_format == ExportArmatureFormat::Spine {
+                spine::write_spine(file, &armature, &edit_mode).unwrap();
+            } else if was_exporting && edit_mode.export_format == ExportArmatureFormat::DragonBones
+            {
+                dragonbones::write_dragonbones(file, &armature, &edit_mode).unwrap();
+            } else if was_exporting && edit_mode.export_format == ExportArmatureFormat::Godot {
+                godot::write_godot(file, &armature, &edit_mode).unwrap();
+            } else {
+                utils::write_skf(file, &armature, &camera, &edit_mode, Some(&thumb_buf)).unwrap();
             }
 
-            zip.finish().unwrap();
-
             let _ = std::fs::copy(save_path.clone(), save_path + "~");
 
             // trigger saving modal if manually saving
@@ -1433,7 +1482,8 @@ impl BackendRenderer {
         );
 
         rendered_frames.push(RenderedFrame {
-            buffer: output_buffer.clone(),
+            buffer: Some(output_buffer.clone()),
+            image: None,
             width,
             height,
         });
@@ -1450,113 +1500,37 @@ impl BackendRenderer {
         });
     }
 
-    fn encode_video(
-        rendered_frames: Vec<Vec<u8>>,
-        fps: i32,
-        window: Vec2,
-        path: &String,
-        _ffmpeg_bin: &String,
-    ) -> String {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            #[rustfmt::skip]
-            let mut child = Command::new(_ffmpeg_bin)
-                .args(["-y", "-f", "rawvideo", "-pixel_format", "rgba", "-video_size", &format!("{}x{}", window.x, window.y), 
-                    "-framerate", &fps.to_string(), "-i", "pipe:0", 
-                    // disabled: manual encoder codec - not needed for now
-                    // "-c:v", codec, 
-                    "-pix_fmt", "yuv420p",
-                    &format!("{}.mp4", path.to_string())])
-                .stdin(Stdio::piped())
-                .spawn();
-
-            if let Err(e) = child {
-                return format!("spawn ffmpeg: {}", e.to_string());
-            }
-
-            // process video frames
-            let stdin = child.as_mut().unwrap().stdin.as_mut().unwrap();
-            for frame in &rendered_frames {
-                let rgb = image::load_from_memory(&frame).unwrap();
-                if let Err(e) = stdin.write_all(&rgb.to_rgba8()) {
-                    return format!("stdin: {}", e.to_string());
-                }
-            }
-
-            drop(child.as_mut().unwrap().stdin.take());
-            child.as_mut().unwrap().wait().unwrap();
-        }
-
-        #[cfg(target_arch = "wasm32")]
-        {
-            let mut raw_video = vec![];
-            for frame in rendered_frames {
-                let rgb = image::load_from_memory(&frame).unwrap();
-                for chunk in rgb.to_rgba8().chunks_exact(4) {
-                    raw_video.push(chunk[0]);
-                    raw_video.push(chunk[1]);
-                    raw_video.push(chunk[2]);
-                }
-            }
-            let split: Vec<&str> = path.split('/').collect();
-            downloadMp4(raw_video, window.x, window.y, split.last().unwrap(), fps);
-        }
-
-        "".to_string()
+    /// Render a frame and wait for its pixels, instead of mapping them in the background.
+    pub fn render_export_frame(
+        &self,
+        size: Vec2,
+        armature: &Armature,
+        camera: &Camera,
+        clear_color: &Color,
+        renderer: &Renderer,
+    ) -> image::RgbaImage {
+        let (mut frames, mut mapped_frames) = (vec![], Arc::new(Mutex::new(0)));
+        #[rustfmt::skip]
+        self.take_screenshot(size, armature, camera, clear_color, &mut frames, &mut mapped_frames, renderer);
+        utils::rendered_frame_image(&frames[0], Some(self), size)
     }
 
-    fn encode_gif(
-        rendered_frames: Vec<Vec<u8>>,
-        fps: i32,
-        window: Vec2,
-        path: &String,
-        _ffmpeg_bin: &String,
-    ) -> String {
-        #[cfg(target_arch = "wasm32")]
-        {
-            let split: Vec<&str> = path.split('/').collect();
-            for frame in rendered_frames {
-                addGifFrame(frame);
+    #[cfg(target_arch = "wasm32")]
+    fn encode_video(rendered_frames: Vec<Vec<u8>>, fps: i32, window: Vec2, name: &str) {
+        let mut raw_video = vec![];
+        for frame in rendered_frames {
+            let rgb = image::load_from_memory(&frame).unwrap();
+            for chunk in rgb.to_rgba8().chunks_exact(4) {
+                raw_video.push(chunk[0]);
+                raw_video.push(chunk[1]);
+                raw_video.push(chunk[2]);
             }
-            downloadGif(window.x, window.y, split.last().unwrap(), fps);
         }
-
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            #[rustfmt::skip]
-            let mut child = Command::new(_ffmpeg_bin)
-            .args(["-y", "-f", "rawvideo", "-pixel_format", "rgba", "-video_size", &format!("{}x{}", window.x, window.y), 
-                "-framerate", &fps.to_string(), "-i", "pipe:0", "-filter_complex",
-                "[0:v] fps=30,split [a][b]; \
-                 [a] palettegen=stats_mode=diff [p]; \
-                 [b][p] paletteuse=dither=sierra2_4a",
-                "-loop", "0",
-                &format!("{}.gif", path)
-            ])
-            .stdin(Stdio::piped())
-            .stderr(Stdio::inherit())
-            .spawn();
-
-            if let Err(e) = child {
-                return format!("spawn ffmpeg: {}", e.to_string());
-            }
-
-            let stdin = child.as_mut().unwrap().stdin.as_mut().unwrap();
-            for frame in &rendered_frames {
-                let rgb = image::load_from_memory(&frame).unwrap();
-                if let Err(e) = stdin.write_all(&rgb.to_rgba8()) {
-                    return "stdin: ".to_string() + &e.to_string();
-                }
-            }
-
-            child.as_mut().unwrap().wait().unwrap();
-            let _ = std::fs::remove_file("palette.png");
-        }
-
-        "".to_string()
+        downloadMp4(raw_video, window.x, window.y, name, fps);
     }
 }
 
+#[cfg(feature = "editor")]
 pub struct Gpu {
     pub surface: wgpu::Surface<'static>,
     pub device: wgpu::Device,
@@ -1565,6 +1539,7 @@ pub struct Gpu {
     pub surface_format: wgpu::TextureFormat,
 }
 
+#[cfg(feature = "editor")]
 impl Gpu {
     pub fn aspect_ratio(&self) -> f32 {
         self.surface_config.width as f32 / self.surface_config.height.max(1) as f32
@@ -1663,22 +1638,29 @@ impl Gpu {
     }
 }
 
+#[cfg(feature = "editor")]
 struct Scene {
     pub pipeline: wgpu::RenderPipeline,
+    /// previews premultiplied atlases
+    pub premultiplied_pipeline: wgpu::RenderPipeline,
     pub blit_pipeline: wgpu::RenderPipeline,
 }
 
+#[cfg(feature = "editor")]
 impl Scene {
     pub fn new(
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
         bind_group_layout: &BindGroupLayout,
     ) -> Self {
-        let pipeline = Self::create_pipeline(device, surface_format, &bind_group_layout);
+        let pipeline = Self::create_pipeline(device, surface_format, bind_group_layout, false);
+        #[rustfmt::skip]
+        let premultiplied_pipeline = Self::create_pipeline(device, surface_format, bind_group_layout, true);
         let blit_pipeline = Self::create_blit_pipeline(device, surface_format);
 
         Self {
             pipeline,
+            premultiplied_pipeline,
             blit_pipeline,
         }
     }
@@ -1777,6 +1759,7 @@ impl Scene {
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
         bind_group_layout: &BindGroupLayout,
+        premultiplied: bool,
     ) -> wgpu::RenderPipeline {
         let shader_str = &String::from_utf8(include_bytes!("shader.wgsl").to_vec())
             .unwrap()
@@ -1825,10 +1808,18 @@ impl Scene {
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader_module,
-                entry_point: Some("fs_main"),
+                entry_point: Some(if premultiplied {
+                    "fs_premultiplied"
+                } else {
+                    "fs_main"
+                }),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    blend: Some(if premultiplied {
+                        wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING
+                    } else {
+                        wgpu::BlendState::ALPHA_BLENDING
+                    }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
@@ -1841,27 +1832,65 @@ impl Scene {
 
 #[cfg(test)]
 mod tests {
-    use crate::shared::{AnimElement, Shared, Vec2};
-    use crate::{armature_window, file_reader};
-
+    use crate::{
+        backwards_compat, binary, dragonbones, export_common, godot, renderer, runtime, shared,
+        spine, utils,
+    };
+    #[cfg(feature = "editor")]
+    use crate::{editor, file_reader, shared::Shared};
+    #[cfg(feature = "editor")]
+    use std::path::PathBuf;
+
+    #[cfg(feature = "editor")]
     fn init_shared() -> Shared {
         let mut shared = Shared::default();
         shared.armature.new_bone(-1);
         shared.armature.new_bone(-1);
         shared.armature.new_bone(-1);
-        armature_window::drag_bone(&mut shared.armature, false, 2, 1);
-        armature_window::drag_bone(&mut shared.armature, false, 1, 0);
+        editor::drag_bone(&mut shared.armature, 1, &vec![2], false);
+        editor::drag_bone(&mut shared.armature, 0, &vec![1], false);
         shared.ui.init_empty_loc();
         shared
     }
 
+    // bones of these ids, parents and names, with the second a 4x4 textured mesh half-bound to
+    // the first
+    fn textured_armature(bones: &[(i32, i32, &str)]) -> shared::Armature {
+        let mut armature = shared::Armature::default();
+        for (id, parent_id, name) in bones {
+            #[rustfmt::skip]
+            armature.bones.push(shared::Bone { id: *id, parent_id: *parent_id, name: name.to_string(), scale: shared::Vec2::new(1., 1.), pivot_scale: shared::Vec2::new(1., 1.), tint: shared::TintColor::new(1., 1., 1., 1.), ik_family_id: -1, ..Default::default() });
+        }
+        armature.tex_data.push(shared::TextureData {
+            id: 0,
+            image: image::DynamicImage::new_rgba8(4, 4),
+            ..Default::default()
+        });
+        #[rustfmt::skip]
+        armature.styles.push(shared::Style { id: 0, name: "Style".to_string(), active: true, textures: vec![shared::Texture { name: "Tex".to_string(), size: shared::Vec2::new(4., 4.), ..Default::default() }] });
+        let bone = &mut armature.bones[1];
+        bone.tex = "Tex".to_string();
+        (bone.vertices, bone.indices) = renderer::create_tex_rect(&shared::Vec2::new(4., 4.));
+        let first_vert = bone.vertices[0].id as i32;
+        bone.binds.push(shared::BoneBind {
+            bone_id: 0,
+            verts: vec![shared::BoneBindVert {
+                id: first_vert,
+                weight: 0.5,
+            }],
+            ..Default::default()
+        });
+        armature
+    }
+
     // todo: add headless wgpu and egui to test those that depend on it
 
     #[test]
+    #[cfg(feature = "editor")]
     fn import_skf() {
         let mut shared = init_shared();
-        *shared.ui.file_name.lock().unwrap() = "./samples/skellington.skf".to_string();
-        *shared.ui.import_contents.lock().unwrap() = vec![0];
+        *shared.ui.file_path.lock().unwrap() = vec![PathBuf::from("./samples/_skellington.skf")];
+        *shared.ui.file_type.lock().unwrap() = 2;
         file_reader::read_import(&mut shared, None, None, None, None);
         assert_eq!(shared.armature.bones[0].name != "New Bone", true);
         assert_eq!(shared.armature.styles.len() > 0, true);
@@ -1869,42 +1898,1449 @@ mod tests {
 
     // check if exported skf is same as imported
     #[test]
+    #[cfg(feature = "editor")]
     fn export_skf() {
         let mut shared = init_shared();
-        *shared.ui.file_name.lock().unwrap() = "./samples/skellington.skf".to_string();
-        *shared.ui.import_contents.lock().unwrap() = vec![0];
+        *shared.ui.file_path.lock().unwrap() = vec![PathBuf::from("./samples/_skellington.skf")];
+        *shared.ui.file_type.lock().unwrap() = 2;
         file_reader::read_import(&mut shared, None, None, None, None);
         assert_eq!(shared.armature.bones[0].name != "New Bone", true);
         assert_eq!(shared.armature.styles.len() > 0, true);
     }
 
+    // atlas settings and trimmed/turned textures survive an export and import
     #[test]
+    #[cfg(feature = "editor")]
+    fn atlas_settings() {
+        let mut shared = init_shared();
+        *shared.ui.file_path.lock().unwrap() = vec![PathBuf::from("./samples/_skellington.skf")];
+        *shared.ui.file_type.lock().unwrap() = 2;
+        file_reader::read_import(&mut shared, None, None, None, None);
+
+        // a tall texture with a transparent border, to be trimmed and turned
+        let mut image = image::RgbaImage::new(6, 10);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            if (1..4).contains(&x) && (2..9).contains(&y) {
+                *pixel = image::Rgba([x as u8 * 40, y as u8 * 20, 100, 255]);
+            }
+        }
+        let data_id = shared.armature.tex_data.len() as i32;
+        #[rustfmt::skip]
+        shared.armature.tex_data.push(shared::TextureData { id: data_id, image: image.clone().into(), ..Default::default() });
+        #[rustfmt::skip]
+        shared.armature.styles[0].textures.push(shared::Texture { name: "Tall".to_string(), size: shared::Vec2::new(6., 10.), data_id, ..Default::default() });
+
+        let settings = shared::AtlasSettings {
+            max_size: shared::Vec2I::new(4000, 1500),
+            power_of_two: true,
+            square: false,
+            extrude: 2,
+            trim: true,
+            rotate: true,
+        };
+        shared.edit_mode.export_atlas = settings.clone();
+        shared.edit_mode.export_tex_padding = shared::Vec2::new(1., 1.);
+        let before: Vec<Vec<image::RgbaImage>> = shared
+            .armature
+            .styles
+            .iter()
+            .map(|style| {
+                let texes = style.textures.iter();
+                texes
+                    .map(|t| shared.armature.tex_data(t).unwrap().image.to_rgba8())
+                    .collect()
+            })
+            .collect();
+
+        let cursor = std::io::Cursor::new(vec![]);
+        #[rustfmt::skip]
+        let bytes = utils::write_skf(cursor, &shared.armature, &shared.camera, &shared.edit_mode, None).unwrap().into_inner();
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes.clone())).unwrap();
+        let root: shared::Root =
+            serde_json::from_reader(zip.by_name("armature.json").unwrap()).unwrap();
+        for atlas in &root.atlases {
+            assert_eq!(atlas.settings, settings);
+        }
+        let tall = root.styles[0].textures.last().unwrap();
+        assert_eq!(tall.rotated, true);
+        assert_eq!(tall.trim_offset, shared::Vec2I::new(1, 2));
+        assert_eq!(tall.source_size, shared::Vec2I::new(6, 10));
+        assert_eq!(tall.ser_size, shared::Vec2I::new(3, 7));
+        let mut png = vec![];
+        let page = &root.atlases[tall.atlas_idx as usize].filename;
+        std::io::Read::read_to_end(&mut zip.by_name(page).unwrap(), &mut png).unwrap();
+        let page = image::load_from_memory(&png).unwrap().into_rgba8();
+        assert_eq!(page.width().is_power_of_two(), true);
+        assert_eq!(page.height().is_power_of_two(), true);
+        assert_eq!(page.width() <= 4000 && page.height() <= 1500, true);
+        // extruded edges repeat the texture's corner
+        let (x, y) = (tall.ser_offset.x as u32, tall.ser_offset.y as u32);
+        assert_eq!(page.get_pixel(x - 2, y - 2), page.get_pixel(x, y));
+
+        // runtimes draw only the trimmed part, where it was, and sample it from its turned region
+        let mut skf = runtime::SkfRuntime::load(std::io::Cursor::new(bytes.clone())).unwrap();
+        #[rustfmt::skip]
+        let rect = shared::Bone { id: 1000, parent_id: -1, tex: "Tall".to_string(), scale: shared::Vec2::new(1., 1.), pivot_scale: shared::Vec2::new(1., 1.), ik_family_id: -1, ..Default::default() };
+        let mut mesh = rect.clone();
+        mesh.id = 1001;
+        mesh.verts_edited = true;
+        let pixels = [(2, 5), (1, 2), (3, 8)];
+        for (i, (x, y)) in pixels.iter().enumerate() {
+            let uv = shared::Vec2::new((*x as f32 + 0.5) / 6., (*y as f32 + 0.5) / 10.);
+            #[rustfmt::skip]
+            mesh.vertices.push(shared::Vertex { id: i as u32, uv, ..Default::default() });
+        }
+        mesh.indices = vec![0, 1, 2];
+        skf.armature.bones.push(rect);
+        skf.armature.bones.push(mesh);
+        let posed = skf.pose_layered(&[]);
+
+        let offset = shared::Vec2::new(x as f32, y as f32);
+        let region = shared::Vec2::new(tall.ser_size.y as f32, tall.ser_size.x as f32);
+        let rect = posed.iter().find(|b| b.id == 1000).unwrap();
+        assert_eq!(rect.vertices.len(), 4);
+        for vert in &rect.vertices {
+            // both corners are nudged inwards, onto their pixel
+            let src = shared::Vec2::new(vert.pos.x + 3., 5. - vert.pos.y);
+            let src = src + (shared::Vec2::new(2.5, 5.5) - src) * 0.1;
+            let uv = vert.uv + (shared::Vec2::new(0.5, 0.5) - vert.uv) * 0.1;
+            let atlas = offset + uv * region;
+            let atlas_pixel = page.get_pixel(atlas.x as u32, atlas.y as u32);
+            assert_eq!(atlas_pixel, image.get_pixel(src.x as u32, src.y as u32));
+        }
+        let mesh = posed.iter().find(|b| b.id == 1001).unwrap();
+        for (vert, (x, y)) in mesh.vertices.iter().zip(pixels) {
+            let atlas = offset + vert.uv * region;
+            let atlas_pixel = page.get_pixel(atlas.x as u32, atlas.y as u32);
+            assert_eq!(atlas_pixel, image.get_pixel(x, y));
+        }
+
+        let mut imported = init_shared();
+        utils::import(
+            std::io::Cursor::new(bytes),
+            &mut imported,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(imported.edit_mode.export_atlas, settings);
+        for (s, style) in imported.armature.styles.iter().enumerate() {
+            for (t, tex) in style.textures.iter().enumerate() {
+                let image = imported.armature.tex_data(tex).unwrap().image.to_rgba8();
+                assert_eq!(image == before[s][t], true);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn webp_atlases() {
+        let mut shared = init_shared();
+        *shared.ui.file_path.lock().unwrap() = vec![PathBuf::from("./samples/_skellington.skf")];
+        *shared.ui.file_type.lock().unwrap() = 2;
+        file_reader::read_import(&mut shared, None, None, None, None);
+        let before: Vec<image::RgbaImage> = shared.armature.styles[0]
+            .textures
+            .iter()
+            .map(|t| shared.armature.tex_data(t).unwrap().image.to_rgba8())
+            .collect();
+
+        let export = |edit_mode: &shared::EditMode| {
+            let cursor = std::io::Cursor::new(vec![]);
+            #[rustfmt::skip]
+            let bytes = utils::write_skf(cursor, &shared.armature, &shared.camera, edit_mode, None).unwrap().into_inner();
+            let mut imported = init_shared();
+            utils::import(
+                std::io::Cursor::new(bytes.clone()),
+                &mut imported,
+                None,
+                None,
+                None,
+                None,
+            );
+            let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+            let root: shared::Root =
+                serde_json::from_reader(zip.by_name("armature.json").unwrap()).unwrap();
+            let mut atlas = vec![];
+            let file = zip.by_name(&root.atlases[0].filename);
+            std::io::Read::read_to_end(&mut file.unwrap(), &mut atlas).unwrap();
+            let atlas = image::load_from_memory(&atlas).unwrap().into_rgba8();
+            (root, atlas, imported)
+        };
+
+        // lossless is exact
+        let mut edit_mode = shared.edit_mode.clone();
+        edit_mode.export_img_format = shared::ExportImgFormat::Webp;
+        let (root, _, imported) = export(&edit_mode);
+        assert_eq!(root.atlases[0].filename, "atlas0.webp");
+        assert_eq!(root.premultiplied_alpha, false);
+        for (t, tex) in imported.armature.styles[0].textures.iter().enumerate() {
+            let image = imported.armature.tex_data(tex).unwrap().image.to_rgba8();
+            assert_eq!(image == before[t], true);
+        }
+
+        // reduced colors and premultiplied is close, and is straight alpha again in the editor
+        edit_mode.export_img_format = shared::ExportImgFormat::WebpReducedColors;
+        edit_mode.export_premultiplied = true;
+        let (root, atlas, imported) = export(&edit_mode);
+        assert_eq!(root.img_format, shared::ExportImgFormat::WebpReducedColors);
+        assert_eq!(root.premultiplied_alpha, true);
+        assert_eq!(
+            atlas
+                .pixels()
+                .all(|p| p.0[..3].iter().all(|c| *c <= p.0[3])),
+            true
+        );
+        for (t, tex) in imported.armature.styles[0].textures.iter().enumerate() {
+            let image = imported.armature.tex_data(tex).unwrap().image.to_rgba8();
+            let pixels = image.pixels().zip(before[t].pixels());
+            for (after, before) in pixels.filter(|(_, b)| b.0[3] == 255) {
+                let diff = (0..3).map(|c| after.0[c].abs_diff(before.0[c]));
+                assert_eq!(diff.max().unwrap() <= 2, true);
+            }
+        }
+
+        // only formats that can say so are premultiplied
+        edit_mode.export_format = shared::ExportArmatureFormat::Godot;
+        assert_eq!(edit_mode.premultiplied_atlases(), false);
+        edit_mode.export_format = shared::ExportArmatureFormat::Spine;
+        let pages = vec!["skeleton.webp".to_string()];
+        let sizes = vec![shared::Vec2I::new(1, 1)];
+        let atlas = spine::atlas(
+            &shared.armature,
+            &pages,
+            &sizes,
+            edit_mode.premultiplied_atlases(),
+        );
+        assert_eq!(atlas.contains("pma: true\n"), true);
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
     fn import_psd() {
+        // the sample PSDs are kept zipped
+        let file = std::fs::File::open("./samples/PSDs.zip").unwrap();
+        let mut zip = zip::ZipArchive::new(file).unwrap();
+        let path = std::env::temp_dir().join("skelform_test_skellington.psd");
+        let mut psd = std::fs::File::create(&path).unwrap();
+        std::io::copy(&mut zip.by_name("skellington.psd").unwrap(), &mut psd).unwrap();
+
         let mut shared = init_shared();
-        *shared.ui.file_name.lock().unwrap() = "./samples/skellington.psd".to_string();
-        *shared.ui.import_contents.lock().unwrap() = vec![0];
+        *shared.ui.file_path.lock().unwrap() = vec![path];
+        *shared.ui.file_type.lock().unwrap() = 2;
         file_reader::read_import(&mut shared, None, None, None, None);
-        assert_eq!(shared.armature.bones[0].name != "New Bone", true);
-        assert_eq!(shared.armature.styles.len() > 0, true);
+
+        // the rig waits in `psd_armature` until it's accepted
+        let arm = &shared.psd_armature;
+        assert_eq!(arm.bones[0].name, "Root");
+        assert_eq!(arm.bones.len() > 1, true);
+        assert_eq!(arm.styles.iter().any(|s| !s.textures.is_empty()), true);
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn import_aseprite() {
+        fn chunk(kind: u16, data: Vec<u8>) -> Vec<u8> {
+            let mut bytes = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+            bytes.extend(kind.to_le_bytes());
+            bytes.extend(data);
+            bytes
+        }
+        fn string(str: &str) -> Vec<u8> {
+            let mut bytes = (str.len() as u16).to_le_bytes().to_vec();
+            bytes.extend(str.as_bytes());
+            bytes
+        }
+        fn layer(kind: u16, level: u16, name: &str) -> Vec<u8> {
+            let mut data = [1, 0].to_vec();
+            for word in [kind, level, 0, 0, 0] {
+                data.extend(word.to_le_bytes());
+            }
+            data.extend([255, 0, 0, 0]);
+            data.extend(string(name));
+            chunk(0x2004, data)
+        }
+        fn cel(layer: u16, pos: (i16, i16), size: (u16, u16), color: [u8; 4]) -> Vec<u8> {
+            let mut data = layer.to_le_bytes().to_vec();
+            data.extend(pos.0.to_le_bytes());
+            data.extend(pos.1.to_le_bytes());
+            data.extend([255, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            data.extend(size.0.to_le_bytes());
+            data.extend(size.1.to_le_bytes());
+            data.extend(color.repeat((size.0 * size.1) as usize));
+            chunk(0x2005, data)
+        }
+        fn frame(chunks: Vec<Vec<u8>>) -> Vec<u8> {
+            let data = chunks.concat();
+            let mut bytes = ((data.len() + 16) as u32).to_le_bytes().to_vec();
+            bytes.extend([0xFA, 0xF1, 0, 0, 100, 0, 0, 0]);
+            bytes.extend((chunks.len() as u32).to_le_bytes());
+            bytes.extend(data);
+            bytes
+        }
+
+        // Body (with a pivot) holds Head, which moves down a pixel in the tagged frame
+        let (red, white, blue) = ([255, 0, 0, 255], [255; 4], [0, 0, 255, 255]);
+        let mut tags = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].to_vec();
+        tags.extend([1, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        tags.extend(string("Blink"));
+        #[rustfmt::skip]
+        let frames = [
+            frame(vec![layer(1, 0, "Body"), layer(0, 1, "body"), layer(0, 1, "$pivot"), layer(1, 1, "Head"), layer(0, 2, "head"), chunk(0x2018, tags), cel(1, (2, 2), (4, 4), red), cel(2, (2, 4), (1, 1), red), cel(4, (3, 0), (2, 2), white)]),
+            frame(vec![cel(1, (2, 2), (4, 4), red), cel(4, (3, 1), (2, 2), blue)]),
+        ];
+        let mut bytes = 0u32.to_le_bytes().to_vec();
+        for word in [0xA5E0, 2, 8, 8, 32, 1, 0] {
+            bytes.extend((word as u16).to_le_bytes());
+        }
+        bytes.resize(128, 0);
+        bytes.extend(frames.concat());
+
+        let header = bytes[..128].to_vec();
+
+        let mut shared = init_shared();
+        file_reader::read_aseprite(bytes, &mut shared, None, None, None, None);
+        let arm = &shared.psd_armature;
+        let names: Vec<&str> = arm.bones.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["Root", "Body", "Head"]);
+        assert_eq!(arm.bones[2].parent_id, arm.bones[1].id);
+        assert_eq!(arm.bones[1].pivot_pos, shared::Vec2::new(0.375, 0.125));
+
+        // the head covers both frames, and only it differs in the tag's style
+        let default = &arm.styles[0].textures;
+        assert_eq!(default[1].size, shared::Vec2::new(2., 3.));
+        let blink = arm.styles.iter().find(|s| s.name == "Blink").unwrap();
+        assert_eq!(blink.textures.len(), 1);
+        assert_eq!(blink.textures[0].name, "Head");
+        let img = arm.tex_data(&blink.textures[0]).unwrap().image.to_rgba8();
+        assert_eq!(img.get_pixel(0, 0).0, [0; 4]);
+        assert_eq!(img.get_pixel(1, 2).0, blue);
+
+        // palette sizes are capped rather than trusted, and entries must be within them
+        for (size, last) in [(u32::MAX, u32::MAX - 1), (4, 9)] {
+            let mut palette = vec![];
+            for dword in [size, 0, last] {
+                palette.extend(dword.to_le_bytes());
+            }
+            palette.extend([0; 8]);
+            for _ in 0..(last as usize + 1).min(10) {
+                palette.extend([0, 0, 255, 255, 255, 255]);
+            }
+            let mut bad = header.clone();
+            bad[6] = 1;
+            bad.extend(frame(vec![chunk(0x2019, palette)]));
+            assert_eq!(crate::aseprite::Aseprite::from_bytes(&bad).is_err(), true);
+        }
     }
 
     #[test]
+    #[cfg(feature = "editor")]
+    fn import_openraster() {
+        use std::io::Write;
+        fn png(img: image::RgbaImage) -> Vec<u8> {
+            let mut bytes = std::io::Cursor::new(vec![]);
+            img.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+            bytes.into_inner()
+        }
+        fn block(size: u32, pos: (u32, u32), block: u32, color: [u8; 4]) -> image::RgbaImage {
+            image::RgbaImage::from_fn(size, size, |x, y| {
+                let inside = x >= pos.0 && y >= pos.1 && x < pos.0 + block && y < pos.1 + block;
+                image::Rgba(if inside { color } else { [0; 4] })
+            })
+        }
+
+        // Body holds Head, a hidden pivot and an angry style. Its layer covers the whole canvas
+        let stack = r#"<image w="16" h="16"><stack>
+            <stack name="Body">
+                <stack name="Head"><layer name="head" src="data/head.png" x="4" y="0"/></stack>
+                <stack name="$&quot;Angry&quot;"><layer name="angry" src="data/angry.png" x="4" y="8"/></stack>
+                <layer name="$pivot" src="data/pivot.png" x="6" y="10" visibility="hidden"/>
+                <layer name="body" src="data/body.png" opacity="0.5"/>
+            </stack>
+        </stack></image>"#;
+        #[rustfmt::skip]
+        let files = [
+            ("stack.xml", stack.as_bytes().to_vec()),
+            ("data/head.png", png(block(4, (0, 0), 4, [255; 4]))),
+            ("data/angry.png", png(block(8, (0, 0), 8, [0, 0, 255, 255]))),
+            ("data/pivot.png", png(block(2, (0, 0), 2, [0, 0, 0, 255]))),
+            ("data/body.png", png(block(16, (4, 8), 8, [255, 0, 0, 255]))),
+        ];
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        for (name, bytes) in files {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(&bytes).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let mut shared = init_shared();
+        file_reader::read_ora(bytes, &mut shared, None, None, None, None);
+        let arm = &shared.psd_armature;
+        let names: Vec<&str> = arm.bones.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["Root", "Body", "Head"]);
+        assert_eq!(arm.bones[2].parent_id, arm.bones[1].id);
+        assert_eq!(arm.bones[1].pivot_pos, shared::Vec2::new(0.125, -0.125));
+
+        // trimmed to the block, at half opacity
+        let body = &arm.styles[0].textures[0];
+        assert_eq!(body.size, shared::Vec2::new(8., 8.));
+        let img = arm.tex_data(body).unwrap().image.to_rgba8();
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 128]);
+        let angry = arm.styles.iter().find(|s| s.name == "Angry").unwrap();
+        assert_eq!(angry.textures[0].name, "Body");
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn import_krita() {
+        use std::io::Write;
+
+        // one compressed tile of green, each channel a literal then back references
+        let mut lzf = vec![];
+        for value in [0, 255, 0, 255] {
+            lzf.extend([0, value]);
+            let mut left = 64 * 64 - 1;
+            while left > 0 {
+                let len = left.min(264);
+                lzf.extend([0xE0, (len - 9) as u8, 0]);
+                left -= len;
+            }
+        }
+        let header = "VERSION 2\nTILEWIDTH 64\nTILEHEIGHT 64\nPIXELSIZE 4\nDATA 1\n";
+        let mut tiles = format!("{}64,0,LZF,{}\n", header, lzf.len() + 1).into_bytes();
+        tiles.push(1);
+        tiles.extend(lzf);
+
+        // the vector layer has no pixels to read
+        let maindoc = r#"<DOC><IMAGE width="128" height="128" name="Doc"><layers>
+            <layer name="Arm" nodetype="grouplayer" visible="1" opacity="255" filename="layer2"><layers>
+                <layer name="arm" nodetype="paintlayer" visible="1" opacity="255" x="0" y="0" filename="layer3"/>
+                <layer name="lines" nodetype="shapelayer" visible="1" opacity="255" filename="layer4"/>
+            </layers></layer>
+        </layers></IMAGE></DOC>"#;
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        for (name, bytes) in [
+            ("maindoc.xml", maindoc.as_bytes()),
+            ("Doc/layers/layer3", &tiles),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let mut shared = init_shared();
+        file_reader::read_ora(bytes, &mut shared, None, None, None, None);
+        let arm = &shared.psd_armature;
+        assert_eq!(arm.bones[1].name, "Arm");
+        assert_eq!(arm.bones[1].pos, shared::Vec2::new(32., 32.));
+        let tex = &arm.styles[0].textures[0];
+        assert_eq!(tex.size, shared::Vec2::new(64., 64.));
+        let img = arm.tex_data(tex).unwrap().image.to_rgba8();
+        assert_eq!(img.get_pixel(10, 10).0, [0, 255, 0, 255]);
+
+        // a tile that decompresses short is an error, not a panic
+        let mut short = format!("{}64,0,LZF,3\n", header).into_bytes();
+        short.extend([1, 0, 0]);
+        assert_eq!(crate::openraster::read_kra_tiles(&short).is_err(), true);
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn animated_images() {
+        use image::AnimationDecoder;
+
+        // a gradient with too many colors for a palette, fading out halfway across
+        let frame = |shift: u32| {
+            image::RgbaImage::from_fn(20, 20, |x, y| {
+                let alpha = if x < 10 { 255 } else { 0 };
+                image::Rgba([(x * 12) as u8, (y * 12) as u8, (shift * 80) as u8, alpha])
+            })
+        };
+        let frames: Vec<image::RgbaImage> = (0..3).map(frame).collect();
+
+        // 60 fps is too fast for a GIF, so the second and third frames are merged
+        let gif = crate::animated::encode_gif(&frames, 60, 3).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(gif.as_slice()).unwrap();
+        let mut delays = vec![];
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            let transparent = frame.transparent.unwrap();
+            assert_eq!(frame.buffer[0] != transparent, true);
+            assert_eq!(frame.buffer[19], transparent);
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, vec![2, 3]);
+        assert_eq!(decoder.repeat(), gif::Repeat::Finite(2));
+
+        let apng = crate::animated::encode_apng(&frames, 60, 3).unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(apng))
+            .read_info()
+            .unwrap();
+        let control = reader.info().animation_control().unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (3, 3));
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, frames[0].as_raw().clone());
+
+        // lossless, so frames should come back exactly
+        let webp = crate::animated::encode_webp(&frames, 60, 0).unwrap();
+        let decoder = image::codecs::webp::WebPDecoder::new(std::io::Cursor::new(webp)).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(decoded.len(), 3);
+        for (decoded, frame) in decoded.iter().zip(&frames) {
+            assert_eq!(decoded.buffer(), frame);
+        }
+        // rounded frame durations still add up to the exact length
+        let ms = decoded.iter().map(|f| f.delay().numer_denom_ms());
+        assert_eq!(ms.map(|(num, den)| num / den).sum::<u32>(), 50);
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn video_streaming() {
+        use shared::ExportVideoType as Type;
+        let size = shared::Vec2::new(33., 20.);
+        let has = |video_type, arg: &str| {
+            let encoder = shared::ExportVideoEncoder::AV1;
+            let args = utils::ffmpeg_args(&video_type, &encoder, size, 24, "out").join(" ");
+            args.contains(arg)
+        };
+        assert_eq!(
+            has(Type::Mp4, "-c:v libaom-av1 -pix_fmt yuv420p -vf pad="),
+            true
+        );
+        assert_eq!(has(Type::Webm, "-video_size 33x20 -framerate 24"), true);
+        assert_eq!(has(Type::Webm, "-c:v libvpx-vp9 -pix_fmt yuva420p"), true);
+        assert_eq!(
+            has(Type::ProRes, "-profile:v 4444 -pix_fmt yuva444p10le out"),
+            true
+        );
+        assert_eq!(has(Type::PngMov, "-c:v png -pix_fmt rgba out"), true);
+
+        // there's no downloaded ffmpeg next to the test binary
+        let mut shared = init_shared();
+        let anims = &mut shared.armature.animations;
+        anims.push(shared::Animation::default());
+        shared.ui.exporting_anims = vec![true];
+        shared.ui.exporting_video_type = Type::Webm;
+        shared.ui.sprite_size = size;
+        #[rustfmt::skip]
+        let result = utils::stream_videos(&shared.armature, &shared.ui, &shared.camera, &shared.config, None, &shared.renderer, &std::env::temp_dir());
+        assert_eq!(result.unwrap_err().starts_with("spawn ffmpeg"), true);
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
     fn drag_bone_above() {
         let mut shared = init_shared();
         shared.armature.bones[0].name = "Bone0".to_string();
         shared.armature.bones[1].name = "Bone1".to_string();
         shared.armature.bones[2].name = "Bone2".to_string();
-        armature_window::drag_bone(&mut shared, true, 2, 1);
+        editor::drag_bone(&mut shared.armature, 1, &vec![2], true);
         assert_eq!(shared.armature.bones[1].name, "Bone2");
     }
 
     #[test]
+    #[cfg(feature = "editor")]
     fn drag_bone_directly() {
         let mut shared = init_shared();
         shared.armature.bones[0].name = "Bone0".to_string();
         shared.armature.bones[1].name = "Bone1".to_string();
         shared.armature.bones[2].name = "Bone2".to_string();
-        armature_window::drag_bone(&mut shared, false, 2, 1);
+        editor::drag_bone(&mut shared.armature, 1, &vec![2], false);
         assert_eq!(shared.armature.bones[2].parent_id, 1);
     }
+
+    #[test]
+    fn runtime_pose() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut skf = runtime::SkfRuntime::load(file).unwrap();
+        let anim_idx = skf.anim_idx("Jump").unwrap();
+        let posed = skf.pose(anim_idx, 0.25);
+        assert_eq!(posed.len(), skf.armature.bones.len());
+        assert_eq!(posed.iter().any(|b| b.vertices.len() > 0), true);
+    }
+
+    #[test]
+    fn runtime_blend() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut skf = runtime::SkfRuntime::load(file).unwrap();
+        let stand = skf.anim_idx("Stand").unwrap();
+        let jump = skf.anim_idx("Jump").unwrap();
+        let posed = skf.pose(stand, 0.1);
+        let blended = skf.pose_blended(&[(stand, 0.1, 1.), (jump, 0.1, 0.)]);
+        for b in 0..posed.len() {
+            assert_eq!((posed[b].pos - blended[b].pos).mag() < 0.001, true);
+        }
+    }
+
+    #[test]
+    fn subframe_sampling() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let skf = runtime::SkfRuntime::load(file).unwrap();
+        let jump = skf.anim_idx("Jump").unwrap();
+        let anim = &skf.armature.animations[jump];
+
+        let own = utils::export_frames(anim, 0, 1);
+        let doubled = utils::export_frames(anim, anim.fps * 2, 1);
+        assert_eq!(doubled.len(), own.len() * 2);
+        assert_eq!(doubled[1], 0.5);
+
+        let frame = skf.frame_at(jump, 0.5 / anim.fps as f32);
+        assert_eq!(frame, 0.5);
+    }
+
+    #[test]
+    fn import_errors() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut zip = zip::ZipArchive::new(file).unwrap();
+        let mut json: serde_json::Value =
+            serde_json::from_reader(zip.by_name("armature.json").unwrap()).unwrap();
+
+        let mut bad_rot = json.clone();
+        bad_rot["bones"][2]["rot"] = "up".into();
+        let err = backwards_compat::proceed(bad_rot).err().unwrap();
+        let is_bad_rot =
+            matches!(err, shared::SkfError::BadValue { ref path, .. } if path == "bones[2].rot");
+        assert_eq!(is_bad_rot, true);
+
+        // indices are checked after parsing
+        let load = |json: &serde_json::Value| {
+            let mut buf = std::io::Cursor::new(vec![]);
+            let mut writer = zip::ZipWriter::new(&mut buf);
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("armature.json", options).unwrap();
+            std::io::Write::write_all(&mut writer, json.to_string().as_bytes()).unwrap();
+            writer.finish().unwrap();
+            runtime::SkfRuntime::load(buf).err().unwrap()
+        };
+        let mut bad_visuals = json.clone();
+        bad_visuals["bones"][1]["visuals_id"] = 999.into();
+        let err = load(&bad_visuals);
+        let path = "bones[1].visuals_id".to_string();
+        assert_eq!(
+            matches!(err, shared::SkfError::OutOfRange { path: p, idx: 999, .. } if p == path),
+            true
+        );
+
+        // bones can go without visuals, but textures can't go without an atlas
+        json["styles"][0]["textures"][0]["atlas_idx"] = (-1).into();
+        let err = load(&json);
+        let path = "styles[0].textures[0].atlas_idx".to_string();
+        assert_eq!(
+            matches!(err, shared::SkfError::OutOfRange { path: p, idx: -1, .. } if p == path),
+            true
+        );
+    }
+
+    #[test]
+    fn armature_schema() {
+        let schema = utils::armature_schema();
+
+        // a new version needs its own schema file, for runtimes to follow
+        let path = format!(
+            "./assets/schemas/armature-{}.json",
+            env!("CARGO_PKG_VERSION")
+        );
+        let file = std::fs::read_to_string(&path).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&file).unwrap();
+        assert_eq!(saved, schema);
+
+        // and older ones stay, for runtimes still reading them
+        for entry in std::fs::read_dir("./assets/schemas").unwrap() {
+            let name = entry.unwrap().file_name().to_string_lossy().to_string();
+            if !name.ends_with(".json") {
+                continue;
+            }
+            let version = name
+                .trim_start_matches("armature-")
+                .trim_end_matches(".json");
+            assert_eq!(version.split('.').all(|n| n.parse::<u32>().is_ok()), true);
+            let file = std::fs::read_to_string(format!("./assets/schemas/{}", name)).unwrap();
+            let old: serde_json::Value = serde_json::from_str(&file).unwrap();
+            assert_eq!(jsonschema::validator_for(&old).is_ok(), true);
+        }
+
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        for sample in ["_skellington", "_skellina"] {
+            let file = std::fs::File::open(format!("./samples/{}.skf", sample)).unwrap();
+            let mut zip = zip::ZipArchive::new(file).unwrap();
+            let json: serde_json::Value =
+                serde_json::from_reader(zip.by_name("armature.json").unwrap()).unwrap();
+            let errors: Vec<String> = validator
+                .iter_errors(&json)
+                .map(|e| e.to_string())
+                .collect();
+            assert_eq!(errors, Vec::<String>::new());
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut zip = zip::ZipArchive::new(file).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_reader(zip.by_name("armature.json").unwrap()).unwrap();
+        let root = backwards_compat::proceed(json.clone()).unwrap();
+
+        let bytes = binary::encode(&json);
+        let decoded = binary::decode(&bytes).unwrap();
+        let decoded_root = backwards_compat::proceed(decoded).unwrap();
+        let original = serde_json::to_string(&root).unwrap();
+        assert_eq!(serde_json::to_string(&decoded_root).unwrap(), original);
+        assert_eq!(bytes.len() < json.to_string().len() / 2, true);
+
+        let bytes = binary::encode_root(&root);
+        let decoded: shared::Root =
+            serde_json::from_value(binary::decode(&bytes).unwrap()).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), original);
+
+        // truncations fail cleanly
+        for len in (0..bytes.len()).step_by(bytes.len() / 500 + 1) {
+            assert_eq!(binary::decode(&bytes[..len]).is_err(), true);
+        }
+    }
+
+    #[test]
+    fn binary_bad_input() {
+        // magic, version 1 and an empty string table
+        let header = [b'S', b'K', b'F', b'B', 1, 0, 0];
+        let with = |body: &[u8]| [&header[..], body].concat();
+        let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+
+        // a table of many rows without columns, or with more values than bytes
+        assert_eq!(
+            binary::decode(&with(&[&[9][..], &huge, &[0]].concat())).is_err(),
+            true
+        );
+        assert_eq!(
+            binary::decode(&with(&[&[9][..], &huge, &[1, 0]].concat())).is_err(),
+            true
+        );
+        assert_eq!(binary::decode(&with(&[9, 0, 0])).is_ok(), true);
+
+        // arrays, objects and strings longer than the file
+        assert_eq!(
+            binary::decode(&with(&[&[7][..], &huge].concat())).is_err(),
+            true
+        );
+        assert_eq!(binary::decode(&with(&[8, 100, 0, 0])).is_err(), true);
+        let strings = [&[b'S', b'K', b'F', b'B', 1, 0][..], &huge].concat();
+        assert_eq!(binary::decode(&strings).is_err(), true);
+
+        // deeply nested arrays
+        let mut nested = vec![7, 1].repeat(100_000);
+        nested.push(0);
+        assert_eq!(binary::decode(&with(&nested)).is_err(), true);
+        let mut nested = vec![7, 1].repeat(32);
+        nested.push(0);
+        assert_eq!(binary::decode(&with(&nested)).is_ok(), true);
+    }
+
+    #[test]
+    fn repair_armature() {
+        use crate::shared::Warnings;
+        let mut armature = shared::Armature::default();
+        for (id, parent_id) in [(0, 2), (1, 0), (2, 1)] {
+            #[rustfmt::skip]
+            armature.bones.push(shared::Bone { id, parent_id, ..Default::default() });
+        }
+        armature.bones[1].rot = f32::NAN;
+        armature.bones[2].binds.push(shared::BoneBind {
+            bone_id: 0,
+            verts: vec![shared::BoneBindVert { id: 4, weight: 1. }],
+            ..Default::default()
+        });
+        let mut anim = shared::Animation::default();
+        anim.keyframes.push(shared::Keyframe {
+            bone_id: 99,
+            ..Default::default()
+        });
+        armature.animations.push(anim);
+
+        let repairs = utils::repair_armature(&mut armature);
+        let types: Vec<Warnings> = repairs.iter().map(|r| r.warn_type.clone()).collect();
+        #[rustfmt::skip]
+        assert_eq!(types == vec![Warnings::BrokenCycle, Warnings::MissingBindVerts, Warnings::OrphanKeyframes, Warnings::NanTransform], true);
+
+        // the whole hierarchy should now lead to a root
+        assert_eq!(armature.bones.iter().any(|b| b.parent_id == -1), true);
+        assert_eq!(armature.bones[1].rot, 0.);
+        assert_eq!(armature.bones[2].binds[0].verts.len(), 0);
+        assert_eq!(armature.animations[0].keyframes.len(), 0);
+        assert_eq!(utils::repair_armature(&mut armature).len(), 0);
+
+        // runtimes get the same repairs
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut zip = zip::ZipArchive::new(file).unwrap();
+        let mut json: serde_json::Value =
+            serde_json::from_reader(zip.by_name("armature.json").unwrap()).unwrap();
+        json["bones"][5]["parent_id"] = 60.into();
+        let mut buf = std::io::Cursor::new(vec![]);
+        let mut writer = zip::ZipWriter::new(&mut buf);
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("armature.json", options).unwrap();
+        std::io::Write::write_all(&mut writer, json.to_string().as_bytes()).unwrap();
+        writer.finish().unwrap();
+        let mut skf = runtime::SkfRuntime::load(buf).unwrap();
+        let roots = skf.armature.bones.iter().filter(|b| b.parent_id == -1);
+        assert_eq!(roots.count(), 6);
+        assert_eq!(skf.pose_frame(0, 0.).len(), skf.armature.bones.len());
+    }
+
+    #[test]
+    #[cfg(feature = "editor")]
+    fn cli() {
+        let dest = std::env::temp_dir().join("skelform_cli_test.skf");
+        let dest = dest.to_str().unwrap().to_string();
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+        // not a subcommand, so the editor should open it
+        assert_eq!(crate::cli::run(&args(&["skelform", "file.skf"])), None);
+
+        let sample = "./samples/_skellington.skf";
+        #[rustfmt::skip]
+        let export = args(&["skelform", "export", sample, &dest, "--binary", "--padding", "2,2"]);
+        assert_eq!(crate::cli::run(&export), Some(0));
+        #[rustfmt::skip]
+        let export = args(&["skelform", "export", sample, &dest, "--atlas-size", "1024,512", "--non-square", "--extrude", "1", "--trim", "--img-format", "webp-reduced-colors", "--premultiplied"]);
+        assert_eq!(crate::cli::run(&export), Some(0));
+        assert_eq!(
+            crate::cli::run(&args(&["skelform", "validate", &dest])),
+            Some(0)
+        );
+
+        // converting a file onto itself keeps it whole
+        let convert = args(&["skelform", "convert", &dest, &dest]);
+        assert_eq!(crate::cli::run(&convert), Some(0));
+        assert_eq!(
+            std::path::Path::new(&format!("{}.part", dest)).exists(),
+            false
+        );
+        let validate = args(&["skelform", "validate", "./samples/missing.skf", &dest]);
+        assert_eq!(crate::cli::run(&validate), Some(1));
+        let validate = args(&["skelform", "validate", &dest]);
+        assert_eq!(crate::cli::run(&validate), Some(0));
+        assert_eq!(
+            crate::cli::run(&args(&[
+                "skelform",
+                "export",
+                sample,
+                &dest,
+                "--img-format",
+                "gif"
+            ])),
+            Some(1)
+        );
+
+        // no GPU in tests, so this also covers the CPU rasterizer
+        #[rustfmt::skip]
+        let export = args(&["skelform", "export", sample, &dest, "--sequences", "--anims", "Stand", "--sprite-size", "32,32"]);
+        assert_eq!(crate::cli::run(&export), Some(0));
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut png = vec![];
+        std::io::Read::read_to_end(&mut zip.by_name("Stand/0.png").unwrap(), &mut png).unwrap();
+        let img = image::load_from_memory(&png).unwrap().into_rgba8();
+        assert_eq!(img.dimensions(), (32, 32));
+        assert_eq!(img.pixels().any(|p| p.0[3] == 255), true);
+
+        // the second cycle repeats the first, so its frames should be deduplicated
+        #[rustfmt::skip]
+        let export = args(&["skelform", "export", sample, &dest, "--packed", "--anims", "Stand", "--cycles", "2"]);
+        assert_eq!(crate::cli::run(&export), Some(0));
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_reader(zip.by_name("0.json").unwrap()).unwrap();
+        let frames = json["frames"].as_object().unwrap();
+        let names = json["animations"]["Stand"].as_array().unwrap();
+        assert_eq!(frames.len(), names.len());
+        let rects: std::collections::HashSet<String> =
+            frames.values().map(|f| f["frame"].to_string()).collect();
+        assert_eq!(rects.len() < frames.len(), true);
+        for frame in frames.values() {
+            let (src, size) = (&frame["spriteSourceSize"], &frame["sourceSize"]);
+            assert_eq!(
+                src["x"].as_u64().unwrap() + src["w"].as_u64().unwrap()
+                    <= size["w"].as_u64().unwrap(),
+                true
+            );
+            assert_eq!(frame["trimmed"], true);
+        }
+        assert_eq!(zip.by_name("0.png").is_ok(), true);
+
+        #[rustfmt::skip]
+        let export = args(&["skelform", "export", sample, &dest, "--apng", "--anims", "Stand", "--sprite-size", "32,32", "--plays", "2"]);
+        assert_eq!(crate::cli::run(&export), Some(0));
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut apng = vec![];
+        std::io::Read::read_to_end(&mut zip.by_name("Stand.png").unwrap(), &mut apng).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(apng))
+            .read_info()
+            .unwrap();
+        assert_eq!(reader.info().animation_control().unwrap().num_plays, 2);
+        let _ = std::fs::remove_file(dest);
+    }
+
+    #[test]
+    fn spine_export() {
+        let mut armature = textured_armature(&[(0, -1, "Bone"), (1, 0, "Bone"), (2, -1, "Bone")]);
+        armature.bones[1].pos = shared::Vec2::new(10., 0.);
+        armature.bones[1].rot = std::f32::consts::FRAC_PI_2;
+        armature.bones[1].binds.push(shared::BoneBind {
+            bone_id: 2,
+            is_path: true,
+            ..Default::default()
+        });
+
+        let mut anim = shared::Animation {
+            name: "Spin".to_string(),
+            fps: 10,
+            ..Default::default()
+        };
+        let (start_handle, end_handle) = utils::interp_preset(shared::HandlePreset::SineIn);
+        for (frame, value) in [(0, std::f32::consts::FRAC_PI_2), (10, std::f32::consts::PI)] {
+            anim.keyframes.push(shared::Keyframe {
+                frame,
+                bone_id: 1,
+                element: shared::AnimElement::Rotation,
+                value,
+                start_handle,
+                end_handle,
+                ..Default::default()
+            });
+        }
+        armature.animations.push(anim);
+
+        // with the parent moved and the pivot offset, each bone sees the vertex elsewhere
+        armature.bones[0].pos = shared::Vec2::new(0., 5.);
+        armature.bones[1].pivot_pos = shared::Vec2::new(0.5, 0.);
+
+        let json = spine::skeleton_json(&armature);
+
+        // duplicate names are made unique, and root bones are parented to 'root'
+        assert_eq!(json["bones"][1]["parent"], "root");
+        assert_eq!(json["bones"][2]["name"], "Bone (1)");
+        assert_eq!(json["bones"][2]["parent"], "Bone");
+        assert_eq!(json["bones"][2]["rotation"], 90.);
+
+        // rotation is relative to the setup pose, with absolute curves
+        let keys = &json["animations"]["Spin"]["bones"]["Bone (1)"]["rotate"];
+        assert_eq!(keys[1]["value"], 90.);
+        assert_eq!(keys[0]["curve"], serde_json::json!([0.5, 0., 1., 90.]));
+
+        // the bound vertex is split between both bones
+        let mesh = &json["skins"][0]["attachments"]["Bone (1)"]["Tex"];
+        assert_eq!(mesh["type"], "mesh");
+        assert_eq!(mesh["vertices"][0], 2);
+        assert_eq!(mesh["vertices"][4], 0.5);
+        assert_eq!(mesh["vertices"][8], 0.5);
+        assert_eq!(mesh["path"], "Style/Tex");
+
+        // and it's in the setup space of each of them, with the pivot turned by the mesh bone
+        let first = armature.bones[1].vertices[0].pos;
+        let coord = |i: usize| mesh["vertices"][i].as_f64().unwrap() as f32;
+        let near = |a: f32, b: f32| (a - b).abs() < 0.001;
+        assert_eq!(mesh["vertices"][1], 2);
+        assert_eq!(
+            near(coord(2), first.x + 2.) && near(coord(3), first.y),
+            true
+        );
+        assert_eq!(mesh["vertices"][5], 1);
+        assert_eq!(
+            near(coord(6), first.x) && near(coord(7), first.y + 2.),
+            true
+        );
+
+        let lost = export_common::lost_features(&armature, spine::UNSUPPORTED);
+        assert_eq!(lost, vec!["export_modal.lost.path_binds"]);
+
+        let cursor = std::io::Cursor::new(vec![]);
+        let edit_mode = shared::EditMode::default();
+        let bytes = spine::write_spine(cursor, &armature, &edit_mode)
+            .unwrap()
+            .into_inner();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        let mut atlas = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name("skeleton.atlas").unwrap(), &mut atlas)
+            .unwrap();
+        assert_eq!(atlas.starts_with("skeleton.png\n"), true);
+        assert_eq!(atlas.contains("Style/Tex\nbounds: "), true);
+        assert_eq!(zip.by_name("skeleton.png").is_ok(), true);
+    }
+
+    #[test]
+    fn spine_import() {
+        let skeleton = r#"{
+            "skeleton": { "spine": "4.1.00", "fps": 10 },
+            "bones": [
+                { "name": "root" },
+                { "name": "hip", "parent": "root", "x": 5, "rotation": 90 },
+                { "name": "leg", "parent": "hip", "x": 10 }
+            ],
+            "slots": [
+                { "name": "leg", "bone": "leg", "attachment": "leg", "color": "ffffff80" },
+                { "name": "shadow", "bone": "hip", "attachment": "blob" }
+            ],
+            "skins": [{ "name": "default", "attachments": {
+                "leg": { "leg": { "type": "mesh", "uvs": [0, 0, 1, 0, 1, 1], "triangles": [0, 1, 2],
+                    "vertices": [1, 2, 0, 0, 1, 2, 1, 4, 0, 0.5, 2, -6, 0, 0.5, 1, 2, 0, 4, 1] } },
+                "shadow": { "blob": { "x": 2, "width": 4, "height": 2 } }
+            }}],
+            "animations": { "walk": {
+                "bones": { "leg": { "rotate": [
+                    { "value": 0, "curve": [0.25, 0, 0.75, 45] },
+                    { "time": 1, "value": 90, "curve": "stepped" },
+                    { "time": 2, "value": 0 }
+                ]}},
+                "slots": { "shadow": { "attachment": [{ "time": 0.5, "name": null }] } }
+            }}
+        }"#;
+        let atlas = "skeleton.png\nsize: 8,4\nfilter: Nearest,Nearest\nleg\nbounds: 0,0,4,4\nblob\nbounds: 4,0,4,2\nrotate: 90\n";
+        let mut page = image::RgbaImage::new(8, 4);
+        page.put_pixel(4, 0, image::Rgba([255, 0, 0, 255]));
+        let mut load_page = |name: &str| {
+            assert_eq!(name, "skeleton.png");
+            Some(image::DynamicImage::ImageRgba8(page.clone()))
+        };
+        let armature = spine::read_spine(skeleton, atlas, &mut load_page).unwrap();
+
+        // the empty root is left out, and the shadow slot gets its own bone
+        let names: Vec<&str> = armature.bones.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["hip", "leg", "shadow"]);
+        let (hip, leg, shadow) = (&armature.bones[0], &armature.bones[1], &armature.bones[2]);
+        assert_eq!(hip.parent_id, -1);
+        assert_eq!(hip.rot, std::f32::consts::FRAC_PI_2);
+        assert_eq!(leg.parent_id, hip.id);
+        assert_eq!(shadow.parent_id, hip.id);
+        assert_eq!((leg.zindex, shadow.zindex), (0, 1));
+        assert_eq!(leg.tint.a, 128. / 255.);
+
+        // regions keep their offset as a pivot, and rotated ones are turned back
+        assert_eq!(armature.styles.len(), 1);
+        assert_eq!(armature.styles[0].textures.len(), 2);
+        assert_eq!(shadow.tex, "blob");
+        assert_eq!(shadow.pivot_pos, shared::Vec2::new(0.5, 0.));
+        let blob = armature.tex_of(shadow.id).unwrap();
+        assert_eq!(blob.size, shared::Vec2::new(4., 2.));
+        let image = &armature.tex_data[blob.data_id as usize].image;
+        assert_eq!(
+            image::GenericImageView::get_pixel(image, 3, 0).0,
+            [255, 0, 0, 255]
+        );
+
+        // only the vertex split with the hip is bound to it, and it's placed where both bones
+        // agree it is in setup pose
+        assert_eq!(leg.verts_edited, true);
+        let pos = leg.vertices[1].pos;
+        assert_eq!((pos - shared::Vec2::new(-1., 0.)).mag() < 0.0001, true);
+        assert_eq!(leg.binds.len(), 1);
+        assert_eq!(leg.binds[0].bone_id, hip.id);
+        let bind_vert = shared::BoneBindVert { id: 1, weight: 0.5 };
+        assert_eq!(leg.binds[0].verts, vec![bind_vert]);
+
+        // curves are kept in the keyframe that ends them
+        let anim = &armature.animations[0];
+        assert_eq!(anim.fps, 10);
+        let rotations: Vec<&shared::Keyframe> = anim
+            .keyframes
+            .iter()
+            .filter(|kf| kf.element == shared::AnimElement::Rotation)
+            .collect();
+        assert_eq!(
+            rotations.iter().map(|kf| kf.frame).collect::<Vec<_>>(),
+            vec![0, 10, 20]
+        );
+        assert_eq!(rotations[1].value, std::f32::consts::FRAC_PI_2);
+        assert_eq!(rotations[1].start_handle, shared::Vec2::new(0.25, 0.));
+        assert_eq!(rotations[1].end_handle, shared::Vec2::new(0.75, 0.5));
+        assert_eq!(rotations[2].start_handle.y, 999.);
+        let hide = anim
+            .keyframes
+            .iter()
+            .find(|kf| kf.element == shared::AnimElement::Texture);
+        assert_eq!(
+            hide.map(|kf| (kf.frame, kf.value_str.as_str())),
+            Some((5, ""))
+        );
+
+        // malformed meshes are errors rather than panics
+        let broken = [
+            ("[0, 0, 1, 0, 1, 1]", "[0, 0, 1, 0, 1]"),
+            ("2, 0, 4, 1]", "2, 0, 4]"),
+            ("[1, 2, 0, 0, 1,", "[1, 7, 0, 0, 1,"),
+        ];
+        for (from, to) in broken {
+            let skeleton = skeleton.replace(from, to);
+            let result = spine::read_spine(&skeleton, atlas, &mut load_page);
+            assert_eq!(result.is_err(), true);
+        }
+        let skeleton = skeleton.replace("[0, 1, 2]", "[0, 1, 3]");
+        let err = spine::read_spine(&skeleton, atlas, &mut load_page).err();
+        let path = "skins.default.leg.leg.triangles[2]";
+        #[rustfmt::skip]
+        assert_eq!(matches!(err, Some(shared::SkfError::OutOfRange { path: p, idx: 3, len: 3 }) if p == path), true);
+    }
+
+    #[test]
+    fn dragonbones_round_trip() {
+        let mut armature = textured_armature(&[(0, -1, "Hip"), (1, 0, "Leg")]);
+        armature.bones[1].pos = shared::Vec2::new(10., 5.);
+        armature.bones[1].rot = std::f32::consts::FRAC_PI_2;
+        let image = armature.tex_data[0].image.as_mut_rgba8().unwrap();
+        image.put_pixel(3, 0, image::Rgba([255, 0, 0, 255]));
+
+        // eased, then a turn of more than half a circle
+        let mut anim = shared::Animation {
+            name: "Spin".to_string(),
+            fps: 10,
+            ..Default::default()
+        };
+        let sine_in = utils::interp_preset(shared::HandlePreset::SineIn);
+        let linear = utils::interp_preset(shared::HandlePreset::Linear);
+        let turns = [(0, 90., linear), (10, 180., sine_in), (20, 450., linear)];
+        for (frame, degrees, (start_handle, end_handle)) in turns {
+            anim.keyframes.push(shared::Keyframe {
+                frame,
+                bone_id: 1,
+                element: shared::AnimElement::Rotation,
+                value: f32::to_radians(degrees),
+                start_handle,
+                end_handle,
+                ..Default::default()
+            });
+        }
+        armature.animations.push(anim);
+        assert_eq!(
+            export_common::lost_features(&armature, dragonbones::UNSUPPORTED).len(),
+            0
+        );
+
+        let json = dragonbones::skeleton_json(&armature);
+        let db = &json["armature"][0];
+
+        // y and angles are flipped, under a generated root
+        assert_eq!(db["bone"][0]["name"], "root");
+        assert_eq!(db["bone"][2]["parent"], "Hip");
+        assert_eq!(db["bone"][2]["transform"]["y"], -5.);
+        assert_eq!(db["bone"][2]["transform"]["skY"], -90.);
+
+        // rotation is relative to the setup pose, turning clockwise past half a circle
+        let keys = &db["animation"][0]["bone"][0]["rotateFrame"];
+        assert_eq!(db["animation"][0]["bone"][0]["name"], "Leg");
+        assert_eq!(keys[0]["curve"], serde_json::json!([0.5, 0., 1., 1.]));
+        assert_eq!(keys[1]["rotate"], -90.);
+        assert_eq!(keys[1]["tweenEasing"], 0);
+        assert_eq!(keys[1]["clockwise"], -1);
+        assert_eq!(keys[2]["rotate"], -360.);
+
+        // the bound vertex is split between both bones (offset by the root)
+        let mesh = &db["skin"][0]["slot"][0]["display"][0];
+        assert_eq!(mesh["type"], "mesh");
+        assert_eq!(mesh["path"], "Style/Tex");
+        let weights = &mesh["weights"];
+        assert_eq!(
+            weights.as_array().unwrap()[..5],
+            serde_json::json!([2, 2, 0.5, 1, 0.5]).as_array().unwrap()[..]
+        );
+
+        // back through a zip, like the export
+        let cursor = std::io::Cursor::new(vec![]);
+        let edit_mode = shared::EditMode::default();
+        let bytes = dragonbones::write_dragonbones(cursor, &armature, &edit_mode)
+            .unwrap()
+            .into_inner();
+        let imported = export_common::read_skeleton_zip(std::io::Cursor::new(bytes)).unwrap();
+
+        let names: Vec<&str> = imported.bones.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["Hip", "Leg"]);
+        let (hip, leg) = (&imported.bones[0], &imported.bones[1]);
+        assert_eq!(leg.parent_id, hip.id);
+        assert_eq!(leg.pos, armature.bones[1].pos);
+        assert_eq!((leg.rot - std::f32::consts::FRAC_PI_2).abs() < 0.0001, true);
+        assert_eq!(leg.tex, "Tex");
+        assert_eq!(leg.binds.len(), 1);
+        assert_eq!(leg.binds[0].bone_id, hip.id);
+        assert_eq!(leg.binds[0].verts.len(), 1);
+        assert_eq!(leg.binds[0].verts[0].weight, 0.5);
+        let tex = imported.tex_of(leg.id).unwrap();
+        let image = &imported.tex_data[tex.data_id as usize].image;
+        assert_eq!(
+            image::GenericImageView::get_pixel(image, 3, 0).0,
+            [255, 0, 0, 255]
+        );
+
+        let anim = &imported.animations[0];
+        assert_eq!(anim.fps, 10);
+        let rotations: Vec<(i32, f32)> = anim
+            .keyframes
+            .iter()
+            .filter(|kf| kf.element == shared::AnimElement::Rotation)
+            .map(|kf| (kf.frame, kf.value.to_degrees().round()))
+            .collect();
+        assert_eq!(rotations, vec![(0, 90.), (10, 180.), (20, 450.)]);
+        assert_eq!(anim.keyframes[1].start_handle, sine_in.0);
+        assert_eq!(anim.keyframes[1].end_handle, sine_in.1);
+
+        // DragonBones' own skins pose vertices in the armature's space instead, which the
+        // weighted bones should agree on in setup pose. (10, -7) is (10, 7) in SkelForm's space
+        let mut posed = json.clone();
+        let mesh = &mut posed["armature"][0]["skin"][0]["slot"][0]["display"][0];
+        mesh["vertices"][0] = 10.into();
+        mesh["vertices"][1] = (-7).into();
+        mesh["bonePose"] = serde_json::json!([1, 1, 0, 0, 1, 0, 0, 2, 0, -1, 1, 0, 10, -5]);
+        #[rustfmt::skip]
+        let atlas = r#"{ "imagePath": "tex.png", "SubTexture": [{ "name": "Style/Tex", "x": 0, "y": 0, "width": 4, "height": 4 }] }"#.to_string();
+        let mut load_page = |_: &str| Some(image::DynamicImage::new_rgba8(4, 4));
+        let skeleton = posed.to_string();
+        let imported =
+            dragonbones::read_dragonbones(&skeleton, &[atlas.clone()], &mut load_page).unwrap();
+        let pos = imported.bones[1].vertices[0].pos;
+        assert_eq!((pos - shared::Vec2::new(9.5, -0.5)).mag() < 0.0001, true);
+
+        // malformed meshes are errors rather than panics
+        let mesh = &mut posed["armature"][0]["skin"][0]["slot"][0]["display"][0];
+        mesh["weights"][1] = 9.into();
+        let skeleton = posed.to_string();
+        let result = dragonbones::read_dragonbones(&skeleton, &[atlas.clone()], &mut load_page);
+        assert_eq!(result.is_err(), true);
+        let mesh = &mut posed["armature"][0]["skin"][0]["slot"][0]["display"][0];
+        mesh["vertices"].as_array_mut().unwrap().push(0.into());
+        let skeleton = posed.to_string();
+        let result = dragonbones::read_dragonbones(&skeleton, &[atlas], &mut load_page);
+        assert_eq!(result.is_err(), true);
+        // only IK constraints can be keyed
+        armature.animations[0].keyframes.push(shared::Keyframe {
+            element: shared::AnimElement::IkMode,
+            ..Default::default()
+        });
+        let lost = export_common::lost_features(&armature, dragonbones::UNSUPPORTED);
+        assert_eq!(lost, vec!["export_modal.lost.ik_keys"]);
+    }
+
+    #[test]
+    fn godot_scene() {
+        let bones = [(0, -1, "Hip"), (1, 0, "Leg"), (2, -1, "Arm/Hand")];
+        let mut armature = textured_armature(&bones);
+        armature.bones[1].pos = shared::Vec2::new(10., 5.);
+        armature.bones[1].rot = std::f32::consts::FRAC_PI_2;
+        armature.bones[2].pos = shared::Vec2::new(0., 20.);
+
+        // and a plain rect
+        #[rustfmt::skip]
+        armature.styles[0].textures.push(shared::Texture { name: "Other".to_string(), size: shared::Vec2::new(2., 2.), offset: shared::Vec2::new(4., 0.), ..Default::default() });
+        armature.bones[2].tex = "Other".to_string();
+
+        // eased, then stepped. The rect swaps textures, and an event fires
+        let mut anim = shared::Animation {
+            name: "Wave".to_string(),
+            fps: 10,
+            ..Default::default()
+        };
+        let sine_in = utils::interp_preset(shared::HandlePreset::SineIn);
+        let snap = utils::interp_preset(shared::HandlePreset::Snap);
+        let keys = [(0, 0., sine_in), (10, 1., sine_in), (20, 2., snap)];
+        for (frame, value, (start_handle, end_handle)) in keys {
+            #[rustfmt::skip]
+            anim.keyframes.push(shared::Keyframe { frame, bone_id: 1, element: shared::AnimElement::Rotation, value, start_handle, end_handle, ..Default::default() });
+        }
+        #[rustfmt::skip]
+        anim.keyframes.push(shared::Keyframe { frame: 5, bone_id: 2, element: shared::AnimElement::Texture, value_str: "Tex".to_string(), ..Default::default() });
+        #[rustfmt::skip]
+        anim.events.push(shared::AnimEvent { frame: 5, name: "step".to_string(), ..Default::default() });
+        armature.animations.push(anim);
+
+        let scene = godot::scene(&armature, &["skeleton.png".to_string()]);
+
+        // y and rotations are flipped, and names are made valid
+        #[rustfmt::skip]
+        assert!(scene.contains("[node name=\"Leg\" type=\"Bone2D\" parent=\"Skeleton2D/Hip\"]\nposition = Vector2(10, -5)\nrotation = -1.5708\n"));
+        assert!(scene.contains("[node name=\"Arm_Hand\" type=\"Bone2D\""));
+
+        // the skinned mesh sits next to the skeleton, resting both bones at identity
+        #[rustfmt::skip]
+        assert!(scene.contains("[node name=\"Leg Texture\" type=\"Polygon2D\" parent=\".\"]\nskeleton = NodePath(\"../Skeleton2D\")\n"));
+        assert!(scene.contains("bones = [NodePath(\"Hip\"), PackedFloat32Array(0.5, 0, 0, 0)"));
+        let identity_rests = scene.matches("rest = Transform2D(1, 0, 0, 1, 0, 0)");
+        assert_eq!(identity_rests.count(), 2);
+        #[rustfmt::skip]
+        assert!(scene.contains("[node name=\"Arm_Hand Texture\" type=\"Sprite2D\" parent=\"Skeleton2D/Arm_Hand\"]"));
+
+        // handles become offsets in seconds, and steps jump just before their key
+        let rotation = "tracks/0/path = NodePath(\"Skeleton2D/Hip/Leg:rotation\")";
+        assert!(scene.contains(rotation));
+        #[rustfmt::skip]
+        assert!(scene.contains("\"points\": PackedFloat32Array(0, 0, 0, 0.5, 0, -1, 0, 0, 0, 0, -1, 0, 0, 0, 0, -2, 0, 0, 0, 0),\n\"times\": PackedFloat32Array(0, 1, 1.999, 2)"));
+
+        // the rect's texture swap, and the event
+        #[rustfmt::skip]
+        assert!(scene.contains("\"values\": [Rect2(4, 0, 2, 2), Rect2(0, 0, 4, 4)]"));
+        assert!(scene.contains("\"args\": [\"step\", 0, \"\"],\n\"method\": &\"skelform_event\""));
+        assert!(scene.contains("\"Wave\": SubResource(\"anim_0\")"));
+    }
+
+    #[test]
+    fn root_motion() {
+        let mut armature = shared::Armature::default();
+        armature.bones.push(shared::Bone {
+            id: 0,
+            root_motion: true,
+            ..Default::default()
+        });
+        let (start_handle, end_handle) = utils::interp_preset(shared::HandlePreset::Linear);
+        let mut anim = shared::Animation::default();
+        for (frame, value) in [(0, 0.), (10, 100.)] {
+            anim.keyframes.push(shared::Keyframe {
+                frame,
+                element: shared::AnimElement::PositionX,
+                value,
+                start_handle,
+                end_handle,
+                ..Default::default()
+            });
+        }
+        armature.animations.push(anim);
+
+        let motion = armature.root_motion_at(0, 5.);
+        assert_eq!((motion.x - 50.).abs() < 0.01, true);
+        assert_eq!(motion.y, 0.);
+
+        // moved by the animation, unless the game moves it instead
+        let mut skf = runtime::SkfRuntime {
+            armature: armature.clone(),
+            ..Default::default()
+        };
+        assert_eq!((skf.pose_frame(0, 5.)[0].pos.x - 50.).abs() < 0.01, true);
+        skf.extract_root_motion = true;
+        assert_eq!(skf.pose_frame(0, 5.)[0].pos.x.abs() < 0.01, true);
+        assert_eq!(
+            skf.pose_blended(&[(0, 0.5, 1.)])[0].pos.x.abs() < 0.01,
+            true
+        );
+
+        armature.bones[0].root_motion = false;
+        assert_eq!(armature.root_motion_at(0, 5.).x, 0.);
+    }
+
+    #[test]
+    fn loop_modes() {
+        let mut anim = shared::Animation {
+            fps: 10,
+            length: 10,
+            ..Default::default()
+        };
+        assert_eq!(anim.loop_frame(12.), 2.);
+
+        anim.loop_mode = shared::LoopMode::Once;
+        assert_eq!(anim.loop_frame(12.), 9.);
+        assert_eq!(*utils::export_frames(&anim, 0, 2).last().unwrap(), 9.);
+
+        anim.loop_mode = shared::LoopMode::PingPong;
+        assert_eq!(anim.loop_frame(12.), 6.);
+        assert_eq!(utils::export_frames(&anim, 0, 1).len(), 18);
+
+        anim.loop_mode = shared::LoopMode::Reverse;
+        assert_eq!(anim.loop_frame(2.), 7.);
+        assert_eq!(utils::export_frames(&anim, 0, 1)[0], 9.);
+    }
+
+    #[test]
+    fn loop_modes_agree() {
+        use std::time::{Duration, Instant};
+        let mut skf = runtime::SkfRuntime::default();
+        #[rustfmt::skip]
+        skf.armature.animations.push(shared::Animation { fps: 10, length: 10, ..Default::default() });
+        skf.armature.animations[0]
+            .keyframes
+            .push(shared::Keyframe::default());
+
+        let modes = [
+            shared::LoopMode::Loop,
+            shared::LoopMode::Once,
+            shared::LoopMode::Reverse,
+            shared::LoopMode::PingPong,
+        ];
+        for mode in modes {
+            skf.armature.animations[0].loop_mode = mode;
+            let exported = utils::export_frames(&skf.armature.animations[0], 0, 2);
+            for (f, &frame) in exported.iter().enumerate() {
+                assert_eq!(frame >= 0. && frame < 10., true);
+
+                // runtime
+                assert_eq!(skf.frame_at(0, f as f32 / 10.), frame);
+
+                // editor, a tenth of a frame in
+                let anim = &mut skf.armature.animations[0];
+                let elapsed = Duration::from_secs_f32((f as f32 + 0.1) / 10.);
+                anim.elapsed = Some(Instant::now() - elapsed);
+                assert_eq!((anim.set_frame() - frame).abs() < 0.2, true);
+            }
+        }
+    }
+
+    #[test]
+    fn anim_events_between() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut skf = runtime::SkfRuntime::load(file).unwrap();
+        let jump = skf.anim_idx("Jump").unwrap();
+        let anim = &mut skf.armature.animations[jump];
+        anim.events = vec![shared::AnimEvent {
+            frame: 1,
+            name: "footstep".to_string(),
+            ..Default::default()
+        }];
+        let fps = anim.fps as f32;
+        let loop_len = anim.keyframes.last().unwrap().frame as f32;
+
+        assert_eq!(skf.events_between(jump, 0., 1. / fps).len(), 0);
+        assert_eq!(skf.events_between(jump, 1. / fps, 2. / fps).len(), 1);
+
+        // fires again on the next cycle
+        let next_cycle = (loop_len + 1.) / fps;
+        let events = skf.events_between(jump, next_cycle, next_cycle + 0.001);
+        assert_eq!(events.len(), 1);
+
+        // shown (and fired) backwards
+        let anim = &mut skf.armature.animations[jump];
+        anim.loop_mode = shared::LoopMode::Reverse;
+        let last = anim.len_frames() as f32 - 1.;
+        let at = |frame: f32| (frame - 0.5) / fps;
+        assert_eq!(skf.events_between(jump, 0., at(last - 1.)).len(), 0);
+        assert_eq!(skf.events_between(jump, at(last - 1.), at(last)).len(), 1);
+
+        // fires on both passes
+        let anim = &mut skf.armature.animations[jump];
+        anim.loop_mode = shared::LoopMode::PingPong;
+        let cycle = anim.cycle_frames() as f32;
+        assert_eq!(skf.events_between(jump, at(1.), at(2.)).len(), 1);
+        assert_eq!(skf.events_between(jump, at(2.), at(cycle - 1.)).len(), 0);
+        assert_eq!(skf.events_between(jump, at(cycle - 1.), at(cycle)).len(), 1);
+    }
+
+    #[test]
+    fn additive_mask() {
+        let file = std::fs::File::open("./samples/_skellington.skf").unwrap();
+        let mut skf = runtime::SkfRuntime::load(file).unwrap();
+        let stand = skf.anim_idx("Stand").unwrap();
+        let jump = skf.anim_idx("Jump").unwrap();
+        let arm = &mut skf.armature;
+        let rest = arm.bones.clone();
+        let base = arm.animate(stand, 0., None);
+        let full_jump = arm.animate(jump, 5., None);
+
+        let masked_id = arm.bones[1].id;
+        arm.animations[jump].additive = true;
+        arm.animations[jump].mask = vec![masked_id];
+        let layered = arm.animate(jump, 5., Some(&base));
+
+        for b in 0..layered.len() {
+            let mut expected = base[b].pos;
+            if layered[b].id == masked_id {
+                expected += full_jump[b].pos - rest[b].pos;
+            }
+            assert_eq!((layered[b].pos - expected).mag() < 0.001, true);
+        }
+    }
 }
@@ -2,6 +2,8 @@
 
 use global_hotkey::hotkey::{Code, HotKey, Modifiers};
 
+#[cfg(not(target_arch = "wasm32"))]
+use skelform_lib::cli;
 use skelform_lib::{shared::*, utils};
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -27,6 +29,15 @@ fn main() -> Result<(), winit::error::EventLoopError> {
         console_log::init().expect("Failed to initialize logger!");
     }
 
+    // run subcommands (validate, convert, etc) without opening the window
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(code) = cli::run(&args) {
+            std::process::exit(code);
+        }
+    }
+
     // setup default values for shared values
     let mut app = skelform_lib::App::default();
     init_shared(&mut app.shared);
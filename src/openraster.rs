@@ -0,0 +1,460 @@
+//! OpenRaster (`.ora`) and Krita (`.kra`) reading, to rig images from their layer groups like
+//! a PSD.
+//!
+//! Both are zips with an XML layer stack: OpenRaster's `stack.xml` points to PNGs, while
+//! Krita's `maindoc.xml` points to its own tiled layer data (8-bit RGBA only). Layers are
+//! composited with normal blending, and groups are trimmed to what they draw.
+
+use crate::file_reader::{LayerGroup, LayerMarker, LayerRig};
+use crate::*;
+use image::{Rgba, RgbaImage};
+use quick_xml::events::{BytesStart, Event};
+use std::io::{Read, Seek};
+
+const KRA_TILE_COMPRESSED: u8 = 1;
+
+/// A layer's pixels and their top-left on the canvas.
+type LayerImage = (RgbaImage, i32, i32);
+
+/// A layer or group in the stack.
+pub struct StackNode {
+    pub name: String,
+    pub visible: bool,
+    /// 0 to 1
+    pub opacity: f32,
+    pub x: i32,
+    pub y: i32,
+    pub group: bool,
+    /// `src` of an OpenRaster layer, or `filename` of a Krita one
+    pub src: String,
+    /// top to bottom
+    pub children: Vec<StackNode>,
+}
+
+/// Layer in the order it will be rigged, with its image already loaded.
+struct FlatLayer {
+    name: String,
+    group: bool,
+    visible: bool,
+    parent: Option<usize>,
+    /// image and its top-left, with opacity applied
+    image: Option<LayerImage>,
+}
+
+fn corrupt(file: &str, message: String) -> SkfError {
+    SkfError::Corrupt {
+        file: file.to_string(),
+        message,
+    }
+}
+
+/// Layer rig of an OpenRaster or Krita document.
+pub fn read_layered_zip<R: Read + Seek>(data: R) -> Result<LayerRig, SkfError> {
+    let mut zip = zip::ZipArchive::new(data).map_err(|e| SkfError::Io(e.to_string()))?;
+    let mut read = |name: &str| -> Result<Vec<u8>, SkfError> {
+        let mut file = match zip.by_name(name) {
+            Ok(file) => file,
+            Err(_) => return Err(SkfError::MissingFile(name.to_string())),
+        };
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)
+            .map_err(|e| SkfError::Io(e.to_string()))?;
+        Ok(bytes)
+    };
+
+    let kra = read("maindoc.xml");
+    let (doc, xml) = match kra {
+        Ok(xml) => ("maindoc.xml", xml),
+        Err(_) => ("stack.xml", read("stack.xml")?),
+    };
+    let xml = String::from_utf8_lossy(&xml).to_string();
+    let (size, image_name, mut stack) = parse_stack(&xml).map_err(|e| corrupt(doc, e))?;
+
+    // OpenRaster's outermost stack is the image itself
+    if doc == "stack.xml" && stack.len() == 1 && stack[0].group {
+        stack = stack.remove(0).children;
+    }
+
+    let mut layers = vec![];
+    let mut load = |node: &StackNode| -> Result<LayerImage, SkfError> {
+        if doc == "stack.xml" {
+            let png = read(&node.src)?;
+            let img = image::load_from_memory(&png).map_err(|e| corrupt(&node.src, e.to_string()));
+            Ok((img?.to_rgba8(), node.x, node.y))
+        } else {
+            let path = format!("{}/layers/{}", image_name, node.src);
+            let tiles = read(&path)?;
+            let (img, x, y) = read_kra_tiles(&tiles).map_err(|e| corrupt(&path, e))?;
+            Ok((img, node.x + x, node.y + y))
+        }
+    };
+    flatten_stack(&stack, None, true, &mut load, &mut layers)?;
+
+    Ok(layer_rig(size, layers))
+}
+
+/// Canvas size, Krita's image name, and the top-level nodes of a `stack.xml` or `maindoc.xml`.
+pub fn parse_stack(xml: &str) -> Result<(Vec2, String, Vec<StackNode>), String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut size = Vec2::ZERO;
+    let mut image_name = String::new();
+
+    // nodes being read, with a root to collect the top-level ones
+    let mut open = vec![node(&BytesStart::new("root"), &reader)?];
+    loop {
+        let event = reader.read_event().map_err(|e| e.to_string())?;
+        let (e, empty) = match &event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                let tag = e.local_name();
+                if (tag.as_ref() == b"stack" || tag.as_ref() == b"layer") && open.len() > 1 {
+                    let done = open.pop().unwrap();
+                    open.last_mut().unwrap().children.push(done);
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        match e.local_name().as_ref() {
+            b"image" | b"IMAGE" => {
+                let w = attr(e, &reader, &["w", "width"])?;
+                let h = attr(e, &reader, &["h", "height"])?;
+                size = Vec2::new(w.parse().unwrap_or(0.), h.parse().unwrap_or(0.));
+                image_name = attr(e, &reader, &["name"])?;
+            }
+            b"stack" | b"layer" => {
+                let mut new = node(e, &reader)?;
+                new.group |= e.local_name().as_ref() == b"stack";
+                if empty {
+                    open.last_mut().unwrap().children.push(new);
+                } else {
+                    open.push(new);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok((size, image_name, open.remove(0).children))
+}
+
+fn node(e: &BytesStart, reader: &quick_xml::Reader<&[u8]>) -> Result<StackNode, String> {
+    let visibility = attr(e, reader, &["visibility", "visible"])?;
+    let opacity = attr(e, reader, &["opacity"])?;
+    let nodetype = attr(e, reader, &["nodetype"])?;
+    let mut src = attr(e, reader, &["src"])?;
+
+    // only paint layers have pixels (Krita also has vector, filter, etc layers)
+    if nodetype == "paintlayer" {
+        src = attr(e, reader, &["filename"])?;
+    }
+
+    // Krita's opacity is out of 255, OpenRaster's out of 1
+    let mut opacity = opacity.parse::<f32>().unwrap_or(1.);
+    if !nodetype.is_empty() {
+        opacity /= 255.;
+    }
+
+    Ok(StackNode {
+        name: attr(e, reader, &["name"])?,
+        visible: visibility != "hidden" && visibility != "0",
+        opacity,
+        x: attr(e, reader, &["x"])?.parse().unwrap_or(0),
+        y: attr(e, reader, &["y"])?.parse().unwrap_or(0),
+        group: nodetype == "grouplayer",
+        src,
+        children: vec![],
+    })
+}
+
+/// The first of these attributes that exists, or an empty string.
+fn attr(
+    e: &BytesStart,
+    reader: &quick_xml::Reader<&[u8]>,
+    names: &[&str],
+) -> Result<String, String> {
+    for name in names {
+        if let Some(attr) = e.try_get_attribute(name).map_err(|e| e.to_string())? {
+            let value = attr.decode_and_unescape_value(reader.decoder());
+            return Ok(value.map_err(|e| e.to_string())?.to_string());
+        }
+    }
+    Ok(String::new())
+}
+
+/// Rig order: parents before children, and lower layers before higher ones.
+fn flatten_stack(
+    nodes: &[StackNode],
+    parent: Option<usize>,
+    visible: bool,
+    load: &mut dyn FnMut(&StackNode) -> Result<LayerImage, SkfError>,
+    layers: &mut Vec<FlatLayer>,
+) -> Result<(), SkfError> {
+    for node in nodes.iter().rev() {
+        let visible = visible && node.visible;
+        let mut image = None;
+
+        if !node.group && !node.src.is_empty() && (visible || node.name.contains("$")) {
+            image = Some(load(node)?);
+        }
+        if let Some((img, _, _)) = &mut image {
+            for px in img.pixels_mut() {
+                px[3] = (px[3] as f32 * node.opacity).round() as u8;
+            }
+        }
+
+        layers.push(FlatLayer {
+            name: node.name.clone(),
+            group: node.group,
+            visible,
+            parent,
+            image,
+        });
+        if node.group {
+            let idx = Some(layers.len() - 1);
+            flatten_stack(&node.children, idx, visible, load, layers)?;
+        }
+    }
+    Ok(())
+}
+
+/// Groups and `$` layers to rig from, with the same naming as PSDs.
+fn layer_rig(dimensions: Vec2, layers: Vec<FlatLayer>) -> LayerRig {
+    let mut groups = vec![];
+    let mut markers = vec![];
+    for g in 0..layers.len() {
+        let children: Vec<usize> = (0..layers.len())
+            .filter(|l| layers[*l].parent == Some(g) && layers[*l].visible)
+            .filter(|l| !layers[*l].group)
+            .collect();
+        if !layers[g].group || children.is_empty() {
+            continue;
+        }
+
+        let content: Vec<&LayerImage> = children
+            .iter()
+            .filter(|l| !layers[**l].name.contains("$"))
+            .filter_map(|l| layers[*l].image.as_ref())
+            .collect();
+        let (image, pos) = composite(&content);
+        groups.push(LayerGroup {
+            id: g as u32,
+            name: layers[g].name.clone(),
+            parent_id: layers[g].parent.map(|p| p as u32),
+            image,
+            offset: Vec2::new(pos.x as f32, pos.y as f32),
+        });
+    }
+
+    for layer in &layers {
+        if layer.parent.is_none() || !layer.name.contains("$") {
+            continue;
+        }
+        let (img, x, y) = match &layer.image {
+            Some(image) => image,
+            None => continue,
+        };
+        if let Some((pos, size)) = opaque_bounds(img) {
+            markers.push(LayerMarker {
+                group_id: layer.parent.unwrap() as u32,
+                name: layer.name.clone(),
+                pos: Vec2::new((x + pos.x) as f32, (y + pos.y) as f32),
+                size: Vec2::new(size.x as f32, size.y as f32),
+            });
+        }
+    }
+
+    LayerRig {
+        dimensions,
+        groups,
+        markers,
+    }
+}
+
+/// Layers (bottom to top) blended together and trimmed, with the result's top-left.
+fn composite(layers: &[&LayerImage]) -> (RgbaImage, Vec2I) {
+    let mut min = Vec2I::new(i32::MAX, i32::MAX);
+    let mut max = Vec2I::new(i32::MIN, i32::MIN);
+    for (img, x, y) in layers {
+        min = Vec2I::new(min.x.min(*x), min.y.min(*y));
+        max.x = max.x.max(x + img.width() as i32);
+        max.y = max.y.max(y + img.height() as i32);
+    }
+    if min.x >= max.x || min.y >= max.y {
+        return (RgbaImage::new(0, 0), Vec2I::new(0, 0));
+    }
+
+    let mut canvas = RgbaImage::new((max.x - min.x) as u32, (max.y - min.y) as u32);
+    for (img, x, y) in layers {
+        let (x, y) = ((x - min.x) as i64, (y - min.y) as i64);
+        image::imageops::overlay(&mut canvas, img, x, y);
+    }
+
+    match opaque_bounds(&canvas) {
+        Some((pos, size)) => {
+            let (x, y, w, h) = (pos.x as u32, pos.y as u32, size.x as u32, size.y as u32);
+            let trimmed = image::imageops::crop_imm(&canvas, x, y, w, h).to_image();
+            (trimmed, Vec2I::new(min.x + pos.x, min.y + pos.y))
+        }
+        None => (RgbaImage::new(0, 0), Vec2I::new(0, 0)),
+    }
+}
+
+/// Top-left and size of the area with any visible pixels.
+fn opaque_bounds(img: &RgbaImage) -> Option<(Vec2I, Vec2I)> {
+    let mut min = Vec2I::new(i32::MAX, i32::MAX);
+    let mut max = Vec2I::new(-1, -1);
+    for (x, y, px) in img.enumerate_pixels() {
+        if px[3] == 0 {
+            continue;
+        }
+        min = Vec2I::new(min.x.min(x as i32), min.y.min(y as i32));
+        max = Vec2I::new(max.x.max(x as i32), max.y.max(y as i32));
+    }
+    if max.x == -1 {
+        return None;
+    }
+    Some((
+        min.clone(),
+        Vec2I::new(max.x - min.x + 1, max.y - min.y + 1),
+    ))
+}
+
+/// Krita's tiled layer data, as an image covering all its tiles and that image's top-left.
+pub fn read_kra_tiles(bytes: &[u8]) -> Result<LayerImage, String> {
+    let mut pos = 0;
+
+    // header of "KEY value" lines, ending with the tile count
+    let (mut tile_w, mut tile_h, mut pixel_size) = (64, 64, 4);
+    let tile_count;
+    loop {
+        let header = line(bytes, &mut pos)?;
+        let (key, value) = header.split_once(' ').unwrap_or((&header, ""));
+        let value = value.trim().parse::<usize>().unwrap_or(0);
+        match key {
+            "TILEWIDTH" => tile_w = value,
+            "TILEHEIGHT" => tile_h = value,
+            "PIXELSIZE" => pixel_size = value,
+            "DATA" => {
+                tile_count = value;
+                break;
+            }
+            _ => {}
+        }
+    }
+    if pixel_size != 4 {
+        return Err("Only 8-bit RGBA layers are supported.".to_string());
+    }
+
+    let mut tiles = vec![];
+    for _ in 0..tile_count {
+        let header = line(bytes, &mut pos)?;
+        let fields: Vec<&str> = header.split(',').collect();
+        if fields.len() < 4 {
+            return Err(format!("Bad tile header: {}", header));
+        }
+        let x: i32 = fields[0].parse().unwrap_or(0);
+        let y: i32 = fields[1].parse().unwrap_or(0);
+        let len: usize = fields[3].parse().unwrap_or(0);
+        tiles.push((x, y, pos, len));
+        pos = pos.saturating_add(len);
+    }
+    if pos > bytes.len() {
+        return Err("Unexpected end of layer data.".to_string());
+    }
+    if tiles.is_empty() {
+        return Ok((RgbaImage::new(0, 0), 0, 0));
+    }
+
+    let min_x = tiles.iter().map(|t| t.0).min().unwrap();
+    let min_y = tiles.iter().map(|t| t.1).min().unwrap();
+    let max_x = tiles.iter().map(|t| t.0).max().unwrap() + tile_w as i32;
+    let max_y = tiles.iter().map(|t| t.1).max().unwrap() + tile_h as i32;
+    let mut img = RgbaImage::new((max_x - min_x) as u32, (max_y - min_y) as u32);
+
+    let tile_size = tile_w * tile_h * pixel_size;
+    for (tx, ty, start, len) in tiles {
+        let data = &bytes[start..start + len];
+        if data.is_empty() {
+            continue;
+        }
+
+        // compressed tiles store each channel separately, raw ones pixel by pixel
+        let pixels = if data[0] == KRA_TILE_COMPRESSED {
+            let planar = lzf_decompress(&data[1..], tile_size)?;
+            if planar.len() < tile_size {
+                return Err("Tile is missing pixels.".to_string());
+            }
+            let plane = tile_w * tile_h;
+            (0..tile_size)
+                .map(|i| planar[(i % pixel_size) * plane + i / pixel_size])
+                .collect()
+        } else {
+            data[1..].to_vec()
+        };
+        if pixels.len() < tile_size {
+            return Err("Tile is missing pixels.".to_string());
+        }
+
+        for i in 0..tile_w * tile_h {
+            let (x, y) = ((tx - min_x) as u32, (ty - min_y) as u32);
+            let (x, y) = (x + (i % tile_w) as u32, y + (i / tile_w) as u32);
+            let bgra = &pixels[i * 4..i * 4 + 4];
+            img.put_pixel(x, y, Rgba([bgra[2], bgra[1], bgra[0], bgra[3]]));
+        }
+    }
+
+    Ok((img, min_x, min_y))
+}
+
+// next line of text in the layer data
+fn line(bytes: &[u8], pos: &mut usize) -> Result<String, String> {
+    let start = *pos;
+    while *pos < bytes.len() && bytes[*pos] != b'\n' {
+        *pos += 1;
+    }
+    if *pos >= bytes.len() {
+        return Err("Unexpected end of layer data.".to_string());
+    }
+    *pos += 1;
+    Ok(String::from_utf8_lossy(&bytes[start..*pos - 1]).to_string())
+}
+
+/// LZF, as Krita compresses tiles with.
+pub fn lzf_decompress(data: &[u8], out_len: usize) -> Result<Vec<u8>, String> {
+    let err = || "Tile data is corrupted.".to_string();
+    let mut out: Vec<u8> = Vec::with_capacity(out_len);
+    let mut i = 0;
+    while i < data.len() {
+        let ctrl = data[i] as usize;
+        i += 1;
+
+        // literal run
+        if ctrl < 32 {
+            let run = data.get(i..i + ctrl + 1).ok_or_else(err)?;
+            out.extend_from_slice(run);
+            i += ctrl + 1;
+            continue;
+        }
+
+        // back reference
+        let mut len = ctrl >> 5;
+        if len == 7 {
+            len += *data.get(i).ok_or_else(err)? as usize;
+            i += 1;
+        }
+        let back = ((ctrl & 0x1f) << 8) + *data.get(i).ok_or_else(err)? as usize + 1;
+        i += 1;
+        if back > out.len() {
+            return Err(err());
+        }
+        let start = out.len() - back;
+        for b in 0..len + 2 {
+            out.push(out[start + b]);
+        }
+    }
+    Ok(out)
+}
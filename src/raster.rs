@@ -0,0 +1,139 @@
+//! CPU rasterizer, for rendering armatures where no GPU is available (eg; headless exports).
+//!
+//! Draws the same triangles as `renderer::render_screenshot`, and shades them like
+//! shader.wgsl: `tex * tint + add_color`, alpha-blended in zindex order.
+
+use crate::*;
+use image::RgbaImage;
+
+/// Render the armature's `animated_bones` into an image, as seen by `camera`.
+pub fn render(armature: &Armature, camera: &Camera, clear_color: &Color, size: Vec2) -> RgbaImage {
+    let width = size.x as u32;
+    let height = size.y as u32;
+
+    // colors are blended in floats, and only quantized at the end
+    let clear = clear_color.as_f32();
+    let mut pixels = vec![[clear.r, clear.g, clear.b, 0.]; (width * height) as usize];
+
+    let bones = renderer::screenshot_bones(armature, camera);
+    let hiddens = armature.get_propagated_hidden();
+
+    for bone in &bones {
+        let tex = armature.anim_tex_of(bone.id);
+        let is_hidden = hiddens.get(&bone.id).is_none_or(|hidden| *hidden);
+        if bone.world_verts.is_empty() || tex.is_none() || is_hidden {
+            continue;
+        }
+        let data = match armature.tex_data(tex.unwrap()) {
+            Some(data) => data,
+            None => continue,
+        };
+
+        let converted;
+        let tex_img = match data.image.as_rgba8() {
+            Some(img) => img,
+            None => {
+                converted = data.image.to_rgba8();
+                &converted
+            }
+        };
+
+        for tri in bone.indices.chunks_exact(3) {
+            let verts = [
+                &bone.world_verts[tri[0] as usize],
+                &bone.world_verts[tri[1] as usize],
+                &bone.world_verts[tri[2] as usize],
+            ];
+            draw_triangle(&mut pixels, width, height, verts, tex_img);
+        }
+    }
+
+    let mut img = RgbaImage::new(width, height);
+    for (pixel, color) in img.pixels_mut().zip(pixels) {
+        let to_u8 = |c: f32| (c.clamp(0., 1.) * 255.).round() as u8;
+        *pixel = image::Rgba(color.map(to_u8));
+    }
+    img
+}
+
+fn draw_triangle(
+    pixels: &mut [[f32; 4]],
+    width: u32,
+    height: u32,
+    verts: [&Vertex; 3],
+    tex: &RgbaImage,
+) {
+    // clip space to pixel space (y is up in clip space, down in images)
+    let pos = verts.map(|v| {
+        let x = (v.pos.x + 1.) / 2. * width as f32;
+        let y = (1. - v.pos.y) / 2. * height as f32;
+        (x, y)
+    });
+    let edge = |a: (f32, f32), b: (f32, f32), p: (f32, f32)| {
+        (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+    };
+    let area = edge(pos[0], pos[1], pos[2]);
+    if area == 0. || !area.is_finite() {
+        return;
+    }
+
+    // bounding box, clamped to the image
+    let (xs, ys) = (pos.map(|p| p.0), pos.map(|p| p.1));
+    let min_x = xs[0].min(xs[1]).min(xs[2]).floor().max(0.) as u32;
+    let min_y = ys[0].min(ys[1]).min(ys[2]).floor().max(0.) as u32;
+    let max_x = xs[0].max(xs[1]).max(xs[2]).ceil().min(width as f32) as u32;
+    let max_y = ys[0].max(ys[1]).max(ys[2]).ceil().min(height as f32) as u32;
+
+    let add = verts.map(|v| v.add_color.as_f32());
+    let tint = verts[0].tint;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            // barycentric weights at the pixel's center
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+            let w = [
+                edge(pos[1], pos[2], p) / area,
+                edge(pos[2], pos[0], p) / area,
+                edge(pos[0], pos[1], p) / area,
+            ];
+            if w[0] < 0. || w[1] < 0. || w[2] < 0. {
+                continue;
+            }
+
+            let u = w[0] * verts[0].uv.x + w[1] * verts[1].uv.x + w[2] * verts[2].uv.x;
+            let v = w[0] * verts[0].uv.y + w[1] * verts[1].uv.y + w[2] * verts[2].uv.y;
+            let texel = sample_nearest(tex, u, v);
+
+            let lerp = |c: [f32; 3]| w[0] * c[0] + w[1] * c[1] + w[2] * c[2];
+            let add_r = lerp([add[0].r, add[1].r, add[2].r]);
+            let add_g = lerp([add[0].g, add[1].g, add[2].g]);
+            let add_b = lerp([add[0].b, add[1].b, add[2].b]);
+            let add_a = lerp([add[0].a, add[1].a, add[2].a]);
+
+            let src = [
+                (texel[0] * tint.r + add_r).clamp(0., 1.),
+                (texel[1] * tint.g + add_g).clamp(0., 1.),
+                (texel[2] * tint.b + add_b).clamp(0., 1.),
+                (texel[3] * tint.a + add_a).clamp(0., 1.),
+            ];
+
+            // same as wgpu::BlendState::ALPHA_BLENDING
+            let dst = &mut pixels[(y * width + x) as usize];
+            let a = src[3];
+            dst[0] = src[0] * a + dst[0] * (1. - a);
+            dst[1] = src[1] * a + dst[1] * (1. - a);
+            dst[2] = src[2] * a + dst[2] * (1. - a);
+            dst[3] = a + dst[3] * (1. - a);
+        }
+    }
+}
+
+// nearest filtering with clamp-to-edge, like the texture samplers in renderer.rs
+fn sample_nearest(tex: &RgbaImage, u: f32, v: f32) -> [f32; 4] {
+    if tex.width() == 0 || tex.height() == 0 {
+        return [0.; 4];
+    }
+    let x = ((u * tex.width() as f32).floor() as i64).clamp(0, tex.width() as i64 - 1);
+    let y = ((v * tex.height() as f32).floor() as i64).clamp(0, tex.height() as i64 - 1);
+    tex.get_pixel(x as u32, y as u32).0.map(|c| c as f32 / 255.)
+}
@@ -1,10 +1,13 @@
 //! Core rendering logic, abstracted from the rest of WGPU.
 
 use crate::*;
+#[cfg(feature = "editor")]
 use image::GenericImageView;
 use utils::shortest_angle_delta;
+#[cfg(feature = "editor")]
 use wgpu::{BindGroup, BindGroupLayout, Device, Queue, RenderPass};
 
+#[cfg(feature = "editor")]
 /// The `main` of this module.
 pub fn render(
     render_pass: &mut RenderPass,
@@ -644,6 +647,7 @@ pub fn render(
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn draw_armature(
     armature: &Armature,
     src_arm: &Armature,
@@ -695,6 +699,7 @@ pub fn draw_armature(
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn world_camera(camera: &Camera, config: &Config) -> Camera {
     let mut cam = camera.clone();
     match config.layout {
@@ -710,12 +715,14 @@ pub fn sel_tex_img(bone: &Bone, armature: &Armature) -> image::DynamicImage {
     armature.tex_data(tex).unwrap().image.clone()
 }
 
+#[cfg(feature = "editor")]
 pub fn mouse_vel(input: &InputStates, camera: &Camera) -> Vec2 {
     let mouse_world = utils::screen_to_world_space(input.mouse, camera.window);
     let mouse_prev_world = utils::screen_to_world_space(input.mouse_prev, camera.window);
     mouse_prev_world - mouse_world
 }
 
+#[cfg(feature = "editor")]
 fn vert(pos: Option<Vec2>, col: Option<Color>, uv: Option<Vec2>) -> Vertex {
     Vertex {
         pos: pos.unwrap_or_default(),
@@ -725,6 +732,7 @@ fn vert(pos: Option<Vec2>, col: Option<Color>, uv: Option<Vec2>) -> Vertex {
     }
 }
 
+#[cfg(feature = "editor")]
 fn tri_point(p: &Vec2, a: &Vec2, b: &Vec2, c: &Vec2) -> (f32, f32, f32, f32) {
     let s = a.y * c.x - a.x * c.y + (c.y - a.y) * p.x + (a.x - c.x) * p.y;
     let t = a.x * b.y - a.y * b.x + (a.y - b.y) * p.x + (b.x - a.x) * p.y;
@@ -749,6 +757,7 @@ fn tri_point(p: &Vec2, a: &Vec2, b: &Vec2, c: &Vec2) -> (f32, f32, f32, f32) {
     (-1., -1., -1., -1.)
 }
 
+#[cfg(feature = "editor")]
 pub fn get_sprite_boundary(armature: &Armature, camera: &Camera, config: &Config) -> (Vec2, Vec2) {
     let mut temp_arm = Armature::default();
     temp_arm.bones = armature.bones.clone();
@@ -780,6 +789,7 @@ pub fn get_sprite_boundary(armature: &Armature, camera: &Camera, config: &Config
     (left_top, right_bot)
 }
 
+#[cfg(feature = "editor")]
 /// Stripped-down renderer for screenshot purposes.
 pub fn render_screenshot(
     render_pass: &mut RenderPass,
@@ -788,32 +798,41 @@ pub fn render_screenshot(
     renderer: &Renderer,
     queue: &wgpu::Queue,
 ) {
-    let mut temp_arm = Armature::default();
-    temp_arm.bones = armature.animated_bones.clone();
-    construction(&mut temp_arm.bones, &armature.bones);
-    temp_arm.bones.sort_by(|a, b| a.zindex.cmp(&b.zindex));
+    let temp_arm = Armature {
+        bones: screenshot_bones(armature, camera),
+        ..Default::default()
+    };
     let sel = SelectionState::default();
 
+    #[rustfmt::skip]
+    draw_armature(&temp_arm, armature, false, &sel, queue, render_pass, &renderer.bone_buffer);
+}
+
+/// Constructed bones in zindex order, with world verts ready to be drawn for screenshots.
+/// Shared by the GPU and CPU (`raster`) renderers.
+pub fn screenshot_bones(armature: &Armature, camera: &Camera) -> Vec<Bone> {
+    let mut bones = armature.animated_bones.clone();
+    construction(&mut bones, &armature.bones);
+    bones.sort_by_key(|bone| bone.zindex);
+
     let hiddens = armature.get_propagated_hidden();
 
-    for b in 0..temp_arm.bones.len() {
-        let id = &temp_arm.bones[b].id;
-        let tex = armature.tex_of(*id);
-        let is_hidden = hiddens.get(&id) == None || *hiddens.get(&id).unwrap();
-        if tex == None || is_hidden {
+    for bone in &mut bones {
+        let tex = armature.tex_of(bone.id);
+        let is_hidden = hiddens.get(&bone.id).is_none_or(|hidden| *hidden);
+        if tex.is_none() || is_hidden {
             continue;
         }
 
-        for v in 0..temp_arm.bones[b].vertices.len() {
-            let tb = &temp_arm.bones[b];
-            let final_pivot = utils::rotate(&(tex.unwrap().size * tb.pivot_pos), tb.rot);
-            let mut new_vert = world_vert(tb.vertices[v], camera, 1., final_pivot);
-            new_vert.tint = temp_arm.bones[b].tint;
-            temp_arm.bones[b].world_verts.push(new_vert);
+        let final_pivot = utils::rotate(&(tex.unwrap().size * bone.pivot_pos), bone.rot);
+        for v in 0..bone.vertices.len() {
+            let mut new_vert = world_vert(bone.vertices[v], camera, 1., final_pivot);
+            new_vert.tint = bone.tint;
+            bone.world_verts.push(new_vert);
         }
     }
-    #[rustfmt::skip]
-    draw_armature(&temp_arm, armature, false, &sel, queue, render_pass, &renderer.bone_buffer);
+
+    bones
 }
 
 pub fn construction(bones: &mut Vec<Bone>, og_bones: &Vec<Bone>) {
@@ -941,8 +960,8 @@ fn simulate_physics(armature_bones: &mut Vec<Bone>, constructed_bones: &mut Vec<
                 damping.x *= 1. - arm_bone.phys_pos_ratio;
             }
 
-            phys_pos.x = utils::interp(2, damping.x as i32, phys_pos.x, const_bone.pos.x, s, e);
-            phys_pos.y = utils::interp(2, damping.y as i32, phys_pos.y, const_bone.pos.y, s, e);
+            phys_pos.x = utils::interp(2., damping.x.trunc(), phys_pos.x, const_bone.pos.x, s, e);
+            phys_pos.y = utils::interp(2., damping.y.trunc(), phys_pos.y, const_bone.pos.y, s, e);
         }
 
         // interpolate scale
@@ -957,10 +976,22 @@ fn simulate_physics(armature_bones: &mut Vec<Bone>, constructed_bones: &mut Vec<
                 damping.x *= 1. - arm_bone.phys_scale_ratio;
             }
 
-            phys_scale.x =
-                utils::interp(2, damping.x as i32, phys_scale.x, const_bone.scale.x, s, e);
-            phys_scale.y =
-                utils::interp(2, damping.y as i32, phys_scale.y, const_bone.scale.y, s, e);
+            phys_scale.x = utils::interp(
+                2.,
+                damping.x.trunc(),
+                phys_scale.x,
+                const_bone.scale.x,
+                s,
+                e,
+            );
+            phys_scale.y = utils::interp(
+                2.,
+                damping.y.trunc(),
+                phys_scale.y,
+                const_bone.scale.y,
+                s,
+                e,
+            );
         }
 
         // interpolate rotation
@@ -1190,6 +1221,7 @@ pub fn fabrik(bones: &mut Vec<Bone>, root: Vec2, target: Vec2) {
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn edit_bone(
     events: &mut EventState,
     edit_mode: &EditMode,
@@ -1429,6 +1461,7 @@ pub fn inheritance(
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn draw(
     buffer: &RenderBuffer,
     render_pass: &mut RenderPass,
@@ -1441,6 +1474,7 @@ pub fn draw(
     render_pass.draw_indexed(indices_start as u32..indices_end as u32, 0, 0..1);
 }
 
+#[cfg(feature = "editor")]
 pub fn bone_vertices(
     world_verts: &Vec<Vertex>,
     editable: bool,
@@ -1551,6 +1585,7 @@ pub fn bone_vertices(
     (all_verts, all_indices, hovering_vert_id)
 }
 
+#[cfg(feature = "editor")]
 fn bone_triangle(tb: &Bone, mouse_world_vert: &Vertex, wv: Vec<Vertex>) -> (u32, Vec<Vertex>) {
     let mut hovering_tri = vec![];
     let mut idx: usize = 0;
@@ -1572,6 +1607,7 @@ fn bone_triangle(tb: &Bone, mouse_world_vert: &Vertex, wv: Vec<Vertex>) -> (u32,
     (idx as u32, hovering_tri)
 }
 
+#[cfg(feature = "editor")]
 pub fn vert_lines(
     bone: &Bone,
     bones: &Vec<Bone>,
@@ -1706,6 +1742,7 @@ pub fn vert_lines(
     (all_verts, all_indices, hovered_once)
 }
 
+#[cfg(feature = "editor")]
 fn draw_line(origin: Vec2, target: Vec2) -> (Vec<Vertex>, Vec<u32>) {
     let dir = target - origin;
 
@@ -1732,6 +1769,23 @@ fn draw_line(origin: Vec2, target: Vec2) -> (Vec<Vertex>, Vec<u32>) {
     (verts, indices)
 }
 
+/// sort vertices in cw (or ccw?) order
+pub fn sort_vertices(mut verts: Vec<Vertex>) -> Vec<Vertex> {
+    let mut center = Vec2::default();
+    for v in 0..verts.len() {
+        center += verts[v].pos;
+    }
+    center /= verts.len() as f32;
+
+    verts.sort_by(|a, b| {
+        let angle_a = (a.pos.y - center.y).atan2(a.pos.x - center.x);
+        let angle_b = (b.pos.y - center.y).atan2(b.pos.x - center.x);
+        angle_a.partial_cmp(&angle_b).unwrap()
+    });
+
+    verts
+}
+
 pub fn create_tex_rect(tex_size: &Vec2) -> (Vec<Vertex>, Vec<u32>) {
     #[rustfmt::skip]
     macro_rules! vert {
@@ -1744,11 +1798,12 @@ pub fn create_tex_rect(tex_size: &Vec2) -> (Vec<Vertex>, Vec<u32>) {
         vert!(Vec2::new(tex.x, -tex.y), Vec2::new(1., 1.), 2),
         vert!(Vec2::new(-tex.x, -tex.y), Vec2::new(0., 1.), 3),
     ];
-    verts = editor::sort_vertices(verts.clone());
+    verts = sort_vertices(verts.clone());
     let indices = vec![0, 1, 2, 0, 2, 3];
     (verts, indices)
 }
 
+#[cfg(feature = "editor")]
 fn draw_point(
     offset: &Vec2,
     camera: &Camera,
@@ -1775,6 +1830,7 @@ fn draw_point(
     draw_rect(verts, offset, camera, config, pos, camera_pos, rotation)
 }
 
+#[cfg(feature = "editor")]
 fn draw_flow_kite(
     offset: &Vec2,
     camera: &Camera,
@@ -1801,6 +1857,7 @@ fn draw_flow_kite(
     draw_rect(verts, offset, camera, config, pos, camera_pos, rotation)
 }
 
+#[cfg(feature = "editor")]
 fn draw_rect(
     mut temp_verts: [Vertex; 4],
     offset: &Vec2,
@@ -1832,6 +1889,7 @@ fn draw_rect(
     (point_verts, vec![0, 1, 2, 1, 2, 3])
 }
 
+#[cfg(feature = "editor")]
 /// Get bind group of a texture.
 pub fn create_texture_bind_group(
     pixels: Vec<u8>,
@@ -1905,6 +1963,7 @@ pub fn create_texture_bind_group(
     bind_group
 }
 
+#[cfg(feature = "editor")]
 pub fn index_buffer(indices: Vec<u32>, device: &Device) -> wgpu::Buffer {
     wgpu::util::DeviceExt::create_buffer_init(
         device,
@@ -1916,6 +1975,7 @@ pub fn index_buffer(indices: Vec<u32>, device: &Device) -> wgpu::Buffer {
     )
 }
 
+#[cfg(feature = "editor")]
 pub fn vertex_buffer(vertices: &Vec<Vertex>, device: &Device) -> wgpu::Buffer {
     let gpu_verts: Vec<GpuVertex> = vertices.iter().map(|vert| (*vert).into()).collect();
 
@@ -1945,6 +2005,7 @@ pub fn world_vert(mut vert: Vertex, camera: &Camera, aspect_ratio: f32, pivot: V
     vert
 }
 
+#[cfg(feature = "editor")]
 fn draw_gridline(
     render_pass: &mut RenderPass,
     renderer: &mut Renderer,
@@ -2017,6 +2078,7 @@ fn draw_gridline(
     draw(&renderer.gridline_buffer, render_pass, 0, indices.len());
 }
 
+#[cfg(feature = "editor")]
 macro_rules! vert {
     ($pos:expr, $color:expr) => {
         Vertex {
@@ -2027,6 +2089,7 @@ macro_rules! vert {
     };
 }
 
+#[cfg(feature = "editor")]
 pub fn draw_horizontal_line(
     y: f32,
     width: f32,
@@ -2044,6 +2107,7 @@ pub fn draw_horizontal_line(
     vertices
 }
 
+#[cfg(feature = "editor")]
 pub fn draw_vertical_line(
     x: f32,
     width: f32,
@@ -2078,6 +2142,7 @@ pub fn add_offseted_indices(src: &mut Vec<u32>, dst: &mut Vec<u32>) {
     dst.append(src);
 }
 
+#[cfg(feature = "editor")]
 pub fn draw_points(
     config: &Config,
     camera: &Camera,
@@ -2185,6 +2250,7 @@ pub fn draw_points(
     renderer.on_point |= on_point;
 }
 
+#[cfg(feature = "editor")]
 pub fn draw_kites(
     config: &Config,
     camera: &Camera,
@@ -2250,6 +2316,7 @@ pub fn draw_kites(
 }
 
 // Add vertex and index data to the specified buffer.
+#[cfg(feature = "editor")]
 fn setup_render_buffer(
     buffer: &RenderBuffer,
     verts: &Vec<Vertex>,
@@ -2263,6 +2330,7 @@ fn setup_render_buffer(
     queue.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(&gpu_verts));
 }
 
+#[cfg(feature = "editor")]
 fn transform_ring(
     config: &Config,
     camera: &Camera,
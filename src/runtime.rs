@@ -0,0 +1,237 @@
+//! Headless runtime for loading and posing armatures, without the editor.
+//!
+//! Nothing in here should touch egui, winit or wgpu.
+
+use crate::*;
+use std::io::{Read, Seek};
+
+/// A loaded .skf, ready to be posed.
+#[derive(Clone, Default)]
+pub struct SkfRuntime {
+    pub root: Root,
+    pub armature: Armature,
+    /// Keep the root motion bone in place, for games that move the character by each
+    /// animation's `root_motion` (or `Armature::root_motion_at`) themselves.
+    pub extract_root_motion: bool,
+}
+
+/// A bone after animation, inheritance and IK have been applied.
+#[derive(Clone, Default, Debug)]
+pub struct PosedBone {
+    pub id: i32,
+    pub name: String,
+    pub parent_id: i32,
+    pub pos: Vec2,
+    pub rot: f32,
+    pub scale: Vec2,
+    pub tint: TintColor,
+    pub zindex: i32,
+    /// also true if any parent is hidden
+    pub hidden: bool,
+    pub tex: String,
+    /// world-space vertices, with pivot offset applied. UVs are within the texture's region of
+    /// its atlas (at `Texture::offset`, taking up `size` flipped if `rotated`), so trims and
+    /// turns are already accounted for
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl SkfRuntime {
+    /// Load an .skf from any zip source. All styles are active by default.
+    pub fn load<R: Read + Seek>(data: R) -> Result<Self, SkfError> {
+        let zip = zip::ZipArchive::new(data);
+        if let Err(e) = zip {
+            return Err(SkfError::Io(format!("Could not open .skf:\n{}", e)));
+        }
+
+        let (root, mut armature, _) = utils::import_armature(&mut zip.unwrap())?;
+        for style in &mut armature.styles {
+            style.active = true;
+        }
+
+        Ok(SkfRuntime {
+            root,
+            armature,
+            extract_root_motion: false,
+        })
+    }
+
+    pub fn anim_idx(&self, name: &str) -> Option<usize> {
+        self.armature.animations.iter().position(|a| a.name == name)
+    }
+
+    /// Frame of an animation at `time` (in seconds), following its loop mode.
+    ///
+    /// Frames are fractional, so that playback is smooth at any refresh rate.
+    pub fn frame_at(&self, anim_idx: usize, time: f32) -> f32 {
+        let anim = &self.armature.animations[anim_idx];
+        anim.loop_frame(time * anim.fps as f32)
+    }
+
+    /// Pose the armature at `time` (in seconds) of an animation.
+    pub fn pose(&mut self, anim_idx: usize, time: f32) -> Vec<PosedBone> {
+        let frame = self.frame_at(anim_idx, time);
+        self.pose_frame(anim_idx, frame)
+    }
+
+    /// Pose the armature at a specific (possibly fractional) frame of an animation.
+    ///
+    /// Physics is stateful and is not simulated here.
+    pub fn pose_frame(&mut self, anim_idx: usize, frame: f32) -> Vec<PosedBone> {
+        let mut animated_bones = self.armature.animate(anim_idx, frame, None);
+        self.remove_root_motion(&mut animated_bones, &[(anim_idx, frame, 1.)]);
+        self.construct(animated_bones)
+    }
+
+    /// Pose the armature with several animations blended together.
+    ///
+    /// Each layer is (animation index, time in seconds, weight).
+    pub fn pose_blended(&mut self, layers: &[(usize, f32, f32)]) -> Vec<PosedBone> {
+        let mut frame_layers = vec![];
+        for layer in layers {
+            frame_layers.push((layer.0, self.frame_at(layer.0, layer.1), layer.2));
+        }
+        let mut animated_bones = self.armature.animate_blended(&frame_layers, None);
+        let total_weight: f32 = frame_layers.iter().map(|l| l.2.max(0.)).sum();
+        if total_weight > 0. {
+            for layer in &mut frame_layers {
+                layer.2 = layer.2.max(0.) / total_weight;
+            }
+            self.remove_root_motion(&mut animated_bones, &frame_layers);
+        }
+        self.construct(animated_bones)
+    }
+
+    /// Pose the armature with animations stacked in order, each on top of the last.
+    ///
+    /// Each layer is (animation index, time in seconds). Additive and masked
+    /// animations (eg breathing, recoil) only affect their part of the pose.
+    pub fn pose_layered(&mut self, layers: &[(usize, f32)]) -> Vec<PosedBone> {
+        let mut animated_bones = self.armature.bones.clone();
+        for layer in layers {
+            let frame = self.frame_at(layer.0, layer.1);
+            animated_bones = self.armature.animate(layer.0, frame, Some(&animated_bones));
+            self.remove_root_motion(&mut animated_bones, &[(layer.0, frame, 1.)]);
+        }
+        self.construct(animated_bones)
+    }
+
+    /// Events of an animation from `t0` (inclusive) to `t1` (exclusive), in seconds.
+    ///
+    /// Times are not looped, so querying consecutive ranges of playback fires
+    /// each event once per cycle (or only once, if the animation doesn't loop).
+    pub fn events_between(&self, anim_idx: usize, t0: f32, t1: f32) -> Vec<&AnimEvent> {
+        let anim = &self.armature.animations[anim_idx];
+        let fps = anim.fps as f32;
+        anim.events_between(t0 * fps, t1 * fps)
+    }
+
+    // subtract the (weighted) root motion of each (animation index, frame, weight), if extracting it
+    fn remove_root_motion(&self, bones: &mut [Bone], layers: &[(usize, f32, f32)]) {
+        if !self.extract_root_motion {
+            return;
+        }
+        if let Some(bone) = bones.iter_mut().find(|b| b.root_motion) {
+            for (anim_idx, frame, weight) in layers {
+                bone.pos -= self.armature.root_motion_at(*anim_idx, *frame) * *weight;
+            }
+        }
+    }
+
+    fn construct(&self, mut animated_bones: Vec<Bone>) -> Vec<PosedBone> {
+        // initialize non-mesh bone verts and indices
+        for bone in &mut animated_bones {
+            if bone.verts_edited {
+                continue;
+            }
+            if let Some(tex) = self.tex_of(&bone.tex) {
+                // only the trimmed part is drawn, where it was in the whole texture
+                (bone.vertices, bone.indices) = renderer::create_tex_rect(&tex.size);
+                let trim = trim_offset(tex);
+                let shift = trim + (tex.size - full_size(tex)) / 2.;
+                for vert in &mut bone.vertices {
+                    vert.pos += Vec2::new(shift.x, 0. - shift.y);
+                    vert.uv = (trim + vert.uv * tex.size) / full_size(tex);
+                }
+            }
+        }
+
+        let mut bones = animated_bones.clone();
+        renderer::construction(&mut bones, &animated_bones);
+
+        let mut hiddens: std::collections::HashMap<i32, bool> = Default::default();
+        let mut posed = vec![];
+        for bone in &bones {
+            let parent_hidden = *hiddens.get(&bone.parent_id).unwrap_or(&false);
+            let hidden = bone.hidden || parent_hidden;
+            hiddens.insert(bone.id, hidden);
+
+            // apply pivot offset, same as the editor renderer
+            let mut vertices = bone.vertices.clone();
+            if let Some(tex) = self.tex_of(&bone.tex) {
+                let size = full_size(tex);
+                let pivot = utils::rotate(&(size * bone.pivot_pos), bone.rot) * bone.scale;
+                for vert in &mut vertices {
+                    vert.pos += pivot;
+                    vert.tint = bone.tint;
+                    vert.uv = atlas_uv(tex, vert.uv);
+                }
+            }
+
+            posed.push(PosedBone {
+                id: bone.id,
+                name: bone.name.clone(),
+                parent_id: bone.parent_id,
+                pos: bone.pos,
+                rot: bone.rot,
+                scale: bone.scale,
+                tint: bone.tint,
+                zindex: bone.zindex,
+                hidden,
+                tex: bone.tex.clone(),
+                vertices,
+                indices: bone.indices.clone(),
+            });
+        }
+
+        posed
+    }
+
+    /// First texture of this name among active styles.
+    pub fn tex_of(&self, name: &str) -> Option<&Texture> {
+        if name.is_empty() {
+            return None;
+        }
+        for style in &self.armature.styles {
+            if !style.active {
+                continue;
+            }
+            if let Some(tex) = style.textures.iter().find(|t| t.name == name) {
+                return Some(tex);
+            }
+        }
+        None
+    }
+}
+
+// size of a texture before its transparent edges were trimmed
+fn full_size(tex: &Texture) -> Vec2 {
+    match tex.source_size == Vec2I::new(0, 0) {
+        true => tex.size,
+        false => Vec2::new(tex.source_size.x as f32, tex.source_size.y as f32),
+    }
+}
+
+fn trim_offset(tex: &Texture) -> Vec2 {
+    Vec2::new(tex.trim_offset.x as f32, tex.trim_offset.y as f32)
+}
+
+// UV of the whole texture, in its region of the atlas
+fn atlas_uv(tex: &Texture, uv: Vec2) -> Vec2 {
+    let uv = (uv * full_size(tex) - trim_offset(tex)) / tex.size;
+    // turned 90° clockwise, so the left edge is now the top
+    match tex.rotated {
+        true => Vec2::new(1. - uv.y, uv.x),
+        false => uv,
+    }
+}
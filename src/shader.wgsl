@@ -36,3 +36,14 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let tex = textureSample(t_diffuse, s_diffuse, in.tex_coords);
     return (tex * in.color * in.tint) + in.add_color;
 }
+
+// as runtimes would draw premultiplied atlases (quantized like the exported colors), for
+// previewing them with premultiplied blending
+@fragment
+fn fs_premultiplied(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let premultiplied = round(tex.rgb * tex.a * 255.) / 255.;
+    let tint = in.color * in.tint;
+    let alpha = tex.a * tint.a + in.add_color.a;
+    return vec4(premultiplied * tint.rgb * tint.a + in.add_color.rgb * alpha, alpha);
+}
@@ -5,11 +5,12 @@ use crate::*;
 use std::{
     fmt,
     ops::{DivAssign, MulAssign},
-    path::PathBuf,
     str::FromStr,
 };
+#[cfg(feature = "editor")]
+use std::{path::PathBuf, sync::Mutex};
 
-use std::sync::Mutex;
+#[cfg(feature = "editor")]
 use wgpu::BindGroup;
 
 use strum::{EnumString, FromRepr};
@@ -18,18 +19,20 @@ use serde::{Deserialize, Serialize};
 
 #[rustfmt::skip]
 #[repr(C)]
-#[derive(Debug,Serialize,Deserialize,Default,Copy,Clone,bytemuck::Pod,bytemuck::Zeroable)]
+#[derive(Debug,Serialize,Deserialize,Default,Copy,Clone,bytemuck::Pod,bytemuck::Zeroable,schemars::JsonSchema)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
 }
 
+#[cfg(feature = "editor")]
 impl From<egui::Pos2> for Vec2 {
     fn from(pos: egui::Pos2) -> Vec2 {
         Vec2::new(pos.x, pos.y)
     }
 }
 
+#[cfg(feature = "editor")]
 impl From<egui::Vec2> for Vec2 {
     fn from(pos: egui::Vec2) -> Vec2 {
         Vec2::new(pos.x, pos.y)
@@ -42,12 +45,14 @@ impl From<(u32, u32)> for Vec2 {
     }
 }
 
+#[cfg(feature = "editor")]
 impl Into<egui::Pos2> for Vec2 {
     fn into(self) -> egui::Pos2 {
         egui::Pos2::new(self.x, self.y)
     }
 }
 
+#[cfg(feature = "editor")]
 impl Into<egui::Vec2> for Vec2 {
     fn into(self) -> egui::Vec2 {
         egui::Vec2::new(self.x, self.y)
@@ -185,7 +190,7 @@ macro_rules! enum_string {
 // See GpuVertex for actual vertex data supplied to wgpu
 #[rustfmt::skip]
 #[repr(C)]
-#[derive(PartialEq,serde::Serialize,serde::Deserialize,Copy,Clone,bytemuck::Pod,bytemuck::Zeroable,Debug)]
+#[derive(PartialEq,serde::Serialize,serde::Deserialize,Copy,Clone,bytemuck::Pod,bytemuck::Zeroable,Debug,schemars::JsonSchema)]
 #[serde(default)]
 pub struct Vertex {
     pub id: u32,
@@ -256,7 +261,7 @@ pub struct VertexColor {
 
 #[rustfmt::skip]
 #[repr(C)]
-#[derive(PartialEq,Copy,Clone,bytemuck::Pod,bytemuck::Zeroable,Debug,serde::Deserialize,serde::Serialize)]
+#[derive(PartialEq,Copy,Clone,bytemuck::Pod,bytemuck::Zeroable,Debug,serde::Deserialize,serde::Serialize,schemars::JsonSchema)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -269,10 +274,12 @@ impl Color {
         Color { r, g, b, a }
     }
 
+    #[cfg(feature = "editor")]
     pub fn egui_rgba(self) -> egui::Color32 {
         egui::Color32::from_rgba_premultiplied(self.r, self.g, self.b, self.a)
     }
 
+    #[cfg(feature = "editor")]
     pub fn from_egui_rgba(&mut self, rhs: egui::Color32) {
         self.r = rhs.r();
         self.g = rhs.g();
@@ -331,12 +338,14 @@ impl std::ops::Add for Color {
     }
 }
 
+#[cfg(feature = "editor")]
 impl From<egui::Color32> for Color {
     fn from(col: egui::Color32) -> Color {
         Color::new(col.r(), col.g(), col.b(), col.a())
     }
 }
 
+#[cfg(feature = "editor")]
 impl Into<egui::Color32> for Color {
     fn into(self) -> egui::Color32 {
         egui::Color32::from_rgb(self.r, self.g, self.b)
@@ -352,7 +361,7 @@ impl Default for Color {
 
 #[rustfmt::skip]
 #[repr(C)]
-#[derive(PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize, Default, Debug, bytemuck::Pod,bytemuck::Zeroable)]
+#[derive(PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize, Default, Debug, bytemuck::Pod,bytemuck::Zeroable, schemars::JsonSchema)]
 pub struct TintColor {
     pub r: f32,
     pub g: f32,
@@ -382,6 +391,7 @@ impl Camera {
     }
 }
 
+#[cfg(feature = "editor")]
 /// Input-related fields.
 #[derive(Default)]
 pub struct InputStates {
@@ -464,7 +474,7 @@ pub struct UiBar {
     pub prev_expanded: bool,
 }
 
-#[derive(Clone, Default, PartialEq)]
+#[derive(Clone, Default, PartialEq, Debug)]
 pub enum Warnings {
     #[default]
     SameZIndex,
@@ -477,7 +487,15 @@ pub enum Warnings {
     BoneOutOfFamily,
     EmptyStyles,
     UnusedTextures,
+
+    // repairs made on import
+    BrokenCycle,
+    OrphanBone,
+    OrphanKeyframes,
+    MissingBindVerts,
+    NanTransform,
 }
+enum_string!(Warnings);
 
 #[derive(Clone, Default, PartialEq)]
 pub struct Warning {
@@ -516,6 +534,7 @@ impl Warning {
     }
 }
 
+#[cfg(feature = "editor")]
 #[derive(Clone, Default)]
 pub struct Ui {
     pub startup: Startup,
@@ -625,6 +644,8 @@ pub struct Ui {
     pub can_quit: bool,
     pub warnings: Vec<Warning>,
     pub warnings_open: bool,
+    /// repairs made to the last imported armature, until the warnings popup is dismissed
+    pub repairs: Vec<Warning>,
     pub save_path: Option<PathBuf>,
     pub changed_window_name: bool,
 
@@ -635,11 +656,19 @@ pub struct Ui {
     pub rendered_spritesheets: Vec<Vec<RenderedFrame>>,
     pub exporting_anims: Vec<bool>,
     pub image_sequences: bool,
+    /// trim, dedupe and pack spritesheet frames, with a JSON sidecar
+    pub pack_spritesheets: bool,
+    /// normalized position of the armature's origin in the frames of each rendered spritesheet
+    pub rendered_pivots: Vec<Vec2>,
     pub exporting_video_type: ExportVideoType,
+    /// times each animation plays in an animated image (0 = forever), parallel to `exporting_anims`
+    pub exporting_loops: Vec<u16>,
     pub exporting_video_encoder: ExportVideoEncoder,
     pub use_system_ffmpeg: bool,
     pub video_clear_bg: Color,
     pub anim_cycles: i32,
+    /// frame rate to resample exported animations to (0 = animation's own fps)
+    pub export_fps: i32,
     pub mapped_frames: Arc<Mutex<usize>>,
     pub export_global_bounds: bool,
     pub custom_error: String,
@@ -705,9 +734,55 @@ pub enum ExportVideoType {
     None,
     Mp4,
     Gif,
+    Apng,
+    Webp,
+    /// VP9 with alpha
+    Webm,
+    /// ProRes 4444 with alpha
+    ProRes,
+    /// PNG frames in a MOV, lossless with alpha
+    PngMov,
 }
 enum_string!(ExportVideoType);
 
+impl ExportVideoType {
+    /// Animated image formats, which are encoded without ffmpeg.
+    pub fn is_animated_image(&self) -> bool {
+        matches!(self, Self::Gif | Self::Apng | Self::Webp)
+    }
+
+    /// Whether the background is left transparent, instead of `video_clear_bg`.
+    pub fn has_alpha(&self) -> bool {
+        !matches!(self, Self::None | Self::Mp4)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Mp4 => "mp4",
+            Self::Gif => "gif",
+            Self::Apng => "png",
+            Self::Webp => "webp",
+            Self::Webm => "webm",
+            Self::ProRes | Self::PngMov => "mov",
+        }
+    }
+
+    /// Name in the format dropdown.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Mp4 => "MP4",
+            Self::Gif => "GIF",
+            Self::Apng => "APNG",
+            Self::Webp => "WEBP",
+            Self::Webm => "WEBM (VP9)",
+            Self::ProRes => "MOV (ProRes 4444)",
+            Self::PngMov => "MOV (PNG)",
+        }
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Default, PartialEq, Eq, Debug, Clone)]
 pub enum ExportVideoEncoder {
     #[default]
@@ -716,6 +791,17 @@ pub enum ExportVideoEncoder {
 }
 enum_string!(ExportVideoEncoder);
 
+impl ExportVideoEncoder {
+    /// ffmpeg's name for this encoder.
+    pub fn codec(&self) -> &'static str {
+        match self {
+            Self::Libx264 => "libx264",
+            Self::AV1 => "libaom-av1",
+        }
+    }
+}
+
+#[cfg(feature = "editor")]
 impl Ui {
     pub fn is_animating(&self, edit_mode: &EditMode, selections: &SelectionState) -> bool {
         edit_mode.anim_open && selections.anim != usize::MAX
@@ -793,6 +879,7 @@ pub enum UiLayout {
 
 enum_string!(UiLayout);
 
+#[cfg(feature = "editor")]
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(default)]
 pub struct Config {
@@ -840,6 +927,7 @@ pub struct ColorConfig {
     pub transform_rings: Color,
 }
 
+#[cfg(feature = "editor")]
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -888,6 +976,7 @@ impl Default for ColorConfig {
     }
 }
 
+#[cfg(feature = "editor")]
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 #[serde(default)]
 pub struct KeyboardConfig {
@@ -923,10 +1012,12 @@ pub struct KeyboardConfig {
     pub toggle_edit_vertices: egui::KeyboardShortcut,
 }
 
+#[cfg(feature = "editor")]
 pub trait Display {
     fn display(self) -> String;
 }
 
+#[cfg(feature = "editor")]
 impl Display for egui::KeyboardShortcut {
     /// Return this shortcut as a presentable string.
     fn display(self) -> String {
@@ -953,6 +1044,7 @@ impl Display for egui::KeyboardShortcut {
     }
 }
 
+#[cfg(feature = "editor")]
 impl Display for egui::Key {
     fn display(self) -> String {
         match self {
@@ -967,18 +1059,21 @@ impl Display for egui::Key {
     }
 }
 
+#[cfg(feature = "editor")]
 macro_rules! regular_key {
     ($key:expr) => {
         egui::KeyboardShortcut::new(egui::Modifiers::NONE, $key)
     };
 }
 
+#[cfg(feature = "editor")]
 macro_rules! shortcut_key {
     ($mod:expr, $key:expr) => {
         egui::KeyboardShortcut::new($mod, $key)
     };
 }
 
+#[cfg(feature = "editor")]
 impl Default for KeyboardConfig {
     #[rustfmt::skip]
     fn default() -> Self {
@@ -1028,7 +1123,15 @@ pub enum JointEffector {
 enum_string!(JointEffector);
 
 #[derive(
-    serde::Serialize, serde::Deserialize, Clone, Copy, Default, PartialEq, Debug, EnumString,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Debug,
+    EnumString,
+    schemars::JsonSchema,
 )]
 pub enum JointConstraint {
     #[default]
@@ -1040,7 +1143,9 @@ pub enum JointConstraint {
 
 enum_string!(JointConstraint);
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct Visuals {
     #[serde(skip_serializing_if = "is_str_empty")]
@@ -1069,7 +1174,12 @@ pub struct Visuals {
     pub init_zindex: i32,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug)]
+/// Physics of a bone, in groups (position, rotation and scale).
+///
+/// Fields of disabled groups are f32::MAX in the editor, and are left out when saved.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct Physics {
     #[serde(skip_serializing_if = "is_vec2_max")]
@@ -1104,7 +1214,9 @@ pub struct Physics {
     pub rot_bounce: f32,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct Bone {
     pub id: i32,
@@ -1127,6 +1239,9 @@ pub struct Bone {
     pub rot: f32,
     #[serde(skip_serializing_if = "is_false")]
     pub hidden: bool,
+    /// if true, this bone's translation is exported as root motion
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub root_motion: bool,
 
     #[serde(skip)]
     pub ik_constraint: JointConstraint,
@@ -1219,7 +1334,9 @@ pub struct Bone {
     pub blacklist: Vec<u32>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct BoneBind {
     #[serde(default = "default_neg_one")]
@@ -1228,7 +1345,9 @@ pub struct BoneBind {
     pub verts: Vec<BoneBindVert>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, PartialEq, Default, Debug, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct BoneBindVert {
     pub id: i32,
@@ -1273,6 +1392,7 @@ pub struct EditorBone {
     Debug,
     EnumString,
     FromRepr,
+    schemars::JsonSchema,
 )]
 #[repr(i32)]
 pub enum InverseKinematicsMode {
@@ -1387,13 +1507,14 @@ impl Armature {
                     continue;
                 }
 
-                let mut children = vec![];
-                crate::armature_window::get_all_children(
-                    &self.bones,
-                    &mut children,
-                    &self.bones[i],
-                );
-                let idx = i + children.len() + 1;
+                // the targeted bone and its children that come after it
+                let mut family = vec![id];
+                for bone in &self.bones[i + 1..] {
+                    if family.contains(&bone.parent_id) {
+                        family.push(bone.id);
+                    }
+                }
+                let idx = i + family.len();
                 self.bones.insert(idx, new_bone.clone());
                 return (new_bone, idx);
             }
@@ -1405,7 +1526,7 @@ impl Armature {
     pub fn animate(
         &mut self,
         anim_idx: usize,
-        anim_frame: i32,
+        anim_frame: f32,
         og_bones: Option<&Vec<Bone>>,
     ) -> Vec<Bone> {
         let mut bones = if og_bones != None {
@@ -1421,8 +1542,41 @@ impl Armature {
         }
 
         let kfs = &self.animations[anim_idx].keyframes;
+        let additive = self.animations[anim_idx].additive;
+        let mask = &self.animations[anim_idx].mask;
+
+        type AE = AnimElement;
 
         for b in &mut bones {
+            // bones outside of the mask are left untouched
+            if !mask.is_empty() && !mask.contains(&b.id) {
+                continue;
+            }
+
+            // runtime: additive animations only add their offset from the rest pose,
+            // and don't affect tint or snapping elements
+            if additive {
+                let rest = match self.bones.iter().find(|rest| rest.id == b.id) {
+                    Some(rest) => rest,
+                    None => continue,
+                };
+                macro_rules! offset {
+                    ($element:expr, $rest:expr) => {
+                        self.interpolate_keyframes(anim_idx, b.id, $element, $rest, anim_frame)
+                            - $rest
+                    };
+                }
+                #[rustfmt::skip]
+                {
+                    b.pos.x   += offset!(AE::PositionX, rest.pos.x);
+                    b.pos.y   += offset!(AE::PositionY, rest.pos.y);
+                    b.rot     += offset!(AE::Rotation,  rest.rot);
+                    b.scale.x += offset!(AE::ScaleX,    rest.scale.x);
+                    b.scale.y += offset!(AE::ScaleY,    rest.scale.y);
+                };
+                continue;
+            }
+
             macro_rules! prev_frame {
                 ($element:expr, $default:expr) => {{
                     let prev = utils::get_prev_frame(anim_frame, kfs, b.id, &$element);
@@ -1446,7 +1600,6 @@ impl Armature {
             }
 
             // iterable anim interps
-            type AE = AnimElement;
             #[rustfmt::skip]
             {
                 b.pos.x   = self.interpolate_keyframes(anim_idx, b.id, AE::PositionX, b.pos.x,   anim_frame);
@@ -1483,19 +1636,77 @@ impl Armature {
         bones
     }
 
+    // runtime: blend several animations into one pose
+    //
+    // Each layer is (animation index, frame, weight). Frames can be fractional. Weights are normalized,
+    // and snapping elements (texture, zindex, hidden, IK mode, etc) are taken
+    // from the heaviest layer.
+    pub fn animate_blended(
+        &mut self,
+        layers: &[(usize, f32, f32)],
+        og_bones: Option<&Vec<Bone>>,
+    ) -> Vec<Bone> {
+        let layers: Vec<&(usize, f32, f32)> = layers.iter().filter(|l| l.2 > 0.).collect();
+        let total_weight: f32 = layers.iter().map(|l| l.2).sum();
+        if layers.is_empty() || total_weight == 0. {
+            return og_bones.unwrap_or(&self.bones).clone();
+        }
+
+        let mut heaviest = 0;
+        for (l, layer) in layers.iter().enumerate() {
+            if layer.2 > layers[heaviest].2 {
+                heaviest = l;
+            }
+        }
+
+        let mut poses = vec![];
+        for layer in &layers {
+            poses.push(self.animate(layer.0, layer.1, og_bones));
+        }
+
+        // start from the heaviest pose, so that its snapping elements are kept
+        let mut bones = poses[heaviest].clone();
+        for (b, blended) in bones.iter_mut().enumerate() {
+            let base_rot = blended.rot;
+            let mut pos = Vec2::default();
+            let mut rot = 0.;
+            let mut scale = Vec2::default();
+            let mut tint = TintColor::default();
+            for (layer, pose) in layers.iter().zip(&poses) {
+                let weight = layer.2 / total_weight;
+                let bone = &pose[b];
+                pos += bone.pos * weight;
+                rot += utils::shortest_angle_delta(base_rot, bone.rot) * weight;
+                scale += bone.scale * weight;
+                tint.r += bone.tint.r * weight;
+                tint.g += bone.tint.g * weight;
+                tint.b += bone.tint.b * weight;
+                tint.a += bone.tint.a * weight;
+            }
+            blended.pos = pos;
+            blended.rot = base_rot + rot;
+            blended.scale = scale;
+            blended.tint = tint;
+        }
+
+        bones
+    }
+
     pub fn interpolate_keyframes(
         &self,
         anim_id: usize,
         bone_id: i32,
         element: AnimElement,
         default: f32,
-        frame: i32,
+        frame: f32,
     ) -> f32 {
         let keyframes = &self.animations[anim_id].keyframes;
         let mut prev = utils::get_prev_frame(frame, keyframes, bone_id, &element);
         let mut next = keyframes
             .iter()
-            .position(|kf| kf.frame > frame && kf.bone_id == bone_id && kf.element == element)
+            .position(|kf| {
+                kf.frame as f32 > frame && kf.bone_id == bone_id && kf.element == element
+            })
             .unwrap_or(usize::MAX);
 
         // ensure prev and next are pointing somewhere
@@ -1511,8 +1722,8 @@ impl Armature {
         }
 
         utils::interp(
-            frame - keyframes[prev].frame,
-            keyframes[next].frame - keyframes[prev].frame,
+            frame - keyframes[prev].frame as f32,
+            (keyframes[next].frame - keyframes[prev].frame) as f32,
             keyframes[prev].value,
             keyframes[next].value,
             keyframes[next].start_handle,
@@ -1520,6 +1731,23 @@ impl Armature {
         )
     }
 
+    // runtime: offset of the root motion bone at `frame`, from the first frame of the animation
+    pub fn root_motion_at(&self, anim_idx: usize, frame: f32) -> Vec2 {
+        let anim = &self.animations[anim_idx];
+        let bone = match self.bones.iter().find(|b| b.root_motion) {
+            Some(bone) if anim.mask.is_empty() || anim.mask.contains(&bone.id) => bone,
+            _ => return Vec2::new(0., 0.),
+        };
+
+        type AE = AnimElement;
+        let pos_at = |frame: f32| {
+            let x = self.interpolate_keyframes(anim_idx, bone.id, AE::PositionX, bone.pos.x, frame);
+            let y = self.interpolate_keyframes(anim_idx, bone.id, AE::PositionY, bone.pos.y, frame);
+            Vec2::new(x, y)
+        };
+        pos_at(frame) - pos_at(0.)
+    }
+
     pub fn get_all_parents(&self, is_anim: bool, bone_id: i32) -> Vec<Bone> {
         let bones = if is_anim {
             &self.animated_bones
@@ -1722,14 +1950,57 @@ impl Armature {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, schemars::JsonSchema)]
 pub struct TexAtlas {
     pub filename: String,
-    #[serde(skip_deserializing)]
+    #[serde(default)]
     pub size: Vec2I,
+    /// how the atlas was packed, so the next export packs it the same way
+    #[serde(flatten)]
+    pub settings: AtlasSettings,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, schemars::JsonSchema)]
+#[serde(default)]
+pub struct AtlasSettings {
+    /// largest atlas size (a style that doesn't fit still gets an atlas of its own)
+    #[serde(skip_serializing_if = "is_default_atlas_size")]
+    pub max_size: Vec2I,
+    /// round atlas sizes up to powers of two, instead of fitting them to their textures
+    #[serde(skip_serializing_if = "is_false")]
+    pub power_of_two: bool,
+    /// keep atlas width and height the same
+    #[serde(skip_serializing_if = "is_true")]
+    pub square: bool,
+    /// pixels to repeat each texture's edges by, so filtering doesn't bleed in its neighbours
+    #[serde(skip_serializing_if = "is_i32_zero")]
+    pub extrude: i32,
+    /// crop transparent edges off textures (see `Texture::trim_offset`)
+    #[serde(skip_serializing_if = "is_false")]
+    pub trim: bool,
+    /// allow textures to be turned 90° clockwise (see `Texture::rotated`)
+    #[serde(skip_serializing_if = "is_false")]
+    pub rotate: bool,
+}
+
+const DEFAULT_ATLAS_SIZE: Vec2I = Vec2I::new(2048, 2048);
+
+impl Default for AtlasSettings {
+    fn default() -> Self {
+        AtlasSettings {
+            max_size: DEFAULT_ATLAS_SIZE,
+            power_of_two: false,
+            square: true,
+            extrude: 0,
+            trim: false,
+            rotate: false,
+        }
+    }
+}
+
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Default, Debug, PartialEq, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct InverseKinematics {
     #[serde(default = "default_neg_one")]
@@ -1754,12 +2025,15 @@ pub struct InverseKinematics {
 }
 
 // used for the json
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, schemars::JsonSchema)]
 #[serde(default)]
 pub struct Root {
     pub version: String,
     pub baked_ik: bool,
     pub img_format: ExportImgFormat,
+    /// atlas colors are premultiplied by their alpha
+    #[serde(skip_serializing_if = "is_false")]
+    pub premultiplied_alpha: bool,
     #[serde(default, skip_serializing_if = "is_color_empty")]
     pub clear_color: Color,
     pub bones: Vec<Bone>,
@@ -1772,7 +2046,52 @@ pub struct Root {
     pub physics: Vec<Physics>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq)]
+/// Problems found while reading an .skf.
+///
+/// Paths are in the same format as serde errors (eg `bones[2].visuals_id`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum SkfError {
+    /// the .skf itself could not be opened or read
+    Io(String),
+    /// a file that should be in the .skf is missing
+    MissingFile(String),
+    /// a file in the .skf is not valid JSON, or not valid binary
+    Corrupt { file: String, message: String },
+    /// a required entry is missing
+    MissingEntry(String),
+    /// an entry has the wrong type or value
+    BadValue { path: String, message: String },
+    /// an ID points to nothing
+    DanglingId { path: String, id: i32 },
+    /// an index is outside of the array it points to
+    OutOfRange { path: String, idx: i32, len: usize },
+}
+
+impl fmt::Display for SkfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkfError::Io(message) => write!(f, "{}", message),
+            SkfError::MissingFile(file) => write!(f, "{} could not be found.", file),
+            SkfError::Corrupt { file, message } => write!(f, "{}:\n{}", file, message),
+            SkfError::MissingEntry(path) => write!(f, "{} is missing.", path),
+            SkfError::BadValue { path, message } => write!(f, "{}:\n{}", path, message),
+            SkfError::DanglingId { path, id } => {
+                write!(f, "{} points to ID {}, which does not exist.", path, id)
+            }
+            SkfError::OutOfRange { path, idx, len } => {
+                write!(
+                    f,
+                    "{} is {}, but there are only {} entries.",
+                    path, idx, len
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SkfError {}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq, schemars::JsonSchema)]
 #[serde(default)]
 pub struct Style {
     pub id: i32,
@@ -1782,7 +2101,9 @@ pub struct Style {
     pub textures: Vec<Texture>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, schemars::JsonSchema,
+)]
 pub struct Vec2I {
     pub x: i32,
     pub y: i32,
@@ -1794,7 +2115,7 @@ impl Vec2I {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, PartialEq, schemars::JsonSchema)]
 #[serde(default)]
 pub struct Texture {
     pub name: String,
@@ -1811,6 +2132,16 @@ pub struct Texture {
     pub ser_size: Vec2I,
     pub atlas_idx: i32,
 
+    /// where `offset` and `size` start in the original image, if its transparent edges were trimmed
+    #[serde(skip_serializing_if = "is_vec2i_zero")]
+    pub trim_offset: Vec2I,
+    /// size of the original image, if it was trimmed
+    #[serde(skip_serializing_if = "is_vec2i_zero")]
+    pub source_size: Vec2I,
+    /// turned 90° clockwise in the atlas, where it takes up `size` flipped (`size` itself is upright)
+    #[serde(skip_serializing_if = "is_false")]
+    pub rotated: bool,
+
     #[serde(skip)]
     pub data_id: i32,
 }
@@ -1819,17 +2150,37 @@ pub struct Texture {
 pub struct TextureData {
     pub id: i32,
     pub image: image::DynamicImage,
+    #[cfg(feature = "editor")]
     pub bind_group: Option<BindGroup>,
+    #[cfg(feature = "editor")]
     pub ui_img: Option<egui::TextureHandle>,
 }
 
-#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default)]
+#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, schemars::JsonSchema)]
 #[serde(default)]
 pub struct Animation {
     pub name: String,
     pub id: i32,
     pub fps: i32,
     pub keyframes: Vec<Keyframe>,
+    /// if true, only the offset from the armature's rest pose is applied
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub additive: bool,
+    /// bone ids affected by this animation (all bones if empty)
+    #[serde(default, skip_serializing_if = "is_i32_empty")]
+    pub mask: Vec<i32>,
+    /// named markers for runtimes to react to (footsteps, hitboxes, etc)
+    #[serde(default, skip_serializing_if = "are_anim_events_empty")]
+    pub events: Vec<AnimEvent>,
+    /// length in frames (inferred from the last keyframe if 0)
+    #[serde(default)]
+    pub length: i32,
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+    /// offset of the root motion bone from the first frame, for every frame.
+    /// Generated on export, and already included in the bone's keyframes.
+    #[serde(default, skip_serializing_if = "are_vec2s_empty")]
+    pub root_motion: Vec<Vec2>,
     #[serde(skip)]
     pub elapsed: Option<Instant>,
 }
@@ -1886,6 +2237,51 @@ impl Animation {
         self.keyframes.sort_by(|a, b| a.frame.cmp(&b.frame));
     }
 
+    pub fn sort_events(&mut self) {
+        self.events.sort_by_key(|e| e.frame);
+    }
+
+    // runtime: events from frame `from` (inclusive) to `to` (exclusive)
+    //
+    // Frames are unlooped. An event fires whenever its frame is the one shown
+    // (see `loop_frame`), so it's repeated every cycle and fires on both
+    // passes of PingPong.
+    pub fn events_between(&self, from: f32, to: f32) -> Vec<&AnimEvent> {
+        let len = self.len_frames();
+        let last = len - 1;
+        let cycle = self.cycle_frames();
+        let mut events = vec![];
+        for event in &self.events {
+            if event.frame < 0 || event.frame >= len {
+                continue;
+            }
+
+            // unlooped frames in the first cycle that show this event
+            let frames = match self.loop_mode {
+                LoopMode::Loop | LoopMode::Once => vec![event.frame],
+                LoopMode::Reverse => vec![last - event.frame],
+                LoopMode::PingPong if event.frame == 0 || event.frame == last => {
+                    vec![event.frame]
+                }
+                LoopMode::PingPong => vec![event.frame, cycle - event.frame],
+            };
+
+            let fires = frames.iter().any(|&frame| {
+                let mut frame = frame as f32;
+                if self.loop_mode != LoopMode::Once {
+                    // first occurrence at or after `from`
+                    let cycle = cycle as f32;
+                    frame += ((from - frame) / cycle).ceil() * cycle;
+                }
+                frame >= from && frame < to
+            });
+            if fires {
+                events.push(event);
+            }
+        }
+        events
+    }
+
     pub fn remove_all_keyframes_of_frame(&mut self, frame: i32) {
         for k in (0..self.keyframes.len()).rev() {
             let kf = &self.keyframes[k];
@@ -1896,11 +2292,21 @@ impl Animation {
     }
 
     pub fn get_frame(&self) -> i32 {
+        self.get_subframe() as i32
+    }
+
+    /// Same as `get_frame`, but including progress towards the next frame.
+    pub fn get_subframe(&self) -> f32 {
+        self.loop_frame(self.elapsed_frames())
+    }
+
+    /// Frames played since `elapsed` was set, without looping.
+    pub fn elapsed_frames(&self) -> f32 {
         if self.elapsed == None || self.keyframes.len() == 0 {
-            return 0;
+            return 0.;
         }
 
-        let elapsed = self.elapsed.unwrap().elapsed().as_millis() as f32 / 1e3 as f32;
+        let elapsed = self.elapsed.unwrap().elapsed().as_secs_f32();
         let frametime = 1. / self.fps as f32;
 
         // Offset elapsed time with the selected frame.
@@ -1908,26 +2314,79 @@ impl Animation {
         // is reset on the next one.
         // elapsed += shared.ui.anim.played_frame as f32 * frametime;
 
-        (elapsed / frametime) as i32
+        elapsed / frametime
     }
 
-    pub fn set_frame(&mut self) -> i32 {
+    pub fn set_frame(&mut self) -> f32 {
         if self.elapsed == None || self.keyframes.len() == 0 {
-            return 0;
+            return 0.;
         }
 
-        let mut frame = self.get_frame();
+        let mut frame = self.elapsed_frames();
 
-        if frame >= self.keyframes.last().unwrap().frame {
-            self.elapsed = Some(Instant::now());
-            frame = 0;
+        // restart every cycle, unless holding the last frame
+        let cycle = match self.loop_mode {
+            LoopMode::Once => f32::MAX,
+            _ => self.cycle_frames() as f32,
+        };
+        if frame >= cycle {
+            // keep the time past the end, so the next cycle stays in step
+            frame %= cycle;
+            let into_cycle = std::time::Duration::from_secs_f32(frame / self.fps as f32);
+            self.elapsed = Some(Instant::now() - into_cycle);
         }
 
-        frame
+        self.loop_frame(frame)
+    }
+
+    // runtime: length of the animation in frames
+    pub fn len_frames(&self) -> i32 {
+        if self.length > 0 {
+            return self.length;
+        }
+        self.keyframes.last().map_or(0, |kf| kf.frame)
+    }
+
+    // runtime: frames played before the animation repeats
+    //
+    // PingPong doesn't repeat its first and last frames when turning around.
+    pub fn cycle_frames(&self) -> i32 {
+        let len = self.len_frames();
+        match self.loop_mode {
+            LoopMode::PingPong => ((len - 1) * 2).max(len),
+            _ => len,
+        }
+    }
+
+    // runtime: map an unlooped frame to the one to show, based on the loop mode
+    //
+    // Shown frames are always in [0, len). The editor, `SkfRuntime` and the
+    // exporters all go through this.
+    pub fn loop_frame(&self, frame: f32) -> f32 {
+        let len = self.len_frames() as f32;
+        if len <= 0. {
+            return 0.;
+        }
+        let last = len - 1.;
+        match self.loop_mode {
+            LoopMode::Loop => frame.rem_euclid(len),
+            LoopMode::Once => frame.clamp(0., last),
+            LoopMode::Reverse => (last - frame.rem_euclid(len)).max(0.),
+            LoopMode::PingPong => {
+                let frame = frame.rem_euclid(self.cycle_frames() as f32);
+                if frame > last {
+                    self.cycle_frames() as f32 - frame
+                } else {
+                    frame
+                }
+            }
+        }
     }
 }
 
-#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+#[derive(
+    PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, Debug, schemars::JsonSchema,
+)]
 #[serde(default)]
 pub struct Keyframe {
     pub frame: i32,
@@ -1953,7 +2412,52 @@ pub struct Keyframe {
     pub label_top: f32,
 }
 
-#[derive(PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, Debug, FromRepr)]
+#[derive(
+    PartialEq, serde::Serialize, serde::Deserialize, Clone, Default, Debug, schemars::JsonSchema,
+)]
+#[serde(default)]
+pub struct AnimEvent {
+    pub frame: i32,
+    pub name: String,
+
+    // optional payload
+    #[serde(default, skip_serializing_if = "is_str_empty")]
+    pub value_str: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub value: f32,
+}
+
+#[derive(
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Default,
+    Debug,
+    FromRepr,
+    schemars::JsonSchema,
+)]
+pub enum LoopMode {
+    #[default]
+    Loop,
+    /// play once and hold the last frame
+    Once,
+    PingPong,
+    Reverse,
+}
+enum_string!(LoopMode);
+
+#[derive(
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Default,
+    Debug,
+    FromRepr,
+    schemars::JsonSchema,
+)]
 pub enum HandlePreset {
     #[default]
     Linear,
@@ -1968,7 +2472,7 @@ enum_string!(HandlePreset);
 // Despite the name, not all of these are animatable.
 // Elements with NA are not animated.
 #[derive(
-    Eq, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize, Clone, Default, Debug, FromRepr
+    Eq, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize, Clone, Default, Debug, FromRepr, schemars::JsonSchema
 )]
 #[rustfmt::skip]
 pub enum AnimElement {
@@ -2055,6 +2559,8 @@ enum_string!(AnimElement);
 #[derive(Default, Debug, Clone)]
 pub struct BoneTops {
     pub tops: Vec<BoneTop>,
+    /// Y of the animation events row, if shown
+    pub events_top: Option<f32>,
 }
 
 #[derive(Default, PartialEq, Clone, FromRepr)]
@@ -2066,14 +2572,65 @@ pub enum EditModes {
     Other,
 }
 
-#[derive(Default, PartialEq, Clone, FromRepr, serde::Serialize, serde::Deserialize, Debug)]
+#[derive(
+    Default,
+    PartialEq,
+    Clone,
+    FromRepr,
+    serde::Serialize,
+    serde::Deserialize,
+    Debug,
+    schemars::JsonSchema,
+)]
 pub enum ExportImgFormat {
     #[default]
     PNG,
     JPG,
+    Webp,
+    // WebP with reduced color precision, which compresses much better. Still losslessly
+    // encoded, as there's no lossy WebP encoder to use
+    WebpReducedColors,
 }
 enum_string!(ExportImgFormat);
 
+impl ExportImgFormat {
+    /// Whether atlases keep transparency, instead of being filled with `clear_color`.
+    pub fn has_alpha(&self) -> bool {
+        *self != Self::JPG
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::PNG => "png",
+            Self::JPG => "jpg",
+            Self::Webp | Self::WebpReducedColors => "webp",
+        }
+    }
+
+    /// Name in the format dropdown.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::PNG => "PNG",
+            Self::JPG => "JPG",
+            Self::Webp => "WEBP (lossless)",
+            Self::WebpReducedColors => "WEBP (lossless, reduced colors)",
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Clone, FromRepr, Debug)]
+pub enum ExportArmatureFormat {
+    #[default]
+    Skf,
+    /// skeleton JSON + atlas, for Spine runtimes
+    Spine,
+    /// `_ske.json` + `_tex.json` atlases, for DragonBones runtimes
+    DragonBones,
+    /// `.tscn` scene with a Skeleton2D, for Godot 4
+    Godot,
+}
+enum_string!(ExportArmatureFormat);
+
 #[derive(Default, Clone)]
 pub struct EditMode {
     pub current: EditModes,
@@ -2089,24 +2646,51 @@ pub struct EditMode {
     pub sel_time: f32,
     pub export_bake_ik: bool,
     pub export_exclude_ik: bool,
+    /// save armature.bin instead of armature.json
+    pub export_binary: bool,
     pub export_img_format: ExportImgFormat,
+    pub export_format: ExportArmatureFormat,
     pub export_clear_color: Color,
     pub export_tex_padding: Vec2,
+    pub export_atlas: AtlasSettings,
+    /// premultiply atlas colors by their alpha (also previewed in the editor)
+    pub export_premultiplied: bool,
     pub onion_layers: bool,
     pub holding_edit_mod: bool,
     pub holding_edit_snap: bool,
     pub editing_pivot: bool,
 }
 
+impl EditMode {
+    /// Whether atlases can be premultiplied, which needs alpha and an armature format that can
+    /// tell runtimes (DragonBones and Godot scenes can't).
+    pub fn can_premultiply(&self) -> bool {
+        let format = &self.export_format;
+        self.export_img_format.has_alpha()
+            && matches!(
+                format,
+                ExportArmatureFormat::Skf | ExportArmatureFormat::Spine
+            )
+    }
+
+    pub fn premultiplied_atlases(&self) -> bool {
+        self.export_premultiplied && self.can_premultiply()
+    }
+}
+
 #[derive(Default, PartialEq, Debug, Clone)]
 pub struct BoneTop {
     pub id: i32,
     pub element: AnimElement,
     pub height: f32,
 }
+#[cfg(feature = "editor")]
 #[derive(Clone)]
 pub struct RenderedFrame {
-    pub buffer: wgpu::Buffer,
+    /// GPU readback buffer, mapped asynchronously
+    pub buffer: Option<wgpu::Buffer>,
+    /// frame drawn by the CPU rasterizer, if there was no GPU
+    pub image: Option<image::RgbaImage>,
     pub width: u32,
     pub height: u32,
 }
@@ -2114,6 +2698,7 @@ pub struct RenderedFrame {
 #[derive(Default)]
 pub struct CopyBuffer {
     pub keyframes: Vec<Keyframe>,
+    pub anim_events: Vec<AnimEvent>,
     pub anims: Vec<Animation>,
     pub bones: Vec<Bone>,
 }
@@ -2214,12 +2799,14 @@ impl UndoStates {
     }
 }
 
+#[cfg(feature = "editor")]
 #[derive(Default)]
 pub struct RenderBuffer {
     pub vertex: Option<wgpu::Buffer>,
     pub index: Option<wgpu::Buffer>,
 }
 
+#[cfg(feature = "editor")]
 impl RenderBuffer {
     pub fn init(&mut self, device: &wgpu::Device, size: u64) {
         if self.vertex == None {
@@ -2241,6 +2828,7 @@ impl RenderBuffer {
     }
 }
 
+#[cfg(feature = "editor")]
 #[derive(Default)]
 pub struct Renderer {
     pub editing_bone: bool,
@@ -2337,6 +2925,11 @@ pub enum Events {
     MoveStyle,
 
     ToggleAnimPlaying,
+    ToggleAnimAdditive,
+    SetAnimLength,
+    SetAnimLoopMode,
+    ToggleRootMotion,
+    ToggleBoneInAnimMask,
     ToggleStyleActive,
     ToggleShowingMesh,
     ToggleSettingIkTarget,
@@ -2350,6 +2943,7 @@ pub enum Events {
     ToggleBindPathing,
     ToggleBakingIk,
     ToggleExcludeIk,
+    ToggleExportBinary,
 
     OpenModal,
     UnselectAll,
@@ -2378,7 +2972,15 @@ pub enum Events {
     OpenFileErrModal,
     SetExportClearColor,
     SetExportImgFormat,
+    SetExportFormat,
     SetExportTexPadding,
+    TogglePremultiplied,
+    SetExportAtlasMaxSize,
+    SetExportAtlasExtrude,
+    ToggleAtlasPowerOfTwo,
+    ToggleAtlasSquare,
+    ToggleAtlasTrim,
+    ToggleAtlasRotate,
     OpenExportModal,
     UpdateConfig,
     UpdateKeyframeTransition,
@@ -2417,6 +3019,17 @@ pub enum Events {
     ToggleSelectedTexture,
     ToggleEditingPivot,
     ReduceGlobalIkFamilyIds,
+
+    NewAnimEvent,
+    SelectAnimEvents,
+    DeleteAnimEvent,
+    DeleteAnimEventsByFrame,
+    RenameAnimEvent,
+    SetAnimEventStr,
+    SetAnimEventValue,
+    SetAnimEventFrame,
+    CopyAnimEvents,
+    PasteAnimEventsOnFrame,
 }
 
 enum_string!(Events);
@@ -2509,8 +3122,31 @@ impl EventState {
     event_with_value!(save_bone, Events::SaveBone, bone_idx, usize);
     event_with_value!(toggle_baking_ik, Events::ToggleBakingIk, toggle, usize);
     event_with_value!(toggle_exclude_ik, Events::ToggleExcludeIk, toggle, usize);
+    event_with_value!(toggle_export_binary, E::ToggleExportBinary, toggle, usize);
     event_with_value!(set_export_img_format, E::SetExportImgFormat, idx, usize);
+    event_with_value!(set_export_format, E::SetExportFormat, idx, usize);
+    event_with_value!(toggle_premultiplied, E::TogglePremultiplied, toggle, usize);
+    event_with_value!(set_export_atlas_extrude, E::SetExportAtlasExtrude, px, i32);
+    event_with_value!(
+        toggle_atlas_power_of_two,
+        E::ToggleAtlasPowerOfTwo,
+        toggle,
+        usize
+    );
+    event_with_value!(toggle_atlas_square, E::ToggleAtlasSquare, toggle, usize);
+    event_with_value!(toggle_atlas_trim, E::ToggleAtlasTrim, toggle, usize);
+    event_with_value!(toggle_atlas_rotate, E::ToggleAtlasRotate, toggle, usize);
     event_with_value!(toggle_onion_layers, E::ToggleOnionLayers, toggle, usize);
+    event_with_value!(toggle_anim_additive, E::ToggleAnimAdditive, toggle, usize);
+    event_with_value!(set_anim_length, E::SetAnimLength, length, i32);
+    event_with_value!(set_anim_loop_mode, E::SetAnimLoopMode, mode, usize);
+    event_with_value!(toggle_root_motion, E::ToggleRootMotion, toggle, usize);
+    event_with_value!(new_anim_event, E::NewAnimEvent, frame, i32);
+    event_with_value!(select_anim_events, E::SelectAnimEvents, frame, i32);
+    event_with_value!(delete_anim_event, E::DeleteAnimEvent, idx, usize);
+    #[rustfmt::skip]    event_with_value!(delete_anim_events_by_frame, E::DeleteAnimEventsByFrame, frame, i32);
+    event_with_value!(copy_anim_events, E::CopyAnimEvents, frame, i32);
+    #[rustfmt::skip]    event_with_value!(paste_anim_events_on_frame, E::PasteAnimEventsOnFrame, frame, i32);
     #[rustfmt::skip]    event_with_value!(paste_keyframes_on_frame, Events::PasteKeyframesOnFrame, frame, i32);
     #[rustfmt::skip]    event_with_value!(toggle_anim_panel_open, E::ToggleAnimPanelOpen, toggle, usize);
     #[rustfmt::skip]    event_with_value!(delete_keyframes_by_frame, E::DeleteKeyframesByFrame, frame, i32);
@@ -2607,6 +3243,36 @@ impl EventState {
         self.values.push(if playing { 1. } else { 0. });
     }
 
+    pub fn rename_anim_event(&mut self, event_idx: usize, name: String) {
+        self.events.push(Events::RenameAnimEvent);
+        self.values.push(event_idx as f32);
+        self.str_values.push(name);
+    }
+
+    pub fn set_anim_event_str(&mut self, event_idx: usize, value: String) {
+        self.events.push(Events::SetAnimEventStr);
+        self.values.push(event_idx as f32);
+        self.str_values.push(value);
+    }
+
+    pub fn set_anim_event_value(&mut self, event_idx: usize, value: f32) {
+        self.events.push(Events::SetAnimEventValue);
+        self.values.push(event_idx as f32);
+        self.values.push(value);
+    }
+
+    pub fn set_anim_event_frame(&mut self, event_idx: usize, frame: i32) {
+        self.events.push(Events::SetAnimEventFrame);
+        self.values.push(event_idx as f32);
+        self.values.push(frame as f32);
+    }
+
+    pub fn toggle_bone_in_anim_mask(&mut self, bone_id: i32, include_children: bool) {
+        self.events.push(Events::ToggleBoneInAnimMask);
+        self.values.push(bone_id as f32);
+        self.values.push(if include_children { 1. } else { 0. });
+    }
+
     pub fn toggle_style_active(&mut self, style_idx: usize, toggle: bool) {
         self.events.push(Events::ToggleStyleActive);
         self.values.push(style_idx as f32);
@@ -2760,6 +3426,12 @@ impl EventState {
         self.values.push(padding.y);
     }
 
+    pub fn set_export_atlas_max_size(&mut self, size: Vec2I) {
+        self.events.push(Events::SetExportAtlasMaxSize);
+        self.values.push(size.x as f32);
+        self.values.push(size.y as f32);
+    }
+
     pub fn trim_texture(&mut self, style_idx: usize, tex_idx: usize) {
         self.events.push(Events::TrimTexture);
         self.values.push(style_idx as f32);
@@ -2803,6 +3475,7 @@ impl SelectionState {
     }
 }
 
+#[cfg(feature = "editor")]
 #[derive(Default)]
 pub struct Shared {
     pub armature: Armature,
@@ -2847,6 +3520,7 @@ fn default_neg_one() -> i32 {
     -1
 }
 
+#[cfg(feature = "editor")]
 fn default_one() -> f32 {
     1.
 }
@@ -2855,6 +3529,7 @@ fn default_scale() -> Vec2 {
     Vec2::new(1., 1.)
 }
 
+#[cfg(feature = "editor")]
 fn gridline_default() -> i32 {
     500
 }
@@ -2875,6 +3550,14 @@ fn is_max(value: &f32) -> bool {
     *value == f32::MAX
 }
 
+fn is_zero(value: &f32) -> bool {
+    *value == 0.
+}
+
+fn is_i32_zero(value: &i32) -> bool {
+    *value == 0
+}
+
 fn is_color_empty(value: &Color) -> bool {
     *value == Color::new(0, 0, 0, 0)
 }
@@ -2899,6 +3582,14 @@ fn are_anims_empty(value: &Vec<Animation>) -> bool {
     *value == vec![]
 }
 
+fn are_anim_events_empty(value: &Vec<AnimEvent>) -> bool {
+    *value == vec![]
+}
+
+fn are_vec2s_empty(value: &Vec<Vec2>) -> bool {
+    *value == vec![]
+}
+
 fn is_i32_empty(value: &Vec<i32>) -> bool {
     value.len() == 0
 }
@@ -2919,6 +3610,18 @@ fn is_false(value: &bool) -> bool {
     *value == false
 }
 
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_default_atlas_size(value: &Vec2I) -> bool {
+    *value == DEFAULT_ATLAS_SIZE
+}
+
+fn is_vec2i_zero(value: &Vec2I) -> bool {
+    *value == Vec2I::new(0, 0)
+}
+
 fn is_vec2_max(value: &Vec2) -> bool {
     *value == Vec2::new(f32::MAX, f32::MAX)
 }
@@ -2935,28 +3638,28 @@ pub fn bool_as_f32(value: bool) -> f32 {
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn config_path() -> std::path::PathBuf {
     directories_next::ProjectDirs::from("com", "retropaint", "skelform")
         .map(|proj_dirs| proj_dirs.data_dir().join("config.json"))
         .unwrap()
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn color_path() -> std::path::PathBuf {
     directories_next::ProjectDirs::from("com", "retropaint", "skelform")
         .map(|proj_dirs| proj_dirs.data_dir().join("colors.json"))
         .unwrap()
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn keys_path() -> std::path::PathBuf {
     directories_next::ProjectDirs::from("com", "retropaint", "skelform")
         .map(|proj_dirs| proj_dirs.data_dir().join("keys.json"))
         .unwrap()
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn recents_path() -> std::path::PathBuf {
     directories_next::ProjectDirs::from("com", "retropaint", "skelform")
         .map(|proj_dirs| proj_dirs.data_dir().join("recent_files.json"))
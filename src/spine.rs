@@ -0,0 +1,1190 @@
+//! Spine export (skeleton JSON + atlas), for engines that only have Spine runtimes,
+//! and import of Spine skeletons.
+//!
+//! Bones, meshes, weights, styles (as skins), animations and IK families are converted.
+//! Anything Spine has no equivalent for is listed in `UNSUPPORTED`.
+
+use crate::export_common::*;
+use crate::*;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::{Seek, Write};
+use std::str::FromStr;
+
+/// Spine editor version that exported skeletons are formatted for.
+pub const SPINE_VERSION: &str = "4.1.00";
+
+/// Features that Spine skeletons can't represent.
+pub const UNSUPPORTED: &[Feature] = &[
+    Feature::Sway,
+    Feature::Physics,
+    Feature::PathBinds,
+    Feature::LongIk,
+    Feature::IkMimic,
+    Feature::Additive,
+    Feature::PingPong,
+    Feature::Reverse,
+];
+
+/// Write a Spine skeleton (skeleton.json, skeleton.atlas and its pages) into a zip.
+pub fn write_spine<W: Write + Seek>(
+    writer: W,
+    armature: &Armature,
+    edit_mode: &EditMode,
+) -> zip::result::ZipResult<W> {
+    let mut carmature = armature.clone();
+    let mut bufs = vec![];
+    let mut sizes = vec![];
+    if carmature.styles.iter().any(|s| !s.textures.is_empty()) {
+        (bufs, sizes) = utils::create_tex_sheet(&mut carmature, edit_mode);
+    }
+
+    let ext = format!(".{}", edit_mode.export_img_format.extension());
+    // same page names as Spine's texture packer
+    let pages: Vec<String> = (0..bufs.len())
+        .map(|i| match i {
+            0 => format!("skeleton{}", ext),
+            _ => format!("skeleton{}{}", i + 1, ext),
+        })
+        .collect();
+
+    let mut zip = zip::ZipWriter::new(writer);
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    let skeleton = serde_json::to_string_pretty(&skeleton_json(&carmature)).unwrap();
+    zip.start_file("skeleton.json", options)?;
+    zip.write_all(skeleton.as_bytes())?;
+    zip.start_file("skeleton.atlas", options)?;
+    let pma = edit_mode.premultiplied_atlases();
+    zip.write_all(atlas(&carmature, &pages, &sizes, pma).as_bytes())?;
+    for (page, buf) in pages.iter().zip(&bufs) {
+        zip.start_file(page, options)?;
+        zip.write_all(buf)?;
+    }
+
+    zip.finish()
+}
+
+/// Spine atlas of the textures placed by `utils::create_tex_sheet`, with `pma` marking
+/// premultiplied pages.
+///
+/// Regions are named `style/texture`, since texture names are only unique within a style.
+pub fn atlas(armature: &Armature, pages: &[String], sizes: &[Vec2I], pma: bool) -> String {
+    let mut atlas = String::new();
+    for (p, page) in pages.iter().enumerate() {
+        if p > 0 {
+            atlas += "\n";
+        }
+        atlas += &format!("{}\nsize: {},{}\n", page, sizes[p].x, sizes[p].y);
+        // textures are drawn with nearest filtering in the editor
+        atlas += "filter: Nearest,Nearest\n";
+        if pma {
+            atlas += "pma: true\n";
+        }
+        for style in &armature.styles {
+            for tex in &style.textures {
+                if tex.atlas_idx != p as i32 {
+                    continue;
+                }
+                atlas += &format!("{}\n", region_name(style, tex));
+                #[rustfmt::skip]
+                let bounds = format!("bounds: {},{},{},{}\n", tex.offset.x, tex.offset.y, tex.size.x, tex.size.y);
+                atlas += &bounds;
+            }
+        }
+    }
+    atlas
+}
+
+/// Spine skeleton JSON of the armature.
+///
+/// Textures should already be placed in atlases, for region names to line up with `atlas`.
+pub fn skeleton_json(armature: &Armature) -> Value {
+    let names = unique_names(armature);
+
+    // bones (SkelForm can have several root bones, but Spine expects one)
+    let mut bones = vec![json!({ "name": "root" })];
+    for bone in &armature.bones {
+        let mut json = json!({
+            "name": names[&bone.id],
+            "parent": "root",
+            "length": num(bone_length(armature, bone)),
+            "x": num(bone.pos.x),
+            "y": num(bone.pos.y),
+            "rotation": num(bone.rot.to_degrees()),
+            "scaleX": num(bone.scale.x),
+            "scaleY": num(bone.scale.y),
+        });
+        if let Some(parent) = names.get(&bone.parent_id) {
+            json["parent"] = json!(parent);
+        }
+        bones.push(json);
+    }
+
+    // slots, in draw order
+    let hiddens = propagated_hidden(armature);
+    let slot_bones = slot_bones(armature);
+    let mut slots = vec![];
+    for bone in &slot_bones {
+        let mut json = json!({ "name": names[&bone.id], "bone": names[&bone.id] });
+        if !bone.tex.is_empty() && !hiddens[&bone.id] {
+            json["attachment"] = json!(bone.tex);
+        }
+        if bone.tint != TintColor::new(1., 1., 1., 1.) {
+            json["color"] = json!(hex_color(&bone.tint));
+        }
+        slots.push(json);
+    }
+
+    // inverse kinematics (Spine can only bend 1 or 2 bones towards a target)
+    let mut iks = vec![];
+    for (f, joints) in ik_families(armature).iter().enumerate() {
+        let target = names.get(&joints[0].ik_target_id);
+        let skipped = joints[0].ik_mode == InverseKinematicsMode::Skip;
+        if target.is_none() || joints.len() < 2 || skipped {
+            continue;
+        }
+        let bent: Vec<&String> = joints[..joints.len().min(3) - 1]
+            .iter()
+            .map(|joint| &names[&joint.id])
+            .collect();
+        iks.push(json!({
+            "name": ik_name(joints),
+            "order": f,
+            "bones": bent,
+            "target": target.unwrap(),
+            "bendPositive": joints[0].ik_constraint != JointConstraint::Clockwise,
+        }));
+    }
+
+    // styles as skins, with the active ones combined into the default skin
+    let mut skins = vec![];
+    let mut default = Map::new();
+    for bone in &slot_bones {
+        let mut attachments = Map::new();
+        for tex_name in slot_tex_names(armature, bone) {
+            if let Some((style, tex)) = active_tex(armature, &tex_name) {
+                let path = region_name(style, tex);
+                attachments.insert(tex_name, attachment(armature, bone, tex, path));
+            }
+        }
+        if !attachments.is_empty() {
+            default.insert(names[&bone.id].clone(), Value::Object(attachments));
+        }
+    }
+    skins.push(json!({ "name": "default", "attachments": default }));
+    for style in &armature.styles {
+        let mut skin = Map::new();
+        for bone in &slot_bones {
+            let mut attachments = Map::new();
+            for tex_name in slot_tex_names(armature, bone) {
+                if let Some(tex) = style.textures.iter().find(|t| t.name == tex_name) {
+                    let path = region_name(style, tex);
+                    attachments.insert(tex_name, attachment(armature, bone, tex, path));
+                }
+            }
+            if !attachments.is_empty() {
+                skin.insert(names[&bone.id].clone(), Value::Object(attachments));
+            }
+        }
+        skins.push(json!({ "name": style.name, "attachments": skin }));
+    }
+
+    // events are declared once, and keyed in animations
+    let mut events = Map::new();
+    for anim in &armature.animations {
+        for event in &anim.events {
+            events.insert(event.name.clone(), json!({}));
+        }
+    }
+
+    let mut animations = Map::new();
+    for a in 0..armature.animations.len() {
+        let name = armature.animations[a].name.clone();
+        animations.insert(name, animation(armature, a, &names, &slot_bones));
+    }
+
+    json!({
+        "skeleton": { "spine": SPINE_VERSION, "images": "./" },
+        "bones": bones,
+        "slots": slots,
+        "ik": iks,
+        "skins": skins,
+        "events": events,
+        "animations": animations,
+    })
+}
+
+fn animation(
+    armature: &Armature,
+    anim_idx: usize,
+    names: &HashMap<i32, String>,
+    slot_bones: &[&Bone],
+) -> Value {
+    let anim = &armature.animations[anim_idx];
+    let fps = anim.fps.max(1) as f32;
+    type AE = AnimElement;
+
+    // transforms are relative to the setup pose in Spine
+    let mut bones = Map::new();
+    for bone in &armature.bones {
+        let mut timelines = Map::new();
+        #[rustfmt::skip]
+        let elements = [
+            (AE::Rotation,  "rotate"),
+            (AE::PositionX, "translatex"),
+            (AE::PositionY, "translatey"),
+            (AE::ScaleX,    "scalex"),
+            (AE::ScaleY,    "scaley"),
+        ];
+        for (element, timeline) in elements {
+            let kfs = track(anim, bone.id, &element);
+            if !kfs.is_empty() {
+                let keys = curve_timeline(&kfs, fps, &|v| to_setup_relative(bone, &element, v));
+                timelines.insert(timeline.to_string(), Value::Array(keys));
+            }
+        }
+        if !timelines.is_empty() {
+            bones.insert(names[&bone.id].clone(), Value::Object(timelines));
+        }
+    }
+
+    let mut slots = Map::new();
+    for bone in slot_bones {
+        let mut timelines = Map::new();
+
+        // textures and visibility both decide the attachment
+        let mut keys = vec![];
+        let mut last: Option<Value> = None;
+        for frame in tex_frames(armature, anim, bone) {
+            let tex = tex_at(armature, anim, bone, frame);
+            let name = if tex.is_empty() {
+                Value::Null
+            } else {
+                json!(tex)
+            };
+            if last.as_ref() != Some(&name) {
+                keys.push(json!({ "time": num(frame as f32 / fps), "name": name }));
+                last = Some(name);
+            }
+        }
+        if !keys.is_empty() {
+            timelines.insert("attachment".to_string(), Value::Array(keys));
+        }
+
+        // tint channels are keyed separately in SkelForm, so they're sampled together here
+        let tints = [AE::TintR, AE::TintG, AE::TintB, AE::TintA];
+        let mut frames: Vec<i32> = anim
+            .keyframes
+            .iter()
+            .filter(|kf| kf.bone_id == bone.id && tints.contains(&kf.element))
+            .map(|kf| kf.frame)
+            .collect();
+        frames.sort();
+        frames.dedup();
+        let mut keys = vec![];
+        for frame in frames {
+            let channel = |element: AE, setup: f32| {
+                armature.interpolate_keyframes(anim_idx, bone.id, element, setup, frame as f32)
+            };
+            let tint = TintColor::new(
+                channel(AE::TintR, bone.tint.r),
+                channel(AE::TintG, bone.tint.g),
+                channel(AE::TintB, bone.tint.b),
+                channel(AE::TintA, bone.tint.a),
+            );
+            keys.push(json!({ "time": num(frame as f32 / fps), "color": hex_color(&tint) }));
+        }
+        if !keys.is_empty() {
+            timelines.insert("rgba".to_string(), Value::Array(keys));
+        }
+
+        if !timelines.is_empty() {
+            slots.insert(names[&bone.id].clone(), Value::Object(timelines));
+        }
+    }
+
+    // zindex changes become draw order keys
+    let mut frames: Vec<i32> = anim
+        .keyframes
+        .iter()
+        .filter(|kf| kf.element == AE::Zindex)
+        .map(|kf| kf.frame)
+        .collect();
+    frames.sort();
+    frames.dedup();
+    let mut draw_order = vec![];
+    for frame in frames {
+        let mut offsets = vec![];
+        for (s, offset) in draw_order_offsets(anim, slot_bones, frame) {
+            offsets.push(json!({ "slot": names[&slot_bones[s].id], "offset": offset }));
+        }
+        draw_order.push(json!({ "time": num(frame as f32 / fps), "offsets": offsets }));
+    }
+
+    // IK constraints can only be turned off or bent the other way
+    let mut iks = Map::new();
+    for joints in ik_families(armature) {
+        let kfs = track(anim, joints[0].id, &AE::IkConstraint);
+        if kfs.is_empty() {
+            continue;
+        }
+        let keys: Vec<Value> = kfs
+            .iter()
+            .map(|kf| {
+                let constraint = JointConstraint::from_str(&kf.value_str).unwrap_or_default();
+                json!({
+                    "time": num(kf.frame as f32 / fps),
+                    "mix": if constraint == JointConstraint::Skip { 0 } else { 1 },
+                    "bendPositive": constraint != JointConstraint::Clockwise,
+                    "curve": "stepped",
+                })
+            })
+            .collect();
+        iks.insert(ik_name(&joints), Value::Array(keys));
+    }
+
+    let events: Vec<Value> = anim
+        .events
+        .iter()
+        .map(|event| {
+            let mut json = json!({ "time": num(event.frame as f32 / fps), "name": event.name });
+            if event.value != 0. {
+                json["float"] = num(event.value);
+            }
+            if !event.value_str.is_empty() {
+                json["string"] = json!(event.value_str);
+            }
+            json
+        })
+        .collect();
+
+    let mut json = Map::new();
+    macro_rules! insert {
+        ($key:expr, $value:expr, $is_empty:expr) => {
+            if !$is_empty {
+                json.insert($key.to_string(), json!($value));
+            }
+        };
+    }
+    insert!("bones", bones, bones.is_empty());
+    insert!("slots", slots, slots.is_empty());
+    insert!("drawOrder", draw_order, draw_order.is_empty());
+    insert!("ik", iks, iks.is_empty());
+    insert!("events", events, events.is_empty());
+    Value::Object(json)
+}
+
+/// Keys of a single-value timeline, with SkelForm's bezier handles converted to Spine curves.
+fn curve_timeline(kfs: &[&Keyframe], fps: f32, to_spine: &dyn Fn(f32) -> f32) -> Vec<Value> {
+    let mut keys = vec![];
+
+    // hold the first value until its keyframe, like interpolate_keyframes does
+    if kfs[0].frame > 0 {
+        keys.push(json!({ "time": 0, "value": num(to_spine(kfs[0].value)) }));
+    }
+
+    for (k, kf) in kfs.iter().enumerate() {
+        let time = kf.frame as f32 / fps;
+        let value = to_spine(kf.value);
+        let mut key = json!({ "time": num(time), "value": num(value) });
+
+        // the handles of a segment are stored in its last keyframe
+        if let Some(next) = kfs.get(k + 1) {
+            let (start, end) = (next.start_handle, next.end_handle);
+            let next_time = next.frame as f32 / fps;
+            let next_value = to_spine(next.value);
+            if start.y == 999. && end.y == 999. {
+                key["curve"] = json!("stepped");
+            } else if (start, end) != utils::interp_preset(HandlePreset::Linear) {
+                // Spine curves are in absolute time and value
+                let x = |h: f32| num(time + h * (next_time - time));
+                let y = |h: f32| num(value + h * (next_value - value));
+                key["curve"] = json!([x(start.x), y(start.y), x(end.x), y(end.y)]);
+            }
+        }
+
+        keys.push(key);
+    }
+    keys
+}
+
+fn attachment(armature: &Armature, bone: &Bone, tex: &Texture, path: String) -> Value {
+    let size = tex.size;
+    let pivot = size * bone.pivot_pos;
+
+    // untouched rects are plain regions
+    if !bone.verts_edited && bone.binds.is_empty() {
+        return json!({
+            "path": path,
+            "x": num(pivot.x),
+            "y": num(pivot.y),
+            "rotation": num(bone.pivot_rot.to_degrees()),
+            "scaleX": num(bone.pivot_scale.x),
+            "scaleY": num(bone.pivot_scale.y),
+            "width": num(size.x),
+            "height": num(size.y),
+        });
+    }
+
+    let local =
+        |vert: &Vertex| utils::rotate(&(vert.pos * bone.pivot_scale), bone.pivot_rot) + pivot;
+    let mut uvs = vec![];
+    for vert in &bone.vertices {
+        uvs.push(num(vert.uv.x));
+        uvs.push(num(vert.uv.y));
+    }
+
+    let binds: Vec<&BoneBind> = bone
+        .binds
+        .iter()
+        .filter(|bind| !bind.is_path && armature.bones.iter().any(|b| b.id == bind.bone_id))
+        .collect();
+    let mut vertices = vec![];
+    if binds.is_empty() {
+        for vert in &bone.vertices {
+            let pos = local(vert);
+            vertices.push(num(pos.x));
+            vertices.push(num(pos.y));
+        }
+    } else {
+        // weighted vertices are in the setup space of each of their bones
+        let world = world_bones(armature);
+        let mesh = world.iter().find(|b| b.id == bone.id).unwrap();
+        for vert in &bone.vertices {
+            let weights = vertex_weights(armature, bone, vert);
+            vertices.push(json!(weights.len()));
+            for (idx, weight) in weights {
+                let bind = &world[idx];
+                let pos = to_bone_space(bind, weighted_pos(mesh, bind, vert.pos, pivot));
+
+                // Spine bones are offset by the generated root
+                vertices.push(json!(idx + 1));
+                vertices.push(num(pos.x));
+                vertices.push(num(pos.y));
+                vertices.push(num(weight));
+            }
+        }
+    }
+
+    json!({
+        "type": "mesh",
+        "path": path,
+        "uvs": uvs,
+        "triangles": bone.indices,
+        "vertices": vertices,
+        "hull": bone.vertices.len(),
+        "width": num(size.x),
+        "height": num(size.y),
+    })
+}
+
+/// Armature from a Spine skeleton JSON and its atlas.
+///
+/// `load_page` returns an atlas page's image by name. Features without a SkelForm
+/// equivalent (shear, deforms, clipping, paths, etc) are skipped.
+pub fn read_spine(
+    skeleton: &str,
+    atlas_text: &str,
+    load_page: &mut dyn FnMut(&str) -> Option<image::DynamicImage>,
+) -> Result<Armature, SkfError> {
+    let json: Value = match serde_json::from_str(skeleton) {
+        Ok(json) => json,
+        Err(e) => {
+            let file = "skeleton JSON".to_string();
+            return Err(SkfError::Corrupt {
+                file,
+                message: e.to_string(),
+            });
+        }
+    };
+    let version = json["skeleton"]["spine"].as_str().unwrap_or(SPINE_VERSION);
+    let curves = Curves {
+        // Spine 3 curves are normalized like SkelForm's, while Spine 4 curves are absolute
+        normalized: version.starts_with('3') || version.starts_with('2'),
+        fps: float(&json["skeleton"], "fps", 30.),
+    };
+    let mut armature = Armature::default();
+
+    // bones (with Spine's root left out if it does nothing, since SkelForm doesn't need one)
+    let skip_root = is_empty_root(&json);
+    let mut bone_ids: HashMap<String, i32> = HashMap::new();
+    let mut spine_bones: Vec<i32> = vec![];
+    for (b, spine_bone) in array(&json["bones"]).iter().enumerate() {
+        let name = str_of(spine_bone, "name").to_string();
+        if b == 0 && skip_root {
+            spine_bones.push(-1);
+            bone_ids.insert(name, -1);
+            continue;
+        }
+        let parent_id = match spine_bone["parent"].as_str() {
+            Some(parent) => match bone_ids.get(parent) {
+                Some(id) => *id,
+                None => {
+                    let path = format!("bones[{}].parent", b);
+                    let message = format!("There is no bone named '{}' before it.", parent);
+                    return Err(SkfError::BadValue { path, message });
+                }
+            },
+            None => -1,
+        };
+        armature.new_bone(-1);
+        let bone = armature.bones.last_mut().unwrap();
+        bone.name = name.clone();
+        bone.parent_id = parent_id;
+        bone.pos = Vec2::new(float(spine_bone, "x", 0.), float(spine_bone, "y", 0.));
+        bone.rot = float(spine_bone, "rotation", 0.).to_radians();
+        bone.scale = Vec2::new(
+            float(spine_bone, "scaleX", 1.),
+            float(spine_bone, "scaleY", 1.),
+        );
+        spine_bones.push(bone.id);
+        bone_ids.insert(name, bone.id);
+    }
+
+    // IK constraints only list the bent bones, so the child of the last one ends the family
+    let mut ik_starts: HashMap<String, i32> = HashMap::new();
+    for ik in array(&json["ik"]) {
+        let mut joints: Vec<i32> = array(&ik["bones"])
+            .iter()
+            .filter_map(|name| bone_ids.get(name.as_str().unwrap_or_default()).copied())
+            .filter(|id| *id != -1)
+            .collect();
+        let target = bone_ids.get(str_of(ik, "target")).copied().unwrap_or(-1);
+        let last = joints.last().copied().unwrap_or(-1);
+        let end = armature
+            .bones
+            .iter()
+            .find(|b| last != -1 && b.parent_id == last);
+        let taken = |id: &i32| {
+            armature
+                .bones
+                .iter()
+                .any(|b| b.id == *id && b.ik_family_id != -1)
+        };
+        if end.is_none() || target == -1 || joints.iter().any(taken) {
+            continue;
+        }
+        joints.push(end.unwrap().id);
+        let family_id = ik_starts.len() as i32;
+        for id in &joints {
+            armature.find_bone_mut(*id).unwrap().ik_family_id = family_id;
+        }
+        let start = armature.find_bone_mut(joints[0]).unwrap();
+        start.ik_target_id = target;
+        start.ik_constraint = ik_constraint(ik);
+        ik_starts.insert(str_of(ik, "name").to_string(), joints[0]);
+    }
+
+    // slots become the bones that show textures, in draw order
+    let slots = array(&json["slots"]);
+    let mut slot_ids: HashMap<String, i32> = HashMap::new();
+    for (s, slot) in slots.iter().enumerate() {
+        let name = str_of(slot, "name");
+        let bone_name = str_of(slot, "bone");
+        let bone_id = bone_ids.get(bone_name).copied().unwrap_or(-1);
+        let shared_bone = slots
+            .iter()
+            .filter(|other| other["bone"] == slot["bone"])
+            .count()
+            > 1;
+        let id = if bone_id != -1 && name == bone_name && !shared_bone {
+            bone_id
+        } else {
+            // bones only show one texture, so other slots get a child bone each
+            armature.new_bone(-1);
+            let bone = armature.bones.last_mut().unwrap();
+            bone.name = name.to_string();
+            bone.parent_id = bone_id;
+            bone.id
+        };
+        let bone = armature.find_bone_mut(id).unwrap();
+        bone.zindex = s as i32;
+        if let Some(color) = slot["color"].as_str() {
+            bone.tint = tint_from_hex(color);
+        }
+        slot_ids.insert(name.to_string(), id);
+    }
+
+    let skins = skins(&json);
+    let entries: Vec<SkinEntries> = skins
+        .iter()
+        .map(|(name, skin)| (name.clone(), skin_entries(skin)))
+        .collect();
+    let default = skins.iter().position(|(name, _)| name == "default");
+    let atlas = parse_atlas(atlas_text);
+    let mut pages: Vec<Option<image::DynamicImage>> = vec![None; atlas.0.len()];
+    let mut image = |path: &str| region_image(&atlas, path, &mut pages, load_page);
+    let clashing = skins_to_styles(&mut armature, &entries, default, &mut image)?;
+    let tex_name = |slot: &str, key: &str| skin_tex_name(&clashing, slot, key);
+
+    // geometry comes from each slot's setup attachment, or its first one
+    let world = world_bones(&armature);
+    for slot in slots {
+        let name = str_of(slot, "name");
+        let setup = slot["attachment"].as_str();
+        let attachments: Vec<(&String, &String, &Value)> = skins
+            .iter()
+            .filter_map(|(skin_name, skin)| {
+                let atts = skin.get(name).and_then(|a| a.as_object());
+                atts.map(|atts| atts.iter().map(move |(key, att)| (skin_name, key, att)))
+            })
+            .flatten()
+            .filter(|(_, _, att)| is_textured(att))
+            .collect();
+        let att = attachments
+            .iter()
+            .find(|(_, key, _)| Some(key.as_str()) == setup);
+        let att = att.or(attachments.first());
+        if att.is_none() {
+            continue;
+        }
+        let (skin_name, key, att) = att.unwrap();
+        let tex = tex_name(name, key);
+        let size = armature
+            .styles
+            .iter()
+            .flat_map(|style| &style.textures)
+            .find(|t| t.name == tex)
+            .map(|t| t.size);
+
+        // linked meshes share their parent's geometry
+        let mut geometry = (*skin_name, *key, *att);
+        if att["type"] == "linkedmesh" {
+            let parent = att["parent"].as_str();
+            if let Some(mesh) = attachments
+                .iter()
+                .find(|(_, k, _)| Some(k.as_str()) == parent)
+            {
+                geometry = *mesh;
+            }
+        }
+
+        let bone = armature.find_bone_mut(slot_ids[name]).unwrap();
+        if setup == Some(key.as_str()) {
+            bone.tex = tex;
+        }
+        if let Some(size) = size {
+            let path = format!("skins.{}.{}.{}", geometry.0, name, geometry.1);
+            set_geometry(bone, geometry.2, size, &spine_bones, &world, &path)?;
+        }
+    }
+
+    let mut animations = vec![];
+    for (name, spine_anim) in json["animations"].as_object().into_iter().flatten() {
+        let mut anim = Animation {
+            name: name.clone(),
+            id: animations.len() as i32,
+            fps: curves.fps as i32,
+            ..Default::default()
+        };
+        type AE = AnimElement;
+
+        // transforms are relative to the setup pose in Spine
+        let angle = if curves.normalized { "angle" } else { "value" };
+        for (bone_name, timelines) in objects(&spine_anim["bones"]) {
+            let id = bone_ids.get(bone_name).copied().unwrap_or(-1);
+            if id == -1 {
+                continue;
+            }
+            let setup = armature.bones.iter().find(|b| b.id == id).unwrap();
+            for (timeline, keys) in objects(timelines) {
+                #[rustfmt::skip]
+                let channels: &[(AE, usize, &str, f32)] = match timeline.as_str() {
+                    "rotate"     => &[(AE::Rotation, 0, angle, 0.)],
+                    "translate"  => &[(AE::PositionX, 0, "x", 0.), (AE::PositionY, 1, "y", 0.)],
+                    "translatex" => &[(AE::PositionX, 0, "value", 0.)],
+                    "translatey" => &[(AE::PositionY, 0, "value", 0.)],
+                    "scale"      => &[(AE::ScaleX, 0, "x", 1.), (AE::ScaleY, 1, "y", 1.)],
+                    "scalex"     => &[(AE::ScaleX, 0, "value", 1.)],
+                    "scaley"     => &[(AE::ScaleY, 0, "value", 1.)],
+                    _ => &[],
+                };
+                for (element, channel, field, default) in channels {
+                    let raw = |key: &Value| float(key, field, *default);
+                    let to_skf = |value: f32| from_setup_relative(setup, element, value);
+                    let track = Track {
+                        bone_id: id,
+                        element: element.clone(),
+                        channel: *channel,
+                    };
+                    let kfs = curves.keyframes(array(keys), &track, &raw, &to_skf);
+                    anim.keyframes.extend(kfs);
+                }
+            }
+        }
+
+        for (slot_name, timelines) in objects(&spine_anim["slots"]) {
+            let id = match slot_ids.get(slot_name) {
+                Some(id) => *id,
+                None => continue,
+            };
+            for (timeline, keys) in objects(timelines) {
+                // no attachment is an empty texture
+                if timeline == "attachment" {
+                    for key in array(keys) {
+                        let mut kf = keyframe(curves.frame(key), id, AE::Texture);
+                        kf.value_str = key["name"]
+                            .as_str()
+                            .map_or(String::new(), |n| tex_name(slot_name, n));
+                        anim.keyframes.push(kf);
+                    }
+                    continue;
+                }
+
+                #[rustfmt::skip]
+                let channels: &[(AE, usize)] = match timeline.as_str() {
+                    "rgba" | "color" => &[(AE::TintR, 0), (AE::TintG, 1), (AE::TintB, 2), (AE::TintA, 3)],
+                    "rgb"            => &[(AE::TintR, 0), (AE::TintG, 1), (AE::TintB, 2)],
+                    "alpha"          => &[(AE::TintA, 0)],
+                    _ => &[],
+                };
+                for (element, channel) in channels {
+                    let raw = |key: &Value| match timeline.as_str() {
+                        "alpha" => float(key, "value", 1.),
+                        _ => hex_channel(str_of(key, "color"), *channel),
+                    };
+                    let track = Track {
+                        bone_id: id,
+                        element: element.clone(),
+                        channel: *channel,
+                    };
+                    anim.keyframes
+                        .extend(curves.keyframes(array(keys), &track, &raw, &|v| v));
+                }
+            }
+        }
+
+        // draw order keys become zindex changes, for the slots that move
+        let draw_order = spine_anim.get("drawOrder").or(spine_anim.get("draworder"));
+        let orders: Vec<(i32, Vec<usize>)> = array(draw_order.unwrap_or(&Value::Null))
+            .iter()
+            .map(|key| (curves.frame(key), draw_order_key(key, slots)))
+            .collect();
+        for (s, slot) in slots.iter().enumerate() {
+            if orders.iter().all(|(_, order)| order[s] == s) {
+                continue;
+            }
+            for (frame, order) in &orders {
+                let mut kf = keyframe(*frame, slot_ids[str_of(slot, "name")], AE::Zindex);
+                kf.value = order[s] as f32;
+                anim.keyframes.push(kf);
+            }
+        }
+
+        for (ik_name, keys) in objects(&spine_anim["ik"]) {
+            if let Some(id) = ik_starts.get(ik_name) {
+                for key in array(keys) {
+                    let mut kf = keyframe(curves.frame(key), *id, AE::IkConstraint);
+                    kf.value_str = ik_constraint(key).to_string();
+                    anim.keyframes.push(kf);
+                }
+            }
+        }
+
+        // event payloads fall back to the event's defaults
+        for key in array(&spine_anim["events"]) {
+            let name = str_of(key, "name");
+            let def = &json["events"][name];
+            let payload = |field: &str| key[field].as_f64().or(def[field].as_f64());
+            anim.events.push(AnimEvent {
+                frame: curves.frame(key),
+                name: name.to_string(),
+                value: payload("float").or(payload("int")).unwrap_or(0.) as f32,
+                value_str: key["string"]
+                    .as_str()
+                    .or(def["string"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+
+        anim.sort_keyframes();
+        anim.sort_events();
+        animations.push(anim);
+    }
+    armature.animations = animations;
+
+    Ok(armature)
+}
+
+struct Curves {
+    normalized: bool,
+    fps: f32,
+}
+
+struct Track {
+    bone_id: i32,
+    element: AnimElement,
+    // index of this value in Spine 4 curves, which have 4 numbers per value
+    channel: usize,
+}
+
+impl Curves {
+    fn frame(&self, key: &Value) -> i32 {
+        (float(key, "time", 0.) * self.fps).round() as i32
+    }
+
+    /// Keyframes of one value of a Spine timeline, with its curves converted to SkelForm's handles.
+    fn keyframes(
+        &self,
+        keys: &[Value],
+        track: &Track,
+        raw: &dyn Fn(&Value) -> f32,
+        to_skf: &dyn Fn(f32) -> f32,
+    ) -> Vec<Keyframe> {
+        let mut kfs = vec![];
+        for (k, key) in keys.iter().enumerate() {
+            let mut kf = keyframe(self.frame(key), track.bone_id, track.element.clone());
+            kf.value = to_skf(raw(key));
+
+            // Spine keeps a segment's curve in its first key, and SkelForm in its last
+            if k > 0 {
+                let prev = &keys[k - 1];
+                let span = (float(prev, "time", 0.), float(key, "time", 0.));
+                let values = (raw(prev), raw(key));
+                let handles = self.handles(prev, track, span, values);
+                (kf.start_handle, kf.end_handle, kf.handle_preset) = handles;
+            }
+            kfs.push(kf);
+        }
+        kfs
+    }
+
+    fn handles(
+        &self,
+        key: &Value,
+        track: &Track,
+        (t0, t1): (f32, f32),
+        (v0, v1): (f32, f32),
+    ) -> (Vec2, Vec2, HandlePreset) {
+        let curve = &key["curve"];
+        let preset = |preset: HandlePreset| {
+            let (start, end) = utils::interp_preset(preset.clone());
+            (start, end, preset)
+        };
+        if *curve == "stepped" {
+            return preset(HandlePreset::Snap);
+        }
+
+        let c = match curve {
+            // Spine 3.8 splits the curve into fields
+            Value::Number(c1) if self.normalized => vec![
+                c1.as_f64().unwrap_or(0.) as f32,
+                float(key, "c2", 0.),
+                float(key, "c3", 1.),
+                float(key, "c4", 1.),
+            ],
+            Value::Array(_) if self.normalized => floats(curve),
+            Value::Array(_) => floats(curve).into_iter().skip(track.channel * 4).collect(),
+            _ => vec![],
+        };
+        if c.len() < 4 {
+            return preset(HandlePreset::Linear);
+        }
+        if self.normalized {
+            return (
+                Vec2::new(c[0], c[1]),
+                Vec2::new(c[2], c[3]),
+                HandlePreset::Custom,
+            );
+        }
+
+        // flat segments have no vertical scale, so they get handles along their line
+        let x = |cx: f32| if t1 != t0 { (cx - t0) / (t1 - t0) } else { cx };
+        let y = |cy: f32, hx: f32| if v1 != v0 { (cy - v0) / (v1 - v0) } else { hx };
+        let (x1, x2) = (x(c[0]), x(c[2]));
+        let start = Vec2::new(x1, y(c[1], x1));
+        let end = Vec2::new(x2, y(c[3], x2));
+        (start, end, HandlePreset::Custom)
+    }
+}
+
+// `world` is the setup pose of every bone (see `world_bones`), and `path` where the
+// attachment is, for errors
+fn set_geometry(
+    bone: &mut Bone,
+    att: &Value,
+    size: Vec2,
+    spine_bones: &[i32],
+    world: &[Bone],
+    path: &str,
+) -> Result<(), SkfError> {
+    if att["type"].as_str().unwrap_or("region") == "region" {
+        let dims = Vec2::new(float(att, "width", size.x), float(att, "height", size.y));
+        let scale = Vec2::new(float(att, "scaleX", 1.), float(att, "scaleY", 1.));
+        bone.pivot_pos = Vec2::new(float(att, "x", 0.), float(att, "y", 0.)) / size;
+        bone.pivot_rot = float(att, "rotation", 0.).to_radians();
+        bone.pivot_scale = scale * dims / size;
+        (bone.vertices, bone.indices) = renderer::create_tex_rect(&size);
+        return Ok(());
+    }
+
+    let bad_value = |field: &str, message: String| {
+        let path = format!("{}.{}", path, field);
+        SkfError::BadValue { path, message }
+    };
+    let uvs = floats(&att["uvs"]);
+    let raw = floats(&att["vertices"]);
+    if !uvs.len().is_multiple_of(2) {
+        let message = format!("{} UV coordinates do not make whole pairs.", uvs.len());
+        return Err(bad_value("uvs", message));
+    }
+    let mut positions = vec![];
+    let mut weights: Vec<Vec<(i32, f32)>> = vec![];
+    if raw.len() == uvs.len() {
+        positions = raw.chunks(2).map(|pos| Vec2::new(pos[0], pos[1])).collect();
+    } else {
+        // weighted vertices have a position in the space of each of their bones, so they're
+        // rebuilt in setup pose and moved to where SkelForm's weighing places them
+        #[rustfmt::skip]
+        let identity = Bone { scale: Vec2::new(1., 1.), pivot_scale: Vec2::new(1., 1.), ..Default::default() };
+        let world_bone = |id: i32| world.iter().find(|b| b.id == id).unwrap_or(&identity);
+        let mesh = world_bone(bone.id);
+        let pivot = size * bone.pivot_pos;
+        let mut i = 0;
+        while i < raw.len() && positions.len() < uvs.len() / 2 {
+            let count = raw[i] as usize;
+            i += 1;
+            if raw.len() < i + count * 4 {
+                let message = format!("Vertex {} is cut short.", positions.len());
+                return Err(bad_value("vertices", message));
+            }
+            let mut setup_pos = Vec2::ZERO;
+            let mut binds = vec![];
+            let mut vert_weights = vec![];
+            for entry in raw[i..i + count * 4].chunks(4) {
+                let id = match spine_bones.get(entry[0] as usize) {
+                    Some(id) if entry[0] >= 0. => *id,
+                    _ => {
+                        let path = format!("{}.vertices[{}]", path, i);
+                        let (idx, len) = (entry[0] as i32, spine_bones.len());
+                        return Err(SkfError::OutOfRange { path, idx, len });
+                    }
+                };
+                let bind = world_bone(id);
+                setup_pos += from_bone_space(bind, Vec2::new(entry[1], entry[2])) * entry[3];
+                binds.push((bind, entry[3]));
+                if id != -1 && id != bone.id {
+                    vert_weights.push((id, entry[3]));
+                }
+                i += 4;
+            }
+            let pos = unweighted_pos(mesh, &binds, setup_pos, pivot);
+            positions.push(pos.unwrap_or_else(|| to_bone_space(mesh, setup_pos)));
+            weights.push(vert_weights);
+        }
+    }
+
+    let indices = floats(&att["triangles"]);
+    set_mesh(bone, &positions, &uvs, &indices, &weights, path)
+}
+
+// zindex of each slot, from a Spine draw order key's offsets
+fn draw_order_key(key: &Value, slots: &[Value]) -> Vec<usize> {
+    let offsets: Vec<(usize, i64)> = array(&key["offsets"])
+        .iter()
+        .filter_map(|offset| {
+            let slot = slots.iter().position(|s| s["name"] == offset["slot"])?;
+            Some((slot, float(offset, "offset", 0.) as i64))
+        })
+        .collect();
+    draw_order(&offsets, slots.len())
+}
+
+fn ik_constraint(json: &Value) -> JointConstraint {
+    if float(json, "mix", 1.) == 0. {
+        JointConstraint::Skip
+    } else if json["bendPositive"].as_bool().unwrap_or(true) {
+        JointConstraint::CounterClockwise
+    } else {
+        JointConstraint::Clockwise
+    }
+}
+
+// the first bone, if it's a root that has nothing bound, keyed or drawn on it
+fn is_empty_root(json: &Value) -> bool {
+    let root = &json["bones"][0];
+    let name = str_of(root, "name");
+    #[rustfmt::skip]
+    let untransformed = ["x", "y", "rotation"].iter().all(|k| float(root, k, 0.) == 0.)
+        && ["scaleX", "scaleY"].iter().all(|k| float(root, k, 1.) == 1.);
+    let used = array(&json["slots"])
+        .iter()
+        .any(|slot| slot["bone"] == name)
+        || array(&json["ik"]).iter().any(|ik| ik["target"] == name)
+        || objects(&json["animations"]).any(|(_, anim)| anim["bones"].get(name).is_some());
+    !name.is_empty() && root.get("parent").is_none() && untransformed && !used
+}
+
+// skins by name (an array since Spine 3.8, and an object before)
+fn skins(json: &Value) -> Vec<(String, &Map<String, Value>)> {
+    match &json["skins"] {
+        Value::Array(skins) => skins
+            .iter()
+            .filter_map(|skin| {
+                Some((
+                    str_of(skin, "name").to_string(),
+                    skin["attachments"].as_object()?,
+                ))
+            })
+            .collect(),
+        Value::Object(skins) => skins
+            .iter()
+            .filter_map(|(name, skin)| Some((name.clone(), skin.as_object()?)))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// slot, attachment name and atlas region of every textured attachment in a skin
+fn skin_entries(skin: &Map<String, Value>) -> Vec<(String, String, String)> {
+    let mut entries = vec![];
+    for (slot, attachments) in skin {
+        for (key, att) in objects(attachments) {
+            if is_textured(att) {
+                let path = att["path"].as_str().or(att["name"].as_str()).unwrap_or(key);
+                entries.push((slot.clone(), key.clone(), path.to_string()));
+            }
+        }
+    }
+    entries
+}
+
+fn is_textured(att: &Value) -> bool {
+    let kind = att["type"].as_str().unwrap_or("region");
+    ["region", "mesh", "linkedmesh"].contains(&kind)
+}
+
+#[derive(Default)]
+struct AtlasRegion {
+    name: String,
+    page: usize,
+    // x, y, width, height
+    bounds: [u32; 4],
+    // left, bottom, original width, original height
+    offsets: [u32; 4],
+    rotated: bool,
+    // the page's colors are premultiplied by alpha
+    pma: bool,
+}
+
+// page names and regions of a Spine atlas (in the format since Spine 4, or the one before)
+fn parse_atlas(text: &str) -> (Vec<String>, Vec<AtlasRegion>) {
+    let mut pages = vec![];
+    let mut regions: Vec<AtlasRegion> = vec![];
+    let mut page_next = true;
+    let mut pma = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            page_next = true;
+            continue;
+        }
+
+        // names are the only lines without a colon
+        let (key, values) = match line.split_once(':') {
+            Some((key, values)) => (key.trim(), values.split(',').map(|v| v.trim())),
+            None => {
+                if page_next {
+                    pages.push(line.to_string());
+                    page_next = false;
+                    pma = false;
+                } else if !pages.is_empty() {
+                    let page = pages.len() - 1;
+                    #[rustfmt::skip]
+                    regions.push(AtlasRegion { name: line.to_string(), page, pma, ..Default::default() });
+                }
+                continue;
+            }
+        };
+
+        let values: Vec<&str> = values.collect();
+
+        // page entries come before the page's first region
+        let region = match regions.last_mut() {
+            Some(region) if region.page + 1 == pages.len() => region,
+            _ => {
+                if key == "pma" {
+                    pma = values[0] == "true";
+                }
+                continue;
+            }
+        };
+        let nums: Vec<u32> = values.iter().map(|v| v.parse().unwrap_or(0)).collect();
+        match (key, nums.as_slice()) {
+            ("bounds", [x, y, w, h]) => region.bounds = [*x, *y, *w, *h],
+            ("xy", [x, y]) => (region.bounds[0], region.bounds[1]) = (*x, *y),
+            ("size", [w, h]) => (region.bounds[2], region.bounds[3]) = (*w, *h),
+            ("offsets", [l, b, w, h]) => region.offsets = [*l, *b, *w, *h],
+            ("offset", [l, b]) => (region.offsets[0], region.offsets[1]) = (*l, *b),
+            ("orig", [w, h]) => (region.offsets[2], region.offsets[3]) = (*w, *h),
+            ("rotate", _) => region.rotated = values[0] == "true" || values[0] == "90",
+            _ => {}
+        }
+    }
+    (pages, regions)
+}
+
+// image of an atlas region, as it was before packing
+fn region_image(
+    (page_names, regions): &(Vec<String>, Vec<AtlasRegion>),
+    name: &str,
+    pages: &mut [Option<image::DynamicImage>],
+    load_page: &mut dyn FnMut(&str) -> Option<image::DynamicImage>,
+) -> Result<image::DynamicImage, SkfError> {
+    let region = regions.iter().find(|r| r.name == name);
+    if region.is_none() {
+        return Err(SkfError::MissingEntry(format!("Atlas region '{}'", name)));
+    }
+    let region = region.unwrap();
+    if pages[region.page].is_none() {
+        let page_name = &page_names[region.page];
+        pages[region.page] = load_page(page_name);
+        if pages[region.page].is_none() {
+            return Err(SkfError::MissingFile(page_name.clone()));
+        }
+    }
+    let page = pages[region.page].as_ref().unwrap();
+
+    // rotated regions are packed 90 degrees counter-clockwise
+    let [x, y, w, h] = region.bounds;
+    let mut image = match region.rotated {
+        true => page.crop_imm(x, y, h, w).rotate90(),
+        false => page.crop_imm(x, y, w, h),
+    };
+    if region.pma {
+        let mut rgba = image.to_rgba8();
+        utils::unpremultiply_alpha(&mut rgba);
+        image = rgba.into();
+    }
+
+    // add back the whitespace that was stripped when packing
+    let [left, bottom, orig_w, orig_h] = region.offsets;
+    if orig_w > w || orig_h > h {
+        let mut canvas = image::DynamicImage::new_rgba8(orig_w.max(w), orig_h.max(h));
+        let top = orig_h as i64 - bottom as i64 - h as i64;
+        image::imageops::overlay(&mut canvas, &image, left as i64, top);
+        image = canvas;
+    }
+    Ok(image)
+}
+
+fn tint_from_hex(hex: &str) -> TintColor {
+    let channel = |c: usize| hex_channel(hex, c);
+    TintColor::new(channel(0), channel(1), channel(2), channel(3))
+}
+
+// channel of an rrggbb(aa) color, from 0 to 1
+fn hex_channel(hex: &str, channel: usize) -> f32 {
+    let byte = hex.get(channel * 2..channel * 2 + 2);
+    byte.and_then(|b| u8::from_str_radix(b, 16).ok())
+        .map_or(1., |b| b as f32 / 255.)
+}
+
+fn hex_color(tint: &TintColor) -> String {
+    let to_u8 = |c: f32| (c.clamp(0., 1.) * 255.).round() as u8;
+    let [r, g, b, a] = [tint.r, tint.g, tint.b, tint.a].map(to_u8);
+    format!("{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
@@ -239,7 +239,7 @@ pub fn draw(
         selected_bone = armature.sel_bone(&sel).unwrap().clone();
 
         if edit_mode.anim_open && selections.anim != usize::MAX {
-            let frame = selections.anim_frame;
+            let frame = selections.anim_frame as f32;
             let animated_bones = armature.animate(selections.anim, frame, None);
             selected_bone = animated_bones[selections.bone_idx].clone();
         }
@@ -271,6 +271,8 @@ pub fn draw(
                     );
                 } else if shared_ui.last_selected == "keyframe" {
                     keyframe_panel::draw(ui, &selections, &armature, events, shared_ui, config);
+                } else if shared_ui.last_selected == "anim_event" {
+                    keyframe_panel::draw_anim_events(ui, selections, armature, events, shared_ui);
                 } else if armature.bones.len() > 1 {
                     ui.heading("Armature Shortcuts");
                     ui.add_space(10.);
@@ -955,10 +957,7 @@ fn context_menu_content(
             shared_ui.context_menu.close();
         }
     } else if id == "kfline" {
-        // copy option, if there are any keyframes in this frame
         let frame: i32 = split[1].parse().unwrap();
-        let anim = armature.sel_anim(&selections).unwrap();
-        let has_kf = anim.keyframes.iter().find(|kf| kf.frame == frame) != None;
 
         // paste option, if there are keyframes in copy buffer
         if copy_buffer.keyframes.len() > 0
@@ -968,8 +967,31 @@ fn context_menu_content(
             shared_ui.context_menu.close();
         }
 
-        // immediately close menu if there's nothing to show
-        if !has_kf && copy_buffer.keyframes.len() == 0 {
+        if !copy_buffer.anim_events.is_empty()
+            && ui.context_button("Paste Event(s)", config).clicked()
+        {
+            events.paste_anim_events_on_frame(frame);
+            shared_ui.context_menu.close();
+        }
+
+        if ui.context_button("Add Event", config).clicked() {
+            events.new_anim_event(frame);
+            shared_ui.context_menu.close();
+        }
+    } else if id == "animevent" {
+        let frame: i32 = split[1].parse().unwrap();
+        if ui.context_button("Copy Event(s)", config).clicked() {
+            events.copy_anim_events(frame);
+            shared_ui.context_menu.close();
+        }
+        if !copy_buffer.anim_events.is_empty()
+            && ui.context_button("Paste Event(s)", config).clicked()
+        {
+            events.paste_anim_events_on_frame(frame);
+            shared_ui.context_menu.close();
+        }
+        if ui.context_button("Delete Event(s)", config).clicked() {
+            events.delete_anim_events_by_frame(frame);
             shared_ui.context_menu.close();
         }
     } else if id == "armature" {
@@ -1096,6 +1118,10 @@ fn top_panel(
                         .add(egui::Button::selectable(false, count))
                         .on_hover_cursor(pointing_hand);
                     if header.clicked() {
+                        // repairs are only reported until the popup is dismissed
+                        if shared_ui.warnings_open {
+                            shared_ui.repairs = vec![];
+                        }
                         shared_ui.warnings_open = !shared_ui.warnings_open;
                     }
                     if !shared_ui.warnings_open {
@@ -1140,6 +1166,7 @@ fn top_panel(
                             .on_hover_text(shared_ui.loc("warnings.desc"));
                         modal_x(ui, [0., 0.].into(), || {
                             shared_ui.warnings_open = false;
+                            shared_ui.repairs = vec![];
                         });
                         ui.add_space(5.);
                         for w in 0..shared_ui.warnings.len() {
@@ -1644,7 +1671,9 @@ fn menu_edit_button(
             ui.close();
         }
 
-        let can_paste = copy_buffer.bones.len() > 0 || copy_buffer.keyframes.len() > 0;
+        let can_paste = !copy_buffer.bones.is_empty()
+            || !copy_buffer.keyframes.is_empty()
+            || !copy_buffer.anim_events.is_empty();
         let str_paste = &shared_ui.loc("top_bar.edit.paste");
         let key_paste = Some(&config.keys.paste);
         #[rustfmt::skip]
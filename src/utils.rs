@@ -16,12 +16,15 @@ use renderer::construction;
 pub use web::*;
 
 use image::{GenericImage, ImageEncoder};
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 use std::sync::Mutex;
 use std::{collections::HashMap, path::PathBuf};
+#[cfg(feature = "editor")]
 use zip::read::ZipFile;
 
-use std::io::{Read, Write};
+use std::io::Read;
+#[cfg(feature = "editor")]
+use std::io::Write;
 
 /// Convert a point from screen to world space.
 pub fn screen_to_world_space(pos: Vec2, window: Vec2) -> Vec2 {
@@ -87,20 +90,59 @@ pub fn to_vec2(f: f32) -> Vec2 {
     Vec2::new(f, f)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn open_save_dialog(
     file_path: &Arc<Mutex<Vec<PathBuf>>>,
     saving: &Arc<Mutex<Saving>>,
     save_result: Saving,
 ) {
-    let filepath = Arc::clone(&file_path);
-    let csaving = Arc::clone(&saving);
     let (ext, name) = match save_result {
         Saving::Exporting => ("skfe", "SkelForm Armature"),
         Saving::Spritesheet => ("zip", "Sprites/Spritesheet"),
         Saving::Video => ("", "Video"),
         _ => ("skf", "SkelForm Armature"),
     };
+    save_dialog(file_path, saving, save_result, ext, name);
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
+pub fn open_export_dialog(
+    file_path: &Arc<Mutex<Vec<PathBuf>>>,
+    saving: &Arc<Mutex<Saving>>,
+    format: &ExportArmatureFormat,
+) {
+    match format {
+        ExportArmatureFormat::Skf => open_save_dialog(file_path, saving, Saving::Exporting),
+        ExportArmatureFormat::Spine => save_dialog(
+            file_path,
+            saving,
+            Saving::Exporting,
+            "zip",
+            "Spine Skeleton",
+        ),
+        ExportArmatureFormat::DragonBones => save_dialog(
+            file_path,
+            saving,
+            Saving::Exporting,
+            "zip",
+            "DragonBones Skeleton",
+        ),
+        ExportArmatureFormat::Godot => {
+            save_dialog(file_path, saving, Saving::Exporting, "zip", "Godot Scene")
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
+fn save_dialog(
+    file_path: &Arc<Mutex<Vec<PathBuf>>>,
+    saving: &Arc<Mutex<Saving>>,
+    save_result: Saving,
+    ext: &'static str,
+    name: &'static str,
+) {
+    let filepath = Arc::clone(file_path);
+    let csaving = Arc::clone(saving);
     std::thread::spawn(move || {
         let task = rfd::FileDialog::new().add_filter(name, &[ext]).save_file();
         if task == None {
@@ -111,7 +153,7 @@ pub fn open_save_dialog(
     });
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn open_style_dialog(file_path: &Arc<Mutex<PathBuf>>) {
     let filepath = Arc::clone(&file_path);
     std::thread::spawn(move || {
@@ -125,7 +167,7 @@ pub fn open_style_dialog(file_path: &Arc<Mutex<PathBuf>>) {
     });
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn open_import_dialog(file_path: &Arc<Mutex<Vec<PathBuf>>>, file_type: &Arc<Mutex<i32>>) {
     let filepath = Arc::clone(&file_path);
     let filetype = Arc::clone(&file_type);
@@ -134,6 +176,9 @@ pub fn open_import_dialog(file_path: &Arc<Mutex<Vec<PathBuf>>>, file_type: &Arc<
             .add_filter("SkelForm file", &["skf", "skfe"])
             .add_filter("Shockwave Flash file", &["swf"])
             .add_filter("Photoshop Document", &["psd"])
+            .add_filter("Aseprite file", &["aseprite", "ase"])
+            .add_filter("OpenRaster or Krita image", &["ora", "kra"])
+            .add_filter("Spine or DragonBones skeleton", &["json", "zip"])
             .pick_file();
         if task == None {
             return;
@@ -143,59 +188,134 @@ pub fn open_import_dialog(file_path: &Arc<Mutex<Vec<PathBuf>>>, file_type: &Arc<
     });
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn save_web(armature: &Armature, camera: &Camera, edit_mode: &EditMode, save_result: Saving) {
+/// Write an .skf file (armature, editor data, atlases, etc) with the export options in `edit_mode`.
+#[cfg(feature = "editor")]
+pub fn write_skf<W: Write + std::io::Seek>(
+    writer: W,
+    armature: &Armature,
+    camera: &Camera,
+    edit_mode: &EditMode,
+    thumbnail: Option<&[u8]>,
+) -> zip::result::ZipResult<W> {
     let mut png_bufs = vec![];
     let mut sizes = vec![];
     let mut carmature = armature.clone();
 
     if carmature.styles.len() > 0 && carmature.styles[0].textures.len() > 0 {
-        (png_bufs, sizes) = utils::create_tex_sheet(&mut carmature, edit_mode);
+        (png_bufs, sizes) = create_tex_sheet(&mut carmature, edit_mode);
     }
 
     let (armatures_json, editor_json) =
         prepare_files(&carmature, camera.clone(), sizes.clone(), edit_mode);
 
-    // create zip file
-    let mut buf: Vec<u8> = Vec::new();
-    let cursor = std::io::Cursor::new(&mut buf);
-    let mut zip = zip::ZipWriter::new(cursor);
+    let mut zip = zip::ZipWriter::new(writer);
 
     let options =
         zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
 
     // save armature json and texture image
-    zip.start_file("armature.json", options).unwrap();
-    zip.write(armatures_json.as_bytes()).unwrap();
-    zip.start_file("editor.json", options).unwrap();
-    zip.write(editor_json.as_bytes()).unwrap();
-    zip.start_file("readme.md", options.clone()).unwrap();
-    zip.write(include_bytes!("../assets/skf_readme.md"))
-        .unwrap();
-    let atlas_ext = match edit_mode.export_img_format {
-        ExportImgFormat::PNG => ".png",
-        ExportImgFormat::JPG => ".jpg",
-    };
+    if edit_mode.export_binary {
+        let value = serde_json::from_str(&armatures_json).unwrap();
+        zip.start_file("armature.bin", options)?;
+        zip.write_all(&binary::encode(&value))?;
+    } else {
+        zip.start_file("armature.json", options)?;
+        zip.write_all(armatures_json.as_bytes())?;
+    }
+    zip.start_file("editor.json", options)?;
+    zip.write_all(editor_json.as_bytes())?;
+    let schema = serde_json::to_string_pretty(&armature_schema()).unwrap();
+    zip.start_file("armature.schema.json", options)?;
+    zip.write_all(schema.as_bytes())?;
+    if let Some(thumbnail) = thumbnail {
+        zip.start_file("thumbnail.png", options)?;
+        zip.write_all(thumbnail)?;
+    }
+    zip.start_file("readme.md", options)?;
+    zip.write_all(include_bytes!("../assets/skf_readme.md"))?;
+    let atlas_ext = edit_mode.export_img_format.extension();
     for i in 0..png_bufs.len() {
-        let name = format!("atlas{}{}", i.to_string(), atlas_ext);
-        zip.start_file(name, options.clone()).unwrap();
-        zip.write(&png_bufs[i]).unwrap();
+        let name = format!("atlas{}.{}", i, atlas_ext);
+        zip.start_file(name, options)?;
+        zip.write_all(&png_bufs[i])?;
     }
 
-    let bytes = zip.finish().unwrap().into_inner().to_vec();
+    zip.finish()
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "editor"))]
+pub fn save_web(armature: &Armature, camera: &Camera, edit_mode: &EditMode, save_result: Saving) {
+    let cursor = std::io::Cursor::new(vec![]);
+    let format = match save_result {
+        Saving::Exporting => edit_mode.export_format.clone(),
+        _ => ExportArmatureFormat::Skf,
+    };
+    let zip = match format {
+        ExportArmatureFormat::Spine => spine::write_spine(cursor, armature, edit_mode),
+        ExportArmatureFormat::DragonBones => {
+            dragonbones::write_dragonbones(cursor, armature, edit_mode)
+        }
+        ExportArmatureFormat::Godot => godot::write_godot(cursor, armature, edit_mode),
+        ExportArmatureFormat::Skf => write_skf(cursor, armature, camera, edit_mode, None),
+    };
+    let bytes = zip.unwrap().into_inner();
     downloadZip(bytes, save_result.to_string());
 }
 
+#[cfg(feature = "editor")]
 // process spritesheet buffers, to be used later
 pub fn render_spritesheets(
     armature: &Armature,
     shared_ui: &mut shared::Ui,
     camera: &Camera,
     config: &Config,
-    backend: &BackendRenderer,
+    backend: Option<&BackendRenderer>,
     renderer: &Renderer,
 ) {
     shared_ui.rendered_spritesheets = vec![];
+    shared_ui.rendered_pivots = vec![];
+
+    for (a, all_frames, cam) in export_cameras(armature, shared_ui, camera, config) {
+        shared_ui.rendered_spritesheets.push(vec![]);
+        let mut new_arm = armature.clone();
+
+        // where the armature's origin ends up in each frame
+        let origin = (Vec2::new(0., 0.) - cam.pos) / cam.zoom;
+        let pivot = Vec2::new((origin.x + 1.) / 2., (1. - origin.y) / 2.);
+        shared_ui.rendered_pivots.push(pivot);
+
+        // take screenshots of each frame
+        for f in &all_frames {
+            pose_export_frame(&mut new_arm, armature, a, *f);
+
+            let frames = shared_ui.rendered_spritesheets.last_mut().unwrap();
+            let clear = &shared_ui.video_clear_bg;
+            let mapped_frames = &mut shared_ui.mapped_frames;
+            let size = shared_ui.sprite_size;
+            if let Some(backend) = backend {
+                #[rustfmt::skip]
+                backend.take_screenshot(size, &new_arm, &cam, clear, frames, mapped_frames, renderer);
+                continue;
+            }
+
+            // no GPU, so draw on the CPU instead
+            let image = raster::render(&new_arm, &cam, clear, size);
+            #[rustfmt::skip]
+            frames.push(RenderedFrame { buffer: None, width: image.width(), height: image.height(), image: Some(image) });
+            *mapped_frames.lock().unwrap() += 1;
+        }
+    }
+}
+
+/// Animations to export, with the frames to sample from each and a camera that fits them.
+#[cfg(feature = "editor")]
+pub fn export_cameras(
+    armature: &Armature,
+    shared_ui: &shared::Ui,
+    camera: &Camera,
+    config: &Config,
+) -> Vec<(usize, Vec<f32>, Camera)> {
+    let mut cameras = vec![];
 
     // get the biggest animation's boundaries, if global_bounds is true
     let mut left_top = Vec2::new(f32::MAX, -f32::MAX);
@@ -203,15 +323,10 @@ pub fn render_spritesheets(
     if shared_ui.export_global_bounds {
         for a in 0..armature.animations.len() {
             let anim = &armature.animations[a];
-            let last_frame = if let Some(kf) = anim.keyframes.last() {
-                kf.frame + 1
-            } else {
-                1
-            };
-            let all_frames = last_frame * shared_ui.anim_cycles;
+            let all_frames = export_frames(anim, shared_ui.export_fps, shared_ui.anim_cycles);
             let mut new_arm = armature.clone();
-            for f in 0..all_frames {
-                new_arm.bones = new_arm.animate(a, f % last_frame, Some(&armature.bones));
+            for f in &all_frames {
+                new_arm.bones = new_arm.animate(a, *f, Some(&armature.bones));
                 let (lt, br) = renderer::get_sprite_boundary(&new_arm, camera, config);
                 left_top = Vec2::new(left_top.x.min(lt.x), left_top.y.max(lt.y));
                 right_bot = Vec2::new(right_bot.x.max(br.x), right_bot.y.min(br.y));
@@ -219,27 +334,20 @@ pub fn render_spritesheets(
         }
     }
 
-    let mut spritesheet_idx = 0;
     for a in 0..armature.animations.len() {
         if !shared_ui.exporting_anims[a] {
             continue;
         }
-        shared_ui.rendered_spritesheets.push(vec![]);
         let anim = &armature.animations[a];
-        let last_frame = if let Some(kf) = anim.keyframes.last() {
-            kf.frame + 1
-        } else {
-            1
-        };
-        let all_frames = last_frame * shared_ui.anim_cycles;
+        let all_frames = export_frames(anim, shared_ui.export_fps, shared_ui.anim_cycles);
         let mut new_arm = armature.clone();
 
         // get maximum boundary of this animation alone, if global_bounds is false
         if !shared_ui.export_global_bounds {
             left_top = Vec2::new(f32::MAX, -f32::MAX);
             right_bot = Vec2::new(-f32::MAX, f32::MAX);
-            for f in 0..all_frames {
-                new_arm.bones = new_arm.animate(a, f % last_frame, Some(&armature.bones));
+            for f in &all_frames {
+                new_arm.bones = new_arm.animate(a, *f, Some(&armature.bones));
                 let (lt, br) = renderer::get_sprite_boundary(&new_arm, camera, config);
                 left_top = Vec2::new(left_top.x.min(lt.x), left_top.y.max(lt.y));
                 right_bot = Vec2::new(right_bot.x.max(br.x), right_bot.y.min(br.y));
@@ -254,40 +362,141 @@ pub fn render_spritesheets(
             .max(left_top.y - cam.pos.y)
             .max(left_top.x.abs() - cam.pos.x);
 
-        // take screenshots of each frame
-        for f in 0..all_frames {
-            new_arm.bones = new_arm.animate(a, f % last_frame, Some(&armature.bones));
-            new_arm.animated_bones = new_arm.bones.clone();
-
-            // initialize non-mesh bone verts and indices
-            for b in 0..new_arm.bones.len() {
-                let tex = new_arm.anim_tex_of(new_arm.bones[b].id);
-                if tex != None && !new_arm.bones[b].verts_edited {
-                    let size = tex.unwrap().size;
-                    let bone = &mut new_arm.bones[b];
-                    (bone.vertices, bone.indices) = renderer::create_tex_rect(&size);
-                    let bone = &mut new_arm.animated_bones[b];
-                    (bone.vertices, bone.indices) = renderer::create_tex_rect(&size);
-                    bone.verts_edited = false;
-                }
-            }
+        cameras.push((a, all_frames, cam));
+    }
 
-            let frames = &mut shared_ui.rendered_spritesheets[spritesheet_idx];
-            let clear = &shared_ui.video_clear_bg;
-            let mapped_frames = &mut shared_ui.mapped_frames;
-            let size = shared_ui.sprite_size;
-            backend.take_screenshot(size, &new_arm, &cam, clear, frames, mapped_frames, renderer);
+    cameras
+}
+
+/// Pose `new_arm` (a clone of `armature`) at a frame of an animation, ready to be rendered.
+#[cfg(feature = "editor")]
+pub fn pose_export_frame(new_arm: &mut Armature, armature: &Armature, anim: usize, frame: f32) {
+    new_arm.bones = new_arm.animate(anim, frame, Some(&armature.bones));
+    new_arm.animated_bones = new_arm.bones.clone();
+
+    // initialize non-mesh bone verts and indices
+    for b in 0..new_arm.bones.len() {
+        let tex_size = new_arm.anim_tex_of(new_arm.bones[b].id).map(|tex| tex.size);
+        if let Some(size) = tex_size.filter(|_| !new_arm.bones[b].verts_edited) {
+            let bone = &mut new_arm.bones[b];
+            (bone.vertices, bone.indices) = renderer::create_tex_rect(&size);
+            let bone = &mut new_arm.animated_bones[b];
+            (bone.vertices, bone.indices) = renderer::create_tex_rect(&size);
+            bone.verts_edited = false;
         }
+    }
+}
 
-        spritesheet_idx += 1;
+/// ffmpeg to encode videos with: the downloaded binary next to SkelForm's, or the system's.
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
+pub fn ffmpeg_bin(shared_ui: &shared::Ui) -> String {
+    if shared_ui.use_system_ffmpeg {
+        return "ffmpeg".to_string();
+    }
+    #[cfg(target_os = "windows")]
+    let bin = "ffmpeg.exe";
+    #[cfg(not(target_os = "windows"))]
+    let bin = "ffmpeg";
+    bin_path().join(bin).to_str().unwrap().to_string()
+}
+
+/// ffmpeg arguments to encode raw RGBA frames from stdin into a video at `out`.
+#[cfg(feature = "editor")]
+pub fn ffmpeg_args(
+    video_type: &ExportVideoType,
+    encoder: &ExportVideoEncoder,
+    size: Vec2,
+    fps: i32,
+    out: &str,
+) -> Vec<String> {
+    let (size, fps) = (format!("{}x{}", size.x, size.y), fps.max(1).to_string());
+    #[rustfmt::skip]
+    let mut args = vec!["-y", "-f", "rawvideo", "-pixel_format", "rgba", "-video_size", &size, "-framerate", &fps, "-i", "pipe:0"];
+
+    #[rustfmt::skip]
+    args.extend(match video_type {
+        ExportVideoType::Webm => vec!["-c:v", "libvpx-vp9", "-pix_fmt", "yuva420p", "-auto-alt-ref", "0"],
+        ExportVideoType::ProRes => vec!["-c:v", "prores_ks", "-profile:v", "4444", "-pix_fmt", "yuva444p10le"],
+        ExportVideoType::PngMov => vec!["-c:v", "png", "-pix_fmt", "rgba"],
+        // 4:2:0 without alpha needs even dimensions
+        _ => vec!["-c:v", encoder.codec(), "-pix_fmt", "yuv420p", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"],
+    });
+
+    args.push(out);
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// Render the exported animations into ffmpeg's stdin one frame at a time, as videos in `dir`.
+///
+/// Unlike `render_spritesheets`, no frames are kept around, so memory use doesn't grow with
+/// the animations' length.
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
+pub fn stream_videos(
+    armature: &Armature,
+    shared_ui: &shared::Ui,
+    camera: &Camera,
+    config: &Config,
+    backend: Option<&BackendRenderer>,
+    renderer: &Renderer,
+    dir: &std::path::Path,
+) -> Result<(), String> {
+    let video_type = &shared_ui.exporting_video_type;
+    let encoder = &shared_ui.exporting_video_encoder;
+    let (size, clear) = (shared_ui.sprite_size, &shared_ui.video_clear_bg);
+
+    for (a, all_frames, cam) in export_cameras(armature, shared_ui, camera, config) {
+        let anim = &armature.animations[a];
+        let fps = if shared_ui.export_fps > 0 {
+            shared_ui.export_fps
+        } else {
+            anim.fps
+        };
+        let out = dir.join(anim.name.clone() + "." + video_type.extension());
+        let args = ffmpeg_args(video_type, encoder, size, fps, out.to_str().unwrap());
+
+        let mut child = Command::new(ffmpeg_bin(shared_ui))
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("spawn ffmpeg: {}", e))?;
+
+        let mut stdin = child.stdin.take().unwrap();
+        let mut new_arm = armature.clone();
+        let mut written = Ok(());
+        for f in &all_frames {
+            pose_export_frame(&mut new_arm, armature, a, *f);
+            let image = match backend {
+                Some(backend) => backend.render_export_frame(size, &new_arm, &cam, clear, renderer),
+                None => raster::render(&new_arm, &cam, clear, size),
+            };
+            written = stdin.write_all(image.as_raw());
+            if written.is_err() {
+                break;
+            }
+        }
+
+        // closing stdin lets ffmpeg finish the file
+        drop(stdin);
+        let status = child.wait().map_err(|e| format!("ffmpeg: {}", e))?;
+        written.map_err(|e| format!("stdin: {}", e))?;
+        if !status.success() {
+            return Err(format!(
+                "ffmpeg couldn't encode {} ({})",
+                out.display(),
+                status
+            ));
+        }
     }
+
+    Ok(())
 }
 
+#[cfg(feature = "editor")]
 // take the buffered spritesheets and encode them to be saveable as images
 pub fn encode_spritesheets(
     armature: &Armature,
     shared_ui: &mut shared::Ui,
-    backend: &BackendRenderer,
+    backend: Option<&BackendRenderer>,
 ) -> Vec<Vec<u8>> {
     let mut bufs = vec![];
 
@@ -322,14 +531,7 @@ pub fn encode_spritesheets(
                 height += shared_ui.sprite_size.y as u32;
                 column = 0;
             }
-            let size = shared_ui.sprite_size;
-            let img = utils::process_screenshot(
-                &sprite.buffer,
-                &backend.gpu.device,
-                backend.gpu.surface_format,
-                size,
-            );
-            let new_img = image::load_from_memory(&img).unwrap();
+            let new_img = rendered_frame_image(sprite, backend, shared_ui.sprite_size);
             let width = column * shared_ui.sprite_size.x as u32;
             sheet.copy_from(&new_img, width, height).unwrap();
             column += 1;
@@ -350,10 +552,156 @@ pub fn encode_spritesheets(
     bufs
 }
 
+#[cfg(feature = "editor")]
+// trim, dedupe and pack the buffered spritesheets, and describe them in a TexturePacker-style JSON
+pub fn encode_packed_spritesheets(
+    armature: &Armature,
+    shared_ui: &mut shared::Ui,
+    backend: Option<&BackendRenderer>,
+) -> Vec<(Vec<u8>, String)> {
+    let mut bufs = vec![];
+    let size = shared_ui.sprite_size;
+
+    let mut idx = 0;
+    for a in 0..armature.animations.len() {
+        if !shared_ui.exporting_anims[a] {
+            continue;
+        }
+        let anim = &armature.animations[a];
+
+        // trim frames, and keep only the first of identical ones
+        let mut unique: Vec<RgbaImage> = vec![];
+        let mut seen: HashMap<(u32, u32, Vec<u8>), usize> = HashMap::new();
+        let mut frames: Vec<(usize, Vec2)> = vec![];
+        for sprite in &shared_ui.rendered_spritesheets[idx] {
+            let img = rendered_frame_image(sprite, backend, size);
+            let (trimmed, offset) = trim_frame(&img);
+            let key = (trimmed.width(), trimmed.height(), trimmed.as_raw().clone());
+            let u = *seen.entry(key).or_insert_with(|| {
+                unique.push(trimmed);
+                unique.len() - 1
+            });
+            frames.push((u, offset));
+        }
+
+        // pack unique frames, with 1px of padding to prevent bleeding
+        let boxes: Vec<PackingBox> = unique
+            .iter()
+            .map(|img| PackingBox::new(img.width() as i32 + 1, img.height() as i32 + 1))
+            .collect();
+        let mut sheet_size = 0;
+        let mut placed = vec![];
+        while placed.len() != boxes.len() || sheet_size == 0 {
+            sheet_size += 128;
+            let bins = vec![max_rects::bucket::Bucket::new(
+                sheet_size - 1,
+                sheet_size - 1,
+                0,
+                0,
+                1,
+            )];
+            (placed, _, _) = max_rects::max_rects::MaxRects::new(boxes.clone(), bins).place();
+        }
+
+        // copy frames into sheet, in the spots they were placed in
+        let mut sheet = RgbaImage::new(sheet_size as u32, sheet_size as u32);
+        let mut rects: Vec<(u32, u32)> = vec![];
+        for img in &unique {
+            let p = placed
+                .iter()
+                .position(|pl| {
+                    pl.width == img.width() as i32 + 1 && pl.height == img.height() as i32 + 1
+                })
+                .unwrap();
+            let (left, _, top, _) = placed[p].get_coords();
+            // ensure another frame of the same size won't overwrite this one
+            placed.remove(p);
+            sheet.copy_from(img, left as u32, top as u32).unwrap();
+            rects.push((left as u32, top as u32));
+        }
+
+        let fps = if shared_ui.export_fps > 0 {
+            shared_ui.export_fps
+        } else {
+            anim.fps.max(1)
+        };
+        let pivot = shared_ui.rendered_pivots[idx];
+        let png_name = bufs.len().to_string() + ".png";
+
+        let mut json_frames = serde_json::Map::new();
+        let mut names = vec![];
+        for (f, (u, offset)) in frames.iter().enumerate() {
+            let name = anim.name.clone() + "/" + &f.to_string();
+            let (x, y) = rects[*u];
+            let (w, h) = (unique[*u].width(), unique[*u].height());
+            let frame = serde_json::json!({
+                "frame": { "x": x, "y": y, "w": w, "h": h },
+                "rotated": false,
+                "trimmed": w != size.x as u32 || h != size.y as u32,
+                "spriteSourceSize": { "x": offset.x as u32, "y": offset.y as u32, "w": w, "h": h },
+                "sourceSize": { "w": size.x as u32, "h": size.y as u32 },
+                "pivot": { "x": pivot.x, "y": pivot.y },
+                "duration": 1000 / fps,
+            });
+            json_frames.insert(name.clone(), frame);
+            names.push(name);
+        }
+        let json = serde_json::json!({
+            "frames": json_frames,
+            "animations": { anim.name.clone(): names },
+            "meta": {
+                "app": "SkelForm",
+                "version": env!("CARGO_PKG_VERSION"),
+                "image": png_name,
+                "format": "RGBA8888",
+                "size": { "w": sheet_size, "h": sheet_size },
+                "scale": "1",
+            },
+        });
+
+        let mut png_buf = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut png_buf);
+        let rgba8 = image::ExtendedColorType::Rgba8;
+        encoder
+            .write_image(sheet.as_raw(), sheet.width(), sheet.height(), rgba8)
+            .unwrap();
+
+        bufs.push((png_buf, serde_json::to_string_pretty(&json).unwrap()));
+        idx += 1;
+    }
+
+    bufs
+}
+
+/// Crop a frame to its visible pixels (like `file_reader::trim_transparent`, but keeping any
+/// alpha), returning it with its offset in the original frame.
+fn trim_frame(img: &RgbaImage) -> (RgbaImage, Vec2) {
+    let (width, height) = img.dimensions();
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if pixel[3] > 0 {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+
+    // fully transparent frames are kept as a single pixel
+    if min_x > max_x {
+        return (RgbaImage::new(1, 1), Vec2::new(0., 0.));
+    }
+
+    let (w, h) = (max_x - min_x + 1, max_y - min_y + 1);
+    let cropped = image::imageops::crop_imm(img, min_x, min_y, w, h).to_image();
+    (cropped, Vec2::new(min_x as f32, min_y as f32))
+}
+
+#[cfg(feature = "editor")]
 pub fn encode_sequence(
     armature: &Armature,
     shared_ui: &mut shared::Ui,
-    backend: &BackendRenderer,
+    backend: Option<&BackendRenderer>,
 ) -> Vec<Vec<Vec<u8>>> {
     let mut bufs = vec![];
 
@@ -366,21 +714,14 @@ pub fn encode_sequence(
         bufs.push(vec![]);
 
         for (_, sprite) in shared_ui.rendered_spritesheets[idx].iter().enumerate() {
-            let img = utils::process_screenshot(
-                &sprite.buffer,
-                &backend.gpu.device,
-                backend.gpu.surface_format,
-                shared_ui.sprite_size,
-            );
-            let new_img = image::load_from_memory(&img).unwrap();
+            let new_img = rendered_frame_image(sprite, backend, shared_ui.sprite_size);
 
             let mut png_buf = Vec::new();
             let encoder = image::codecs::png::PngEncoder::new(&mut png_buf);
 
             let rgba8 = image::ExtendedColorType::Rgba8;
-            let img_rgba = new_img.as_rgba8().unwrap();
             encoder
-                .write_image(img_rgba, new_img.width(), new_img.height(), rgba8)
+                .write_image(new_img.as_raw(), new_img.width(), new_img.height(), rgba8)
                 .unwrap();
 
             bufs.last_mut().unwrap().push(png_buf);
@@ -392,7 +733,118 @@ pub fn encode_sequence(
     bufs
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+/// Exported animations as animated images of the chosen type, named after them.
+///
+/// Each plays at its own (or the export's) fps, as many times as set in `exporting_loops`.
+#[cfg(feature = "editor")]
+pub fn encode_animated(
+    armature: &Armature,
+    shared_ui: &shared::Ui,
+    backend: Option<&BackendRenderer>,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let video_type = &shared_ui.exporting_video_type;
+    let mut files = vec![];
+
+    let mut idx = 0;
+    for a in 0..armature.animations.len() {
+        if !shared_ui.exporting_anims[a] {
+            continue;
+        }
+
+        let anim = &armature.animations[a];
+        let fps = if shared_ui.export_fps > 0 {
+            shared_ui.export_fps
+        } else {
+            anim.fps
+        };
+        let plays = shared_ui.exporting_loops.get(a).copied().unwrap_or(0);
+        let frames: Vec<RgbaImage> = shared_ui.rendered_spritesheets[idx]
+            .iter()
+            .map(|frame| rendered_frame_image(frame, backend, shared_ui.sprite_size))
+            .collect();
+
+        let data = match video_type {
+            ExportVideoType::Gif => animated::encode_gif(&frames, fps, plays),
+            ExportVideoType::Apng => animated::encode_apng(&frames, fps, plays),
+            ExportVideoType::Webp => animated::encode_webp(&frames, fps, plays),
+            _ => return Err(format!("{} isn't an animated image", video_type)),
+        };
+        let name = anim.name.clone() + "." + video_type.extension();
+        let data = data.map_err(|e| format!("{}: {}", name, e))?;
+        files.push((name, data));
+
+        idx += 1;
+    }
+
+    Ok(files)
+}
+
+#[cfg(feature = "editor")]
+// sprite-packing stuff that applies to native, web and the command line
+pub fn pack_sprites<W: Write + std::io::Seek>(
+    armature: &Armature,
+    shared_ui: &mut shared::Ui,
+    backend: Option<&BackendRenderer>,
+    zip: &mut zip::ZipWriter<W>,
+    options: &zip::write::FullFileOptions,
+) -> zip::result::ZipResult<()> {
+    if shared_ui.image_sequences {
+        let bufs = encode_sequence(armature, shared_ui, backend);
+
+        let mut buf_idx = 0;
+        for a in 0..shared_ui.exporting_anims.len() {
+            if !shared_ui.exporting_anims[a] {
+                continue;
+            }
+            zip.add_directory(armature.animations[a].name.clone(), options.clone())?;
+            for (b, buf) in bufs[buf_idx].iter().enumerate() {
+                let png_name =
+                    armature.animations[a].name.to_string() + "/" + &b.to_string() + ".png";
+                zip.start_file(png_name, options.clone())?;
+                zip.write_all(buf)?;
+            }
+            buf_idx += 1;
+        }
+    } else if shared_ui.pack_spritesheets {
+        let bufs = encode_packed_spritesheets(armature, shared_ui, backend);
+        for (b, (png, json)) in bufs.iter().enumerate() {
+            zip.start_file(b.to_string() + ".png", options.clone())?;
+            zip.write_all(png)?;
+            zip.start_file(b.to_string() + ".json", options.clone())?;
+            zip.write_all(json.as_bytes())?;
+        }
+    } else {
+        let bufs = encode_spritesheets(armature, shared_ui, backend);
+        for (b, buf) in bufs.iter().enumerate() {
+            let png_name = b.to_string() + ".png";
+            zip.start_file(png_name, options.clone())?;
+            zip.write_all(buf)?;
+        }
+    }
+    Ok(())
+}
+
+/// Pixels of a rendered frame, whether it was drawn by the GPU or the CPU rasterizer.
+#[cfg(feature = "editor")]
+pub fn rendered_frame_image(
+    frame: &RenderedFrame,
+    backend: Option<&BackendRenderer>,
+    size: Vec2,
+) -> RgbaImage {
+    if let Some(image) = &frame.image {
+        return image.clone();
+    }
+    let (buffer, backend) = (frame.buffer.as_ref().unwrap(), backend.unwrap());
+    let rgba = process_screenshot_raw(
+        buffer,
+        &backend.gpu.device,
+        backend.gpu.surface_format,
+        size,
+    );
+    RgbaImage::from_raw(size.x as u32, size.y as u32, rgba).unwrap()
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn save_native(shared_ui: &mut shared::Ui) {
     let mut open_dialog = true;
     if shared_ui.save_path != None {
@@ -410,112 +862,158 @@ pub fn save_native(shared_ui: &mut shared::Ui) {
     }
 }
 
-pub fn create_tex_sheet(armature: &mut Armature, edit_mode: &EditMode) -> (Vec<Vec<u8>>, Vec<i32>) {
-    let mut atlases: Vec<Vec<PackingBox>> = vec![];
-    let mut sizes: Vec<i32> = vec![];
-    let mut boxes = vec![];
-    let max = 2048;
-
-    atlases.push(vec![]);
-    sizes.push(0);
+/// Pack every style's textures into atlases with `edit_mode.export_atlas`, returning the encoded
+/// atlases and their sizes.
+///
+/// Each texture's place in its atlas (and its trim and turn, if enabled) is saved into `armature`.
+/// A style's textures always share an atlas.
+pub fn create_tex_sheet(
+    armature: &mut Armature,
+    edit_mode: &EditMode,
+) -> (Vec<Vec<u8>>, Vec<Vec2I>) {
+    let mut settings = edit_mode.export_atlas.clone();
+    // only SkelForm's own format has a place for trims and turns
+    if edit_mode.export_format != ExportArmatureFormat::Skf {
+        settings.trim = false;
+        settings.rotate = false;
+    }
+    settings.max_size = Vec2I::new(settings.max_size.x.max(1), settings.max_size.y.max(1));
+    settings.extrude = settings.extrude.max(0);
     let pad = edit_mode.export_tex_padding;
+    let ext = settings.extrude;
+    let margin = Vec2I::new(pad.x as i32 + ext * 2, pad.y as i32 + ext * 2);
 
+    // images as they'll be in the atlas
+    let mut images: Vec<Vec<RgbaImage>> = vec![];
     for s in 0..armature.styles.len() {
-        let mut style_boxes = vec![];
-
-        // add textures to max_rect's bin
+        images.push(vec![]);
         for t in 0..armature.styles[s].textures.len() {
-            let tex = &armature.styles[s].textures[t];
-            let image = &armature.tex_data(tex).unwrap().image;
-
-            // add padding
-            let size = Vec2::new(image.width() as f32 + pad.x, image.height() as f32 + pad.y);
-
-            boxes.push(PackingBox::new(size.x as i32, size.y as i32));
-            style_boxes.push(PackingBox::new(size.x as i32, size.y as i32));
-        }
-
-        'atlas_maker: loop {
-            let first_style_in_atlas = *sizes.last().unwrap() == 0;
-            let og_size = sizes.last().unwrap().clone();
-            let new_atlas;
-            loop {
-                // if this is the first style in the atlas, ignore max limit
-                if *sizes.last().unwrap() >= max && !first_style_in_atlas {
-                    new_atlas = true;
-                    break;
-                }
-                *sizes.last_mut().unwrap() += 128;
-                let size = *sizes.last().unwrap();
-                let bins = vec![max_rects::bucket::Bucket::new(size - 1, size - 1, 0, 0, 1)];
-                let mut problem = max_rects::max_rects::MaxRects::new(boxes.clone(), bins.clone());
-                let (placed, _, _) = problem.place();
-                if placed.len() == boxes.len() {
-                    for tex in &mut armature.styles[s].textures {
-                        tex.atlas_idx = atlases.len() as i32 - 1;
-                    }
-                    *atlases.last_mut().unwrap() = placed;
-                    break 'atlas_maker;
+            let mut img = armature
+                .tex_data(&armature.styles[s].textures[t])
+                .unwrap()
+                .image
+                .to_rgba8();
+            let tex = &mut armature.styles[s].textures[t];
+            tex.trim_offset = Vec2I::new(0, 0);
+            tex.source_size = Vec2I::new(0, 0);
+            tex.rotated = false;
+
+            if settings.trim {
+                let (trimmed, offset) = trim_frame(&img);
+                if trimmed.dimensions() != img.dimensions() {
+                    tex.trim_offset = Vec2I::new(offset.x as i32, offset.y as i32);
+                    tex.source_size = Vec2I::new(img.width() as i32, img.height() as i32);
+                    img = trimmed;
                 }
             }
+            tex.size = Vec2::new(img.width() as f32, img.height() as f32);
 
-            // create new atlas if current is beyond max limit
-            if *sizes.last().unwrap() >= max {
-                if new_atlas {
-                    *sizes.last_mut().unwrap() = og_size;
-                }
-                atlases.push(vec![]);
-                sizes.push(0);
-
-                // since this is a new atlas, keep only this style's textures
-                boxes = style_boxes.clone();
+            // lay tall textures on their side, so they stack with wide ones
+            if settings.rotate && img.height() > img.width() {
+                img = image::imageops::rotate90(&img);
+                tex.rotated = true;
             }
+
+            images[s].push(img);
         }
     }
 
-    let mut bufs = vec![];
-    for i in 0..atlases.len() {
-        let mut raw_buf =
-            <image::ImageBuffer<image::Rgba<u8>, _>>::new(sizes[i] as u32, sizes[i] as u32);
-
-        for s in 0..armature.styles.len() {
-            for t in 0..armature.styles[s].textures.len() {
-                let tex = &armature.styles[s].textures[t];
+    let mut atlases: Vec<PackedAtlas> = vec![];
+    let mut texes: Vec<(usize, usize)> = vec![];
+    let mut boxes: Vec<PackingBox> = vec![];
+    let mut placed: Vec<PackingBox> = vec![];
 
-                // skip texture if this not the right atlas
-                if tex.atlas_idx != i as i32 {
-                    continue;
-                }
+    for (s, style_images) in images.iter().enumerate() {
+        if style_images.is_empty() {
+            continue;
+        }
+        let style_texes: Vec<(usize, usize)> = (0..style_images.len()).map(|t| (s, t)).collect();
+        let style_boxes: Vec<PackingBox> = style_images
+            .iter()
+            .map(|img| {
+                PackingBox::new(
+                    img.width() as i32 + margin.x,
+                    img.height() as i32 + margin.y,
+                )
+            })
+            .collect();
 
-                // find where this texture was assigned in the atlas
-                let p = atlases[i].iter().position(|pl| {
-                    pl.width == tex.size.x as i32 + pad.x as i32
-                        && pl.height == tex.size.y as i32 + pad.y as i32
+        let mut with_style = boxes.clone();
+        with_style.extend(style_boxes.clone());
+        if let Some(fit) = pack_atlas(&with_style, &settings) {
+            boxes = with_style;
+            placed = fit;
+            texes.extend(style_texes);
+        } else {
+            // start a new atlas with only this style, even if it's beyond the max size
+            if !texes.is_empty() {
+                atlases.push(PackedAtlas {
+                    texes: std::mem::take(&mut texes),
+                    placed: std::mem::take(&mut placed),
                 });
+            }
+            placed = pack_atlas(&style_boxes, &settings)
+                .unwrap_or_else(|| pack_oversized_atlas(&style_boxes, &settings));
+            boxes = style_boxes;
+            texes = style_texes;
+        }
 
-                if p == None {
-                    continue;
-                }
-
-                // where the texture will be positioned
-                let offset_x = atlases[i][p.unwrap()].get_coords().0 as u32 + pad.x as u32 / 2;
-                let offset_y = atlases[i][p.unwrap()].get_coords().2 as u32 + pad.y as u32 / 2;
+        for tex in &mut armature.styles[s].textures {
+            tex.atlas_idx = atlases.len() as i32;
+        }
+    }
+    if !texes.is_empty() {
+        atlases.push(PackedAtlas { texes, placed });
+    }
 
-                // ensure another tex of the same size won't overwrite this one
-                atlases[i].remove(p.unwrap());
+    let mut bufs = vec![];
+    let mut sizes = vec![];
+    for PackedAtlas { texes, placed } in atlases {
+        let (origin, atlas_size) = atlas_bounds(&placed, &settings);
+
+        // placements are interchangeable between boxes of the same size
+        let mut spots: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
+        for pl in &placed {
+            let (left, _, top, _) = pl.get_coords();
+            spots
+                .entry((pl.width, pl.height))
+                .or_default()
+                .push((left - origin.x, top - origin.y));
+        }
 
-                // apply texture image onto atlas
-                let img = &armature.tex_data(tex).unwrap().image;
-                raw_buf.copy_from(img, offset_x, offset_y).unwrap();
+        let mut raw_buf = RgbaImage::new(atlas_size.x as u32, atlas_size.y as u32);
+        for (s, t) in texes {
+            let img = &images[s][t];
+            let footprint = (
+                img.width() as i32 + margin.x,
+                img.height() as i32 + margin.y,
+            );
+            let (left, top) = spots.get_mut(&footprint).unwrap().pop().unwrap();
+
+            // where the texture will be positioned
+            let offset_x = (left + pad.x as i32 / 2 + ext) as u32;
+            let offset_y = (top + pad.y as i32 / 2 + ext) as u32;
+
+            raw_buf.copy_from(img, offset_x, offset_y).unwrap();
+            extrude_edges(
+                &mut raw_buf,
+                offset_x,
+                offset_y,
+                img.dimensions(),
+                ext as u32,
+            );
 
-                // save offsets into armature
-                armature.styles[s].textures[t].offset = Vec2::new(offset_x as f32, offset_y as f32);
-            }
+            // save offsets into armature
+            armature.styles[s].textures[t].offset = Vec2::new(offset_x as f32, offset_y as f32);
         }
 
         let mut buf: Vec<u8> = vec![];
         let size = raw_buf.dimensions();
 
+        if edit_mode.premultiplied_atlases() {
+            premultiply_alpha(&mut raw_buf);
+        }
+
         // encode buffer to provided format
         match edit_mode.export_img_format {
             ExportImgFormat::PNG => {
@@ -523,6 +1021,14 @@ pub fn create_tex_sheet(armature: &mut Armature, edit_mode: &EditMode) -> (Vec<V
                 let img = image::ColorType::Rgba8.into();
                 encoder.write_image(&raw_buf, size.0, size.1, img).unwrap();
             }
+            ExportImgFormat::Webp | ExportImgFormat::WebpReducedColors => {
+                if edit_mode.export_img_format == ExportImgFormat::WebpReducedColors {
+                    reduce_precision(&mut raw_buf, edit_mode.premultiplied_atlases());
+                }
+                let encoder = image::codecs::webp::WebPEncoder::new_lossless(&mut buf);
+                let img = image::ColorType::Rgba8.into();
+                encoder.write_image(&raw_buf, size.0, size.1, img).unwrap();
+            }
             ExportImgFormat::JPG => {
                 let mut rgba: RgbaImage = raw_buf.convert();
                 for pixel in rgba.pixels_mut() {
@@ -540,15 +1046,176 @@ pub fn create_tex_sheet(armature: &mut Armature, edit_mode: &EditMode) -> (Vec<V
         }
 
         bufs.push(buf);
+        sizes.push(atlas_size);
     }
 
     (bufs, sizes)
 }
 
+/// Multiply colors by their alpha, for runtimes that blend premultiplied textures.
+pub fn premultiply_alpha(img: &mut RgbaImage) {
+    for pixel in img.pixels_mut() {
+        let a = pixel[3] as u16;
+        for c in 0..3 {
+            pixel[c] = ((pixel[c] as u16 * a + 127) / 255) as u8;
+        }
+    }
+}
+
+/// Undo `premultiply_alpha` (colors of faint pixels lose some precision).
+pub fn unpremultiply_alpha(img: &mut RgbaImage) {
+    for pixel in img.pixels_mut() {
+        let a = pixel[3] as u16;
+        if a == 0 {
+            continue;
+        }
+        for c in 0..3 {
+            pixel[c] = ((pixel[c] as u16 * 255 + a / 2) / a).min(255) as u8;
+        }
+    }
+}
+
+// low bits rounded off each color channel of reduced-color WebP atlases
+const WEBP_REDUCED_COLOR_BITS: u32 = 2;
+
+/// Round colors to fewer levels and clear fully transparent pixels, so that lossless WebP
+/// compresses them far better (like libwebp's near-lossless mode).
+fn reduce_precision(img: &mut RgbaImage, premultiplied: bool) {
+    let step = 1 << WEBP_REDUCED_COLOR_BITS;
+    for pixel in img.pixels_mut() {
+        if pixel[3] == 0 {
+            *pixel = Rgba([0, 0, 0, 0]);
+            continue;
+        }
+        // premultiplied colors can't be brighter than their alpha
+        let max = if premultiplied { pixel[3] as u32 } else { 255 };
+        for c in 0..3 {
+            let level = (pixel[c] as u32 + step / 2) / step * step;
+            pixel[c] = level.min(max) as u8;
+        }
+    }
+}
+
+// textures (style and index) of an atlas, and where their boxes were placed
+struct PackedAtlas {
+    texes: Vec<(usize, usize)>,
+    placed: Vec<PackingBox>,
+}
+
+/// Pack boxes into the smallest atlas that `settings` allow, if they fit within the max size.
+fn pack_atlas(boxes: &[PackingBox], settings: &AtlasSettings) -> Option<Vec<PackingBox>> {
+    let max = &settings.max_size;
+    let steps = |limit: i32| -> Vec<i32> {
+        if settings.power_of_two {
+            (0..31)
+                .map(|p| 1 << p)
+                .take_while(|s| *s <= limit)
+                .collect()
+        } else {
+            let mut steps: Vec<i32> = (1..=limit / 128).map(|s| s * 128).collect();
+            if limit % 128 != 0 {
+                steps.push(limit);
+            }
+            steps
+        }
+    };
+
+    let mut sizes: Vec<Vec2I> = vec![];
+    if settings.square {
+        for side in steps(max.x.min(max.y)) {
+            sizes.push(Vec2I::new(side, side));
+        }
+    } else {
+        for w in steps(max.x) {
+            for h in steps(max.y) {
+                sizes.push(Vec2I::new(w, h));
+            }
+        }
+        // smallest first, preferring squarer atlases
+        sizes.sort_by_key(|s| (s.x as i64 * s.y as i64, (s.x - s.y).abs()));
+    }
+
+    // skip sizes that can't possibly fit
+    let area: i64 = boxes.iter().map(|b| b.width as i64 * b.height as i64).sum();
+    let widest = boxes.iter().map(|b| b.width).max().unwrap_or(0);
+    let tallest = boxes.iter().map(|b| b.height).max().unwrap_or(0);
+    sizes.retain(|s| s.x > widest && s.y > tallest && s.x as i64 * s.y as i64 >= area);
+
+    sizes.into_iter().find_map(|size| place_boxes(boxes, size))
+}
+
+/// Pack boxes into a square atlas beyond the max size, for styles that don't fit otherwise.
+fn pack_oversized_atlas(boxes: &[PackingBox], settings: &AtlasSettings) -> Vec<PackingBox> {
+    let mut side = settings.max_size.x.max(settings.max_size.y);
+    loop {
+        side = match settings.power_of_two {
+            true => (side as u32 + 1).next_power_of_two() as i32,
+            false => side + 128,
+        };
+        if let Some(placed) = place_boxes(boxes, Vec2I::new(side, side)) {
+            return placed;
+        }
+    }
+}
+
+fn place_boxes(boxes: &[PackingBox], size: Vec2I) -> Option<Vec<PackingBox>> {
+    let bins = vec![max_rects::bucket::Bucket::new(
+        size.x - 1,
+        size.y - 1,
+        0,
+        0,
+        1,
+    )];
+    let mut problem = max_rects::max_rects::MaxRects::new(boxes.to_vec(), bins);
+    let (placed, _, _) = problem.place();
+    (placed.len() == boxes.len()).then_some(placed)
+}
+
+/// Top-left corner and size of an atlas fitted around its placed boxes.
+fn atlas_bounds(placed: &[PackingBox], settings: &AtlasSettings) -> (Vec2I, Vec2I) {
+    let (mut min, mut max) = (Vec2I::new(i32::MAX, i32::MAX), Vec2I::new(0, 0));
+    for pl in placed {
+        let (left, right, top, bottom) = pl.get_coords();
+        min = Vec2I::new(min.x.min(left), min.y.min(top));
+        max = Vec2I::new(max.x.max(right), max.y.max(bottom));
+    }
+
+    let mut size = Vec2I::new((max.x - min.x).max(1), (max.y - min.y).max(1));
+    if settings.power_of_two {
+        size.x = (size.x as u32).next_power_of_two() as i32;
+        size.y = (size.y as u32).next_power_of_two() as i32;
+    }
+    if settings.square {
+        size.x = size.x.max(size.y);
+        size.y = size.x;
+    }
+    (min, size)
+}
+
+/// Repeat the edge pixels of an image already copied into an atlas outward by `by` pixels.
+fn extrude_edges(atlas: &mut RgbaImage, x: u32, y: u32, (w, h): (u32, u32), by: u32) {
+    if by == 0 {
+        return;
+    }
+    let (atlas_w, atlas_h) = atlas.dimensions();
+    let x_range = x.saturating_sub(by)..(x + w + by).min(atlas_w);
+    let y_range = y.saturating_sub(by)..(y + h + by).min(atlas_h);
+    for py in y_range {
+        for px in x_range.clone() {
+            let src_x = px.clamp(x, x + w - 1);
+            let src_y = py.clamp(y, y + h - 1);
+            if (src_x, src_y) != (px, py) {
+                let pixel = *atlas.get_pixel(src_x, src_y);
+                atlas.put_pixel(px, py, pixel);
+            }
+        }
+    }
+}
+
 pub fn prepare_files(
     armature: &Armature,
     camera: Camera,
-    sizes: Vec<i32>,
+    sizes: Vec<Vec2I>,
     edit_mode: &EditMode,
 ) -> (String, String) {
     // clone armature and make some edits, then serialize it
@@ -574,7 +1241,7 @@ pub fn prepare_files(
             }
 
             let mut anim_arm = armature_copy.clone();
-            anim_arm.bones = anim_arm.animate(a, keyframe.frame, None);
+            anim_arm.bones = anim_arm.animate(a, keyframe.frame as f32, None);
             let bones = anim_arm.bones.clone();
             construction(&mut anim_arm.bones, &bones);
 
@@ -691,6 +1358,27 @@ pub fn prepare_files(
 
     for a in 0..armature_copy.animations.len() {
         armature_copy.animations[a].id = a as i32;
+
+        // sample root motion on every frame, if a bone is marked for it
+        let has_root_motion = armature.bones.iter().any(|bone| bone.root_motion);
+        let len = armature.animations[a].len_frames();
+        armature_copy.animations[a].root_motion = if has_root_motion {
+            (0..=len)
+                .map(|f| armature.root_motion_at(a, f as f32))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        // index mask bone IDs, dropping those of deleted bones
+        let bones = &armature_copy.bones;
+        let mask = &mut armature_copy.animations[a].mask;
+        *mask = mask
+            .iter()
+            .filter_map(|id| bones.iter().position(|bone| bone.id == *id))
+            .map(|idx| idx as i32)
+            .collect();
+
         for kf in 0..armature_copy.animations[a].keyframes.len() {
             let keyframe = &mut armature_copy.animations[a].keyframes[kf];
             let bones = &mut armature_copy.bones.iter();
@@ -839,19 +1527,17 @@ pub fn prepare_files(
     }
 
     let mut atlases = vec![];
-    let atlas_ext = match edit_mode.export_img_format {
-        ExportImgFormat::PNG => ".png",
-        ExportImgFormat::JPG => ".jpg",
-    };
+    let atlas_ext = edit_mode.export_img_format.extension();
     for s in 0..sizes.len() {
         atlases.push(TexAtlas {
-            filename: format!("atlas{}{}", s.to_string(), atlas_ext),
-            size: Vec2I::new(sizes[s], sizes[s]),
+            filename: format!("atlas{}.{}", s, atlas_ext),
+            size: sizes[s].clone(),
+            settings: edit_mode.export_atlas.clone(),
         });
     }
 
     let mut clear_color = edit_mode.export_clear_color;
-    if edit_mode.export_img_format == ExportImgFormat::PNG {
+    if edit_mode.export_img_format.has_alpha() {
         clear_color = Color::new(0, 0, 0, 0);
     }
 
@@ -958,6 +1644,7 @@ pub fn prepare_files(
         version: env!("CARGO_PKG_VERSION").to_string(),
         baked_ik: edit_mode.export_bake_ik,
         img_format: edit_mode.export_img_format.clone(),
+        premultiplied_alpha: edit_mode.premultiplied_atlases(),
         clear_color,
         bones: armature_copy.bones,
         animations: armature_copy.animations,
@@ -972,43 +1659,42 @@ pub fn prepare_files(
     (armatures_json, editor_json)
 }
 
-pub fn import<R: Read + std::io::Seek>(
-    data: R,
-    shared: &mut crate::Shared,
-    queue: Option<&wgpu::Queue>,
-    device: Option<&wgpu::Device>,
-    bind_group_layout: Option<&BindGroupLayout>,
-    context: Option<&egui::Context>,
-) {
-    let mut zip = zip::ZipArchive::new(data);
-    if let Err(_) = zip {
-        return;
-    }
-    let str = shared.ui.loc("error_skf");
-
-    macro_rules! custom_err {
-        ($error:expr) => {
-            shared.ui.custom_error = $error.to_string();
-            editor::open_modal(&mut shared.ui, false, str);
-            return;
+/// Read and upgrade `armature.json` of an .skf into an armature, without loading any textures.
+/// Also returns the repairs made along the way.
+///
+/// Shared by the editor's `import` and the headless runtime.
+pub fn import_armature<R: Read + std::io::Seek>(
+    zip: &mut zip::ZipArchive<R>,
+) -> Result<(Root, Armature, Vec<Warning>), SkfError> {
+    // prefer the binary encoding, if it was exported with one
+    let value: serde_json::Value = if zip.index_for_name("armature.bin").is_some() {
+        let corrupt = |message: String| SkfError::Corrupt {
+            file: "armature.bin".to_string(),
+            message,
         };
-    }
-
-    // load armature
-    let armature_file = zip.as_mut().unwrap().by_name("armature.json");
-    if let Err(_) = armature_file {
-        custom_err!("armature.json file could not be found.");
-    }
+        let mut bytes = vec![];
+        let file = zip.by_name("armature.bin");
+        if let Err(e) = file.map(|mut file| file.read_to_end(&mut bytes)) {
+            return Err(corrupt(e.to_string()));
+        }
+        binary::decode(&bytes).map_err(corrupt)?
+    } else {
+        let armature_file = zip.by_name("armature.json");
+        if armature_file.is_err() {
+            return Err(SkfError::MissingFile("armature.json".to_string()));
+        }
 
-    let value: std::result::Result<serde_json::Value, _> =
-        serde_json::from_reader(armature_file.unwrap());
-    if let Err(ref e) = value {
-        custom_err!(format!("armature.json:\n{}", e.to_string()));
-    }
-    let (root, root_err) = backwards_compat::proceed(value.unwrap());
-    if root_err != "" {
-        custom_err!(root_err.to_string());
-    }
+        let parsed: std::result::Result<serde_json::Value, _> =
+            serde_json::from_reader(armature_file.unwrap());
+        if let Err(ref e) = parsed {
+            return Err(SkfError::Corrupt {
+                file: "armature.json".to_string(),
+                message: e.to_string(),
+            });
+        }
+        parsed.unwrap()
+    };
+    let root = backwards_compat::proceed(value)?;
 
     let mut temp_arm = shared::Armature {
         bones: root.bones.clone(),
@@ -1018,6 +1704,33 @@ pub fn import<R: Read + std::io::Seek>(
         animated_bones: vec![],
     };
 
+    // ensure bones only point to existing visuals, physics and IK families, and textures to
+    // existing atlases. Only bones can go without (-1)
+    macro_rules! check_idx {
+        ($idx:expr, $array:expr, $path:expr, $optional:expr) => {
+            let unset = $optional && $idx == -1;
+            if !unset && ($idx < 0 || $idx as usize >= $array.len()) {
+                let path = $path;
+                let (idx, len) = ($idx, $array.len());
+                return Err(SkfError::OutOfRange { path, idx, len });
+            }
+        };
+    }
+    for (b, bone) in temp_arm.bones.iter().enumerate() {
+        let path = format!("bones[{}].visuals_id", b);
+        check_idx!(bone.visuals_id, root.visuals, path, true);
+        let path = format!("bones[{}].physics_id", b);
+        check_idx!(bone.physics_id, root.physics, path, true);
+        let path = format!("bones[{}].ik_family_id", b);
+        check_idx!(bone.ik_family_id, root.inverse_kinematics, path, true);
+    }
+    for (s, style) in temp_arm.styles.iter().enumerate() {
+        for (t, tex) in style.textures.iter().enumerate() {
+            let path = format!("styles[{}].textures[{}].atlas_idx", s, t);
+            check_idx!(tex.atlas_idx, root.atlases, path, false);
+        }
+    }
+
     // populate visuals data
     for b in 0..temp_arm.bones.len() {
         if temp_arm.bones[b].visuals_id == -1 {
@@ -1066,11 +1779,18 @@ pub fn import<R: Read + std::io::Seek>(
         bone.verts_edited = bone.vertices.len() > 0;
     }
 
-    // populate style ids
+    // populate style ids and texture sizes
     for s in 0..temp_arm.styles.len() {
         temp_arm.styles[s].id = s as i32;
+        for tex in &mut temp_arm.styles[s].textures {
+            tex.offset = Vec2::new(tex.ser_offset.x as f32, tex.ser_offset.y as f32);
+            tex.size = Vec2::new(tex.ser_size.x as f32, tex.ser_size.y as f32);
+        }
     }
 
+    // repair before anything walks the hierarchy
+    let repairs = repair_armature(&mut temp_arm);
+
     // populate bone IK data
     for b in 0..temp_arm.bones.len() {
         if temp_arm.bones[b].ik_family_id == -1 {
@@ -1084,11 +1804,8 @@ pub fn import<R: Read + std::io::Seek>(
             if let Some(bone) = temp_arm.bones.iter_mut().find(|b| b.id == id) {
                 bone.ik_family_id = fam_id;
             } else {
-                custom_err!(format!(
-                    "Bone of ID {} of IK family #{} could not be found.",
-                    id.to_string(),
-                    fam_id.to_string()
-                ));
+                let path = format!("inverse_kinematics[{}].bone_ids[{}]", fam_id, i);
+                return Err(SkfError::DanglingId { path, id });
             }
         }
 
@@ -1107,13 +1824,197 @@ pub fn import<R: Read + std::io::Seek>(
         }
     }
 
+    Ok((root, temp_arm, repairs))
+}
+
+/// Fix issues in hand-edited or tool-generated armatures that would otherwise
+/// break the editor and runtimes, returning what was changed.
+pub fn repair_armature(armature: &mut Armature) -> Vec<Warning> {
+    let mut repairs: Vec<Warning> = vec![];
+    let bone_ids: Vec<i32> = armature.bones.iter().map(|b| b.id).collect();
+    let bones = &mut armature.bones;
+
+    // Warning::OrphanBone
+    for bone in bones.iter_mut() {
+        if bone.parent_id != -1 && !bone_ids.contains(&bone.parent_id) {
+            bone.parent_id = -1;
+            repairs.push(Warning::new(Warnings::OrphanBone, vec![bone.id as usize]));
+        }
+    }
+
+    // Warning::BrokenCycle
+    for b in 0..bones.len() {
+        let mut visited = vec![bones[b].id];
+        let mut current = b;
+        while bones[current].parent_id != -1 {
+            let parent_id = bones[current].parent_id;
+
+            // break the loop at the bone that leads back into it
+            if visited.contains(&parent_id) {
+                bones[current].parent_id = -1;
+                repairs.push(Warning::new(
+                    Warnings::BrokenCycle,
+                    vec![bones[current].id as usize],
+                ));
+                break;
+            }
+
+            visited.push(parent_id);
+            current = bones.iter().position(|b| b.id == parent_id).unwrap();
+        }
+    }
+
+    // Warning::MissingBindVerts
+    for bone in bones.iter_mut() {
+        let vert_ids: Vec<i32> = bone.vertices.iter().map(|v| v.id as i32).collect();
+        for (b, bind) in bone.binds.iter_mut().enumerate() {
+            let len = bind.verts.len();
+            bind.verts.retain(|v| vert_ids.contains(&v.id));
+            let removed = len - bind.verts.len();
+            if removed > 0 {
+                let ids = vec![bone.id as usize];
+                let count = vec![removed.to_string()];
+                repairs.push(Warning::full(
+                    Warnings::MissingBindVerts,
+                    ids,
+                    b as f32,
+                    count,
+                ));
+            }
+        }
+    }
+
+    // Warning::NanTransform
+    let mut nan_ids: Vec<i32> = vec![];
+    for bone in bones.iter_mut() {
+        let mut nan = false;
+        macro_rules! clamp {
+            ($field:expr, $default:expr) => {
+                if !$field.is_finite() {
+                    $field = $default;
+                    nan = true;
+                }
+            };
+        }
+
+        clamp!(bone.pos.x, 0.);
+        clamp!(bone.pos.y, 0.);
+        clamp!(bone.rot, 0.);
+        clamp!(bone.scale.x, 1.);
+        clamp!(bone.scale.y, 1.);
+        clamp!(bone.pivot_pos.x, 0.);
+        clamp!(bone.pivot_pos.y, 0.);
+        clamp!(bone.pivot_rot, 0.);
+        clamp!(bone.pivot_scale.x, 1.);
+        clamp!(bone.pivot_scale.y, 1.);
+        for vert in &mut bone.vertices {
+            clamp!(vert.pos.x, 0.);
+            clamp!(vert.pos.y, 0.);
+            clamp!(vert.init_pos.x, 0.);
+            clamp!(vert.init_pos.y, 0.);
+            clamp!(vert.uv.x, 0.);
+            clamp!(vert.uv.y, 0.);
+        }
+
+        if nan {
+            nan_ids.push(bone.id);
+        }
+    }
+
+    for anim in &mut armature.animations {
+        // Warning::OrphanKeyframes
+        let len = anim.keyframes.len();
+        anim.keyframes.retain(|kf| bone_ids.contains(&kf.bone_id));
+        let removed = len - anim.keyframes.len();
+        if removed > 0 {
+            let name = vec![anim.name.clone()];
+            repairs.push(Warning::full(
+                Warnings::OrphanKeyframes,
+                vec![],
+                removed as f32,
+                name,
+            ));
+        }
+
+        for kf in &mut anim.keyframes {
+            if kf.value.is_finite() {
+                continue;
+            }
+            kf.value = match kf.element {
+                AnimElement::ScaleX | AnimElement::ScaleY => 1.,
+                _ => 0.,
+            };
+            if !nan_ids.contains(&kf.bone_id) {
+                nan_ids.push(kf.bone_id);
+            }
+        }
+    }
+
+    for id in nan_ids {
+        repairs.push(Warning::new(Warnings::NanTransform, vec![id as usize]));
+    }
+
+    repairs
+}
+
+/// Image of a texture as it was before export, cut out of its atlas (undoing any trim or turn).
+pub fn unpack_texture(atlas: &image::DynamicImage, tex: &Texture) -> image::DynamicImage {
+    let (x, y) = (tex.offset.x as u32, tex.offset.y as u32);
+    let (w, h) = (tex.size.x as u32, tex.size.y as u32);
+    let image = match tex.rotated {
+        true => atlas.crop_imm(x, y, h, w).rotate270(),
+        false => atlas.crop_imm(x, y, w, h),
+    };
+    if tex.source_size == Vec2I::new(0, 0) {
+        return image;
+    }
+
+    let (src_w, src_h) = (tex.source_size.x as u32, tex.source_size.y as u32);
+    let mut source = RgbaImage::new(src_w, src_h);
+    let (trim_x, trim_y) = (tex.trim_offset.x as i64, tex.trim_offset.y as i64);
+    image::imageops::replace(&mut source, &image.to_rgba8(), trim_x, trim_y);
+    image::DynamicImage::ImageRgba8(source)
+}
+
+#[cfg(feature = "editor")]
+pub fn import<R: Read + std::io::Seek>(
+    data: R,
+    shared: &mut crate::Shared,
+    queue: Option<&wgpu::Queue>,
+    device: Option<&wgpu::Device>,
+    bind_group_layout: Option<&BindGroupLayout>,
+    context: Option<&egui::Context>,
+) {
+    let str = shared.ui.loc("error_skf");
+
+    macro_rules! custom_err {
+        ($error:expr) => {
+            shared.ui.custom_error = $error.to_string();
+            editor::open_modal(&mut shared.ui, false, str);
+            return;
+        };
+    }
+
+    let mut zip = zip::ZipArchive::new(data);
+    if let Err(e) = zip {
+        custom_err!(SkfError::Io(e.to_string()));
+    }
+
+    // load armature
+    let armature = import_armature(zip.as_mut().unwrap());
+    if let Err(e) = armature {
+        custom_err!(e);
+    }
+    let (root, mut temp_arm, repairs) = armature.unwrap();
+    shared.ui.repairs = repairs;
+
     // load editor data
     if let Ok(editor_file) = zip.as_mut().unwrap().by_name("editor.json") {
         if let Ok(editor) =
             serde_json::from_reader::<ZipFile<'_, R>, crate::EditorOptions>(editor_file)
         {
             shared.camera = editor.camera;
-            for b in 0..temp_arm.bones.len() {
+            for b in 0..temp_arm.bones.len().min(editor.bones.len()) {
                 let bone = &mut temp_arm.bones[b];
                 let ed_bone = &editor.bones[b];
 
@@ -1126,7 +2027,7 @@ pub fn import<R: Read + std::io::Seek>(
                 bone.group_color = ed_bone.group_color;
                 bone.blacklist = ed_bone.blacklist.clone();
             }
-            for s in 0..temp_arm.styles.len() {
+            for s in 0..temp_arm.styles.len().min(editor.styles.len()) {
                 let style = &mut temp_arm.styles[s];
                 let ed_style = &editor.styles[s];
                 style.active = ed_style.active;
@@ -1143,14 +2044,23 @@ pub fn import<R: Read + std::io::Seek>(
             let filename = &root.atlases[a].filename;
             let texture_file = zip.as_mut().unwrap().by_name(filename);
             if let Err(_) = texture_file {
-                custom_err!(format!("Texture file '{}' could not be found.", filename));
+                custom_err!(SkfError::MissingFile(filename.to_string()));
             }
 
             let mut bytes = vec![];
-            for byte in texture_file.unwrap().bytes() {
-                bytes.push(byte.unwrap());
+            if let Err(e) = texture_file.unwrap().read_to_end(&mut bytes) {
+                custom_err!(SkfError::Io(e.to_string()));
+            }
+            match image::load_from_memory(&bytes) {
+                Ok(img) => imgs.push(img),
+                Err(e) => {
+                    let file = filename.to_string();
+                    custom_err!(SkfError::Corrupt {
+                        file,
+                        message: e.to_string()
+                    });
+                }
             }
-            imgs.push(image::load_from_memory(&bytes).unwrap());
         }
 
         // remove clear color for JPG atlases
@@ -1171,17 +2081,25 @@ pub fn import<R: Read + std::io::Seek>(
             }
         }
 
+        // the editor works with straight alpha
+        if root.premultiplied_alpha {
+            for img in &mut imgs {
+                let mut rgba = img.to_rgba8();
+                unpremultiply_alpha(&mut rgba);
+                *img = image::DynamicImage::ImageRgba8(rgba);
+            }
+        }
+
         for set in &mut temp_arm.styles {
             for tex in &mut set.textures {
-                tex.offset = Vec2::new(tex.ser_offset.x as f32, tex.ser_offset.y as f32);
-                tex.size = Vec2::new(tex.ser_size.x as f32, tex.ser_size.y as f32);
-                let u_offset_x = tex.offset.x as u32;
-                let u_offset_y = tex.offset.y as u32;
-                let u_size_x = tex.size.x as u32;
-                let u_size_y = tex.size.y as u32;
-
-                let image =
-                    imgs[tex.atlas_idx as usize].crop(u_offset_x, u_offset_y, u_size_x, u_size_y);
+                let image = unpack_texture(&imgs[tex.atlas_idx as usize], tex);
+
+                // the editor keeps textures whole, and trims them again on export
+                tex.size = Vec2::new(image.width() as f32, image.height() as f32);
+                tex.trim_offset = Vec2I::new(0, 0);
+                tex.source_size = Vec2I::new(0, 0);
+                tex.rotated = false;
+
                 let mut bind_group: Option<wgpu::BindGroup> = None;
 
                 if queue != None && device != None && bind_group_layout != None {
@@ -1191,34 +2109,38 @@ pub fn import<R: Read + std::io::Seek>(
                     bind_group = Some(func(rgba, tex.size, queue.unwrap(), device.unwrap(), bgl));
                 }
 
-                if context == None {
-                    continue;
-                }
-
-                let img =
-                    imgs[tex.atlas_idx as usize].crop(u_offset_x, u_offset_y, u_size_x, u_size_y);
-                let filter = image::imageops::FilterType::Nearest;
-                let pixels = img.resize_exact(300, 300, filter).into_rgba8().to_vec();
+                // no UI images when importing headlessly (eg; from the command line)
+                let mut ui_img = None;
+                if let Some(context) = context {
+                    let img = &image;
+                    let filter = image::imageops::FilterType::Nearest;
+                    let pixels = img.resize_exact(300, 300, filter).into_rgba8().to_vec();
 
-                let col = egui::ColorImage::from_rgba_unmultiplied([300, 300], &pixels);
-                let file = "anim_icons";
-                let ui_img = context.unwrap().load_texture(file, col, Default::default());
+                    let col = egui::ColorImage::from_rgba_unmultiplied([300, 300], &pixels);
+                    let file = "anim_icons";
+                    ui_img = Some(context.load_texture(file, col, Default::default()));
+                }
 
                 let data_id = temp_arm.tex_data.len() as i32;
                 tex.data_id = data_id;
                 #[rustfmt::skip]
-                temp_arm.tex_data.push(TextureData { id: data_id, image, bind_group, ui_img: Some(ui_img) });
+                temp_arm.tex_data.push(TextureData { id: data_id, image, bind_group, ui_img });
             }
         }
     }
 
+    // pack atlases the same way on the next export
+    if let Some(atlas) = root.atlases.first() {
+        shared.edit_mode.export_atlas = atlas.settings.clone();
+    }
+
     shared.armature = temp_arm;
 
     shared.events.unselect_all();
     shared.ui.startup_window = false;
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(not(target_arch = "wasm32"), feature = "editor"))]
 pub fn save_to_recent_files(paths: &Vec<String>) {
     fs::create_dir_all(recents_path().parent().unwrap()).unwrap();
     let mut file = std::fs::File::create(&recents_path()).unwrap();
@@ -1242,6 +2164,7 @@ pub fn bone_meshes_edited(tex_size: Vec2, verts: &Vec<Vertex>) -> bool {
     !is_rect
 }
 
+#[cfg(feature = "editor")]
 pub fn open_docs(is_dev: bool, mut _path: &str) {
     let docs_name = if is_dev { "dev-docs" } else { "user-docs" };
     #[cfg(target_arch = "wasm32")]
@@ -1276,6 +2199,7 @@ pub fn bin_path() -> std::path::PathBuf {
     exe_dir.to_path_buf()
 }
 
+#[cfg(feature = "editor")]
 pub fn save_config(config: &Config) {
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -1302,6 +2226,7 @@ pub fn save_config(config: &Config) {
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn config_str() -> String {
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -1317,6 +2242,7 @@ pub fn config_str() -> String {
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn color_str() -> String {
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -1332,6 +2258,7 @@ pub fn color_str() -> String {
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn config_keys_str() -> String {
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -1347,6 +2274,7 @@ pub fn config_keys_str() -> String {
     }
 }
 
+#[cfg(feature = "editor")]
 pub fn add_texture_img(
     ctx: &egui::Context,
     img_buf: image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
@@ -1398,6 +2326,7 @@ pub fn without_unicode(str: &str) -> &str {
     str.split('\u{0000}').collect::<Vec<_>>()[0]
 }
 
+#[cfg(feature = "editor")]
 pub fn process_screenshot(
     buffer: &wgpu::Buffer,
     device: &wgpu::Device,
@@ -1420,6 +2349,7 @@ pub fn process_screenshot(
     png_buf
 }
 
+#[cfg(feature = "editor")]
 pub fn process_screenshot_raw(
     buffer: &wgpu::Buffer,
     _device: &wgpu::Device,
@@ -1495,6 +2425,7 @@ pub fn markdown(mut str: String) -> String {
 }
 
 // Simulate text being added to egui and truncate it to fit the max width
+#[cfg(feature = "editor")]
 pub fn trunc_str(ui: &egui::Ui, text: &str, max_width: f32) -> String {
     let f_id = egui::FontId::proportional(14.0);
     let col = egui::Color32::WHITE;
@@ -1517,6 +2448,7 @@ pub fn trunc_str(ui: &egui::Ui, text: &str, max_width: f32) -> String {
     ctext
 }
 
+#[cfg(feature = "editor")]
 pub fn attempt_exit(undo_states: &mut UndoStates, config: &Config, ui: &mut Ui) {
     if undo_states.undo_actions.len() == 0 && !config.ignore_donate {
         ui.donating_modal = true;
@@ -1544,10 +2476,92 @@ pub fn animate_bones(armature: &mut Armature, selection: &SelectionState, edit_m
             let frame = anim.set_frame();
             let anim_bones = armature.animated_bones.clone();
             armature.animated_bones = armature.animate(a, frame, Some(&anim_bones));
+
+            // keep the root motion bone in place
+            let motion = armature.root_motion_at(a, frame);
+            if let Some(bone) = armature.animated_bones.iter_mut().find(|b| b.root_motion) {
+                bone.pos -= motion;
+            }
+        }
+
+        // preview a paused additive or masked animation on top of the playing ones
+        let sel_anim = armature.animations.get(selection.anim);
+        let is_layer = sel_anim
+            .is_some_and(|anim| anim.elapsed.is_none() && (anim.additive || !anim.mask.is_empty()));
+        if edit_mode.anim_open && is_layer && selection.anim_frame != -1 {
+            let anim_bones = armature.animated_bones.clone();
+            armature.animated_bones = armature.animate(
+                selection.anim,
+                selection.anim_frame as f32,
+                Some(&anim_bones),
+            );
         }
     } else if edit_mode.anim_open && selection.anim != usize::MAX && selection.anim_frame != -1 {
         // display the selected animation's frame
-        armature.animated_bones = armature.animate(selection.anim, selection.anim_frame, None);
+        let frame = selection.anim_frame as f32;
+        armature.animated_bones = armature.animate(selection.anim, frame, None);
+    }
+}
+
+/// Frames to sample when exporting an animation, looped `cycles` times.
+///
+/// Resampled to `fps` if it's above 0, so frames may be fractional.
+pub fn export_frames(anim: &Animation, fps: i32, cycles: i32) -> Vec<f32> {
+    let step = if fps > 0 && anim.fps > 0 {
+        anim.fps as f32 / fps as f32
+    } else {
+        1.
+    };
+
+    let cycle_len = (anim.cycle_frames().max(1) as f32 / step).ceil() as i32;
+    (0..cycle_len * cycles)
+        .map(|f| anim.loop_frame(f as f32 * step))
+        .collect()
+}
+
+/// JSON Schema of armature.json for this version, generated from `Root`.
+///
+/// Describes what's written, but only requires what must be there to be read, so that files
+/// saved before a field with a default was added still match.
+pub fn armature_schema() -> serde_json::Value {
+    use schemars::generate::SchemaSettings;
+    let generate = |settings: SchemaSettings| {
+        let schema = settings.into_generator().into_root_schema_for::<Root>();
+        serde_json::to_value(schema).unwrap()
+    };
+    let mut schema = generate(SchemaSettings::draft2020_12().for_serialize());
+    let accepted = generate(SchemaSettings::draft2020_12().for_deserialize());
+    relax_required(&mut schema, &accepted);
+    let title = format!("SkelForm armature.json v{}", env!("CARGO_PKG_VERSION"));
+    schema["title"] = title.into();
+    schema
+}
+
+// drop fields from `required` lists that the same place in `accepted` doesn't require
+fn relax_required(written: &mut serde_json::Value, accepted: &serde_json::Value) {
+    match written {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if let Some(other) = accepted.get(key) {
+                    relax_required(value, other);
+                }
+            }
+            if let Some(serde_json::Value::Array(required)) = map.get_mut("required") {
+                let must = accepted["required"].as_array().cloned().unwrap_or_default();
+                required.retain(|field| must.contains(field));
+                if required.is_empty() {
+                    map.remove("required");
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                if let Some(other) = accepted.get(i) {
+                    relax_required(item, other);
+                }
+            }
+        }
+        _ => {}
     }
 }
 
@@ -1561,8 +2575,8 @@ pub fn crashlog_file() -> PathBuf {
 
 // runtimes: interpolation using bezier splines
 pub fn interp(
-    current: i32,
-    max: i32,
+    current: f32,
+    max: f32,
     start_val: f32,
     end_val: f32,
     start_handle: Vec2,
@@ -1572,12 +2586,12 @@ pub fn interp(
     if start_handle.y == 999. && end_handle.y == 999. {
         return start_val;
     }
-    if max == 0 || current >= max {
+    if max == 0. || current >= max {
         return end_val;
     }
 
     // solve for time (x axis) with Newton-Raphson
-    let initial = current as f32 / max as f32;
+    let initial = current / max;
     let mut t = initial;
     for _ in 0..5 {
         let x = cubic_bezier(t, start_handle.x, end_handle.x);
@@ -1616,9 +2630,9 @@ pub fn interp_preset(preset: HandlePreset) -> (Vec2, Vec2) {
     }
 }
 
-pub fn get_prev_frame(frame: i32, kfs: &Vec<Keyframe>, b_id: i32, el: &AnimElement) -> usize {
+pub fn get_prev_frame(frame: f32, kfs: &[Keyframe], b_id: i32, el: &AnimElement) -> usize {
     kfs.iter()
-        .rposition(|kf| kf.frame <= frame && kf.bone_id == b_id && kf.element == *el)
+        .rposition(|kf| kf.frame as f32 <= frame && kf.bone_id == b_id && kf.element == *el)
         .unwrap_or(usize::MAX)
 }
 
@@ -1628,6 +2642,7 @@ pub fn color_within_range(src: [u8; 3], dst: [u8; 3], tol: u8) -> bool {
         && (src[2] >= dst[2].saturating_sub(tol) && src[2] <= dst[2].saturating_add(tol))
 }
 
+#[cfg(feature = "editor")]
 pub fn open_link(url: &str) {
     #[cfg(target_arch = "wasm32")]
     crate::openLink(url.to_string());
@@ -265,7 +265,87 @@ pub fn warning_line(
                 ui.label(tex_str);
             }
         }
+        // repaired bones may have been deleted since
+        W::BrokenCycle | W::OrphanBone | W::NanTransform => {
+            if let Some(bone) = bones.iter().find(|b| b.id == warning.ids[0] as i32) {
+                let key = format!("warnings.{}", warning.warn_type);
+                let str = shared_ui.loc(&key).replace("$bone", &bone.name);
+                clickable_bone(ui, armature, str, events, warning, warn_color);
+            }
+        }
+        W::OrphanKeyframes => {
+            let str = shared_ui
+                .loc("warnings.OrphanKeyframes")
+                .replace("$count", &warning.value.to_string())
+                .replace("$anim", &warning.str_values[0]);
+            ui.label(egui::RichText::new(str).color(warn_color));
+        }
+        W::MissingBindVerts => {
+            if let Some(bone) = bones.iter().find(|b| b.id == warning.ids[0] as i32) {
+                let str = shared_ui
+                    .loc("warnings.MissingBindVerts")
+                    .replace("$count", &warning.str_values[0])
+                    .replace("$bone", &bone.name)
+                    .replace("$bind", &warning.value.to_string());
+                clickable_bone(ui, armature, str, events, warning, warn_color);
+            }
+        }
+    }
+}
+
+/// Plain-text version of `warning_line()`, for the command line.
+pub fn warning_text(warning: &Warning, shared_ui: &crate::Ui, armature: &Armature) -> String {
+    let bone_name = |id: &usize| {
+        let bone = armature.bones.iter().find(|b| b.id == *id as i32);
+        bone.map(|b| b.name.clone()).unwrap_or_default()
+    };
+    let first_bone = warning.ids.first().map(bone_name).unwrap_or_default();
+    let value = warning.value.to_string();
+    let str_value = warning.str_values.first().cloned().unwrap_or_default();
+
+    let family_id = match warning.warn_type {
+        W::OnlyIk => {
+            let id = warning.ids.first().map(|id| *id as i32);
+            let bone = armature.bones.iter().find(|b| Some(b.id) == id);
+            bone.map(|b| b.ik_family_id.to_string()).unwrap_or_default()
+        }
+        _ => value.clone(),
+    };
+    let count = match warning.warn_type {
+        W::MissingBindVerts => str_value.clone(),
+        _ => value.clone(),
+    };
+
+    let mut str = shared_ui
+        .loc(&format!("warnings.{}", warning.warn_type))
+        .replace("$bone_count", &warning.ids.len().to_string())
+        .replace("$bone", &first_bone)
+        .replace("$zindex", &value)
+        .replace("$bind", &value)
+        .replace("$ik_family_id", &family_id)
+        .replace("$count", &count)
+        .replace("$anim", &str_value);
+
+    let list: Vec<String> = match warning.warn_type {
+        W::SameZIndex | W::BoneOutOfFamily => warning.ids.iter().map(bone_name).collect(),
+        W::EmptyStyles => {
+            let styles = warning.ids.iter().map(|id| {
+                let style = armature.styles.iter().find(|s| s.id == *id as i32);
+                style.map(|s| s.name.clone()).unwrap_or_default()
+            });
+            styles.collect()
+        }
+        W::UnusedTextures => warning.str_values.clone(),
+        _ => vec![],
+    };
+    if warning.warn_type == W::BoneOutOfFamily {
+        str += &(" ".to_string() + &shared_ui.loc("warnings.BoneOutOfFamilyCulprits"));
+    }
+    if !list.is_empty() {
+        str += &(" ".to_string() + &list.join(", "));
     }
+
+    str
 }
 
 fn clickable_bone(